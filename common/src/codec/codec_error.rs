@@ -0,0 +1,47 @@
+use std::error::Error;
+use std::fmt::{Display, Formatter};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CodecError {
+    Truncated { needed: usize, remaining: usize },
+    UnsupportedVersion(u8),
+    UnknownMessageType(u8),
+    UnexpectedMessageType { expected: u8, found: u8 },
+    InvalidValue { field: &'static str, value: u8 },
+    LengthMismatch { declared: usize, actual: usize },
+}
+
+impl Display for CodecError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            CodecError::Truncated { needed, remaining } => {
+                write!(
+                    f,
+                    "truncated message, needed {} bytes with {} remaining",
+                    needed, remaining
+                )
+            }
+            CodecError::UnsupportedVersion(version) => {
+                write!(f, "unsupported wire version {}", version)
+            }
+            CodecError::UnknownMessageType(msg_type) => {
+                write!(f, "unknown message type {}", msg_type)
+            }
+            CodecError::UnexpectedMessageType { expected, found } => {
+                write!(f, "expected message type {} but found {}", expected, found)
+            }
+            CodecError::InvalidValue { field, value } => {
+                write!(f, "invalid value {} for {}", value, field)
+            }
+            CodecError::LengthMismatch { declared, actual } => {
+                write!(
+                    f,
+                    "declared length {} but decoded {} bytes",
+                    declared, actual
+                )
+            }
+        }
+    }
+}
+
+impl Error for CodecError {}
@@ -0,0 +1,43 @@
+use crate::codec::codec_error::CodecError;
+use std::error::Error;
+use std::fmt::{Display, Formatter};
+use std::io;
+
+#[derive(Debug)]
+pub enum FrameError {
+    /// The peer closed the connection between frames.
+    Closed,
+    TooLarge {
+        len: usize,
+        max: usize,
+    },
+    Io(io::Error),
+    Codec(CodecError),
+}
+
+impl Display for FrameError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            FrameError::Closed => write!(f, "connection closed"),
+            FrameError::TooLarge { len, max } => {
+                write!(f, "frame of {} bytes exceeds the {} byte limit", len, max)
+            }
+            FrameError::Io(e) => write!(f, "frame io error: {}", e),
+            FrameError::Codec(e) => write!(f, "invalid frame: {}", e),
+        }
+    }
+}
+
+impl Error for FrameError {}
+
+impl From<io::Error> for FrameError {
+    fn from(e: io::Error) -> Self {
+        FrameError::Io(e)
+    }
+}
+
+impl From<CodecError> for FrameError {
+    fn from(e: CodecError) -> Self {
+        FrameError::Codec(e)
+    }
+}
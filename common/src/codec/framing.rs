@@ -0,0 +1,149 @@
+use crate::codec::frame_error::FrameError;
+use crate::codec::message_codec::{decode_engine_message, encode_engine_message};
+use crate::transport::sequenced_message::EngineMessage;
+use std::io;
+use std::io::{ErrorKind, Read, Write};
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+
+/// Frames on every TCP session are a big endian u32 payload length followed by the payload.
+pub const FRAME_PREFIX_LEN: usize = 4;
+pub const MAX_FRAME_LEN: usize = 4096;
+
+/// Readers pass a buffer of the largest frame they accept, anything longer is refused before
+/// it is read.
+pub fn read_frame<'a, R: Read>(
+    reader: &mut R,
+    buffer: &'a mut [u8],
+) -> Result<&'a [u8], FrameError> {
+    let mut prefix = [0u8; FRAME_PREFIX_LEN];
+    let mut filled = 0;
+    while filled < FRAME_PREFIX_LEN {
+        match reader.read(&mut prefix[filled..]) {
+            Ok(0) => return Err(closed_at(filled)),
+            Ok(n) => filled += n,
+            Err(e) if e.kind() == ErrorKind::Interrupted => {}
+            Err(e) => return Err(e.into()),
+        }
+    }
+
+    let len = frame_len(prefix, buffer.len())?;
+    reader.read_exact(&mut buffer[..len])?;
+    Ok(&buffer[..len])
+}
+
+pub fn write_frame<W: Write>(writer: &mut W, payload: &[u8]) -> Result<(), FrameError> {
+    writer.write_all(&prefix(payload.len())?)?;
+    writer.write_all(payload)?;
+    Ok(())
+}
+
+pub fn read_message<R: Read>(
+    reader: &mut R,
+    buffer: &mut [u8],
+) -> Result<EngineMessage, FrameError> {
+    Ok(decode_engine_message(read_frame(reader, buffer)?)?)
+}
+
+/// Encodes into `scratch` and writes the framed message with a single write.
+pub fn write_message<W: Write>(
+    writer: &mut W,
+    message: &EngineMessage,
+    scratch: &mut Vec<u8>,
+) -> Result<(), FrameError> {
+    encode_frame(message, scratch)?;
+    writer.write_all(scratch)?;
+    Ok(())
+}
+
+pub async fn read_frame_async<'a, R: AsyncRead + Unpin>(
+    reader: &mut R,
+    buffer: &'a mut [u8],
+) -> Result<&'a [u8], FrameError> {
+    let mut prefix = [0u8; FRAME_PREFIX_LEN];
+    let mut filled = 0;
+    while filled < FRAME_PREFIX_LEN {
+        match reader.read(&mut prefix[filled..]).await {
+            Ok(0) => return Err(closed_at(filled)),
+            Ok(n) => filled += n,
+            Err(e) if e.kind() == ErrorKind::Interrupted => {}
+            Err(e) => return Err(e.into()),
+        }
+    }
+
+    let len = frame_len(prefix, buffer.len())?;
+    reader.read_exact(&mut buffer[..len]).await?;
+    Ok(&buffer[..len])
+}
+
+pub async fn write_frame_async<W: AsyncWrite + Unpin>(
+    writer: &mut W,
+    payload: &[u8],
+) -> Result<(), FrameError> {
+    writer.write_all(&prefix(payload.len())?).await?;
+    writer.write_all(payload).await?;
+    Ok(())
+}
+
+pub async fn read_message_async<R: AsyncRead + Unpin>(
+    reader: &mut R,
+    buffer: &mut [u8],
+) -> Result<EngineMessage, FrameError> {
+    Ok(decode_engine_message(
+        read_frame_async(reader, buffer).await?,
+    )?)
+}
+
+pub async fn write_message_async<W: AsyncWrite + Unpin>(
+    writer: &mut W,
+    message: &EngineMessage,
+    scratch: &mut Vec<u8>,
+) -> Result<(), FrameError> {
+    encode_frame(message, scratch)?;
+    writer.write_all(scratch).await?;
+    Ok(())
+}
+
+fn encode_frame(message: &EngineMessage, scratch: &mut Vec<u8>) -> Result<(), FrameError> {
+    encode_frame_with(scratch, |out| encode_engine_message(message, out))
+}
+
+/// Replaces `scratch` with a whole frame, prefix included, of the payload `encode` appends so it
+/// can go out with a single write.
+pub fn encode_frame_with(
+    scratch: &mut Vec<u8>,
+    encode: impl FnOnce(&mut Vec<u8>),
+) -> Result<(), FrameError> {
+    scratch.clear();
+    scratch.extend_from_slice(&[0u8; FRAME_PREFIX_LEN]);
+    encode(scratch);
+
+    let prefix = prefix(scratch.len() - FRAME_PREFIX_LEN)?;
+    scratch[..FRAME_PREFIX_LEN].copy_from_slice(&prefix);
+    Ok(())
+}
+
+fn prefix(len: usize) -> Result<[u8; FRAME_PREFIX_LEN], FrameError> {
+    if len > MAX_FRAME_LEN {
+        return Err(FrameError::TooLarge {
+            len,
+            max: MAX_FRAME_LEN,
+        });
+    }
+    Ok((len as u32).to_be_bytes())
+}
+
+fn frame_len(prefix: [u8; FRAME_PREFIX_LEN], max: usize) -> Result<usize, FrameError> {
+    let len = u32::from_be_bytes(prefix) as usize;
+    if len > max {
+        return Err(FrameError::TooLarge { len, max });
+    }
+    Ok(len)
+}
+
+fn closed_at(filled: usize) -> FrameError {
+    if filled == 0 {
+        FrameError::Closed
+    } else {
+        FrameError::Io(io::Error::from(ErrorKind::UnexpectedEof))
+    }
+}
@@ -0,0 +1,890 @@
+use crate::codec::codec_error::CodecError;
+use crate::codec::wire::{WireDecode, WireEncode, WireReader, WireWriter, HEADER_LEN};
+use crate::transport::sequenced_message::{
+    EngineMessage, SequencedEngineMessage, SequencedHeartbeat, SequencedMessageRangeNack,
+};
+use crate::types::admin::{
+    AdminBookLevel, AdminCommand, AdminError, AdminRequest, AdminResponse, AdminResponseBody,
+    EngineStatus,
+};
+use crate::types::cancel_order::{
+    CancelOrderRequest, CancelOrderStatus, CancelledOrderAck, Reason,
+};
+use crate::types::engine::{EngineCommand, EngineError};
+use crate::types::execution_report::{ExecType, ExecutionReport, FillType};
+use crate::types::latency_trace::LatencyTrace;
+use crate::types::market_data::{
+    AddOrder, MarketDataBody, MarketDataMessage, OrderCancelled, OrderExecuted, OrderReplaced,
+    Trade, TradingState, TradingStatus,
+};
+use crate::types::order::{NewOrderAck, OrderReject, OrderRequest, RejectReason, TimeInForce};
+use crate::types::risk::{RiskLimitKind, RiskLimits};
+use crate::types::side::Side;
+
+pub const MSG_NEW_ORDER: u8 = 1;
+pub const MSG_NEW_ORDER_ACK: u8 = 2;
+pub const MSG_CANCEL_ORDER: u8 = 3;
+pub const MSG_CANCEL_ORDER_ACK: u8 = 4;
+pub const MSG_TRADE_EXECUTION: u8 = 5;
+pub const MSG_ORDER_REJECT: u8 = 6;
+pub const MSG_ENGINE_COMMAND: u8 = 7;
+pub const MSG_ENGINE_ERROR: u8 = 8;
+pub const MSG_ADMIN_REQUEST: u8 = 9;
+pub const MSG_ADMIN_RESPONSE: u8 = 10;
+pub const MSG_MARKET_DATA: u8 = 11;
+pub const MSG_SEQUENCED: u8 = 32;
+pub const MSG_RANGE_NACK: u8 = 33;
+pub const MSG_BATCH: u8 = 34;
+pub const MSG_HEARTBEAT: u8 = 35;
+
+/// Header plus the batch count, the smallest valid batch.
+pub const BATCH_PREFIX_LEN: usize = HEADER_LEN + 2;
+/// A batch of the longest sequenced message, the shortest datagram every message fits in. The
+/// codec tests check it against every message variant, a layout change that lengthens one
+/// updates it.
+pub const MAX_SINGLE_MESSAGE_BATCH_LEN: usize = 187;
+
+pub fn encode_engine_message(message: &EngineMessage, out: &mut Vec<u8>) {
+    message.encode(&mut WireWriter::new(out));
+}
+
+pub fn decode_engine_message(buf: &[u8]) -> Result<EngineMessage, CodecError> {
+    let mut reader = WireReader::new(buf);
+    let message = EngineMessage::decode(&mut reader)?;
+    reader.finish()?;
+    Ok(message)
+}
+
+pub fn encode_sequenced_message(message: &SequencedEngineMessage, out: &mut Vec<u8>) {
+    message.encode(&mut WireWriter::new(out));
+}
+
+pub fn decode_sequenced_message(buf: &[u8]) -> Result<SequencedEngineMessage, CodecError> {
+    let mut reader = WireReader::new(buf);
+    let message = SequencedEngineMessage::decode(&mut reader)?;
+    reader.finish()?;
+    Ok(message)
+}
+
+pub fn encode_range_nack(nack: &SequencedMessageRangeNack, out: &mut Vec<u8>) {
+    nack.encode(&mut WireWriter::new(out));
+}
+
+pub fn decode_range_nack(buf: &[u8]) -> Result<SequencedMessageRangeNack, CodecError> {
+    let mut reader = WireReader::new(buf);
+    let nack = SequencedMessageRangeNack::decode(&mut reader)?;
+    reader.finish()?;
+    Ok(nack)
+}
+
+pub fn encode_heartbeat(heartbeat: &SequencedHeartbeat, out: &mut Vec<u8>) {
+    heartbeat.encode(&mut WireWriter::new(out));
+}
+
+pub fn decode_heartbeat(buf: &[u8]) -> Result<SequencedHeartbeat, CodecError> {
+    let mut reader = WireReader::new(buf);
+    let heartbeat = SequencedHeartbeat::decode(&mut reader)?;
+    reader.finish()?;
+    Ok(heartbeat)
+}
+
+/// Overwrites `engine_publish_ns` in an encoded message that carries a [`LatencyTrace`], so the
+/// message need not be encoded again when its datagram goes out. The trace is the last field of
+/// every message that has one and `engine_publish_ns` the last field of the trace.
+pub fn stamp_engine_publish(encoded: &mut [u8], engine_publish_ns: u64) {
+    let len = encoded.len();
+    encoded[len - 8..].copy_from_slice(&engine_publish_ns.to_le_bytes());
+}
+
+/// What a datagram on a sequenced multicast stream carries.
+pub enum Datagram<'a> {
+    Batch(BatchReader<'a>),
+    Heartbeat(SequencedHeartbeat),
+}
+
+pub fn decode_datagram(buf: &[u8]) -> Result<Datagram<'_>, CodecError> {
+    match buf.first() {
+        Some(&MSG_HEARTBEAT) => decode_heartbeat(buf).map(Datagram::Heartbeat),
+        _ => decode_batch(buf).map(Datagram::Batch),
+    }
+}
+
+/// Starts a batch at the beginning of `out`, sequenced messages are appended after it.
+pub fn begin_batch(out: &mut Vec<u8>) {
+    out.clear();
+    let mut writer = WireWriter::new(out);
+    writer.begin_message(MSG_BATCH);
+    writer.u16(0);
+}
+
+/// Fills in the header length and message count of a batch started with [`begin_batch`].
+pub fn end_batch(out: &mut Vec<u8>, count: u16) {
+    out[HEADER_LEN..BATCH_PREFIX_LEN].copy_from_slice(&count.to_le_bytes());
+    WireWriter::new(out).end_message(0);
+}
+
+/// Validates the batch header, the messages are decoded lazily as the reader is iterated.
+pub fn decode_batch(buf: &[u8]) -> Result<BatchReader<'_>, CodecError> {
+    let mut reader = WireReader::new(buf);
+    let mut body = reader.expect_message(MSG_BATCH)?;
+    reader.finish()?;
+
+    let remaining = body.u16()?;
+    Ok(BatchReader { body, remaining })
+}
+
+pub struct BatchReader<'a> {
+    body: WireReader<'a>,
+    remaining: u16,
+}
+
+impl Iterator for BatchReader<'_> {
+    type Item = Result<SequencedEngineMessage, CodecError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.remaining == 0 {
+            // bytes past the declared count are reported once
+            let trailing = self.body.finish().err();
+            self.body = WireReader::new(&[]);
+            return trailing.map(Err);
+        }
+        self.remaining -= 1;
+
+        let message = SequencedEngineMessage::decode(&mut self.body);
+        if message.is_err() {
+            // the rest of the batch cannot be located
+            self.remaining = 0;
+            self.body = WireReader::new(&[]);
+        }
+        Some(message)
+    }
+}
+
+macro_rules! wire_enum {
+    ($ty:ty, $field:literal, { $($value:literal => $variant:path),+ $(,)? }) => {
+        impl WireEncode for $ty {
+            fn encode(&self, writer: &mut WireWriter) {
+                writer.u8(match self {
+                    $($variant => $value,)+
+                });
+            }
+        }
+
+        impl WireDecode for $ty {
+            fn decode(reader: &mut WireReader) -> Result<Self, CodecError> {
+                match reader.u8()? {
+                    $($value => Ok($variant),)+
+                    value => Err(CodecError::InvalidValue { field: $field, value }),
+                }
+            }
+        }
+    };
+}
+
+wire_enum!(Side, "side", { 0 => Side::Buy, 1 => Side::Sell });
+wire_enum!(TimeInForce, "time_in_force", {
+    0 => TimeInForce::GTC,
+    1 => TimeInForce::IOC,
+    2 => TimeInForce::FOK,
+});
+wire_enum!(RejectReason, "reject_reason", {
+    0 => RejectReason::InstrumentHalted,
+    1 => RejectReason::MaxOrderQty,
+    2 => RejectReason::MaxOrderNotional,
+    3 => RejectReason::MaxOpenOrders,
+    4 => RejectReason::MaxOpenNotional,
+    5 => RejectReason::PriceDeviation,
+    6 => RejectReason::MaxPosition,
+    7 => RejectReason::Throttled,
+    8 => RejectReason::InvalidOrder,
+    9 => RejectReason::UnknownClient,
+});
+wire_enum!(CancelOrderStatus, "cancel_order_status", {
+    0 => CancelOrderStatus::Cancelled,
+    1 => CancelOrderStatus::NotFound,
+    2 => CancelOrderStatus::Rejected,
+});
+wire_enum!(Reason, "cancel_reason", {
+    0 => Reason::ClientRequested,
+    1 => Reason::SelfMatchPrevention,
+    2 => Reason::AdminRequested,
+    3 => Reason::Throttled,
+    4 => Reason::NotOwner,
+    5 => Reason::InvalidCancel,
+});
+wire_enum!(ExecType, "exec_type", {
+    0 => ExecType::MatchEvent,
+    1 => ExecType::SelfMatchPrevented,
+});
+wire_enum!(FillType, "fill_type", {
+    0 => FillType::NoFill,
+    1 => FillType::PartialFill,
+    2 => FillType::FullFill,
+});
+wire_enum!(EngineCommand, "engine_command", {
+    0 => EngineCommand::Start,
+    1 => EngineCommand::Shutdown,
+});
+wire_enum!(EngineError, "engine_error", { 0 => EngineError::GeneralError });
+wire_enum!(AdminError, "admin_error", {
+    0 => AdminError::UnknownInstrument,
+    1 => AdminError::SnapshotNotConfigured,
+    2 => AdminError::SnapshotFailed,
+    3 => AdminError::UnsupportedCommand,
+});
+wire_enum!(RiskLimitKind, "risk_limit", {
+    0 => RiskLimitKind::MaxOrderQty,
+    1 => RiskLimitKind::MaxOrderNotional,
+    2 => RiskLimitKind::MaxOpenOrders,
+    3 => RiskLimitKind::MaxOpenNotional,
+    4 => RiskLimitKind::MaxPriceDeviationBps,
+    5 => RiskLimitKind::MaxPosition,
+});
+wire_enum!(TradingStatus, "trading_status", {
+    0 => TradingStatus::Trading,
+    1 => TradingStatus::Halted,
+});
+
+impl WireEncode for EngineMessage {
+    fn encode(&self, writer: &mut WireWriter) {
+        let (msg_type, body): (u8, &dyn WireEncode) = match self {
+            EngineMessage::NewOrder(order) => (MSG_NEW_ORDER, order),
+            EngineMessage::NewOrderAck(ack) => (MSG_NEW_ORDER_ACK, ack),
+            EngineMessage::CancelOrder(cancel) => (MSG_CANCEL_ORDER, cancel),
+            EngineMessage::CancelOrderAck(ack) => (MSG_CANCEL_ORDER_ACK, ack),
+            EngineMessage::TradeExecution(execution) => (MSG_TRADE_EXECUTION, execution),
+            EngineMessage::OrderReject(reject) => (MSG_ORDER_REJECT, reject),
+            EngineMessage::EngineCommand(command) => (MSG_ENGINE_COMMAND, command),
+            EngineMessage::EngineError(error) => (MSG_ENGINE_ERROR, error),
+            EngineMessage::AdminRequest(request) => (MSG_ADMIN_REQUEST, request),
+            EngineMessage::AdminResponse(response) => (MSG_ADMIN_RESPONSE, response),
+            EngineMessage::MarketData(market_data) => (MSG_MARKET_DATA, market_data),
+        };
+
+        let start = writer.begin_message(msg_type);
+        body.encode(writer);
+        writer.end_message(start);
+    }
+}
+
+impl WireDecode for EngineMessage {
+    fn decode(reader: &mut WireReader) -> Result<Self, CodecError> {
+        let (header, mut body) = reader.message()?;
+        let body = &mut body;
+
+        let message = match header.msg_type {
+            MSG_NEW_ORDER => EngineMessage::NewOrder(OrderRequest::decode(body)?),
+            MSG_NEW_ORDER_ACK => EngineMessage::NewOrderAck(NewOrderAck::decode(body)?),
+            MSG_CANCEL_ORDER => EngineMessage::CancelOrder(CancelOrderRequest::decode(body)?),
+            MSG_CANCEL_ORDER_ACK => EngineMessage::CancelOrderAck(CancelledOrderAck::decode(body)?),
+            MSG_TRADE_EXECUTION => EngineMessage::TradeExecution(ExecutionReport::decode(body)?),
+            MSG_ORDER_REJECT => EngineMessage::OrderReject(OrderReject::decode(body)?),
+            MSG_ENGINE_COMMAND => EngineMessage::EngineCommand(EngineCommand::decode(body)?),
+            MSG_ENGINE_ERROR => EngineMessage::EngineError(EngineError::decode(body)?),
+            MSG_ADMIN_REQUEST => EngineMessage::AdminRequest(AdminRequest::decode(body)?),
+            MSG_ADMIN_RESPONSE => EngineMessage::AdminResponse(AdminResponse::decode(body)?),
+            MSG_MARKET_DATA => EngineMessage::MarketData(MarketDataMessage::decode(body)?),
+            msg_type => return Err(CodecError::UnknownMessageType(msg_type)),
+        };
+
+        body.finish()?;
+        Ok(message)
+    }
+}
+
+impl WireEncode for SequencedEngineMessage {
+    fn encode(&self, writer: &mut WireWriter) {
+        let start = writer.begin_message(MSG_SEQUENCED);
+        writer.u16(self.sender_id);
+        writer.u64(self.epoch);
+        writer.u32(self.sequence_number);
+        writer.u64(self.sent_time);
+        self.message.encode(writer);
+        writer.end_message(start);
+    }
+}
+
+impl WireDecode for SequencedEngineMessage {
+    fn decode(reader: &mut WireReader) -> Result<Self, CodecError> {
+        let mut body = reader.expect_message(MSG_SEQUENCED)?;
+        let message = SequencedEngineMessage {
+            sender_id: body.u16()?,
+            epoch: body.u64()?,
+            sequence_number: body.u32()?,
+            sent_time: body.u64()?,
+            message: EngineMessage::decode(&mut body)?,
+        };
+        body.finish()?;
+        Ok(message)
+    }
+}
+
+impl WireEncode for SequencedHeartbeat {
+    fn encode(&self, writer: &mut WireWriter) {
+        let start = writer.begin_message(MSG_HEARTBEAT);
+        writer.u16(self.sender_id);
+        writer.u64(self.epoch);
+        writer.u32(self.sequence_number);
+        writer.u64(self.watermark_ns);
+        writer.end_message(start);
+    }
+}
+
+impl WireDecode for SequencedHeartbeat {
+    fn decode(reader: &mut WireReader) -> Result<Self, CodecError> {
+        let mut body = reader.expect_message(MSG_HEARTBEAT)?;
+        let heartbeat = SequencedHeartbeat {
+            sender_id: body.u16()?,
+            epoch: body.u64()?,
+            sequence_number: body.u32()?,
+            watermark_ns: body.u64()?,
+        };
+        body.finish()?;
+        Ok(heartbeat)
+    }
+}
+
+impl WireEncode for SequencedMessageRangeNack {
+    fn encode(&self, writer: &mut WireWriter) {
+        let start = writer.begin_message(MSG_RANGE_NACK);
+        writer.u16(self.sender_id);
+        writer.u64(self.epoch);
+        writer.u32(self.start);
+        writer.u32(self.end);
+        writer.end_message(start);
+    }
+}
+
+impl WireDecode for SequencedMessageRangeNack {
+    fn decode(reader: &mut WireReader) -> Result<Self, CodecError> {
+        let mut body = reader.expect_message(MSG_RANGE_NACK)?;
+        let nack = SequencedMessageRangeNack {
+            sender_id: body.u16()?,
+            epoch: body.u64()?,
+            start: body.u32()?,
+            end: body.u32()?,
+        };
+        body.finish()?;
+        Ok(nack)
+    }
+}
+
+impl WireEncode for LatencyTrace {
+    fn encode(&self, writer: &mut WireWriter) {
+        writer.u64(self.oe_api_recv_ns);
+        writer.u64(self.gateway_recv_ns);
+        writer.u64(self.gateway_send_ns);
+        writer.u64(self.engine_recv_ns);
+        writer.u64(self.match_ns);
+        writer.u64(self.engine_batch_ns);
+        writer.u64(self.engine_publish_ns);
+    }
+}
+
+impl WireDecode for LatencyTrace {
+    fn decode(reader: &mut WireReader) -> Result<Self, CodecError> {
+        Ok(LatencyTrace {
+            oe_api_recv_ns: reader.u64()?,
+            gateway_recv_ns: reader.u64()?,
+            gateway_send_ns: reader.u64()?,
+            engine_recv_ns: reader.u64()?,
+            match_ns: reader.u64()?,
+            engine_batch_ns: reader.u64()?,
+            engine_publish_ns: reader.u64()?,
+        })
+    }
+}
+
+impl WireEncode for OrderRequest {
+    fn encode(&self, writer: &mut WireWriter) {
+        writer.u32(self.client_id);
+        writer.u64(self.client_order_id);
+        writer.bytes(&self.instrument);
+        self.order_side.encode(writer);
+        writer.u64(self.px);
+        writer.u64(self.qty);
+        self.time_in_force.encode(writer);
+        writer.u64(self.timestamp);
+        self.trace.encode(writer);
+    }
+}
+
+impl WireDecode for OrderRequest {
+    fn decode(reader: &mut WireReader) -> Result<Self, CodecError> {
+        Ok(OrderRequest {
+            client_id: reader.u32()?,
+            client_order_id: reader.u64()?,
+            instrument: reader.bytes()?,
+            order_side: Side::decode(reader)?,
+            px: reader.u64()?,
+            qty: reader.u64()?,
+            time_in_force: TimeInForce::decode(reader)?,
+            timestamp: reader.u64()?,
+            trace: LatencyTrace::decode(reader)?,
+        })
+    }
+}
+
+impl WireEncode for NewOrderAck {
+    fn encode(&self, writer: &mut WireWriter) {
+        writer.u32(self.client_id);
+        writer.u64(self.client_order_id);
+        writer.u32(self.order_id);
+        writer.bytes(&self.instrument);
+        self.side.encode(writer);
+        writer.u64(self.px);
+        writer.u64(self.qty);
+        writer.u64(self.qty_rem);
+        self.time_in_force.encode(writer);
+        writer.u64(self.ack_time);
+        self.trace.encode(writer);
+    }
+}
+
+impl WireDecode for NewOrderAck {
+    fn decode(reader: &mut WireReader) -> Result<Self, CodecError> {
+        Ok(NewOrderAck {
+            client_id: reader.u32()?,
+            client_order_id: reader.u64()?,
+            order_id: reader.u32()?,
+            instrument: reader.bytes()?,
+            side: Side::decode(reader)?,
+            px: reader.u64()?,
+            qty: reader.u64()?,
+            qty_rem: reader.u64()?,
+            time_in_force: TimeInForce::decode(reader)?,
+            ack_time: reader.u64()?,
+            trace: LatencyTrace::decode(reader)?,
+        })
+    }
+}
+
+impl WireEncode for OrderReject {
+    fn encode(&self, writer: &mut WireWriter) {
+        writer.u32(self.client_id);
+        writer.u64(self.client_order_id);
+        writer.bytes(&self.instrument);
+        self.side.encode(writer);
+        writer.u64(self.px);
+        writer.u64(self.qty);
+        self.reason.encode(writer);
+        writer.u64(self.reject_time);
+        self.trace.encode(writer);
+    }
+}
+
+impl WireDecode for OrderReject {
+    fn decode(reader: &mut WireReader) -> Result<Self, CodecError> {
+        Ok(OrderReject {
+            client_id: reader.u32()?,
+            client_order_id: reader.u64()?,
+            instrument: reader.bytes()?,
+            side: Side::decode(reader)?,
+            px: reader.u64()?,
+            qty: reader.u64()?,
+            reason: RejectReason::decode(reader)?,
+            reject_time: reader.u64()?,
+            trace: LatencyTrace::decode(reader)?,
+        })
+    }
+}
+
+impl WireEncode for CancelOrderRequest {
+    fn encode(&self, writer: &mut WireWriter) {
+        writer.u32(self.client_id);
+        self.order_side.encode(writer);
+        writer.u32(self.order_id);
+        writer.bytes(&self.instrument);
+        self.trace.encode(writer);
+    }
+}
+
+impl WireDecode for CancelOrderRequest {
+    fn decode(reader: &mut WireReader) -> Result<Self, CodecError> {
+        Ok(CancelOrderRequest {
+            client_id: reader.u32()?,
+            order_side: Side::decode(reader)?,
+            order_id: reader.u32()?,
+            instrument: reader.bytes()?,
+            trace: LatencyTrace::decode(reader)?,
+        })
+    }
+}
+
+impl WireEncode for CancelledOrderAck {
+    fn encode(&self, writer: &mut WireWriter) {
+        writer.u32(self.client_id);
+        writer.u32(self.order_id);
+        writer.bytes(&self.instrument);
+        self.cancel_order_status.encode(writer);
+        self.reason.encode(writer);
+        writer.u64(self.ack_time);
+        self.trace.encode(writer);
+    }
+}
+
+impl WireDecode for CancelledOrderAck {
+    fn decode(reader: &mut WireReader) -> Result<Self, CodecError> {
+        Ok(CancelledOrderAck {
+            client_id: reader.u32()?,
+            order_id: reader.u32()?,
+            instrument: reader.bytes()?,
+            cancel_order_status: CancelOrderStatus::decode(reader)?,
+            reason: Reason::decode(reader)?,
+            ack_time: reader.u64()?,
+            trace: LatencyTrace::decode(reader)?,
+        })
+    }
+}
+
+impl WireEncode for ExecutionReport {
+    fn encode(&self, writer: &mut WireWriter) {
+        writer.u32(self.trade_id);
+        writer.u32(self.bid_client_id);
+        writer.u32(self.bid_order_id);
+        writer.u64(self.bid_client_order_id);
+        writer.u64(self.bid_order_px);
+        self.bid_fill_type.encode(writer);
+        writer.u32(self.ask_client_id);
+        writer.u32(self.ask_order_id);
+        writer.u64(self.ask_client_order_id);
+        writer.u64(self.ask_order_px);
+        self.ask_fill_type.encode(writer);
+        writer.bytes(&self.instrument);
+        writer.u64(self.exec_px);
+        writer.u64(self.exec_qty);
+        self.exec_type.encode(writer);
+        writer.u64(self.exec_ns);
+        self.trace.encode(writer);
+    }
+}
+
+impl WireDecode for ExecutionReport {
+    fn decode(reader: &mut WireReader) -> Result<Self, CodecError> {
+        Ok(ExecutionReport {
+            trade_id: reader.u32()?,
+            bid_client_id: reader.u32()?,
+            bid_order_id: reader.u32()?,
+            bid_client_order_id: reader.u64()?,
+            bid_order_px: reader.u64()?,
+            bid_fill_type: FillType::decode(reader)?,
+            ask_client_id: reader.u32()?,
+            ask_order_id: reader.u32()?,
+            ask_client_order_id: reader.u64()?,
+            ask_order_px: reader.u64()?,
+            ask_fill_type: FillType::decode(reader)?,
+            instrument: reader.bytes()?,
+            exec_px: reader.u64()?,
+            exec_qty: reader.u64()?,
+            exec_type: ExecType::decode(reader)?,
+            exec_ns: reader.u64()?,
+            trace: LatencyTrace::decode(reader)?,
+        })
+    }
+}
+
+impl WireEncode for AdminCommand {
+    fn encode(&self, writer: &mut WireWriter) {
+        match self {
+            AdminCommand::Status => writer.u8(0),
+            AdminCommand::HaltInstrument => writer.u8(1),
+            AdminCommand::ResumeInstrument => writer.u8(2),
+            AdminCommand::DumpBook => writer.u8(3),
+            AdminCommand::CancelClientOrders(client_id) => {
+                writer.u8(4);
+                writer.u32(*client_id);
+            }
+            AdminCommand::Snapshot => writer.u8(5),
+            AdminCommand::GetRiskLimits => writer.u8(6),
+            AdminCommand::SetRiskLimit(limit, value) => {
+                writer.u8(7);
+                limit.encode(writer);
+                writer.u64(*value);
+            }
+            AdminCommand::Shutdown => writer.u8(8),
+        }
+    }
+}
+
+impl WireDecode for AdminCommand {
+    fn decode(reader: &mut WireReader) -> Result<Self, CodecError> {
+        Ok(match reader.u8()? {
+            0 => AdminCommand::Status,
+            1 => AdminCommand::HaltInstrument,
+            2 => AdminCommand::ResumeInstrument,
+            3 => AdminCommand::DumpBook,
+            4 => AdminCommand::CancelClientOrders(reader.u32()?),
+            5 => AdminCommand::Snapshot,
+            6 => AdminCommand::GetRiskLimits,
+            7 => AdminCommand::SetRiskLimit(RiskLimitKind::decode(reader)?, reader.u64()?),
+            8 => AdminCommand::Shutdown,
+            value => {
+                return Err(CodecError::InvalidValue {
+                    field: "admin_command",
+                    value,
+                })
+            }
+        })
+    }
+}
+
+impl WireEncode for AdminRequest {
+    fn encode(&self, writer: &mut WireWriter) {
+        writer.u32(self.request_id);
+        writer.bytes(&self.instrument);
+        self.command.encode(writer);
+    }
+}
+
+impl WireDecode for AdminRequest {
+    fn decode(reader: &mut WireReader) -> Result<Self, CodecError> {
+        Ok(AdminRequest {
+            request_id: reader.u32()?,
+            instrument: reader.bytes()?,
+            command: AdminCommand::decode(reader)?,
+        })
+    }
+}
+
+impl WireEncode for AdminResponse {
+    fn encode(&self, writer: &mut WireWriter) {
+        writer.u32(self.request_id);
+        writer.bool(self.last);
+        self.body.encode(writer);
+    }
+}
+
+impl WireDecode for AdminResponse {
+    fn decode(reader: &mut WireReader) -> Result<Self, CodecError> {
+        Ok(AdminResponse {
+            request_id: reader.u32()?,
+            last: reader.bool("last")?,
+            body: AdminResponseBody::decode(reader)?,
+        })
+    }
+}
+
+impl WireEncode for AdminResponseBody {
+    fn encode(&self, writer: &mut WireWriter) {
+        match self {
+            AdminResponseBody::Done => writer.u8(0),
+            AdminResponseBody::Error(error) => {
+                writer.u8(1);
+                error.encode(writer);
+            }
+            AdminResponseBody::Status(status) => {
+                writer.u8(2);
+                status.encode(writer);
+            }
+            AdminResponseBody::BookLevel(level) => {
+                writer.u8(3);
+                level.encode(writer);
+            }
+            AdminResponseBody::OrdersCancelled(cancelled) => {
+                writer.u8(4);
+                writer.u32(*cancelled);
+            }
+            AdminResponseBody::RiskLimits(limits) => {
+                writer.u8(5);
+                limits.encode(writer);
+            }
+        }
+    }
+}
+
+impl WireDecode for AdminResponseBody {
+    fn decode(reader: &mut WireReader) -> Result<Self, CodecError> {
+        Ok(match reader.u8()? {
+            0 => AdminResponseBody::Done,
+            1 => AdminResponseBody::Error(AdminError::decode(reader)?),
+            2 => AdminResponseBody::Status(EngineStatus::decode(reader)?),
+            3 => AdminResponseBody::BookLevel(AdminBookLevel::decode(reader)?),
+            4 => AdminResponseBody::OrdersCancelled(reader.u32()?),
+            5 => AdminResponseBody::RiskLimits(RiskLimits::decode(reader)?),
+            value => {
+                return Err(CodecError::InvalidValue {
+                    field: "admin_response",
+                    value,
+                })
+            }
+        })
+    }
+}
+
+impl WireEncode for EngineStatus {
+    fn encode(&self, writer: &mut WireWriter) {
+        writer.bytes(&self.instrument);
+        writer.bool(self.halted);
+        writer.u32(self.orders_on_book);
+        writer.u32(self.bid_levels);
+        writer.u32(self.ask_levels);
+        writer.u64(self.best_bid);
+        writer.u64(self.best_ask);
+        writer.u64(self.orders_processed);
+        writer.u64(self.executions);
+    }
+}
+
+impl WireDecode for EngineStatus {
+    fn decode(reader: &mut WireReader) -> Result<Self, CodecError> {
+        Ok(EngineStatus {
+            instrument: reader.bytes()?,
+            halted: reader.bool("halted")?,
+            orders_on_book: reader.u32()?,
+            bid_levels: reader.u32()?,
+            ask_levels: reader.u32()?,
+            best_bid: reader.u64()?,
+            best_ask: reader.u64()?,
+            orders_processed: reader.u64()?,
+            executions: reader.u64()?,
+        })
+    }
+}
+
+impl WireEncode for AdminBookLevel {
+    fn encode(&self, writer: &mut WireWriter) {
+        self.side.encode(writer);
+        writer.u64(self.px);
+        writer.u64(self.qty);
+        writer.u32(self.num_orders);
+    }
+}
+
+impl WireDecode for AdminBookLevel {
+    fn decode(reader: &mut WireReader) -> Result<Self, CodecError> {
+        Ok(AdminBookLevel {
+            side: Side::decode(reader)?,
+            px: reader.u64()?,
+            qty: reader.u64()?,
+            num_orders: reader.u32()?,
+        })
+    }
+}
+
+impl WireEncode for RiskLimits {
+    fn encode(&self, writer: &mut WireWriter) {
+        writer.u64(self.max_order_qty);
+        writer.u64(self.max_order_notional);
+        writer.u64(self.max_open_orders);
+        writer.u64(self.max_open_notional);
+        writer.u64(self.max_price_deviation_bps);
+        writer.u64(self.max_position);
+    }
+}
+
+impl WireDecode for RiskLimits {
+    fn decode(reader: &mut WireReader) -> Result<Self, CodecError> {
+        Ok(RiskLimits {
+            max_order_qty: reader.u64()?,
+            max_order_notional: reader.u64()?,
+            max_open_orders: reader.u64()?,
+            max_open_notional: reader.u64()?,
+            max_price_deviation_bps: reader.u64()?,
+            max_position: reader.u64()?,
+        })
+    }
+}
+
+impl WireEncode for MarketDataMessage {
+    fn encode(&self, writer: &mut WireWriter) {
+        writer.u32(self.instrument_id);
+        writer.u64(self.timestamp);
+        self.body.encode(writer);
+    }
+}
+
+impl WireDecode for MarketDataMessage {
+    fn decode(reader: &mut WireReader) -> Result<Self, CodecError> {
+        Ok(MarketDataMessage {
+            instrument_id: reader.u32()?,
+            timestamp: reader.u64()?,
+            body: MarketDataBody::decode(reader)?,
+        })
+    }
+}
+
+impl WireEncode for MarketDataBody {
+    fn encode(&self, writer: &mut WireWriter) {
+        match self {
+            MarketDataBody::AddOrder(add) => {
+                writer.u8(0);
+                writer.u32(add.order_id);
+                add.side.encode(writer);
+                writer.u64(add.px);
+                writer.u64(add.qty);
+            }
+            MarketDataBody::OrderExecuted(executed) => {
+                writer.u8(1);
+                writer.u32(executed.order_id);
+                writer.u64(executed.executed_qty);
+                writer.u64(executed.match_id);
+            }
+            MarketDataBody::OrderCancelled(cancelled) => {
+                writer.u8(2);
+                writer.u32(cancelled.order_id);
+                writer.u64(cancelled.cancelled_qty);
+            }
+            MarketDataBody::OrderReplaced(replaced) => {
+                writer.u8(3);
+                writer.u32(replaced.orig_order_id);
+                writer.u32(replaced.order_id);
+                writer.u64(replaced.px);
+                writer.u64(replaced.qty);
+            }
+            MarketDataBody::Trade(trade) => {
+                writer.u8(4);
+                writer.u64(trade.match_id);
+                trade.side.encode(writer);
+                writer.u64(trade.px);
+                writer.u64(trade.qty);
+            }
+            MarketDataBody::TradingState(trading_state) => {
+                writer.u8(5);
+                trading_state.state.encode(writer);
+            }
+        }
+    }
+}
+
+impl WireDecode for MarketDataBody {
+    fn decode(reader: &mut WireReader) -> Result<Self, CodecError> {
+        Ok(match reader.u8()? {
+            0 => MarketDataBody::AddOrder(AddOrder {
+                order_id: reader.u32()?,
+                side: Side::decode(reader)?,
+                px: reader.u64()?,
+                qty: reader.u64()?,
+            }),
+            1 => MarketDataBody::OrderExecuted(OrderExecuted {
+                order_id: reader.u32()?,
+                executed_qty: reader.u64()?,
+                match_id: reader.u64()?,
+            }),
+            2 => MarketDataBody::OrderCancelled(OrderCancelled {
+                order_id: reader.u32()?,
+                cancelled_qty: reader.u64()?,
+            }),
+            3 => MarketDataBody::OrderReplaced(OrderReplaced {
+                orig_order_id: reader.u32()?,
+                order_id: reader.u32()?,
+                px: reader.u64()?,
+                qty: reader.u64()?,
+            }),
+            4 => MarketDataBody::Trade(Trade {
+                match_id: reader.u64()?,
+                side: Side::decode(reader)?,
+                px: reader.u64()?,
+                qty: reader.u64()?,
+            }),
+            5 => MarketDataBody::TradingState(TradingState {
+                state: TradingStatus::decode(reader)?,
+            }),
+            value => {
+                return Err(CodecError::InvalidValue {
+                    field: "market_data",
+                    value,
+                })
+            }
+        })
+    }
+}
@@ -0,0 +1,5 @@
+pub mod codec_error;
+pub mod frame_error;
+pub mod framing;
+pub mod message_codec;
+pub mod wire;
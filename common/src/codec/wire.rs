@@ -0,0 +1,172 @@
+use crate::codec::codec_error::CodecError;
+
+pub const WIRE_VERSION: u8 = 5;
+/// Message type (u8), wire version (u8) and body length (u16), all little endian.
+pub const HEADER_LEN: usize = 4;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MessageHeader {
+    pub msg_type: u8,
+    pub version: u8,
+    pub length: u16,
+}
+
+pub trait WireEncode {
+    fn encode(&self, writer: &mut WireWriter);
+}
+
+pub trait WireDecode: Sized {
+    fn decode(reader: &mut WireReader) -> Result<Self, CodecError>;
+}
+
+/// Bounds checked cursor over a borrowed buffer, fields are read in place.
+pub struct WireReader<'a> {
+    buf: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> WireReader<'a> {
+    pub fn new(buf: &'a [u8]) -> Self {
+        WireReader { buf, pos: 0 }
+    }
+
+    pub fn position(&self) -> usize {
+        self.pos
+    }
+
+    pub fn remaining(&self) -> usize {
+        self.buf.len() - self.pos
+    }
+
+    pub fn slice(&mut self, len: usize) -> Result<&'a [u8], CodecError> {
+        if len > self.remaining() {
+            return Err(CodecError::Truncated {
+                needed: len,
+                remaining: self.remaining(),
+            });
+        }
+
+        let slice = &self.buf[self.pos..self.pos + len];
+        self.pos += len;
+        Ok(slice)
+    }
+
+    pub fn bytes<const N: usize>(&mut self) -> Result<[u8; N], CodecError> {
+        let mut bytes = [0u8; N];
+        bytes.copy_from_slice(self.slice(N)?);
+        Ok(bytes)
+    }
+
+    pub fn u8(&mut self) -> Result<u8, CodecError> {
+        Ok(self.bytes::<1>()?[0])
+    }
+
+    pub fn u16(&mut self) -> Result<u16, CodecError> {
+        Ok(u16::from_le_bytes(self.bytes()?))
+    }
+
+    pub fn u32(&mut self) -> Result<u32, CodecError> {
+        Ok(u32::from_le_bytes(self.bytes()?))
+    }
+
+    pub fn u64(&mut self) -> Result<u64, CodecError> {
+        Ok(u64::from_le_bytes(self.bytes()?))
+    }
+
+    pub fn bool(&mut self, field: &'static str) -> Result<bool, CodecError> {
+        match self.u8()? {
+            0 => Ok(false),
+            1 => Ok(true),
+            value => Err(CodecError::InvalidValue { field, value }),
+        }
+    }
+
+    /// Reads a header and returns a reader limited to the body it declares.
+    pub fn message(&mut self) -> Result<(MessageHeader, WireReader<'a>), CodecError> {
+        let header = MessageHeader {
+            msg_type: self.u8()?,
+            version: self.u8()?,
+            length: self.u16()?,
+        };
+
+        if header.version != WIRE_VERSION {
+            return Err(CodecError::UnsupportedVersion(header.version));
+        }
+
+        let body = self.slice(header.length as usize)?;
+        Ok((header, WireReader::new(body)))
+    }
+
+    /// Like [`WireReader::message`] but rejects any other message type.
+    pub fn expect_message(&mut self, msg_type: u8) -> Result<WireReader<'a>, CodecError> {
+        let (header, body) = self.message()?;
+        if header.msg_type != msg_type {
+            return Err(CodecError::UnexpectedMessageType {
+                expected: msg_type,
+                found: header.msg_type,
+            });
+        }
+        Ok(body)
+    }
+
+    /// Fails unless every byte of the buffer has been consumed.
+    pub fn finish(&self) -> Result<(), CodecError> {
+        if self.remaining() != 0 {
+            return Err(CodecError::LengthMismatch {
+                declared: self.buf.len(),
+                actual: self.pos,
+            });
+        }
+        Ok(())
+    }
+}
+
+/// Appends little endian fields to a caller owned buffer, reuse the buffer to avoid allocating.
+pub struct WireWriter<'a> {
+    buf: &'a mut Vec<u8>,
+}
+
+impl<'a> WireWriter<'a> {
+    pub fn new(buf: &'a mut Vec<u8>) -> Self {
+        WireWriter { buf }
+    }
+
+    pub fn bytes(&mut self, bytes: &[u8]) {
+        self.buf.extend_from_slice(bytes);
+    }
+
+    pub fn u8(&mut self, value: u8) {
+        self.buf.push(value);
+    }
+
+    pub fn u16(&mut self, value: u16) {
+        self.bytes(&value.to_le_bytes());
+    }
+
+    pub fn u32(&mut self, value: u32) {
+        self.bytes(&value.to_le_bytes());
+    }
+
+    pub fn u64(&mut self, value: u64) {
+        self.bytes(&value.to_le_bytes());
+    }
+
+    pub fn bool(&mut self, value: bool) {
+        self.u8(value as u8);
+    }
+
+    /// Writes a header with a placeholder length, returns its offset for [`WireWriter::end_message`].
+    pub fn begin_message(&mut self, msg_type: u8) -> usize {
+        let start = self.buf.len();
+        self.u8(msg_type);
+        self.u8(WIRE_VERSION);
+        self.u16(0);
+        start
+    }
+
+    pub fn end_message(&mut self, start: usize) {
+        let length = self.buf.len() - start - HEADER_LEN;
+        let length = u16::try_from(length).expect("message body exceeds u16 length");
+        self.buf[start + 2..start + HEADER_LEN].copy_from_slice(&length.to_le_bytes());
+    }
+}
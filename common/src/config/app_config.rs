@@ -0,0 +1,904 @@
+use crate::codec::message_codec::MAX_SINGLE_MESSAGE_BATCH_LEN;
+use crate::config::config_error::ConfigError;
+use crate::network::mutlicast::DEFAULT_BUSY_POLL_MICROS;
+use crate::network::network_constants::{IP_UDP_HEADER_LEN, MAX_UDP_PACKET_SIZE, MIN_MTU};
+use crate::session::session_message::CREDENTIAL_LEN;
+use crate::transport::sequenced_message::{SenderId, SequenceNumber};
+use crate::transport::transport_constants::MAX_SENDERS;
+use crate::types::fixed_point::{FixedPointScale, MAX_DECIMALS};
+use crate::types::risk::RiskLimits;
+use crate::util::idle_strategy::IdleStrategy;
+use crate::util::throttle::ThrottleConfig;
+use serde::Deserialize;
+use std::collections::HashMap;
+
+/// Typed view over the shared JSON config, one section per binary.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct AppConfig {
+    pub engine: EngineSection,
+    #[serde(default)]
+    pub gateway: GatewaySection,
+    #[serde(rename = "oe-api", default)]
+    pub oe_api: OeApiSection,
+    #[serde(rename = "fix-acceptor", default)]
+    pub fix_acceptor: FixAcceptorSection,
+    #[serde(rename = "market-data", default)]
+    pub market_data: MarketDataSection,
+    #[serde(default)]
+    pub recorder: RecorderSection,
+    #[serde(rename = "position-keeper", default)]
+    pub position_keeper: PositionKeeperSection,
+    #[serde(default)]
+    pub transport: TransportSection,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+pub enum MatchStrategyKind {
+    #[serde(rename = "FIFO")]
+    Fifo,
+    #[serde(rename = "PRO_RATA")]
+    ProRata,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct EngineSection {
+    pub msg_in_port: u16,
+    pub msg_out_port: u16,
+    #[serde(default = "default_metrics_port")]
+    pub metrics_port: u16,
+    pub instrument: String,
+    pub instrument_id: u32,
+    /// Decimal places of the instrument's prices and quantities.
+    #[serde(default)]
+    pub scale: FixedPointScale,
+    pub match_strategy: MatchStrategyKind,
+    #[serde(default)]
+    pub snapshot_path: Option<String>,
+    #[serde(default)]
+    pub persistence: PersistenceSection,
+    #[serde(default)]
+    pub threads: EngineThreads,
+}
+
+/// Placement and polling behaviour of one worker thread. Unpinned with a backoff idle by default,
+/// which is safe on a small or shared box; pin and spin only on isolated cores.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields, default)]
+pub struct ThreadConfig {
+    pub core: Option<usize>,
+    pub idle: IdleStrategy,
+    /// SO_BUSY_POLL on the multicast socket this thread reads, 0 to disable.
+    pub busy_poll_micros: u32,
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(deny_unknown_fields, default)]
+pub struct EngineThreads {
+    #[serde(rename = "match")]
+    pub match_thread: ThreadConfig,
+    pub msg_in: ThreadConfig,
+    pub msg_out: ThreadConfig,
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(deny_unknown_fields, default)]
+pub struct GatewayThreads {
+    pub msg_in: ThreadConfig,
+    pub msg_out: ThreadConfig,
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(deny_unknown_fields, default)]
+pub struct MarketDataThreads {
+    pub msg_out: ThreadConfig,
+    pub feed: ThreadConfig,
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(deny_unknown_fields, default)]
+pub struct MsgOutThreads {
+    pub msg_out: ThreadConfig,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields, default)]
+pub struct PersistenceSection {
+    pub host: String,
+    pub db: String,
+    pub user: String,
+    pub password: String,
+    pub order_table: String,
+    pub execution_table: String,
+}
+
+impl PersistenceSection {
+    pub fn database_url(&self) -> String {
+        format!(
+            "postgres://{}:{}@{}/{}",
+            self.user, self.password, self.host, self.db
+        )
+    }
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields, default)]
+pub struct GatewaySection {
+    pub port: u16,
+    pub admin_port: u16,
+    /// Binary OUCH order entry, see the README for the message layouts.
+    pub ouch_port: u16,
+    pub metrics_port: u16,
+    pub msg_in_port: u16,
+    pub msg_out_port: u16,
+    /// Sender id of this gateway's orders on the engine's msg_in stream, each gateway feeding the
+    /// engine needs its own.
+    pub sender_id: SenderId,
+    pub risk: RiskLimits,
+    pub throttle: ThrottleConfig,
+    /// Base url of the position keeper API, e.g. `http://127.0.0.1:7100`. Positions are not
+    /// checked when unset.
+    pub position_keeper_url: Option<String>,
+    pub position_poll_millis: u64,
+    pub threads: GatewayThreads,
+    /// Who may log on to the client and OUCH ports.
+    pub users: Vec<GatewayUser>,
+    /// SequencedData kept per session for replay when the user logs on again.
+    pub retransmit_len: usize,
+    pub drop_copy: DropCopySection,
+}
+
+/// Read only copies of every firm's order state changes and executions, see the README.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields, default)]
+pub struct DropCopySection {
+    pub port: u16,
+    /// Who may log on, each user only receives the copies of its own firm.
+    pub users: Vec<DropCopyUser>,
+    /// Copies kept per firm for replay.
+    pub retained: usize,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct DropCopyUser {
+    pub username: String,
+    pub password: String,
+    pub firm: String,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct GatewayUser {
+    pub username: String,
+    pub password: String,
+    pub firm: String,
+    /// Client id stamped on the session's orders and cancels.
+    pub client_id: u32,
+    /// In-house order routers (oe-api, fix-acceptor) that enter orders for their own clients, the
+    /// client ids on their messages are kept.
+    #[serde(default)]
+    pub trusted: bool,
+    /// The client ids reserved for a trusted user's clients, required when `trusted`.
+    #[serde(default)]
+    pub client_ids: Option<ClientIdRange>,
+    /// The session also receives the acks, rejects and executions of every client of its firm.
+    #[serde(default)]
+    pub drop_copy: bool,
+}
+
+/// An inclusive range of client ids.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ClientIdRange {
+    pub first: u32,
+    pub last: u32,
+}
+
+impl ClientIdRange {
+    pub fn contains(&self, client_id: u32) -> bool {
+        (self.first..=self.last).contains(&client_id)
+    }
+
+    fn overlaps(&self, other: &ClientIdRange) -> bool {
+        self.first <= other.last && other.first <= self.last
+    }
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields, default)]
+pub struct OeApiSection {
+    pub port: u16,
+    pub metrics_port: u16,
+    pub gateway_host: String,
+    pub gateway_port: u16,
+    pub gateway_username: String,
+    pub gateway_password: String,
+    pub throttle: ThrottleConfig,
+    /// Who may log on to the WebSocket API.
+    pub users: Vec<OeApiUser>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct OeApiUser {
+    pub username: String,
+    pub password: String,
+    /// Client id the user's orders and cancels are entered under.
+    pub client_id: u32,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields, default)]
+pub struct FixAcceptorSection {
+    pub port: u16,
+    pub gateway_host: String,
+    pub gateway_port: u16,
+    pub gateway_username: String,
+    pub gateway_password: String,
+    /// CompID the acceptor sends as SenderCompID(49).
+    pub comp_id: String,
+    /// Directory holding each session's sequence numbers and sent messages.
+    pub store_dir: String,
+    pub sessions: Vec<FixSessionConfig>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct FixSessionConfig {
+    /// The counterparty's SenderCompID(49).
+    pub sender_comp_id: String,
+    /// Client id the session's orders are entered under.
+    pub client_id: u32,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields, default)]
+pub struct MarketDataSection {
+    pub port: u16,
+    /// Multicast port of the order-by-order feed.
+    pub feed_port: u16,
+    /// Port the feed's sender listens for NACKs on.
+    pub feed_nack_port: u16,
+    pub threads: MarketDataThreads,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields, default)]
+pub struct RecorderSection {
+    pub journal_path: String,
+    pub threads: MsgOutThreads,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields, default)]
+pub struct PositionKeeperSection {
+    pub port: u16,
+    pub threads: MsgOutThreads,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields, default)]
+pub struct TransportSection {
+    /// Port the engine msg_in stream's sender listens for NACKs on.
+    pub msg_in_nack_port: u16,
+    /// Port the engine msg_out stream's sender listens for NACKs on.
+    pub msg_out_nack_port: u16,
+    /// Messages a receiver buffers for each sender it hears from, allocated on first contact. A
+    /// receiver falling further behind a sender has to NACK what it overwrote.
+    pub receive_ring_len: usize,
+    /// Messages a sender keeps for retransmission, NACKs for anything older go unanswered.
+    pub retransmit_ring_len: usize,
+    pub batching: BatchingConfig,
+}
+
+/// How multicast senders batch messages into datagrams. A batch goes out once it holds
+/// `batch_size` messages, once its first message is `max_flush_delay_micros` old, or as soon as
+/// the sending thread runs out of messages.
+#[derive(Debug, Clone, Copy, Deserialize)]
+#[serde(deny_unknown_fields, default)]
+pub struct BatchingConfig {
+    pub batch_size: u16,
+    pub max_flush_delay_micros: u64,
+    /// MTU of the multicast interface, batches are cut short rather than sent as fragmented
+    /// datagrams.
+    pub mtu: u16,
+}
+
+impl BatchingConfig {
+    pub fn max_datagram_len(&self) -> usize {
+        (self.mtu as usize).saturating_sub(IP_UDP_HEADER_LEN)
+    }
+}
+
+fn default_metrics_port() -> u16 {
+    9100
+}
+
+impl Default for ThreadConfig {
+    fn default() -> Self {
+        ThreadConfig {
+            core: None,
+            idle: IdleStrategy::default(),
+            busy_poll_micros: DEFAULT_BUSY_POLL_MICROS,
+        }
+    }
+}
+
+impl Default for PersistenceSection {
+    fn default() -> Self {
+        PersistenceSection {
+            host: "localhost".to_owned(),
+            db: "engine".to_owned(),
+            user: "root".to_owned(),
+            password: "password".to_owned(),
+            order_table: "orders".to_owned(),
+            execution_table: "trades".to_owned(),
+        }
+    }
+}
+
+impl Default for GatewaySection {
+    fn default() -> Self {
+        GatewaySection {
+            port: 3001,
+            admin_port: 3002,
+            ouch_port: 3003,
+            metrics_port: 9101,
+            msg_in_port: 3000,
+            msg_out_port: 3500,
+            sender_id: 1,
+            risk: RiskLimits::default(),
+            throttle: ThrottleConfig::default(),
+            position_keeper_url: None,
+            position_poll_millis: 100,
+            threads: GatewayThreads::default(),
+            users: Vec::new(),
+            retransmit_len: 10_000,
+            drop_copy: DropCopySection::default(),
+        }
+    }
+}
+
+impl Default for DropCopySection {
+    fn default() -> Self {
+        DropCopySection {
+            port: 3004,
+            users: Vec::new(),
+            retained: 100_000,
+        }
+    }
+}
+
+impl Default for OeApiSection {
+    fn default() -> Self {
+        OeApiSection {
+            port: 8080,
+            metrics_port: 9102,
+            gateway_host: "127.0.0.1".to_owned(),
+            gateway_port: 3001,
+            gateway_username: "oe-api".to_owned(),
+            gateway_password: String::new(),
+            throttle: ThrottleConfig::default(),
+            users: Vec::new(),
+        }
+    }
+}
+
+impl Default for FixAcceptorSection {
+    fn default() -> Self {
+        FixAcceptorSection {
+            port: 9878,
+            gateway_host: "127.0.0.1".to_owned(),
+            gateway_port: 3001,
+            gateway_username: "fix-acceptor".to_owned(),
+            gateway_password: String::new(),
+            comp_id: "MATCH".to_owned(),
+            store_dir: "fix_store".to_owned(),
+            sessions: Vec::new(),
+        }
+    }
+}
+
+impl Default for MarketDataSection {
+    fn default() -> Self {
+        MarketDataSection {
+            port: 7000,
+            feed_port: 3600,
+            feed_nack_port: 9002,
+            threads: MarketDataThreads::default(),
+        }
+    }
+}
+
+impl Default for RecorderSection {
+    fn default() -> Self {
+        RecorderSection {
+            journal_path: "engine_out.journal".to_owned(),
+            threads: MsgOutThreads::default(),
+        }
+    }
+}
+
+impl Default for PositionKeeperSection {
+    fn default() -> Self {
+        PositionKeeperSection {
+            port: 7100,
+            threads: MsgOutThreads::default(),
+        }
+    }
+}
+
+impl Default for TransportSection {
+    fn default() -> Self {
+        TransportSection {
+            msg_in_nack_port: 9000,
+            msg_out_nack_port: 9001,
+            receive_ring_len: 1 << 20,
+            retransmit_ring_len: 5_000_000,
+            batching: BatchingConfig::default(),
+        }
+    }
+}
+
+impl Default for BatchingConfig {
+    fn default() -> Self {
+        BatchingConfig {
+            batch_size: 32,
+            max_flush_delay_micros: 10,
+            mtu: 1500,
+        }
+    }
+}
+
+impl AppConfig {
+    pub fn validate(&self) -> Result<(), ConfigError> {
+        let scale = &self.engine.scale;
+        for (key, decimals) in [
+            ("engine.scale.px_decimals", scale.px_decimals),
+            ("engine.scale.qty_decimals", scale.qty_decimals),
+        ] {
+            if decimals > MAX_DECIMALS {
+                return Err(ConfigError::invalid(
+                    key,
+                    format!("{} must be at most {}", decimals, MAX_DECIMALS),
+                ));
+            }
+        }
+
+        if self.engine.instrument.is_empty() || self.engine.instrument.len() > 16 {
+            return Err(ConfigError::invalid(
+                "engine.instrument",
+                format!(
+                    "'{}' must be between 1 and 16 bytes",
+                    self.engine.instrument
+                ),
+            ));
+        }
+
+        if self.gateway.msg_in_port != self.engine.msg_in_port {
+            return Err(ConfigError::invalid(
+                "gateway.msg_in_port",
+                format!(
+                    "{} does not match engine.msg_in_port {}",
+                    self.gateway.msg_in_port, self.engine.msg_in_port
+                ),
+            ));
+        }
+
+        if self.gateway.msg_out_port != self.engine.msg_out_port {
+            return Err(ConfigError::invalid(
+                "gateway.msg_out_port",
+                format!(
+                    "{} does not match engine.msg_out_port {}",
+                    self.gateway.msg_out_port, self.engine.msg_out_port
+                ),
+            ));
+        }
+
+        if self.oe_api.gateway_port != self.gateway.port {
+            return Err(ConfigError::invalid(
+                "oe-api.gateway_port",
+                format!(
+                    "{} does not match gateway.port {}",
+                    self.oe_api.gateway_port, self.gateway.port
+                ),
+            ));
+        }
+
+        if self.fix_acceptor.gateway_port != self.gateway.port {
+            return Err(ConfigError::invalid(
+                "fix-acceptor.gateway_port",
+                format!(
+                    "{} does not match gateway.port {}",
+                    self.fix_acceptor.gateway_port, self.gateway.port
+                ),
+            ));
+        }
+
+        if self.gateway.sender_id as usize >= MAX_SENDERS {
+            return Err(ConfigError::invalid(
+                "gateway.sender_id",
+                format!("{} must be below {}", self.gateway.sender_id, MAX_SENDERS),
+            ));
+        }
+
+        if self.transport.receive_ring_len == 0 {
+            return Err(ConfigError::invalid(
+                "transport.receive_ring_len",
+                "must be at least 1",
+            ));
+        }
+
+        if self.transport.retransmit_ring_len == 0
+            || self.transport.retransmit_ring_len > SequenceNumber::MAX as usize
+        {
+            return Err(ConfigError::invalid(
+                "transport.retransmit_ring_len",
+                format!("must be between 1 and {}", SequenceNumber::MAX),
+            ));
+        }
+
+        if self.transport.batching.batch_size == 0 {
+            return Err(ConfigError::invalid(
+                "transport.batching.batch_size",
+                "must be at least 1",
+            ));
+        }
+
+        let batching = &self.transport.batching;
+        if batching.mtu < MIN_MTU || batching.max_datagram_len() > MAX_UDP_PACKET_SIZE {
+            return Err(ConfigError::invalid(
+                "transport.batching.mtu",
+                format!(
+                    "{} must be between {} and {}",
+                    batching.mtu,
+                    MIN_MTU,
+                    MAX_UDP_PACKET_SIZE + IP_UDP_HEADER_LEN
+                ),
+            ));
+        }
+        // senders rely on it, a message that fits no datagram could never be sent
+        if batching.max_datagram_len() < MAX_SINGLE_MESSAGE_BATCH_LEN {
+            return Err(ConfigError::invalid(
+                "transport.batching.mtu",
+                format!(
+                    "{} leaves datagrams of {} bytes, the largest message needs {}",
+                    batching.mtu,
+                    batching.max_datagram_len(),
+                    MAX_SINGLE_MESSAGE_BATCH_LEN
+                ),
+            ));
+        }
+
+        validate_fix_sessions(&self.fix_acceptor)?;
+        validate_gateway_users(&self.gateway.users)?;
+        validate_fix_client_ids(&self.fix_acceptor, &self.gateway.users)?;
+        validate_oe_api_users(&self.oe_api, &self.gateway.users)?;
+        if self.gateway.retransmit_len == 0 {
+            return Err(ConfigError::invalid(
+                "gateway.retransmit_len",
+                "must be at least 1",
+            ));
+        }
+        validate_drop_copy(&self.gateway.drop_copy)?;
+
+        let engine_threads = &self.engine.threads;
+        validate_distinct_cores(&[
+            ("engine.threads.match", &engine_threads.match_thread),
+            ("engine.threads.msg_in", &engine_threads.msg_in),
+            ("engine.threads.msg_out", &engine_threads.msg_out),
+        ])?;
+        validate_distinct_cores(&[
+            ("gateway.threads.msg_in", &self.gateway.threads.msg_in),
+            ("gateway.threads.msg_out", &self.gateway.threads.msg_out),
+        ])?;
+        validate_distinct_cores(&[
+            (
+                "market-data.threads.msg_out",
+                &self.market_data.threads.msg_out,
+            ),
+            ("market-data.threads.feed", &self.market_data.threads.feed),
+        ])?;
+
+        let ports = [
+            ("engine.msg_in_port", self.engine.msg_in_port),
+            ("engine.msg_out_port", self.engine.msg_out_port),
+            ("engine.metrics_port", self.engine.metrics_port),
+            ("gateway.port", self.gateway.port),
+            ("gateway.admin_port", self.gateway.admin_port),
+            ("gateway.ouch_port", self.gateway.ouch_port),
+            ("gateway.drop_copy.port", self.gateway.drop_copy.port),
+            ("gateway.metrics_port", self.gateway.metrics_port),
+            ("oe-api.port", self.oe_api.port),
+            ("oe-api.metrics_port", self.oe_api.metrics_port),
+            ("fix-acceptor.port", self.fix_acceptor.port),
+            ("market-data.port", self.market_data.port),
+            ("market-data.feed_port", self.market_data.feed_port),
+            (
+                "market-data.feed_nack_port",
+                self.market_data.feed_nack_port,
+            ),
+            ("position-keeper.port", self.position_keeper.port),
+            (
+                "transport.msg_in_nack_port",
+                self.transport.msg_in_nack_port,
+            ),
+            (
+                "transport.msg_out_nack_port",
+                self.transport.msg_out_nack_port,
+            ),
+        ];
+
+        let mut bound: HashMap<u16, &str> = HashMap::new();
+        for (key, port) in ports {
+            if port == 0 {
+                return Err(ConfigError::invalid(key, "port must not be 0"));
+            }
+            if let Some(other) = bound.insert(port, key) {
+                return Err(ConfigError::invalid(
+                    key,
+                    format!("port {} is already used by {}", port, other),
+                ));
+            }
+        }
+
+        Ok(())
+    }
+}
+
+fn validate_distinct_cores(threads: &[(&str, &ThreadConfig)]) -> Result<(), ConfigError> {
+    let mut pinned: HashMap<usize, &str> = HashMap::new();
+    for (key, thread) in threads {
+        let Some(core) = thread.core else {
+            continue;
+        };
+        if let Some(other) = pinned.insert(core, key) {
+            return Err(ConfigError::invalid(
+                &format!("{}.core", key),
+                format!("core {} is already pinned by {}", core, other),
+            ));
+        }
+    }
+
+    Ok(())
+}
+
+fn validate_fix_sessions(section: &FixAcceptorSection) -> Result<(), ConfigError> {
+    let mut comp_ids: HashMap<&str, usize> = HashMap::new();
+    let mut client_ids: HashMap<u32, usize> = HashMap::new();
+    for (i, session) in section.sessions.iter().enumerate() {
+        let key = format!("fix-acceptor.sessions[{}]", i);
+        if session.sender_comp_id.is_empty() || session.sender_comp_id == section.comp_id {
+            return Err(ConfigError::invalid(
+                &format!("{}.sender_comp_id", key),
+                format!(
+                    "'{}' must be set and differ from fix-acceptor.comp_id",
+                    session.sender_comp_id
+                ),
+            ));
+        }
+        if session.client_id == 0 {
+            return Err(ConfigError::invalid(
+                &format!("{}.client_id", key),
+                "client id must not be 0",
+            ));
+        }
+        if let Some(other) = comp_ids.insert(&session.sender_comp_id, i) {
+            return Err(ConfigError::invalid(
+                &format!("{}.sender_comp_id", key),
+                format!(
+                    "'{}' is already used by sessions[{}]",
+                    session.sender_comp_id, other
+                ),
+            ));
+        }
+        if let Some(other) = client_ids.insert(session.client_id, i) {
+            return Err(ConfigError::invalid(
+                &format!("{}.client_id", key),
+                format!(
+                    "{} is already used by sessions[{}]",
+                    session.client_id, other
+                ),
+            ));
+        }
+    }
+
+    Ok(())
+}
+
+fn validate_gateway_users(users: &[GatewayUser]) -> Result<(), ConfigError> {
+    let mut usernames: HashMap<&str, usize> = HashMap::new();
+    for (i, user) in users.iter().enumerate() {
+        let key = format!("gateway.users[{}]", i);
+        validate_credentials(&key, &user.username, &user.password)?;
+        if user.client_id == 0 {
+            return Err(ConfigError::invalid(
+                &format!("{}.client_id", key),
+                "client id must not be 0",
+            ));
+        }
+        if let Some(other) = usernames.insert(&user.username, i) {
+            return Err(ConfigError::invalid(
+                &format!("{}.username", key),
+                format!("'{}' is already used by users[{}]", user.username, other),
+            ));
+        }
+        validate_client_id_range(&key, i, users)?;
+    }
+
+    Ok(())
+}
+
+/// A trusted user's range may not hold another user's client id, or a trusted session could
+/// trade as that user and receive its output.
+fn validate_client_id_range(
+    key: &str,
+    idx: usize,
+    users: &[GatewayUser],
+) -> Result<(), ConfigError> {
+    let user = &users[idx];
+    let key = format!("{}.client_ids", key);
+    let range = match (user.trusted, &user.client_ids) {
+        (true, Some(range)) => range,
+        (true, None) => return Err(ConfigError::invalid(&key, "must be set for a trusted user")),
+        (false, Some(_)) => {
+            return Err(ConfigError::invalid(
+                &key,
+                "only trusted users have a range",
+            ))
+        }
+        (false, None) => return Ok(()),
+    };
+
+    if range.first == 0 || range.first > range.last {
+        return Err(ConfigError::invalid(
+            &key,
+            format!(
+                "{}..={} must be a non empty range above 0",
+                range.first, range.last
+            ),
+        ));
+    }
+    for (i, other) in users.iter().enumerate() {
+        if range.contains(other.client_id) {
+            return Err(ConfigError::invalid(
+                &key,
+                format!(
+                    "{}..={} holds the client id of users[{}]",
+                    range.first, range.last, i
+                ),
+            ));
+        }
+        let overlaps = other
+            .client_ids
+            .is_some_and(|other_range| range.overlaps(&other_range));
+        if i != idx && overlaps {
+            return Err(ConfigError::invalid(
+                &key,
+                format!(
+                    "{}..={} overlaps the range of users[{}]",
+                    range.first, range.last, i
+                ),
+            ));
+        }
+    }
+
+    Ok(())
+}
+
+/// The gateway refuses orders of FIX sessions whose client id is outside the range of the
+/// acceptor's gateway user.
+fn validate_fix_client_ids(
+    section: &FixAcceptorSection,
+    users: &[GatewayUser],
+) -> Result<(), ConfigError> {
+    let Some(range) = users
+        .iter()
+        .find(|user| user.username == section.gateway_username)
+        .and_then(|user| user.client_ids)
+    else {
+        return Ok(());
+    };
+
+    for (i, session) in section.sessions.iter().enumerate() {
+        if !range.contains(session.client_id) {
+            return Err(ConfigError::invalid(
+                &format!("fix-acceptor.sessions[{}].client_id", i),
+                format!(
+                    "{} is outside the client_ids {}..={} of gateway user '{}'",
+                    session.client_id, range.first, range.last, section.gateway_username
+                ),
+            ));
+        }
+    }
+
+    Ok(())
+}
+
+fn validate_oe_api_users(
+    section: &OeApiSection,
+    gateway_users: &[GatewayUser],
+) -> Result<(), ConfigError> {
+    let range = gateway_users
+        .iter()
+        .find(|user| user.username == section.gateway_username)
+        .and_then(|user| user.client_ids);
+
+    let mut usernames: HashMap<&str, usize> = HashMap::new();
+    let mut client_ids: HashMap<u32, usize> = HashMap::new();
+    for (i, user) in section.users.iter().enumerate() {
+        let key = format!("oe-api.users[{}]", i);
+        if user.username.is_empty() || user.password.is_empty() {
+            return Err(ConfigError::invalid(
+                &key,
+                "username and password must be set",
+            ));
+        }
+        if let Some(other) = usernames.insert(&user.username, i) {
+            return Err(ConfigError::invalid(
+                &format!("{}.username", key),
+                format!("'{}' is already used by users[{}]", user.username, other),
+            ));
+        }
+        if let Some(other) = client_ids.insert(user.client_id, i) {
+            return Err(ConfigError::invalid(
+                &format!("{}.client_id", key),
+                format!("{} is already used by users[{}]", user.client_id, other),
+            ));
+        }
+        if let Some(range) = range.filter(|range| !range.contains(user.client_id)) {
+            return Err(ConfigError::invalid(
+                &format!("{}.client_id", key),
+                format!(
+                    "{} is outside the client_ids {}..={} of gateway user '{}'",
+                    user.client_id, range.first, range.last, section.gateway_username
+                ),
+            ));
+        }
+    }
+
+    Ok(())
+}
+
+fn validate_drop_copy(section: &DropCopySection) -> Result<(), ConfigError> {
+    if section.retained == 0 {
+        return Err(ConfigError::invalid(
+            "gateway.drop_copy.retained",
+            "must be at least 1",
+        ));
+    }
+
+    let mut usernames: HashMap<&str, usize> = HashMap::new();
+    for (i, user) in section.users.iter().enumerate() {
+        let key = format!("gateway.drop_copy.users[{}]", i);
+        validate_credentials(&key, &user.username, &user.password)?;
+        if user.firm.is_empty() {
+            return Err(ConfigError::invalid(
+                &format!("{}.firm", key),
+                "must be set",
+            ));
+        }
+        if let Some(other) = usernames.insert(&user.username, i) {
+            return Err(ConfigError::invalid(
+                &format!("{}.username", key),
+                format!("'{}' is already used by users[{}]", user.username, other),
+            ));
+        }
+    }
+
+    Ok(())
+}
+
+fn validate_credentials(key: &str, username: &str, password: &str) -> Result<(), ConfigError> {
+    for (field, value) in [("username", username), ("password", password)] {
+        if value.is_empty() || value.len() > CREDENTIAL_LEN || value.contains('\0') {
+            return Err(ConfigError::invalid(
+                &format!("{}.{}", key, field),
+                format!("must be between 1 and {} bytes", CREDENTIAL_LEN),
+            ));
+        }
+    }
+    Ok(())
+}
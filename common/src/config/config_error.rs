@@ -0,0 +1,40 @@
+use std::error::Error;
+use std::fmt::{Display, Formatter};
+
+#[derive(Debug)]
+pub enum ConfigError {
+    Read { path: String, reason: String },
+    Parse { path: String, reason: String },
+    Argument(String),
+    Override { key: String, reason: String },
+    Invalid { key: String, reason: String },
+}
+
+impl ConfigError {
+    pub fn invalid(key: &str, reason: impl Into<String>) -> Self {
+        ConfigError::Invalid {
+            key: key.to_owned(),
+            reason: reason.into(),
+        }
+    }
+}
+
+impl Display for ConfigError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ConfigError::Read { path, reason } => {
+                write!(f, "cannot read config file {}: {}", path, reason)
+            }
+            ConfigError::Parse { path, reason } => {
+                write!(f, "invalid config file {}: {}", path, reason)
+            }
+            ConfigError::Argument(reason) => write!(f, "invalid argument: {}", reason),
+            ConfigError::Override { key, reason } => {
+                write!(f, "cannot apply override {}: {}", key, reason)
+            }
+            ConfigError::Invalid { key, reason } => write!(f, "invalid config {}: {}", key, reason),
+        }
+    }
+}
+
+impl Error for ConfigError {}
@@ -0,0 +1,147 @@
+use crate::config::app_config::AppConfig;
+use crate::config::config_error::ConfigError;
+use serde_json::{Map, Value};
+use std::env;
+use std::fs;
+use std::process::exit;
+
+pub const DEFAULT_CONFIG_PATH: &str = "config/engine_btc_usd.json";
+const CONFIG_PATH_ENV: &str = "ME_CONFIG";
+/// `ME__GATEWAY__PORT=3101` overrides `gateway.port`.
+const ENV_OVERRIDE_PREFIX: &str = "ME__";
+
+/// Loads the config file (`--config`, `ME_CONFIG` or the default path), then applies
+/// `ME__SECTION__KEY` env overrides and `--section.key value` flags, in that order.
+/// Arguments that are not config flags are returned for the binary to handle.
+pub fn load_config(
+    args: impl IntoIterator<Item = String>,
+) -> Result<(AppConfig, Vec<String>), ConfigError> {
+    let mut config_path = env::var(CONFIG_PATH_ENV).unwrap_or(DEFAULT_CONFIG_PATH.to_owned());
+    let mut cli_overrides = Vec::new();
+    let mut remaining = Vec::new();
+
+    let mut args = args.into_iter();
+    while let Some(arg) = args.next() {
+        let Some(flag) = arg.strip_prefix("--") else {
+            remaining.push(arg);
+            continue;
+        };
+
+        let (name, inline_value) = match flag.split_once('=') {
+            Some((name, value)) => (name.to_owned(), Some(value.to_owned())),
+            None => (flag.to_owned(), None),
+        };
+
+        if name != "config" && !name.contains('.') {
+            remaining.push(arg);
+            continue;
+        }
+
+        let value = match inline_value {
+            Some(value) => value,
+            None => args
+                .next()
+                .ok_or_else(|| ConfigError::Argument(format!("--{} needs a value", name)))?,
+        };
+
+        if name == "config" {
+            config_path = value;
+        } else {
+            cli_overrides.push((name, value));
+        }
+    }
+
+    let data = fs::read_to_string(&config_path).map_err(|e| ConfigError::Read {
+        path: config_path.clone(),
+        reason: e.to_string(),
+    })?;
+    let mut root: Value = serde_json::from_str(&data).map_err(|e| ConfigError::Parse {
+        path: config_path.clone(),
+        reason: e.to_string(),
+    })?;
+
+    let mut env_overrides: Vec<(String, String)> = env::vars()
+        .filter_map(|(key, value)| {
+            let key = key.strip_prefix(ENV_OVERRIDE_PREFIX)?;
+            Some((key.to_lowercase().replace("__", "."), value))
+        })
+        .collect();
+    env_overrides.sort();
+
+    for (key, value) in env_overrides.iter().chain(cli_overrides.iter()) {
+        apply_override(&mut root, key, value)?;
+    }
+
+    let config: AppConfig = serde_json::from_value(root).map_err(|e| ConfigError::Parse {
+        path: config_path.clone(),
+        reason: e.to_string(),
+    })?;
+    config.validate()?;
+
+    Ok((config, remaining))
+}
+
+/// For binaries that take no arguments of their own.
+pub fn load_config_or_exit() -> AppConfig {
+    let loaded = load_config(env::args().skip(1)).and_then(|(config, remaining)| {
+        match remaining.first() {
+            Some(arg) => Err(ConfigError::Argument(format!("unknown argument {}", arg))),
+            None => Ok(config),
+        }
+    });
+
+    match loaded {
+        Ok(config) => config,
+        Err(e) => {
+            eprintln!("{}", e);
+            exit(2);
+        }
+    }
+}
+
+fn apply_override(root: &mut Value, key: &str, raw_value: &str) -> Result<(), ConfigError> {
+    let override_error = |reason: &str| ConfigError::Override {
+        key: key.to_owned(),
+        reason: reason.to_owned(),
+    };
+
+    let parts: Vec<&str> = key.split('.').collect();
+    if parts.iter().any(|part| part.is_empty()) {
+        return Err(override_error("empty key segment"));
+    }
+
+    let mut node = root;
+    for (i, part) in parts.iter().enumerate() {
+        let object = node
+            .as_object_mut()
+            .ok_or_else(|| override_error(&format!("{} is not a section", parts[..i].join("."))))?;
+        let field = matching_field(object, part);
+
+        if i == parts.len() - 1 {
+            let value = match object.get(&field) {
+                // keep strings as strings, e.g. an instrument that happens to be numeric
+                Some(Value::String(_)) => Value::String(raw_value.to_owned()),
+                _ => serde_json::from_str(raw_value)
+                    .unwrap_or_else(|_| Value::String(raw_value.to_owned())),
+            };
+            object.insert(field, value);
+            return Ok(());
+        }
+
+        node = object
+            .entry(field)
+            .or_insert_with(|| Value::Object(Map::new()));
+    }
+
+    Ok(())
+}
+
+/// Config keys match ignoring case and `-` / `_`, so `oe_api` finds the `oe-api` section.
+fn matching_field(object: &Map<String, Value>, part: &str) -> String {
+    let normalize = |s: &str| s.to_lowercase().replace('-', "_");
+    object
+        .keys()
+        .find(|existing| normalize(existing) == normalize(part))
+        .cloned()
+        .unwrap_or_else(|| part.to_owned())
+}
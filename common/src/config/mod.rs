@@ -0,0 +1,3 @@
+pub mod app_config;
+pub mod config_error;
+pub mod config_loader;
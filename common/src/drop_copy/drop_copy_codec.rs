@@ -0,0 +1,50 @@
+use crate::codec::codec_error::CodecError;
+use crate::codec::message_codec::{decode_engine_message, encode_engine_message};
+use crate::codec::wire::WireReader;
+use crate::drop_copy::drop_copy_message::{DropCopy, DropCopyRequest};
+
+// Requests are a one byte ASCII type and a big endian u64, copies a big endian sequence and
+// timestamp followed by the engine message.
+pub const REQUEST_REPLAY_FROM_SEQUENCE: u8 = b'S';
+pub const REQUEST_REPLAY_FROM_TIME: u8 = b'T';
+
+pub fn encode_request(request: &DropCopyRequest, out: &mut Vec<u8>) {
+    let (request_type, value) = match request {
+        DropCopyRequest::ReplayFromSequence(sequence) => (REQUEST_REPLAY_FROM_SEQUENCE, sequence),
+        DropCopyRequest::ReplayFromTime(timestamp) => (REQUEST_REPLAY_FROM_TIME, timestamp),
+    };
+    out.push(request_type);
+    out.extend_from_slice(&value.to_be_bytes());
+}
+
+pub fn decode_request(buf: &[u8]) -> Result<DropCopyRequest, CodecError> {
+    let mut reader = WireReader::new(buf);
+    let request = match reader.u8()? {
+        REQUEST_REPLAY_FROM_SEQUENCE => {
+            DropCopyRequest::ReplayFromSequence(u64::from_be_bytes(reader.bytes()?))
+        }
+        REQUEST_REPLAY_FROM_TIME => {
+            DropCopyRequest::ReplayFromTime(u64::from_be_bytes(reader.bytes()?))
+        }
+        request_type => return Err(CodecError::UnknownMessageType(request_type)),
+    };
+    reader.finish()?;
+    Ok(request)
+}
+
+pub fn encode_copy(copy: &DropCopy, out: &mut Vec<u8>) {
+    out.extend_from_slice(&copy.sequence.to_be_bytes());
+    out.extend_from_slice(&copy.timestamp.to_be_bytes());
+    encode_engine_message(&copy.message, out);
+}
+
+pub fn decode_copy(buf: &[u8]) -> Result<DropCopy, CodecError> {
+    let mut reader = WireReader::new(buf);
+    let sequence = u64::from_be_bytes(reader.bytes()?);
+    let timestamp = u64::from_be_bytes(reader.bytes()?);
+    Ok(DropCopy {
+        sequence,
+        timestamp,
+        message: decode_engine_message(reader.slice(reader.remaining())?)?,
+    })
+}
@@ -0,0 +1,22 @@
+use crate::transport::sequenced_message::EngineMessage;
+
+/// What a drop copy client may send, carried as UnsequencedData. The session is otherwise read
+/// only.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum DropCopyRequest {
+    /// Carry on from this sequence number, with the same meaning as a Login Request's.
+    ReplayFromSequence(u64),
+    /// Carry on from the first copy logged at or after this time, in nanoseconds since the
+    /// epoch.
+    ReplayFromTime(u64),
+}
+
+/// One copy of an order state change or execution, the payload of the session's SequencedData.
+/// Sequence numbers are per firm, every session of the firm sees the same ones.
+#[derive(Debug, Clone)]
+pub struct DropCopy {
+    pub sequence: u64,
+    /// When the gateway logged the copy, in nanoseconds since the epoch.
+    pub timestamp: u64,
+    pub message: EngineMessage,
+}
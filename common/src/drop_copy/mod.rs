@@ -0,0 +1,2 @@
+pub mod drop_copy_codec;
+pub mod drop_copy_message;
@@ -0,0 +1,59 @@
+use crate::codec::message_codec::decode_sequenced_message;
+use crate::transport::sequenced_message::SequencedEngineMessage;
+use std::fs::File;
+use std::io;
+use std::io::{BufReader, ErrorKind, Read};
+use std::path::Path;
+
+const MAX_JOURNAL_ENTRY: usize = u16::MAX as usize;
+
+pub struct JournalReader {
+    reader: BufReader<File>,
+    buffer: Vec<u8>,
+}
+
+impl JournalReader {
+    pub fn open(path: impl AsRef<Path>) -> io::Result<Self> {
+        Ok(JournalReader {
+            reader: BufReader::new(File::open(path)?),
+            buffer: Vec::new(),
+        })
+    }
+
+    pub fn read_next(&mut self) -> io::Result<Option<SequencedEngineMessage>> {
+        let mut len_buf = [0u8; 4];
+        match self.reader.read_exact(&mut len_buf) {
+            Ok(()) => {}
+            Err(e) if e.kind() == ErrorKind::UnexpectedEof => return Ok(None),
+            Err(e) => return Err(e),
+        }
+
+        let len = u32::from_le_bytes(len_buf) as usize;
+        if len > MAX_JOURNAL_ENTRY {
+            return Err(io::Error::new(
+                ErrorKind::InvalidData,
+                format!("journal entry of {} bytes is too large", len),
+            ));
+        }
+
+        self.buffer.resize(len, 0);
+        self.reader.read_exact(&mut self.buffer)?;
+
+        decode_sequenced_message(&self.buffer)
+            .map(Some)
+            .map_err(|e| {
+                io::Error::new(
+                    ErrorKind::InvalidData,
+                    format!("invalid journal entry: {}", e),
+                )
+            })
+    }
+}
+
+impl Iterator for JournalReader {
+    type Item = io::Result<SequencedEngineMessage>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.read_next().transpose()
+    }
+}
@@ -0,0 +1,35 @@
+use crate::codec::message_codec::encode_sequenced_message;
+use crate::transport::sequenced_message::SequencedEngineMessage;
+use std::fs::{File, OpenOptions};
+use std::io;
+use std::io::{BufWriter, Write};
+use std::path::Path;
+
+/// Append-only record of sequenced engine messages, each entry prefixed with its length.
+pub struct JournalWriter {
+    writer: BufWriter<File>,
+    buffer: Vec<u8>,
+}
+
+impl JournalWriter {
+    pub fn open(path: impl AsRef<Path>) -> io::Result<Self> {
+        let file = OpenOptions::new().create(true).append(true).open(path)?;
+
+        Ok(JournalWriter {
+            writer: BufWriter::new(file),
+            buffer: Vec::new(),
+        })
+    }
+
+    pub fn append(&mut self, message: &SequencedEngineMessage) -> io::Result<()> {
+        self.buffer.clear();
+        encode_sequenced_message(message, &mut self.buffer);
+        self.writer
+            .write_all(&(self.buffer.len() as u32).to_le_bytes())?;
+        self.writer.write_all(&self.buffer)
+    }
+
+    pub fn flush(&mut self) -> io::Result<()> {
+        self.writer.flush()
+    }
+}
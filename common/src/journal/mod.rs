@@ -0,0 +1,2 @@
+pub mod journal_reader;
+pub mod journal_writer;
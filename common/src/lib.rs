@@ -1,6 +1,12 @@
+pub mod codec;
+pub mod config;
+pub mod drop_copy;
+pub mod journal;
 pub mod memory;
+pub mod metrics;
 pub mod network;
-pub mod serialize;
+pub mod ouch;
+pub mod session;
 pub mod transport;
 pub mod types;
 pub mod util;
@@ -0,0 +1,67 @@
+use hdrhistogram::Histogram;
+use std::sync::{Arc, Mutex};
+
+// 1ns .. 60s at 3 significant figures
+const LOWEST_DISCERNIBLE_NS: u64 = 1;
+const HIGHEST_TRACKABLE_NS: u64 = 60_000_000_000;
+const SIGNIFICANT_FIGURES: u8 = 3;
+
+fn new_histogram() -> Histogram<u64> {
+    Histogram::new_with_bounds(LOWEST_DISCERNIBLE_NS, HIGHEST_TRACKABLE_NS, SIGNIFICANT_FIGURES)
+        .unwrap()
+}
+
+/// Shared HDR histogram read by the metrics exporter. Hot path threads never touch the lock
+/// directly, they record into a [`LatencyRecorder`] and flush it periodically.
+pub struct LatencyHistogram {
+    shared: Mutex<Histogram<u64>>,
+}
+
+impl LatencyHistogram {
+    pub fn new() -> Self {
+        LatencyHistogram {
+            shared: Mutex::new(new_histogram()),
+        }
+    }
+
+    pub fn recorder(self: &Arc<Self>) -> LatencyRecorder {
+        LatencyRecorder {
+            local: new_histogram(),
+            shared: self.clone(),
+        }
+    }
+
+    pub fn snapshot(&self) -> Histogram<u64> {
+        self.shared.lock().unwrap().clone()
+    }
+}
+
+impl Default for LatencyHistogram {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Thread local side of a [`LatencyHistogram`].
+pub struct LatencyRecorder {
+    local: Histogram<u64>,
+    shared: Arc<LatencyHistogram>,
+}
+
+impl LatencyRecorder {
+    #[inline(always)]
+    pub fn record(&mut self, nanos: u64) {
+        self.local.saturating_record(nanos.max(LOWEST_DISCERNIBLE_NS));
+    }
+
+    pub fn flush(&mut self) {
+        if self.local.is_empty() {
+            return;
+        }
+
+        if let Ok(mut shared) = self.shared.shared.lock() {
+            shared.add(&self.local).unwrap();
+        }
+        self.local.reset();
+    }
+}
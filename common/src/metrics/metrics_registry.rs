@@ -0,0 +1,99 @@
+use crate::metrics::counter::Counter;
+use crate::metrics::gauge::Gauge;
+use crate::metrics::latency_histogram::LatencyHistogram;
+use std::fmt::Write;
+use std::sync::{Arc, Mutex};
+
+const SUMMARY_QUANTILES: [f64; 5] = [0.5, 0.9, 0.99, 0.999, 1.0];
+
+enum Metric {
+    Counter(Arc<Counter>),
+    Gauge(Arc<Gauge>),
+    Histogram(Arc<LatencyHistogram>),
+}
+
+struct RegisteredMetric {
+    name: String,
+    help: String,
+    metric: Metric,
+}
+
+#[derive(Default)]
+pub struct MetricsRegistry {
+    metrics: Mutex<Vec<RegisteredMetric>>,
+}
+
+impl MetricsRegistry {
+    pub fn new() -> Self {
+        MetricsRegistry {
+            metrics: Mutex::new(Vec::new()),
+        }
+    }
+
+    pub fn counter(&self, name: &str, help: &str) -> Arc<Counter> {
+        let counter = Arc::new(Counter::default());
+        self.register(name, help, Metric::Counter(counter.clone()));
+        counter
+    }
+
+    pub fn gauge(&self, name: &str, help: &str) -> Arc<Gauge> {
+        let gauge = Arc::new(Gauge::default());
+        self.register(name, help, Metric::Gauge(gauge.clone()));
+        gauge
+    }
+
+    pub fn histogram(&self, name: &str, help: &str) -> Arc<LatencyHistogram> {
+        let histogram = Arc::new(LatencyHistogram::new());
+        self.register(name, help, Metric::Histogram(histogram.clone()));
+        histogram
+    }
+
+    /// Renders all registered metrics in the Prometheus text exposition format.
+    /// Latency histograms are exported as summaries.
+    pub fn render(&self) -> String {
+        let metrics = self.metrics.lock().unwrap();
+        let mut out = String::with_capacity(metrics.len() * 128);
+
+        for registered in metrics.iter() {
+            let name = &registered.name;
+            let _ = writeln!(out, "# HELP {} {}", name, registered.help);
+
+            match &registered.metric {
+                Metric::Counter(counter) => {
+                    let _ = writeln!(out, "# TYPE {} counter", name);
+                    let _ = writeln!(out, "{} {}", name, counter.get());
+                }
+                Metric::Gauge(gauge) => {
+                    let _ = writeln!(out, "# TYPE {} gauge", name);
+                    let _ = writeln!(out, "{} {}", name, gauge.get());
+                }
+                Metric::Histogram(histogram) => {
+                    let snapshot = histogram.snapshot();
+                    let _ = writeln!(out, "# TYPE {} summary", name);
+                    for quantile in SUMMARY_QUANTILES {
+                        let _ = writeln!(
+                            out,
+                            "{}{{quantile=\"{}\"}} {}",
+                            name,
+                            quantile,
+                            snapshot.value_at_quantile(quantile)
+                        );
+                    }
+                    let sum = (snapshot.mean() * snapshot.len() as f64) as u64;
+                    let _ = writeln!(out, "{}_sum {}", name, sum);
+                    let _ = writeln!(out, "{}_count {}", name, snapshot.len());
+                }
+            }
+        }
+
+        out
+    }
+
+    fn register(&self, name: &str, help: &str, metric: Metric) {
+        self.metrics.lock().unwrap().push(RegisteredMetric {
+            name: name.to_owned(),
+            help: help.to_owned(),
+            metric,
+        });
+    }
+}
@@ -0,0 +1,61 @@
+use crate::metrics::metrics_registry::MetricsRegistry;
+use std::io::{BufRead, BufReader, Write};
+use std::net::{SocketAddr, TcpListener, TcpStream};
+use std::sync::Arc;
+use std::thread;
+use std::thread::JoinHandle;
+
+/// Serves `GET /metrics` in Prometheus text format from a dedicated thread, away from the hot path.
+pub fn spawn_metrics_server(addr: SocketAddr, registry: Arc<MetricsRegistry>) -> JoinHandle<()> {
+    thread::spawn(move || {
+        let listener = match TcpListener::bind(addr) {
+            Ok(listener) => listener,
+            Err(e) => {
+                eprintln!("Metrics server failed to bind {}: {}", addr, e);
+                return;
+            }
+        };
+
+        println!("Metrics available on http://{}/metrics", addr);
+
+        for stream in listener.incoming() {
+            match stream {
+                Ok(stream) => {
+                    if let Err(e) = handle_connection(stream, &registry) {
+                        eprintln!("Metrics request error: {}", e);
+                    }
+                }
+                Err(e) => eprintln!("Metrics accept error: {}", e),
+            }
+        }
+    })
+}
+
+fn handle_connection(mut stream: TcpStream, registry: &MetricsRegistry) -> std::io::Result<()> {
+    let mut request_line = String::new();
+    let mut reader = BufReader::new(stream.try_clone()?);
+    reader.read_line(&mut request_line)?;
+
+    // Drain headers
+    let mut header = String::new();
+    while reader.read_line(&mut header)? > 2 {
+        header.clear();
+    }
+
+    let path = request_line.split_whitespace().nth(1).unwrap_or("/");
+
+    let (status, body) = if path == "/metrics" {
+        ("200 OK", registry.render())
+    } else {
+        ("404 Not Found", String::from("not found\n"))
+    };
+
+    write!(
+        stream,
+        "HTTP/1.1 {}\r\nContent-Type: text/plain; version=0.0.4\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+        status,
+        body.len(),
+        body
+    )?;
+    stream.flush()
+}
@@ -0,0 +1,5 @@
+pub mod counter;
+pub mod gauge;
+pub mod latency_histogram;
+pub mod metrics_registry;
+pub mod metrics_server;
@@ -6,8 +6,15 @@ use std::net::{Ipv4Addr, SocketAddrV4, UdpSocket};
 use std::os::fd::AsRawFd;
 use std::{io, mem};
 
+pub const DEFAULT_BUSY_POLL_MICROS: u32 = 3;
+
 pub fn multicast_receiver(port: u16) -> UdpSocket {
-    let socket = base_multicast_socket();
+    multicast_receiver_with_busy_poll(port, DEFAULT_BUSY_POLL_MICROS)
+}
+
+/// `busy_poll_micros` of 0 leaves SO_BUSY_POLL off.
+pub fn multicast_receiver_with_busy_poll(port: u16, busy_poll_micros: u32) -> UdpSocket {
+    let socket = base_multicast_socket(busy_poll_micros);
 
     let bind_addr = SocketAddrV4::new(Ipv4Addr::UNSPECIFIED, port);
     socket.bind(&bind_addr.into()).unwrap();
@@ -22,7 +29,7 @@ pub fn multicast_receiver(port: u16) -> UdpSocket {
 }
 
 pub fn multicast_sender() -> UdpSocket {
-    let socket = base_multicast_socket();
+    let socket = base_multicast_socket(DEFAULT_BUSY_POLL_MICROS);
 
     socket
         .bind(&SocketAddrV4::new(Ipv4Addr::UNSPECIFIED, 0).into())
@@ -35,21 +42,21 @@ pub fn multicast_sender() -> UdpSocket {
     std::net::UdpSocket::from(socket)
 }
 
-fn base_multicast_socket() -> Socket {
+fn base_multicast_socket(busy_poll_micros: u32) -> Socket {
     let socket = Socket::new(Domain::IPV4, Type::DGRAM, Some(Protocol::UDP)).unwrap();
     socket.set_reuse_address(true).unwrap();
     socket.set_reuse_port(true).unwrap();
     socket.set_send_buffer_size(16 * 1024 * 1024).unwrap();
     socket.set_recv_buffer_size(16 * 1024 * 1024).unwrap();
-    set_socket_priority_busy_spin(&socket);
+    set_socket_priority_busy_spin(&socket, busy_poll_micros);
 
     socket
 }
 
-fn set_socket_priority_busy_spin(socket: &Socket) {
+fn set_socket_priority_busy_spin(socket: &Socket, busy_poll_micros: u32) {
     let fd = socket.as_raw_fd();
 
-    let busy_poll: i32 = 3; // microseconds
+    let busy_poll = busy_poll_micros as i32;
     let ret = unsafe {
         setsockopt(
             fd,
@@ -1,6 +1,10 @@
 use std::net::Ipv4Addr;
 
 pub const MAX_UDP_PACKET_SIZE: usize = 4096;
+/// IPv4 plus UDP header bytes, an MTU minus this is the largest unfragmented datagram payload.
+pub const IP_UDP_HEADER_LEN: usize = 28;
+/// The smallest MTU every IPv4 host has to accept.
+pub const MIN_MTU: u16 = 576;
 pub const LOOPBACK_INTERFACE: Ipv4Addr = Ipv4Addr::new(127, 0, 0, 1);
 pub const DOCKER_MULTICAST_INTERFACE: Ipv4Addr = Ipv4Addr::new(172, 17, 0, 1);
 pub const ROUTER_INTERFACE: Ipv4Addr = Ipv4Addr::new(192, 168, 1, 108);
@@ -0,0 +1,2 @@
+pub mod ouch_codec;
+pub mod ouch_message;
@@ -0,0 +1,287 @@
+use crate::codec::codec_error::CodecError;
+use crate::ouch::ouch_message::{
+    Accepted, CancelOrder, CancelRejected, Canceled, CanceledReason, EnterOrder, Executed,
+    Liquidity, OuchInbound, OuchOutbound, Rejected, RejectedReason, ReplaceOrder, Replaced,
+};
+use crate::types::order::TimeInForce;
+use crate::types::side::Side;
+
+// Every message is a one byte ASCII type followed by fixed width big endian fields.
+pub const MSG_ENTER_ORDER: u8 = b'O';
+pub const MSG_REPLACE_ORDER: u8 = b'U';
+pub const MSG_CANCEL_ORDER: u8 = b'X';
+pub const MSG_ACCEPTED: u8 = b'A';
+pub const MSG_REPLACED: u8 = b'U';
+pub const MSG_EXECUTED: u8 = b'E';
+pub const MSG_CANCELED: u8 = b'C';
+pub const MSG_REJECTED: u8 = b'J';
+pub const MSG_CANCEL_REJECTED: u8 = b'I';
+
+pub fn encode_inbound(message: &OuchInbound, out: &mut Vec<u8>) {
+    match message {
+        OuchInbound::EnterOrder(enter) => {
+            out.push(MSG_ENTER_ORDER);
+            put_u64(out, enter.token);
+            enter.side.put(out);
+            put_u64(out, enter.qty);
+            out.extend_from_slice(&enter.instrument);
+            put_u64(out, enter.px);
+            enter.time_in_force.put(out);
+        }
+        OuchInbound::ReplaceOrder(replace) => {
+            out.push(MSG_REPLACE_ORDER);
+            put_u64(out, replace.existing_token);
+            put_u64(out, replace.replacement_token);
+            put_u64(out, replace.qty);
+            put_u64(out, replace.px);
+        }
+        OuchInbound::CancelOrder(cancel) => {
+            out.push(MSG_CANCEL_ORDER);
+            put_u64(out, cancel.token);
+        }
+    }
+}
+
+pub fn decode_inbound(buf: &[u8]) -> Result<OuchInbound, CodecError> {
+    let mut reader = OuchReader::new(buf);
+    let message = match reader.u8()? {
+        MSG_ENTER_ORDER => OuchInbound::EnterOrder(EnterOrder {
+            token: reader.u64()?,
+            side: Side::get(&mut reader)?,
+            qty: reader.u64()?,
+            instrument: reader.bytes()?,
+            px: reader.u64()?,
+            time_in_force: TimeInForce::get(&mut reader)?,
+        }),
+        MSG_REPLACE_ORDER => OuchInbound::ReplaceOrder(ReplaceOrder {
+            existing_token: reader.u64()?,
+            replacement_token: reader.u64()?,
+            qty: reader.u64()?,
+            px: reader.u64()?,
+        }),
+        MSG_CANCEL_ORDER => OuchInbound::CancelOrder(CancelOrder {
+            token: reader.u64()?,
+        }),
+        msg_type => return Err(CodecError::UnknownMessageType(msg_type)),
+    };
+
+    reader.finish()?;
+    Ok(message)
+}
+
+pub fn encode_outbound(message: &OuchOutbound, out: &mut Vec<u8>) {
+    match message {
+        OuchOutbound::Accepted(accepted) => {
+            out.push(MSG_ACCEPTED);
+            put_u64(out, accepted.timestamp);
+            put_u64(out, accepted.token);
+            accepted.side.put(out);
+            put_u64(out, accepted.qty);
+            out.extend_from_slice(&accepted.instrument);
+            put_u64(out, accepted.px);
+            accepted.time_in_force.put(out);
+            put_u32(out, accepted.order_ref);
+        }
+        OuchOutbound::Replaced(replaced) => {
+            out.push(MSG_REPLACED);
+            put_u64(out, replaced.timestamp);
+            put_u64(out, replaced.token);
+            replaced.side.put(out);
+            put_u64(out, replaced.qty);
+            out.extend_from_slice(&replaced.instrument);
+            put_u64(out, replaced.px);
+            replaced.time_in_force.put(out);
+            put_u32(out, replaced.order_ref);
+            put_u64(out, replaced.previous_token);
+        }
+        OuchOutbound::Executed(executed) => {
+            out.push(MSG_EXECUTED);
+            put_u64(out, executed.timestamp);
+            put_u64(out, executed.token);
+            put_u64(out, executed.executed_qty);
+            put_u64(out, executed.px);
+            executed.liquidity.put(out);
+        }
+        OuchOutbound::Canceled(canceled) => {
+            out.push(MSG_CANCELED);
+            put_u64(out, canceled.timestamp);
+            put_u64(out, canceled.token);
+            put_u64(out, canceled.decrement_qty);
+            canceled.reason.put(out);
+        }
+        OuchOutbound::Rejected(rejected) => {
+            out.push(MSG_REJECTED);
+            put_u64(out, rejected.timestamp);
+            put_u64(out, rejected.token);
+            rejected.reason.put(out);
+        }
+        OuchOutbound::CancelRejected(cancel_rejected) => {
+            out.push(MSG_CANCEL_REJECTED);
+            put_u64(out, cancel_rejected.timestamp);
+            put_u64(out, cancel_rejected.token);
+        }
+    }
+}
+
+pub fn decode_outbound(buf: &[u8]) -> Result<OuchOutbound, CodecError> {
+    let mut reader = OuchReader::new(buf);
+    let message = match reader.u8()? {
+        MSG_ACCEPTED => OuchOutbound::Accepted(Accepted {
+            timestamp: reader.u64()?,
+            token: reader.u64()?,
+            side: Side::get(&mut reader)?,
+            qty: reader.u64()?,
+            instrument: reader.bytes()?,
+            px: reader.u64()?,
+            time_in_force: TimeInForce::get(&mut reader)?,
+            order_ref: reader.u32()?,
+        }),
+        MSG_REPLACED => OuchOutbound::Replaced(Replaced {
+            timestamp: reader.u64()?,
+            token: reader.u64()?,
+            side: Side::get(&mut reader)?,
+            qty: reader.u64()?,
+            instrument: reader.bytes()?,
+            px: reader.u64()?,
+            time_in_force: TimeInForce::get(&mut reader)?,
+            order_ref: reader.u32()?,
+            previous_token: reader.u64()?,
+        }),
+        MSG_EXECUTED => OuchOutbound::Executed(Executed {
+            timestamp: reader.u64()?,
+            token: reader.u64()?,
+            executed_qty: reader.u64()?,
+            px: reader.u64()?,
+            liquidity: Liquidity::get(&mut reader)?,
+        }),
+        MSG_CANCELED => OuchOutbound::Canceled(Canceled {
+            timestamp: reader.u64()?,
+            token: reader.u64()?,
+            decrement_qty: reader.u64()?,
+            reason: CanceledReason::get(&mut reader)?,
+        }),
+        MSG_REJECTED => OuchOutbound::Rejected(Rejected {
+            timestamp: reader.u64()?,
+            token: reader.u64()?,
+            reason: RejectedReason::get(&mut reader)?,
+        }),
+        MSG_CANCEL_REJECTED => OuchOutbound::CancelRejected(CancelRejected {
+            timestamp: reader.u64()?,
+            token: reader.u64()?,
+        }),
+        msg_type => return Err(CodecError::UnknownMessageType(msg_type)),
+    };
+
+    reader.finish()?;
+    Ok(message)
+}
+
+fn put_u32(out: &mut Vec<u8>, value: u32) {
+    out.extend_from_slice(&value.to_be_bytes());
+}
+
+fn put_u64(out: &mut Vec<u8>, value: u64) {
+    out.extend_from_slice(&value.to_be_bytes());
+}
+
+struct OuchReader<'a> {
+    buf: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> OuchReader<'a> {
+    fn new(buf: &'a [u8]) -> Self {
+        OuchReader { buf, pos: 0 }
+    }
+
+    fn bytes<const N: usize>(&mut self) -> Result<[u8; N], CodecError> {
+        let remaining = self.buf.len() - self.pos;
+        if remaining < N {
+            return Err(CodecError::Truncated {
+                needed: N,
+                remaining,
+            });
+        }
+        let mut bytes = [0u8; N];
+        bytes.copy_from_slice(&self.buf[self.pos..self.pos + N]);
+        self.pos += N;
+        Ok(bytes)
+    }
+
+    fn u8(&mut self) -> Result<u8, CodecError> {
+        Ok(self.bytes::<1>()?[0])
+    }
+
+    fn u32(&mut self) -> Result<u32, CodecError> {
+        Ok(u32::from_be_bytes(self.bytes()?))
+    }
+
+    fn u64(&mut self) -> Result<u64, CodecError> {
+        Ok(u64::from_be_bytes(self.bytes()?))
+    }
+
+    /// Messages are fixed length, trailing bytes are an error.
+    fn finish(&self) -> Result<(), CodecError> {
+        if self.pos != self.buf.len() {
+            return Err(CodecError::LengthMismatch {
+                declared: self.buf.len(),
+                actual: self.pos,
+            });
+        }
+        Ok(())
+    }
+}
+
+/// A one byte ASCII code.
+trait OuchCode: Sized {
+    fn put(&self, out: &mut Vec<u8>);
+    fn get(reader: &mut OuchReader) -> Result<Self, CodecError>;
+}
+
+macro_rules! ouch_code {
+    ($ty:ty, $field:literal, { $($value:literal => $variant:path),+ $(,)? }) => {
+        impl OuchCode for $ty {
+            fn put(&self, out: &mut Vec<u8>) {
+                out.push(match self {
+                    $($variant => $value,)+
+                });
+            }
+
+            fn get(reader: &mut OuchReader) -> Result<Self, CodecError> {
+                match reader.u8()? {
+                    $($value => Ok($variant),)+
+                    value => Err(CodecError::InvalidValue { field: $field, value }),
+                }
+            }
+        }
+    };
+}
+
+ouch_code!(Side, "side", { b'B' => Side::Buy, b'S' => Side::Sell });
+ouch_code!(TimeInForce, "time_in_force", {
+    b'G' => TimeInForce::GTC,
+    b'I' => TimeInForce::IOC,
+    b'F' => TimeInForce::FOK,
+});
+ouch_code!(Liquidity, "liquidity", {
+    b'A' => Liquidity::Added,
+    b'R' => Liquidity::Removed,
+});
+ouch_code!(CanceledReason, "canceled_reason", {
+    b'U' => CanceledReason::UserRequested,
+    b'Q' => CanceledReason::SelfMatchPrevention,
+    b'S' => CanceledReason::Supervisory,
+});
+ouch_code!(RejectedReason, "rejected_reason", {
+    b'H' => RejectedReason::InstrumentHalted,
+    b'Z' => RejectedReason::MaxOrderQty,
+    b'N' => RejectedReason::MaxOrderNotional,
+    b'O' => RejectedReason::MaxOpenOrders,
+    b'L' => RejectedReason::MaxOpenNotional,
+    b'P' => RejectedReason::PriceDeviation,
+    b'M' => RejectedReason::MaxPosition,
+    b'T' => RejectedReason::Throttled,
+    b'D' => RejectedReason::DuplicateToken,
+    b'X' => RejectedReason::InvalidOrder,
+    b'R' => RejectedReason::ReplaceRefused,
+});
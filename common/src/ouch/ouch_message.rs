@@ -0,0 +1,141 @@
+use crate::types::order::TimeInForce;
+use crate::types::side::Side;
+
+/// Client chosen id of an order, unique among the live orders of a session.
+pub type OrderToken = u64;
+
+/// Messages a client sends on an OUCH session.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum OuchInbound {
+    EnterOrder(EnterOrder),
+    ReplaceOrder(ReplaceOrder),
+    CancelOrder(CancelOrder),
+}
+
+/// Messages the gateway sends on an OUCH session.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum OuchOutbound {
+    Accepted(Accepted),
+    Replaced(Replaced),
+    Executed(Executed),
+    Canceled(Canceled),
+    Rejected(Rejected),
+    CancelRejected(CancelRejected),
+}
+
+/// Entered under the client id the session logged on as.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct EnterOrder {
+    pub token: OrderToken,
+    pub side: Side,
+    pub qty: u64,
+    pub instrument: [u8; 16],
+    pub px: u64,
+    pub time_in_force: TimeInForce,
+}
+
+/// Replaces a live order with a new price and quantity under `replacement_token`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ReplaceOrder {
+    pub existing_token: OrderToken,
+    pub replacement_token: OrderToken,
+    pub qty: u64,
+    pub px: u64,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct CancelOrder {
+    pub token: OrderToken,
+}
+
+/// The order is live, `order_ref` is the engine's order id. Sent before any execution of the
+/// order, with the quantity entered.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Accepted {
+    pub timestamp: u64,
+    pub token: OrderToken,
+    pub side: Side,
+    pub qty: u64,
+    pub instrument: [u8; 16],
+    pub px: u64,
+    pub time_in_force: TimeInForce,
+    pub order_ref: u32,
+}
+
+/// The replacement of `previous_token` is live, `previous_token` is gone.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Replaced {
+    pub timestamp: u64,
+    pub token: OrderToken,
+    pub side: Side,
+    pub qty: u64,
+    pub instrument: [u8; 16],
+    pub px: u64,
+    pub time_in_force: TimeInForce,
+    pub order_ref: u32,
+    pub previous_token: OrderToken,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Executed {
+    pub timestamp: u64,
+    pub token: OrderToken,
+    pub executed_qty: u64,
+    pub px: u64,
+    pub liquidity: Liquidity,
+}
+
+/// `decrement_qty` left the book, which is the whole open quantity as there are no partial
+/// cancels.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Canceled {
+    pub timestamp: u64,
+    pub token: OrderToken,
+    pub decrement_qty: u64,
+    pub reason: CanceledReason,
+}
+
+/// An Enter or Replace was refused, `token` is the new order's.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Rejected {
+    pub timestamp: u64,
+    pub token: OrderToken,
+    pub reason: RejectedReason,
+}
+
+/// A Cancel was refused, the order is unknown, not yet accepted, already being cancelled or
+/// already gone.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct CancelRejected {
+    pub timestamp: u64,
+    pub token: OrderToken,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Liquidity {
+    Added,
+    Removed,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CanceledReason {
+    UserRequested,
+    SelfMatchPrevention,
+    Supervisory,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RejectedReason {
+    InstrumentHalted,
+    MaxOrderQty,
+    MaxOrderNotional,
+    MaxOpenOrders,
+    MaxOpenNotional,
+    PriceDeviation,
+    MaxPosition,
+    Throttled,
+    DuplicateToken,
+    InvalidOrder,
+    /// The order being replaced is unknown, not yet accepted, already being cancelled or gone.
+    ReplaceRefused,
+}
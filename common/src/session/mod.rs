@@ -0,0 +1,4 @@
+pub mod session_client;
+pub mod session_codec;
+pub mod session_error;
+pub mod session_message;
@@ -0,0 +1,85 @@
+use crate::codec::framing::{encode_frame_with, read_frame_async, MAX_FRAME_LEN};
+use crate::session::session_codec::{
+    decode_server, encode_client, PACKET_SERVER_HEARTBEAT, PACKET_UNSEQUENCED_DATA,
+};
+use crate::session::session_error::SessionError;
+use crate::session::session_message::{
+    ClientPacket, LoginAccepted, LoginRequest, ServerPacket, SESSION_TIMEOUT,
+};
+use tokio::io::{AsyncRead, AsyncWrite, AsyncWriteExt};
+use tokio::time::timeout;
+
+/// Logs on over a freshly connected stream and waits for the gateway's answer.
+pub async fn logon_async<S: AsyncRead + AsyncWrite + Unpin>(
+    stream: &mut S,
+    login: &LoginRequest,
+) -> Result<LoginAccepted, SessionError> {
+    let mut scratch = Vec::new();
+    send_packet_async(stream, &ClientPacket::LoginRequest(*login), &mut scratch).await?;
+
+    let mut buffer = [0u8; MAX_FRAME_LEN];
+    let frame = read_packet_async(stream, &mut buffer).await?;
+    match decode_server(frame)? {
+        ServerPacket::LoginAccepted(accepted) => Ok(accepted),
+        ServerPacket::LoginRejected(reason) => Err(SessionError::Rejected(reason)),
+        _ => Err(SessionError::UnexpectedPacket(frame[0])),
+    }
+}
+
+pub async fn send_packet_async<W: AsyncWrite + Unpin>(
+    writer: &mut W,
+    packet: &ClientPacket<'_>,
+    scratch: &mut Vec<u8>,
+) -> Result<(), SessionError> {
+    encode_frame_with(scratch, |out| encode_client(packet, out))?;
+    writer
+        .write_all(scratch)
+        .await
+        .map_err(|e| SessionError::Frame(e.into()))
+}
+
+/// Sends the payload `encode` appends as UnsequencedData, without copying it.
+pub async fn send_data_async<W: AsyncWrite + Unpin>(
+    writer: &mut W,
+    scratch: &mut Vec<u8>,
+    encode: impl FnOnce(&mut Vec<u8>),
+) -> Result<(), SessionError> {
+    encode_frame_with(scratch, |out| {
+        out.push(PACKET_UNSEQUENCED_DATA);
+        encode(out);
+    })?;
+    writer
+        .write_all(scratch)
+        .await
+        .map_err(|e| SessionError::Frame(e.into()))
+}
+
+/// Returns the payload of the next SequencedData, skipping heartbeats.
+pub async fn read_data_async<'a, R: AsyncRead + Unpin>(
+    reader: &mut R,
+    buffer: &'a mut [u8],
+) -> Result<&'a [u8], SessionError> {
+    loop {
+        let frame = read_packet_async(reader, buffer).await?;
+        if frame.first() == Some(&PACKET_SERVER_HEARTBEAT) {
+            continue;
+        }
+
+        let len = frame.len();
+        match decode_server(frame)? {
+            ServerPacket::SequencedData(_) => return Ok(&buffer[1..len]),
+            ServerPacket::EndOfSession => return Err(SessionError::Ended),
+            _ => return Err(SessionError::UnexpectedPacket(buffer[0])),
+        }
+    }
+}
+
+async fn read_packet_async<'a, R: AsyncRead + Unpin>(
+    reader: &mut R,
+    buffer: &'a mut [u8],
+) -> Result<&'a [u8], SessionError> {
+    timeout(SESSION_TIMEOUT, read_frame_async(reader, buffer))
+        .await
+        .map_err(|_| SessionError::TimedOut)?
+        .map_err(SessionError::Frame)
+}
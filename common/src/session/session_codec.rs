@@ -0,0 +1,100 @@
+use crate::codec::codec_error::CodecError;
+use crate::codec::wire::WireReader;
+use crate::session::session_message::{
+    ClientPacket, LoginAccepted, LoginRejectReason, LoginRequest, ServerPacket,
+};
+
+// Every packet is a one byte ASCII type, fields are big endian and data packets carry the
+// rest of the frame as their payload.
+pub const PACKET_LOGIN_REQUEST: u8 = b'L';
+pub const PACKET_UNSEQUENCED_DATA: u8 = b'U';
+pub const PACKET_CLIENT_HEARTBEAT: u8 = b'R';
+pub const PACKET_LOGOUT_REQUEST: u8 = b'O';
+pub const PACKET_LOGIN_ACCEPTED: u8 = b'A';
+pub const PACKET_LOGIN_REJECTED: u8 = b'J';
+pub const PACKET_SEQUENCED_DATA: u8 = b'S';
+pub const PACKET_SERVER_HEARTBEAT: u8 = b'H';
+pub const PACKET_END_OF_SESSION: u8 = b'Z';
+
+pub fn encode_client(packet: &ClientPacket, out: &mut Vec<u8>) {
+    match packet {
+        ClientPacket::LoginRequest(login) => {
+            out.push(PACKET_LOGIN_REQUEST);
+            out.extend_from_slice(&login.username);
+            out.extend_from_slice(&login.password);
+            out.extend_from_slice(&login.sequence.to_be_bytes());
+        }
+        ClientPacket::UnsequencedData(payload) => {
+            out.push(PACKET_UNSEQUENCED_DATA);
+            out.extend_from_slice(payload);
+        }
+        ClientPacket::ClientHeartbeat => out.push(PACKET_CLIENT_HEARTBEAT),
+        ClientPacket::LogoutRequest => out.push(PACKET_LOGOUT_REQUEST),
+    }
+}
+
+pub fn decode_client(buf: &[u8]) -> Result<ClientPacket<'_>, CodecError> {
+    let mut reader = WireReader::new(buf);
+    let packet = match reader.u8()? {
+        PACKET_LOGIN_REQUEST => ClientPacket::LoginRequest(LoginRequest {
+            username: reader.bytes()?,
+            password: reader.bytes()?,
+            sequence: u64::from_be_bytes(reader.bytes()?),
+        }),
+        PACKET_UNSEQUENCED_DATA => ClientPacket::UnsequencedData(reader.slice(reader.remaining())?),
+        PACKET_CLIENT_HEARTBEAT => ClientPacket::ClientHeartbeat,
+        PACKET_LOGOUT_REQUEST => ClientPacket::LogoutRequest,
+        packet_type => return Err(CodecError::UnknownMessageType(packet_type)),
+    };
+    reader.finish()?;
+    Ok(packet)
+}
+
+pub fn encode_server(packet: &ServerPacket, out: &mut Vec<u8>) {
+    match packet {
+        ServerPacket::LoginAccepted(accepted) => {
+            out.push(PACKET_LOGIN_ACCEPTED);
+            out.extend_from_slice(&accepted.client_id.to_be_bytes());
+            out.extend_from_slice(&accepted.sequence.to_be_bytes());
+        }
+        ServerPacket::LoginRejected(reason) => {
+            out.push(PACKET_LOGIN_REJECTED);
+            out.push(match reason {
+                LoginRejectReason::NotAuthorized => b'A',
+                LoginRejectReason::SessionUnavailable => b'S',
+            });
+        }
+        ServerPacket::SequencedData(payload) => {
+            out.push(PACKET_SEQUENCED_DATA);
+            out.extend_from_slice(payload);
+        }
+        ServerPacket::ServerHeartbeat => out.push(PACKET_SERVER_HEARTBEAT),
+        ServerPacket::EndOfSession => out.push(PACKET_END_OF_SESSION),
+    }
+}
+
+pub fn decode_server(buf: &[u8]) -> Result<ServerPacket<'_>, CodecError> {
+    let mut reader = WireReader::new(buf);
+    let packet = match reader.u8()? {
+        PACKET_LOGIN_ACCEPTED => ServerPacket::LoginAccepted(LoginAccepted {
+            client_id: u32::from_be_bytes(reader.bytes()?),
+            sequence: u64::from_be_bytes(reader.bytes()?),
+        }),
+        PACKET_LOGIN_REJECTED => ServerPacket::LoginRejected(match reader.u8()? {
+            b'A' => LoginRejectReason::NotAuthorized,
+            b'S' => LoginRejectReason::SessionUnavailable,
+            value => {
+                return Err(CodecError::InvalidValue {
+                    field: "login_reject_reason",
+                    value,
+                })
+            }
+        }),
+        PACKET_SEQUENCED_DATA => ServerPacket::SequencedData(reader.slice(reader.remaining())?),
+        PACKET_SERVER_HEARTBEAT => ServerPacket::ServerHeartbeat,
+        PACKET_END_OF_SESSION => ServerPacket::EndOfSession,
+        packet_type => return Err(CodecError::UnknownMessageType(packet_type)),
+    };
+    reader.finish()?;
+    Ok(packet)
+}
@@ -0,0 +1,45 @@
+use crate::codec::codec_error::CodecError;
+use crate::codec::frame_error::FrameError;
+use crate::session::session_message::LoginRejectReason;
+use std::error::Error;
+use std::fmt::{Display, Formatter};
+
+#[derive(Debug)]
+pub enum SessionError {
+    Rejected(LoginRejectReason),
+    /// A packet that is not valid at this point of the session, e.g. data before logon.
+    UnexpectedPacket(u8),
+    /// Nothing, not even a heartbeat, was received within the session timeout.
+    TimedOut,
+    /// The gateway ended the session.
+    Ended,
+    Frame(FrameError),
+}
+
+impl Display for SessionError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SessionError::Rejected(reason) => write!(f, "login rejected: {:?}", reason),
+            SessionError::UnexpectedPacket(packet_type) => {
+                write!(f, "unexpected packet type {}", *packet_type as char)
+            }
+            SessionError::TimedOut => write!(f, "session timed out"),
+            SessionError::Ended => write!(f, "session ended"),
+            SessionError::Frame(e) => write!(f, "{}", e),
+        }
+    }
+}
+
+impl Error for SessionError {}
+
+impl From<FrameError> for SessionError {
+    fn from(e: FrameError) -> Self {
+        SessionError::Frame(e)
+    }
+}
+
+impl From<CodecError> for SessionError {
+    fn from(e: CodecError) -> Self {
+        SessionError::Frame(FrameError::Codec(e))
+    }
+}
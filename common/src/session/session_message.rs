@@ -0,0 +1,78 @@
+use std::time::Duration;
+
+/// Either side sends a heartbeat after this long without sending anything else.
+pub const HEARTBEAT_INTERVAL: Duration = Duration::from_secs(1);
+/// A peer that sends nothing, not even a heartbeat, for this long is considered gone.
+pub const SESSION_TIMEOUT: Duration = Duration::from_secs(15);
+/// Usernames and passwords are fixed width, NUL padded.
+pub const CREDENTIAL_LEN: usize = 16;
+
+/// Packets a client sends on a gateway session. Application messages (OUCH or engine messages,
+/// depending on the port) are carried as the payload of UnsequencedData.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ClientPacket<'a> {
+    LoginRequest(LoginRequest),
+    UnsequencedData(&'a [u8]),
+    ClientHeartbeat,
+    LogoutRequest,
+}
+
+/// Packets the gateway sends on a session, application messages are SequencedData.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ServerPacket<'a> {
+    LoginAccepted(LoginAccepted),
+    LoginRejected(LoginRejectReason),
+    SequencedData(&'a [u8]),
+    ServerHeartbeat,
+    EndOfSession,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct LoginRequest {
+    pub username: [u8; CREDENTIAL_LEN],
+    pub password: [u8; CREDENTIAL_LEN],
+    /// Sequence number of the next SequencedData the client expects, one past the last it saw.
+    /// 0 starts at the live stream without a replay.
+    pub sequence: u64,
+}
+
+/// The identity the session's orders are entered under, and the sequence number of the first
+/// SequencedData the gateway sends. Each following SequencedData is one higher. A sequence
+/// above the requested one means the messages in between are no longer retained, below it that
+/// the gateway's sequence space was restarted.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct LoginAccepted {
+    pub client_id: u32,
+    pub sequence: u64,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum LoginRejectReason {
+    NotAuthorized,
+    /// The user already has a live session.
+    SessionUnavailable,
+}
+
+impl LoginRequest {
+    /// Logs on to the live stream.
+    pub fn new(username: &str, password: &str) -> LoginRequest {
+        LoginRequest {
+            username: credential(username),
+            password: credential(password),
+            sequence: 0,
+        }
+    }
+
+    /// Logs on again, asking for everything from `sequence` on to be replayed first.
+    pub fn resume(self, sequence: u64) -> LoginRequest {
+        LoginRequest { sequence, ..self }
+    }
+}
+
+/// NUL padded, longer values are cut to [`CREDENTIAL_LEN`] bytes.
+pub fn credential(value: &str) -> [u8; CREDENTIAL_LEN] {
+    let mut buffer = [0u8; CREDENTIAL_LEN];
+    let len = value.len().min(CREDENTIAL_LEN);
+    buffer[..len].copy_from_slice(&value.as_bytes()[..len]);
+    buffer
+}
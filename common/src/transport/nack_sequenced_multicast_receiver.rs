@@ -1,41 +1,105 @@
+use crate::codec::message_codec::{decode_datagram, encode_range_nack, Datagram};
 use crate::memory::ring_buffer::RingBuffer;
 use crate::memory::ring_slot::TransportRingSlot;
 use crate::network::mutlicast::multicast_sender;
 use crate::network::network_constants::MAX_UDP_PACKET_SIZE;
-use crate::serialize::serialize::{as_bytes, from_bytes};
 use crate::transport::sequenced_message::{
-    SequenceNumber, SequencedEngineMessage, SequencedMessageRangeNack,
+    SenderEpoch, SenderId, SequenceNumber, SequencedEngineMessage, SequencedHeartbeat,
+    SequencedMessageRangeNack,
 };
-use crate::transport::transport_constants::MAX_MESSAGE_RETRANSMISSION_RING;
-use crate::transport::zero_alloc::RawWireMessage;
-use crate::util::limit_spin::limit_spin;
+use crate::transport::transport_constants::{MAX_SENDERS, SENDER_SILENT_TIMEOUT};
 use crate::util::time::system_nanos;
+use std::fmt::{Display, Formatter};
 use std::io::ErrorKind;
-use std::mem::MaybeUninit;
 use std::net::{SocketAddr, UdpSocket};
-use std::sync::atomic::Ordering;
-use std::sync::Arc;
+use std::sync::atomic::{AtomicU32, AtomicU64, Ordering};
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::sync::{Arc, Mutex, OnceLock};
 use std::thread;
 use std::time::Duration;
 
 const NACK_INTERVAL_NS: u64 = 20_000;
 
+/// Changes in the state of a stream's senders, for the application to act on.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum TransportEvent {
+    /// Nothing, not even a heartbeat, has been heard from the sender for the silent timeout.
+    SenderSilent(SenderId),
+    /// A silent sender has been heard from again.
+    SenderResumed(SenderId),
+    /// The sender started over under a new epoch. Whatever it had not delivered before is lost,
+    /// its messages from here on are numbered from 1 again.
+    SenderRestarted(SenderId),
+}
+
+impl Display for TransportEvent {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            TransportEvent::SenderSilent(sender_id) => {
+                write!(
+                    f,
+                    "sender {} silent for {:?}",
+                    sender_id, SENDER_SILENT_TIMEOUT
+                )
+            }
+            TransportEvent::SenderResumed(sender_id) => write!(f, "sender {} resumed", sender_id),
+            TransportEvent::SenderRestarted(sender_id) => {
+                write!(f, "sender {} restarted", sender_id)
+            }
+        }
+    }
+}
+
+/// Receives every sender on a stream and merges them. Each sender's messages are delivered in
+/// its own sequence order with gaps NACKed to that sender, and the senders are merged by
+/// `sent_time`, ties going to the lower sender id, so the merged order doesn't depend on when
+/// datagrams arrive. A message is held back until every other sender is known to have nothing
+/// earlier, from a later message or a heartbeat's watermark, so a gap or an idle sender holds up
+/// the others until it is NACKed back or the next heartbeat. A sender not yet heard from, or
+/// silent for [`SENDER_SILENT_TIMEOUT`], is left out of the merge. A sender that restarts is
+/// followed onto its new epoch, see [`TransportEvent::SenderRestarted`].
 pub struct NackSequencedMulticastReceiver {
-    last_seen_sequence_number: SequenceNumber,
-    transport_ring: Arc<Vec<TransportRingSlot<SequencedEngineMessage>>>,
-    nack_ring: Arc<RingBuffer>,
+    streams: Arc<SenderStreams>,
+    epochs: [SenderEpoch; MAX_SENDERS],
+    last_seen_sequence_numbers: [SequenceNumber; MAX_SENDERS],
+    /// Each sender's next message in order, taken from its ring and waiting its turn.
+    heads: [Option<SequencedEngineMessage>; MAX_SENDERS],
+    /// The `sent_time` of each sender's last message taken, its later ones are no earlier.
+    last_sent_times: [u64; MAX_SENDERS],
+    tx_events: Sender<TransportEvent>,
+    rx_events: Receiver<TransportEvent>,
 }
 
-impl NackSequencedMulticastReceiver {
-    pub fn new(recv_socket: UdpSocket, nack_port: u16) -> Self {
-        let recv_side_ring: Arc<Vec<TransportRingSlot<SequencedEngineMessage>>> = Arc::new(
-            (0..MAX_MESSAGE_RETRANSMISSION_RING)
-                .map(|_| TransportRingSlot::new())
-                .collect(),
-        );
+/// Indexed by sender id, a sender's ring of `ring_len` slots is allocated when it is first heard
+/// from.
+struct SenderStreams {
+    streams: [OnceLock<SenderStream>; MAX_SENDERS],
+    ring_len: usize,
+}
+
+struct SenderStream {
+    transport_ring: Vec<TransportRingSlot<SequencedEngineMessage>>,
+    nack_ring: RingBuffer,
+    /// The sender's current epoch, 0 until it is first heard from. Anything from another one is
+    /// dropped unless it shows the sender restarted, see `enter_epoch`.
+    epoch: AtomicU64,
+    /// The highest sequence number the sender is known to have sent, from its messages and
+    /// heartbeats. Anything missing up to it is NACKed.
+    latest_sequence_number: AtomicU32,
+    last_heard_ns: AtomicU64,
+    /// The latest heartbeat's epoch, sequence number and watermark.
+    watermark: Mutex<(SenderEpoch, SequenceNumber, u64)>,
+}
 
-        let nack_ring = Arc::new(RingBuffer::new(128));
-        let nack_ring_for_nack_sender = nack_ring.clone();
+impl NackSequencedMulticastReceiver {
+    /// `ring_len` is the number of messages buffered per sender, `transport.receive_ring_len`.
+    pub fn new(recv_socket: UdpSocket, nack_port: u16, ring_len: usize) -> Self {
+        let streams = Arc::new(SenderStreams {
+            streams: std::array::from_fn(|_| OnceLock::new()),
+            ring_len,
+        });
+        let (tx_events, rx_events) = mpsc::channel();
+        let tx_events_for_liveness = tx_events.clone();
 
         // Single socket for NACK send and retrans receive (replies come back to the source port)
         let nack_io_socket = multicast_sender();
@@ -43,92 +107,46 @@ impl NackSequencedMulticastReceiver {
 
         let nack_send_socket = nack_io_socket;
 
-        let ring_for_main = recv_side_ring.clone();
-        let ring_for_retrans = recv_side_ring.clone();
+        let streams_for_main = streams.clone();
+        let streams_for_retrans = streams.clone();
+        let streams_for_nack_sender = streams.clone();
 
         // Thread: multicast feed
-        thread::spawn(move || {
-            let mut rx_buf = [0u8; MAX_UDP_PACKET_SIZE];
-
-            loop {
-                limit_spin();
-                match recv_socket.recv_from(&mut rx_buf) {
-                    Ok((size, _src)) => {
-                        let raw_wire_msg = from_bytes::<RawWireMessage>(&rx_buf[..size]);
-                        for batch_idx in 0..raw_wire_msg.batch_size {
-                            let mut msg = MaybeUninit::<SequencedEngineMessage>::uninit();
-
-                            let src = &raw_wire_msg.batch[batch_idx as usize]
-                                as *const SequencedEngineMessage;
-                            let dst = msg.as_mut_ptr();
-
-                            unsafe {
-                                std::ptr::copy_nonoverlapping(src, dst, 1);
-                                let msg = msg.assume_init();
-                                let idx =
-                                    msg.sequence_number as usize % MAX_MESSAGE_RETRANSMISSION_RING;
-                                let slot = &ring_for_main[idx];
-                                slot.store(msg.sequence_number, msg);
-                            }
-                        }
-                    }
-                    Err(e) if e.kind() == ErrorKind::WouldBlock => thread::yield_now(),
-                    Err(e) => eprintln!("MAIN_RECV error: {:?}", e),
-                }
-            }
-        });
+        thread::spawn(move || receive(recv_socket, &streams_for_main, "MAIN_RECV"));
 
         // Thread: retransmit receiver
-        thread::spawn(move || {
-            let mut rx_buf = [0u8; MAX_UDP_PACKET_SIZE];
+        thread::spawn(move || receive(retrans_recv_socket, &streams_for_retrans, "RETRANS_RECV"));
 
-            loop {
-                match retrans_recv_socket.recv_from(&mut rx_buf) {
-                    Ok((size, _src)) => {
-                        let raw_wire_msg = from_bytes::<RawWireMessage>(&rx_buf[..size]);
-                        for batch_idx in 0..raw_wire_msg.batch_size {
-                            let mut msg = MaybeUninit::<SequencedEngineMessage>::uninit();
-
-                            let src = &raw_wire_msg.batch[batch_idx as usize]
-                                as *const SequencedEngineMessage;
-                            let dst = msg.as_mut_ptr();
-
-                            unsafe {
-                                std::ptr::copy_nonoverlapping(src, dst, 1);
-                                let msg = msg.assume_init();
-                                let idx =
-                                    msg.sequence_number as usize % MAX_MESSAGE_RETRANSMISSION_RING;
-                                let slot = &ring_for_retrans[idx];
-                                slot.store(msg.sequence_number, msg);
-                            }
-                        }
-                    }
-                    Err(e) if e.kind() == ErrorKind::WouldBlock => thread::yield_now(),
-                    Err(e) => eprintln!("RETRANS_RECV error: {:?}", e),
-                }
-            }
-        });
-
-        // Thread: periodic NACK sender — uses the shared last_seen_atomic and outstanding map.
+        // Thread: periodic NACK sender, drains each sender's NACK ring into range NACKs for it and
+        // watches for senders going silent.
         thread::spawn(move || {
             let mut nack_batch = Vec::<u32>::with_capacity(128);
+            let mut nack_buf = Vec::with_capacity(MAX_UDP_PACKET_SIZE);
             let nack_listen_addr = SocketAddr::from(([239, 255, 0, 1], nack_port));
+            let mut liveness = Liveness::new(tx_events_for_liveness);
 
             loop {
-                let mut nack_seen = [false; 128];
-                while let Some(seq) = nack_ring_for_nack_sender.pop() {
-                    let idx = (seq % 128) as usize;
-                    if nack_seen[idx] {
-                        continue;
+                for (sender_id, stream) in streams_for_nack_sender.iter() {
+                    liveness.check(sender_id, stream);
+                    let epoch = stream.epoch.load(Ordering::Acquire);
+
+                    let mut nack_seen = [false; 128];
+                    while let Some(seq) = stream.nack_ring.pop() {
+                        let idx = (seq % 128) as usize;
+                        if nack_seen[idx] {
+                            continue;
+                        }
+                        nack_seen[idx] = true;
+                        nack_batch.push(seq);
+                        if nack_batch.len() >= 64 {
+                            break;
+                        }
                     }
-                    nack_seen[idx] = true;
-                    nack_batch.push(seq);
-                    if nack_batch.len() >= 64 {
-                        break;
+
+                    if nack_batch.is_empty() {
+                        continue;
                     }
-                }
 
-                if !nack_batch.is_empty() {
                     // sort to detect contiguous ranges
                     // nack_batch.sort_unstable();
                     let mut start = nack_batch[0];
@@ -136,24 +154,26 @@ impl NackSequencedMulticastReceiver {
 
                     for &s in &nack_batch[1..] {
                         if s != prev + 1 {
-                            let nack = SequencedMessageRangeNack { start, end: prev };
-                            nack_send_socket
-                                .send_to(
-                                    as_bytes::<SequencedMessageRangeNack>(&nack),
-                                    nack_listen_addr,
-                                )
-                                .unwrap();
+                            let nack = SequencedMessageRangeNack {
+                                sender_id,
+                                epoch,
+                                start,
+                                end: prev,
+                            };
+                            send_nack(&nack_send_socket, &nack, &mut nack_buf, nack_listen_addr);
                             start = s;
                         }
                         prev = s;
                     }
 
                     // final range
-                    let nack = SequencedMessageRangeNack { start, end: prev };
-                    let _ = nack_send_socket.send_to(
-                        as_bytes::<SequencedMessageRangeNack>(&nack),
-                        nack_listen_addr,
-                    );
+                    let nack = SequencedMessageRangeNack {
+                        sender_id,
+                        epoch,
+                        start,
+                        end: prev,
+                    };
+                    send_nack(&nack_send_socket, &nack, &mut nack_buf, nack_listen_addr);
 
                     nack_batch.clear();
                 }
@@ -163,26 +183,271 @@ impl NackSequencedMulticastReceiver {
         });
 
         NackSequencedMulticastReceiver {
-            last_seen_sequence_number: 0,
-            transport_ring: recv_side_ring,
-            nack_ring,
+            streams,
+            epochs: [0; MAX_SENDERS],
+            last_seen_sequence_numbers: [0; MAX_SENDERS],
+            heads: std::array::from_fn(|_| None),
+            last_sent_times: [0; MAX_SENDERS],
+            tx_events,
+            rx_events,
         }
     }
 
     pub fn try_recv(&mut self) -> Option<SequencedEngineMessage> {
-        limit_spin();
-        let expected_sequence_number = self.last_seen_sequence_number + 1;
-        let index = expected_sequence_number as usize % MAX_MESSAGE_RETRANSMISSION_RING;
+        for (sender_id, stream) in self.streams.iter() {
+            let sender = sender_id as usize;
+
+            let epoch = stream.epoch.load(Ordering::Acquire);
+            if epoch != self.epochs[sender] {
+                let restarted = self.epochs[sender] != 0;
+                self.epochs[sender] = epoch;
+                self.last_seen_sequence_numbers[sender] = 0;
+                self.heads[sender] = None;
+                self.last_sent_times[sender] = 0;
+
+                if restarted {
+                    let _ = self
+                        .tx_events
+                        .send(TransportEvent::SenderRestarted(sender_id));
+                    // the application sees the restart before the new epoch's messages
+                    return None;
+                }
+            }
+
+            if self.heads[sender].is_none() {
+                let last_seen_sequence_number = &mut self.last_seen_sequence_numbers[sender];
+                self.heads[sender] = stream.try_recv(epoch, last_seen_sequence_number);
+                if let Some(head) = &self.heads[sender] {
+                    self.last_sent_times[sender] = head.sent_time;
+                }
+            }
+        }
+
+        let (sent_time, sender) = self
+            .heads
+            .iter()
+            .enumerate()
+            .filter_map(|(sender, head)| Some((head.as_ref()?.sent_time, sender)))
+            .min()?;
+
+        // wait for any sender that could still send something earlier
+        for (other_id, stream) in self.streams.iter() {
+            let other = other_id as usize;
+            if other == sender || self.heads[other].is_some() || stream.quiet() {
+                continue;
+            }
+            let sent_from = stream
+                .watermark(self.epochs[other], self.last_seen_sequence_numbers[other])
+                .max(self.last_sent_times[other]);
+            if (sent_from, other) < (sent_time, sender) {
+                return None;
+            }
+        }
+
+        self.heads[sender].take()
+    }
+
+    /// The next change in a sender's state, if any. Poll it whenever `try_recv` comes back empty.
+    pub fn poll_event(&mut self) -> Option<TransportEvent> {
+        self.rx_events.try_recv().ok()
+    }
+}
+
+/// A NACK that fails to go out is asked for again once the gap is next noticed, by the next
+/// message or heartbeat from the sender, so the failure is only logged.
+fn send_nack(
+    socket: &UdpSocket,
+    nack: &SequencedMessageRangeNack,
+    nack_buf: &mut Vec<u8>,
+    nack_addr: SocketAddr,
+) {
+    nack_buf.clear();
+    encode_range_nack(nack, nack_buf);
+    if let Err(e) = socket.send_to(nack_buf, nack_addr) {
+        eprintln!(
+            "Cannot NACK {} to {} from sender {}: {}",
+            nack.start, nack.end, nack.sender_id, e
+        );
+    }
+}
+
+/// Reads datagrams into the senders' rings until the socket fails for good.
+fn receive(socket: UdpSocket, streams: &SenderStreams, name: &str) {
+    let mut rx_buf = [0u8; MAX_UDP_PACKET_SIZE];
+
+    loop {
+        match socket.recv_from(&mut rx_buf) {
+            Ok((size, _src)) => {
+                // a malformed message is dropped and recovered through a NACK
+                let batch = match decode_datagram(&rx_buf[..size]) {
+                    Ok(Datagram::Batch(batch)) => batch,
+                    Ok(Datagram::Heartbeat(heartbeat)) => {
+                        if let Some(stream) = streams.stream(heartbeat.sender_id) {
+                            if stream.enter_epoch(heartbeat.epoch) {
+                                stream.heartbeat(&heartbeat);
+                            }
+                        }
+                        continue;
+                    }
+                    Err(e) => {
+                        eprintln!("Dropping malformed batch: {}", e);
+                        continue;
+                    }
+                };
+
+                for msg in batch {
+                    let msg = match msg {
+                        Ok(msg) => msg,
+                        Err(e) => {
+                            eprintln!("Dropping malformed message: {}", e);
+                            break;
+                        }
+                    };
+
+                    if let Some(stream) = streams.stream(msg.sender_id) {
+                        if stream.enter_epoch(msg.epoch) {
+                            stream.store(msg);
+                        }
+                    }
+                }
+            }
+            Err(e) if e.kind() == ErrorKind::WouldBlock => thread::yield_now(),
+            Err(e) => eprintln!("{} error: {:?}", name, e),
+        }
+    }
+}
+
+impl SenderStreams {
+    /// The sender's stream, created on first use. `None` if the sender id is out of range.
+    fn stream(&self, sender_id: SenderId) -> Option<&SenderStream> {
+        let Some(stream) = self.streams.get(sender_id as usize) else {
+            eprintln!(
+                "Dropping datagram from sender {}, sender ids stop at {}",
+                sender_id,
+                MAX_SENDERS - 1
+            );
+            return None;
+        };
+        Some(stream.get_or_init(|| SenderStream::new(self.ring_len)))
+    }
+
+    fn iter(&self) -> impl Iterator<Item = (SenderId, &SenderStream)> {
+        self.streams
+            .iter()
+            .enumerate()
+            .filter_map(|(sender_id, stream)| Some((sender_id as SenderId, stream.get()?)))
+    }
+}
+
+impl SenderStream {
+    fn new(ring_len: usize) -> SenderStream {
+        SenderStream {
+            transport_ring: (0..ring_len).map(|_| TransportRingSlot::new()).collect(),
+            nack_ring: RingBuffer::new(128),
+            epoch: AtomicU64::new(0),
+            latest_sequence_number: AtomicU32::new(0),
+            last_heard_ns: AtomicU64::new(system_nanos()),
+            watermark: Mutex::new((0, 0, 0)),
+        }
+    }
+
+    fn store(&self, msg: SequencedEngineMessage) {
+        let sequence_number = msg.sequence_number;
+        let idx = sequence_number as usize % self.transport_ring.len();
+        self.transport_ring[idx].store(sequence_number, msg);
+        self.heard(sequence_number);
+    }
+
+    /// Whether to keep what the sender sent under `epoch`. A later epoch than the stream's
+    /// means the sender restarted, the stream moves on to it and forgets how far the old one got.
+    /// An earlier one is only taken as a restart once the current epoch has gone quiet, the
+    /// restarted sender's clock may have stepped back, before that it is a stale datagram.
+    fn enter_epoch(&self, epoch: SenderEpoch) -> bool {
+        let current = self.epoch.load(Ordering::Acquire);
+        if epoch == current {
+            return true;
+        }
+        if epoch < current && !self.quiet() {
+            return false;
+        }
+
+        // both receive threads get here for the same restart, one of them moves the stream
+        match self
+            .epoch
+            .compare_exchange(current, epoch, Ordering::AcqRel, Ordering::Acquire)
+        {
+            Ok(_) => {
+                self.latest_sequence_number.store(0, Ordering::Release);
+                true
+            }
+            Err(moved_to) => moved_to == epoch,
+        }
+    }
+
+    fn heartbeat(&self, heartbeat: &SequencedHeartbeat) {
+        let mut watermark = self.watermark.lock().unwrap();
+        // heartbeats can arrive out of order, the latest one holds the most
+        if heartbeat.epoch != watermark.0 || heartbeat.watermark_ns > watermark.2 {
+            *watermark = (
+                heartbeat.epoch,
+                heartbeat.sequence_number,
+                heartbeat.watermark_ns,
+            );
+        }
+        drop(watermark);
+        self.heard(heartbeat.sequence_number);
+    }
+
+    /// The earliest `sent_time` the sender's messages after `last_seen_sequence_number` can have,
+    /// by its latest heartbeat. 0 while a message the heartbeat covers is still missing.
+    fn watermark(&self, epoch: SenderEpoch, last_seen_sequence_number: SequenceNumber) -> u64 {
+        let (heartbeat_epoch, sequence_number, watermark_ns) = *self.watermark.lock().unwrap();
+        if heartbeat_epoch == epoch && last_seen_sequence_number >= sequence_number {
+            watermark_ns
+        } else {
+            0
+        }
+    }
+
+    /// Nothing has been heard under the current epoch for the silent timeout.
+    fn quiet(&self) -> bool {
+        let quiet_ns = system_nanos().saturating_sub(self.last_heard_ns.load(Ordering::Relaxed));
+        quiet_ns > SENDER_SILENT_TIMEOUT.as_nanos() as u64
+    }
+
+    fn heard(&self, sequence_number: SequenceNumber) {
+        self.latest_sequence_number
+            .fetch_max(sequence_number, Ordering::AcqRel);
+        self.last_heard_ns.store(system_nanos(), Ordering::Relaxed);
+    }
+
+    fn try_recv(
+        &self,
+        epoch: SenderEpoch,
+        last_seen_sequence_number: &mut SequenceNumber,
+    ) -> Option<SequencedEngineMessage> {
+        let expected_sequence_number = *last_seen_sequence_number + 1;
+        let index = expected_sequence_number as usize % self.transport_ring.len();
         let slot = &self.transport_ring[index];
 
-        if let Some(msg) = slot.load(expected_sequence_number) {
-            self.last_seen_sequence_number = msg.sequence_number;
+        // a slot still holding the previous epoch's message of the same number counts as empty
+        let msg = slot
+            .load(expected_sequence_number)
+            .filter(|msg| msg.epoch == epoch);
+
+        if let Some(msg) = msg {
+            *last_seen_sequence_number = msg.sequence_number;
 
             slot.pending_nack.store(false, Ordering::Release);
             slot.last_nack_ns.store(0, Ordering::Relaxed);
 
-            return Some(msg);
+            Some(msg)
         } else {
+            // caught up, nothing to NACK until the sender sends more
+            if expected_sequence_number > self.latest_sequence_number.load(Ordering::Acquire) {
+                return None;
+            }
+
             let now = system_nanos();
             let pending_nack = slot.pending_nack.swap(true, Ordering::AcqRel);
 
@@ -193,7 +458,40 @@ impl NackSequencedMulticastReceiver {
                 self.nack_ring.push(expected_sequence_number);
             }
 
-            return None;
+            None
         }
     }
 }
+
+/// Which senders have gone silent, owned by the NACK thread.
+struct Liveness {
+    silent: [bool; MAX_SENDERS],
+    tx_events: Sender<TransportEvent>,
+}
+
+impl Liveness {
+    fn new(tx_events: Sender<TransportEvent>) -> Liveness {
+        Liveness {
+            silent: [false; MAX_SENDERS],
+            tx_events,
+        }
+    }
+
+    fn check(&mut self, sender_id: SenderId, stream: &SenderStream) {
+        let silent = stream.quiet();
+
+        let was_silent = &mut self.silent[sender_id as usize];
+        if silent == *was_silent {
+            return;
+        }
+        *was_silent = silent;
+
+        let event = if silent {
+            TransportEvent::SenderSilent(sender_id)
+        } else {
+            TransportEvent::SenderResumed(sender_id)
+        };
+        // the receiver may have been dropped, nobody is left to tell
+        let _ = self.tx_events.send(event);
+    }
+}
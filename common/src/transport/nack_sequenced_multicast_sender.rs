@@ -1,67 +1,138 @@
+use crate::config::app_config::BatchingConfig;
 use crate::memory::ring_slot::TransportRingSlot;
 use crate::network::mutlicast::multicast_receiver;
 use crate::network::network_constants::MAX_UDP_PACKET_SIZE;
 use crate::transport::sequenced_message::{
-    EngineMessage, SequenceNumber, SequencedEngineMessage, SequencedMessageRangeNack,
-    MAX_UDP_MSG_BATCH_SIZE,
+    EngineMessage, SenderEpoch, SenderId, SequenceNumber, SequencedEngineMessage,
+    SequencedHeartbeat,
 };
-use crate::transport::transport_constants::MAX_MESSAGE_RETRANSMISSION_RING;
+use crate::transport::transport_constants::SENDER_HEARTBEAT_INTERVAL;
 use crate::util::time::system_nanos;
 use std::io::ErrorKind;
 
-use crate::serialize::serialize::{as_bytes, from_bytes};
+use crate::codec::message_codec::{decode_range_nack, encode_heartbeat};
 use crate::transport::zero_alloc::RawWireMessage;
-use crate::util::limit_spin::limit_spin;
 use std::net::{SocketAddr, UdpSocket};
+use std::sync::atomic::{AtomicU32, AtomicU64, Ordering};
 use std::sync::Arc;
 use std::thread;
 
-const MAX_FLUSH_GAP_NS: u64 = 10_000;
+/// `pending_since` while the first message of a batch is taking its time.
+const PENDING_UNTIMED: u64 = u64::MAX;
 
 pub struct NackSequencedMulticastSender {
     socket: Arc<UdpSocket>,
     socket_addr: SocketAddr,
+    sender_id: SenderId,
+    epoch: SenderEpoch,
     sequence_number: SequenceNumber,
     resend_ring: Arc<Vec<TransportRingSlot<SequencedEngineMessage>>>,
     raw_batch: RawWireMessage,
-    last_flush_ns: u64,
+    max_datagram_len: usize,
+    batch_size: u16,
+    max_flush_delay_ns: u64,
+    /// When the first message of the current batch was sent.
+    batch_started_ns: u64,
+    /// The last sequence number in the batch and the last one flushed, which heartbeats carry.
+    last_batched: SequenceNumber,
+    last_flushed: Arc<AtomicU32>,
+    /// Never goes back, so `sent_time` orders this sender's messages and heartbeat watermarks
+    /// hold for every message sent after them.
+    clock: Arc<AtomicU64>,
+    /// The `sent_time` of the first message waiting in the batch, 0 once it is flushed.
+    pending_since: Arc<AtomicU64>,
+    stamp_engine_publish: bool,
 }
 
 impl NackSequencedMulticastSender {
-    pub fn new(socket: UdpSocket, socket_addr: SocketAddr, nack_port: u16) -> Self {
-        let resend_ring: Arc<Vec<TransportRingSlot<SequencedEngineMessage>>> = Arc::new(
-            (0..MAX_MESSAGE_RETRANSMISSION_RING)
-                .map(|_| TransportRingSlot::new())
-                .collect(),
-        );
+    /// `sender_id` tells this sender's messages apart from those of other senders on the same
+    /// stream, it has to be unique among them. The last `ring_len` messages are kept for
+    /// retransmission, `transport.retransmit_ring_len`.
+    ///
+    /// `send` only flushes full or overdue batches, callers should `flush` whenever they run out
+    /// of messages so the tail of a burst is not held back until the next one.
+    pub fn new(
+        socket: UdpSocket,
+        socket_addr: SocketAddr,
+        nack_port: u16,
+        sender_id: SenderId,
+        batching: BatchingConfig,
+        ring_len: usize,
+    ) -> Self {
+        // tells receivers this sender's sequence numbers start over
+        let epoch = system_nanos();
+        let resend_ring: Arc<Vec<TransportRingSlot<SequencedEngineMessage>>> =
+            Arc::new((0..ring_len).map(|_| TransportRingSlot::new()).collect());
 
         let send_socket = Arc::new(socket);
+        let last_flushed = Arc::new(AtomicU32::new(0));
 
         // Spawn NACK listener/retransmitter thread
         let nack_ring = resend_ring.clone();
         let nack_socket = multicast_receiver(nack_port);
+        let max_datagram_len = batching.max_datagram_len();
+        // weak, so the heartbeat thread still sees when the sender is dropped
+        let nack_last_flushed = Arc::downgrade(&last_flushed);
 
         thread::spawn(move || {
             let mut rx_buf = [0u8; MAX_UDP_PACKET_SIZE];
+            let mut raw_wire_msg = RawWireMessage::new(max_datagram_len);
+            let mut last_sent = 0;
 
             loop {
                 match nack_socket.recv_from(&mut rx_buf) {
                     Ok((size, remote)) => {
-                        let nack: &SequencedMessageRangeNack =
-                            from_bytes::<SequencedMessageRangeNack>(&rx_buf[..size]);
+                        let nack = match decode_range_nack(&rx_buf[..size]) {
+                            Ok(nack) => nack,
+                            Err(e) => {
+                                eprintln!("Dropping malformed NACK from {}: {}", remote, e);
+                                continue;
+                            }
+                        };
+                        if nack.sender_id != sender_id || nack.epoch != epoch {
+                            continue;
+                        }
 
-                        for seq in nack.start..=nack.end {
-                            let idx = (seq as usize) % MAX_MESSAGE_RETRANSMISSION_RING;
+                        // once the sender is dropped nothing more is sent, the last value stands
+                        if let Some(last_flushed) = nack_last_flushed.upgrade() {
+                            last_sent = last_flushed.load(Ordering::Acquire);
+                        }
+                        let Some((start, end)) =
+                            resendable(nack.start, nack.end, last_sent, nack_ring.len())
+                        else {
+                            continue;
+                        };
+                        if start > nack.start {
+                            eprintln!(
+                                "Cannot resend {} to {} to {}, no longer retained",
+                                nack.start,
+                                start - 1,
+                                remote
+                            );
+                        }
+
+                        // retransmissions are packed as tightly as live traffic
+                        raw_wire_msg.clear();
+                        for seq in start..=end {
+                            let idx = (seq as usize) % nack_ring.len();
                             let slot = &nack_ring[idx];
 
                             if let Some(msg) = slot.load(seq) {
-                                // TODO: batch up nacks as well
-                                let mut raw_wire_msg: RawWireMessage = RawWireMessage::default();
-                                raw_wire_msg.batch_size = 1;
-                                raw_wire_msg.batch[0] = msg;
-                                let _ = nack_socket.send_to(as_bytes(&raw_wire_msg), remote);
+                                if !raw_wire_msg.push(&msg) {
+                                    let _ = nack_socket.send_to(raw_wire_msg.as_bytes(), remote);
+                                    raw_wire_msg.clear();
+                                    if !raw_wire_msg.push(&msg) {
+                                        eprintln!(
+                                            "Cannot resend {} to {}, larger than a {} byte datagram",
+                                            seq, remote, max_datagram_len
+                                        );
+                                    }
+                                }
                             }
                         }
+                        if raw_wire_msg.batch_size > 0 {
+                            let _ = nack_socket.send_to(raw_wire_msg.as_bytes(), remote);
+                        }
                     }
                     Err(e) if e.kind() == ErrorKind::WouldBlock => thread::yield_now(),
                     Err(e) => eprintln!("RETRANS_RECV error: {:?}", e),
@@ -69,45 +140,200 @@ impl NackSequencedMulticastSender {
             }
         });
 
+        // Thread: heartbeats, until the sender is dropped
+        let heartbeat_socket = send_socket.clone();
+        let heartbeat_last_flushed = last_flushed.clone();
+        let clock = Arc::new(AtomicU64::new(0));
+        let pending_since = Arc::new(AtomicU64::new(0));
+        let heartbeat_clock = clock.clone();
+        let heartbeat_pending_since = pending_since.clone();
+
+        thread::spawn(move || {
+            let mut heartbeat_buf = Vec::new();
+
+            while Arc::strong_count(&heartbeat_last_flushed) > 1 {
+                // anything sent after this tick is stamped later, anything stamped earlier is
+                // either flushed by now or waiting in the batch, in which case its start bounds it
+                let now = tick(&heartbeat_clock);
+                let pending = loop {
+                    match heartbeat_pending_since.load(Ordering::SeqCst) {
+                        PENDING_UNTIMED => thread::yield_now(),
+                        pending => break pending,
+                    }
+                };
+                let heartbeat = SequencedHeartbeat {
+                    sender_id,
+                    epoch,
+                    sequence_number: heartbeat_last_flushed.load(Ordering::SeqCst),
+                    watermark_ns: if pending == 0 { now } else { pending.min(now) },
+                };
+                heartbeat_buf.clear();
+                encode_heartbeat(&heartbeat, &mut heartbeat_buf);
+                let _ = heartbeat_socket.send_to(&heartbeat_buf, socket_addr);
+
+                thread::sleep(SENDER_HEARTBEAT_INTERVAL);
+            }
+        });
+
         Self {
             socket: send_socket,
             socket_addr,
+            sender_id,
+            epoch,
             sequence_number: 1,
             resend_ring,
-            raw_batch: RawWireMessage::default(),
-            last_flush_ns: 0,
+            raw_batch: RawWireMessage::new(max_datagram_len),
+            max_datagram_len,
+            batch_size: batching.batch_size,
+            max_flush_delay_ns: batching.max_flush_delay_micros * 1_000,
+            batch_started_ns: 0,
+            last_batched: 0,
+            last_flushed,
+            clock,
+            pending_since,
+            stamp_engine_publish: false,
         }
     }
 
-    pub fn send(&mut self, engine_message: EngineMessage) {
-        limit_spin();
+    /// Stamps `engine_publish_ns` on traced messages as their datagram goes out, for the engine's
+    /// output stream. Retransmissions carry the same stamp.
+    pub fn stamping_engine_publish(mut self) -> Self {
+        self.stamp_engine_publish = true;
+        self
+    }
+
+    /// Returns the sequence number the message was sent with, it has gone out on the wire once
+    /// [`NackSequencedMulticastSender::last_published`] reaches it. `None` if the message is
+    /// larger than a datagram, which a validated `transport.batching.mtu` rules out, it is
+    /// dropped without using up a sequence number so receivers don't wait for it.
+    pub fn send(&mut self, engine_message: EngineMessage) -> Option<SequenceNumber> {
         let seq = self.sequence_number;
 
+        let starts_batch = self.raw_batch.batch_size == 0;
+        if starts_batch {
+            self.pending_since.store(PENDING_UNTIMED, Ordering::SeqCst);
+        }
+        let now = tick(&self.clock);
+        if starts_batch {
+            self.pending_since.store(now, Ordering::SeqCst);
+        }
         let msg = SequencedEngineMessage {
+            sender_id: self.sender_id,
+            epoch: self.epoch,
             sequence_number: seq,
             message: engine_message,
-            sent_time: system_nanos(),
+            sent_time: now,
         };
 
-        let idx = (seq as usize) % MAX_MESSAGE_RETRANSMISSION_RING;
+        if !self.raw_batch.push(&msg) {
+            if self.raw_batch.batch_size > 0 {
+                // the message is pending from here on, heartbeats must not pass it
+                self.publish_batch(now);
+            }
+            if !self.raw_batch.push(&msg) {
+                self.pending_since.store(0, Ordering::SeqCst);
+                eprintln!(
+                    "Dropping message larger than a {} byte datagram: {:?}",
+                    self.max_datagram_len, msg.message
+                );
+                return None;
+            }
+        }
+        if self.raw_batch.batch_size == 1 {
+            self.batch_started_ns = now;
+        }
+        self.last_batched = seq;
+
+        let idx = (seq as usize) % self.resend_ring.len();
         let slot = &self.resend_ring[idx];
         slot.store(seq, msg);
 
-        self.raw_batch.batch[self.raw_batch.batch_size as usize] = slot.load(seq).unwrap();
-        self.raw_batch.batch_size += 1;
-
-        let now = system_nanos();
-        if self.raw_batch.batch_size == MAX_UDP_MSG_BATCH_SIZE as u16
-            || now - self.last_flush_ns > MAX_FLUSH_GAP_NS
+        if self.raw_batch.batch_size >= self.batch_size
+            || now - self.batch_started_ns >= self.max_flush_delay_ns
         {
-            self.socket
-                .send_to(as_bytes(&self.raw_batch), self.socket_addr)
-                .unwrap();
-
-            self.raw_batch.batch_size = 0;
-            self.last_flush_ns = now;
+            self.flush();
         }
 
         self.sequence_number = seq + 1;
+        Some(seq)
+    }
+
+    /// The last sequence number flushed to the socket.
+    pub fn last_published(&self) -> SequenceNumber {
+        self.last_flushed.load(Ordering::Acquire)
+    }
+
+    /// Sends any partially filled batch immediately. A batch the socket refuses is still counted
+    /// as sent, heartbeats announce it and receivers NACK it from the resend ring.
+    pub fn flush(&mut self) {
+        if self.raw_batch.batch_size == 0 {
+            return;
+        }
+        self.publish_batch(0);
+    }
+
+    /// Sends the batch, after which `next_pending_since` is the start of the next one.
+    fn publish_batch(&mut self, next_pending_since: u64) {
+        if self.stamp_engine_publish {
+            self.stamp_batch();
+        }
+
+        if let Err(e) = self
+            .socket
+            .send_to(self.raw_batch.as_bytes(), self.socket_addr)
+        {
+            eprintln!(
+                "Cannot send {} to {} to {}, left to NACKs: {}",
+                self.last_batched + 1 - self.raw_batch.batch_size as SequenceNumber,
+                self.last_batched,
+                self.socket_addr,
+                e
+            );
+        }
+
+        self.raw_batch.clear();
+        self.last_flushed.store(self.last_batched, Ordering::SeqCst);
+        self.pending_since
+            .store(next_pending_since, Ordering::SeqCst);
+    }
+
+    /// Stamps the batch about to go out along with the retained copies of its messages, which
+    /// the NACK thread does not read before the batch is flushed.
+    fn stamp_batch(&mut self) {
+        let now = system_nanos();
+        self.raw_batch.stamp_engine_publish(now);
+
+        let first = self.last_batched + 1 - self.raw_batch.batch_size as SequenceNumber;
+        for seq in first..=self.last_batched {
+            let slot = &self.resend_ring[(seq as usize) % self.resend_ring.len()];
+            if let Some(mut msg) = slot.load(seq) {
+                if let Some(trace) = msg.message.trace_mut() {
+                    trace.engine_publish_ns = now;
+                    slot.store(seq, msg);
+                }
+            }
+        }
     }
 }
+
+/// The time from `clock`, never earlier than any it returned before.
+fn tick(clock: &AtomicU64) -> u64 {
+    let now = system_nanos();
+    clock.fetch_max(now, Ordering::SeqCst).max(now)
+}
+
+/// The part of a NACKed range that can be resent, `None` if there is none. Nothing past the last
+/// sequence sent has gone out yet, and only the latest `retained` messages are kept, so a range
+/// from a confused or hostile receiver never costs more than one pass over the ring.
+pub fn resendable(
+    start: SequenceNumber,
+    end: SequenceNumber,
+    last_sent: SequenceNumber,
+    retained: usize,
+) -> Option<(SequenceNumber, SequenceNumber)> {
+    let window = retained.min(SequenceNumber::MAX as usize) as SequenceNumber;
+    let first_retained = last_sent.saturating_sub(window.saturating_sub(1)).max(1);
+    let start = start.max(first_retained);
+    let end = end.min(last_sent);
+    (start <= end).then_some((start, end))
+}
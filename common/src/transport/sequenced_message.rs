@@ -1,21 +1,47 @@
+use crate::types::admin::{AdminRequest, AdminResponse};
 use crate::types::cancel_order::{CancelOrderRequest, CancelledOrderAck};
 use crate::types::engine::{EngineCommand, EngineError};
 use crate::types::execution_report::ExecutionReport;
-use crate::types::order::{NewOrderAck, OrderRequest};
+use crate::types::latency_trace::LatencyTrace;
+use crate::types::market_data::MarketDataMessage;
+use crate::types::order::{NewOrderAck, OrderReject, OrderRequest};
 
-pub const MAX_UDP_MSG_BATCH_SIZE: usize = 32;
 pub type Subscriber = u32;
 pub type SequenceNumber = u32;
+/// Identifies one publisher on a multicast stream, each numbers its messages from 1 on its own.
+pub type SenderId = u16;
+/// When a sender started, in nanoseconds. A restarted sender numbers its messages from 1 again
+/// under a later epoch.
+pub type SenderEpoch = u64;
 
 #[repr(C)]
+#[derive(Clone)]
 pub struct SequencedEngineMessage {
+    pub sender_id: SenderId,
+    pub epoch: SenderEpoch,
     pub sequence_number: SequenceNumber,
     pub message: EngineMessage,
     pub sent_time: u64,
 }
 
+/// Sent by every sender on an interval, so a receiver can NACK the tail of a stream it lost, tell
+/// an idle sender from a dead one and merge senders without waiting on an idle one.
+#[repr(C)]
+pub struct SequencedHeartbeat {
+    pub sender_id: SenderId,
+    pub epoch: SenderEpoch,
+    /// The latest sequence number sent, 0 before the first message.
+    pub sequence_number: SequenceNumber,
+    /// No message after `sequence_number` has an earlier `sent_time`.
+    pub watermark_ns: u64,
+}
+
 #[repr(C)]
 pub struct SequencedMessageRangeNack {
+    /// The sender asked to retransmit, the others ignore the NACK.
+    pub sender_id: SenderId,
+    /// Only the sender still running under this epoch retransmits.
+    pub epoch: SenderEpoch,
     pub start: SequenceNumber,
     pub end: SequenceNumber,
 }
@@ -29,8 +55,50 @@ pub enum EngineMessage {
     CancelOrder(CancelOrderRequest),
     CancelOrderAck(CancelledOrderAck),
     TradeExecution(ExecutionReport),
+    OrderReject(OrderReject),
 
     // SYS
     EngineCommand(EngineCommand),
     EngineError(EngineError),
+
+    // ADMIN
+    AdminRequest(AdminRequest),
+    AdminResponse(AdminResponse),
+
+    // MARKET DATA
+    MarketData(MarketDataMessage),
+}
+
+impl EngineMessage {
+    pub fn trace(&self) -> Option<&LatencyTrace> {
+        match self {
+            EngineMessage::NewOrder(order) => Some(&order.trace),
+            EngineMessage::NewOrderAck(ack) => Some(&ack.trace),
+            EngineMessage::CancelOrder(cancel) => Some(&cancel.trace),
+            EngineMessage::CancelOrderAck(ack) => Some(&ack.trace),
+            EngineMessage::TradeExecution(execution) => Some(&execution.trace),
+            EngineMessage::OrderReject(reject) => Some(&reject.trace),
+            EngineMessage::EngineCommand(_)
+            | EngineMessage::EngineError(_)
+            | EngineMessage::AdminRequest(_)
+            | EngineMessage::AdminResponse(_)
+            | EngineMessage::MarketData(_) => None,
+        }
+    }
+
+    pub fn trace_mut(&mut self) -> Option<&mut LatencyTrace> {
+        match self {
+            EngineMessage::NewOrder(order) => Some(&mut order.trace),
+            EngineMessage::NewOrderAck(ack) => Some(&mut ack.trace),
+            EngineMessage::CancelOrder(cancel) => Some(&mut cancel.trace),
+            EngineMessage::CancelOrderAck(ack) => Some(&mut ack.trace),
+            EngineMessage::TradeExecution(execution) => Some(&mut execution.trace),
+            EngineMessage::OrderReject(reject) => Some(&mut reject.trace),
+            EngineMessage::EngineCommand(_)
+            | EngineMessage::EngineError(_)
+            | EngineMessage::AdminRequest(_)
+            | EngineMessage::AdminResponse(_)
+            | EngineMessage::MarketData(_) => None,
+        }
+    }
 }
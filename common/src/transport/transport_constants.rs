@@ -1 +1,12 @@
-pub const MAX_MESSAGE_RETRANSMISSION_RING: usize = 5_000_000;
+use crate::transport::sequenced_message::SenderId;
+use std::time::Duration;
+
+/// Senders one receiver merges, sender ids run from 0 to one less.
+pub const MAX_SENDERS: usize = 16;
+/// The sender id of a stream with a single publisher, the engine's output and the market data feed.
+pub const SOLE_SENDER_ID: SenderId = 0;
+/// How often a sender multicasts a heartbeat, also how long a receiver merging several senders
+/// can wait on an idle one.
+pub const SENDER_HEARTBEAT_INTERVAL: Duration = Duration::from_millis(1);
+/// How long a receiver hears nothing from a sender before reporting it silent.
+pub const SENDER_SILENT_TIMEOUT: Duration = Duration::from_secs(1);
@@ -1,19 +1,65 @@
-use crate::memory::memory::uninitialized_arr;
-use crate::transport::sequenced_message::{
-    SequencedEngineMessage, MAX_UDP_MSG_BATCH_SIZE,
+use crate::codec::message_codec::{
+    begin_batch, encode_sequenced_message, end_batch, stamp_engine_publish,
 };
+use crate::transport::sequenced_message::SequencedEngineMessage;
 
-#[repr(C)]
+/// An encoded batch of sequenced messages that fits in a single datagram of at most `max_len`
+/// bytes. Only the pushed messages are encoded, and the buffer is allocated once and reused
+/// between flushes.
 pub struct RawWireMessage {
     pub batch_size: u16,
-    pub batch: [SequencedEngineMessage; MAX_UDP_MSG_BATCH_SIZE],
+    max_len: usize,
+    buffer: Vec<u8>,
+    /// Where each traced message in the batch ends.
+    trace_ends: Vec<usize>,
 }
 
 impl RawWireMessage {
-    pub fn default() -> Self {
+    pub fn new(max_len: usize) -> Self {
+        let mut buffer = Vec::with_capacity(max_len);
+        begin_batch(&mut buffer);
+
         RawWireMessage {
             batch_size: 0,
-            batch: uninitialized_arr(),
+            max_len,
+            buffer,
+            trace_ends: Vec::new(),
+        }
+    }
+
+    /// Returns false, leaving the batch unchanged, if the message would overflow the datagram. An
+    /// empty batch refusing a message means the message can never be sent.
+    pub fn push(&mut self, message: &SequencedEngineMessage) -> bool {
+        let len = self.buffer.len();
+        encode_sequenced_message(message, &mut self.buffer);
+
+        if self.buffer.len() > self.max_len {
+            self.buffer.truncate(len);
+            return false;
+        }
+
+        self.batch_size += 1;
+        if message.message.trace().is_some() {
+            self.trace_ends.push(self.buffer.len());
         }
+        true
     }
-}
\ No newline at end of file
+
+    /// Sets `engine_publish_ns` on every traced message in the batch.
+    pub fn stamp_engine_publish(&mut self, engine_publish_ns: u64) {
+        for &end in &self.trace_ends {
+            stamp_engine_publish(&mut self.buffer[..end], engine_publish_ns);
+        }
+    }
+
+    pub fn as_bytes(&mut self) -> &[u8] {
+        end_batch(&mut self.buffer, self.batch_size);
+        &self.buffer
+    }
+
+    pub fn clear(&mut self) {
+        begin_batch(&mut self.buffer);
+        self.batch_size = 0;
+        self.trace_ends.clear();
+    }
+}
@@ -0,0 +1,95 @@
+use crate::types::risk::{RiskLimitKind, RiskLimits};
+use crate::types::side::Side;
+
+#[derive(PartialEq, Debug, Clone, Copy, Eq)]
+#[repr(C)]
+pub enum AdminCommand {
+    Status,
+    HaltInstrument,
+    ResumeInstrument,
+    DumpBook,
+    CancelClientOrders(u32),
+    Snapshot,
+    /// Stops order entry, the engine drains what was sequenced before it and exits.
+    Shutdown,
+    // answered by the gateway, never sequenced to the engine
+    GetRiskLimits,
+    SetRiskLimit(RiskLimitKind, u64),
+}
+
+impl AdminCommand {
+    pub fn name(&self) -> &'static str {
+        match self {
+            AdminCommand::Status => "status",
+            AdminCommand::HaltInstrument => "halt",
+            AdminCommand::ResumeInstrument => "resume",
+            AdminCommand::DumpBook => "dump-book",
+            AdminCommand::CancelClientOrders(_) => "cancel-client",
+            AdminCommand::Snapshot => "snapshot",
+            AdminCommand::Shutdown => "shutdown",
+            AdminCommand::GetRiskLimits => "risk-limits",
+            AdminCommand::SetRiskLimit(_, _) => "set-risk",
+        }
+    }
+}
+
+/// Sent by an authenticated admin session on the gateway, sequenced with order flow.
+#[derive(Debug, Clone, Copy)]
+#[repr(C)]
+pub struct AdminRequest {
+    pub request_id: u32,
+    pub instrument: [u8; 16],
+    pub command: AdminCommand,
+}
+
+#[derive(Debug, Clone, Copy)]
+#[repr(C)]
+pub struct AdminResponse {
+    pub request_id: u32,
+    /// Set on the final message of a (possibly multi message) response.
+    pub last: bool,
+    pub body: AdminResponseBody,
+}
+
+#[derive(Debug, Clone, Copy)]
+#[repr(C)]
+pub enum AdminResponseBody {
+    Done,
+    Error(AdminError),
+    Status(EngineStatus),
+    BookLevel(AdminBookLevel),
+    OrdersCancelled(u32),
+    RiskLimits(RiskLimits),
+}
+
+#[derive(PartialEq, Debug, Clone, Copy, Eq)]
+#[repr(C)]
+pub enum AdminError {
+    UnknownInstrument,
+    SnapshotNotConfigured,
+    SnapshotFailed,
+    UnsupportedCommand,
+}
+
+#[derive(Debug, Clone, Copy)]
+#[repr(C)]
+pub struct EngineStatus {
+    pub instrument: [u8; 16],
+    pub halted: bool,
+    pub orders_on_book: u32,
+    pub bid_levels: u32,
+    pub ask_levels: u32,
+    pub best_bid: u64,
+    pub best_ask: u64,
+    pub orders_processed: u64,
+    pub executions: u64,
+}
+
+#[derive(Debug, Clone, Copy)]
+#[repr(C)]
+pub struct AdminBookLevel {
+    pub side: Side,
+    pub px: u64,
+    pub qty: u64,
+    pub num_orders: u32,
+}
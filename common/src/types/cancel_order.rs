@@ -1,3 +1,4 @@
+use crate::types::latency_trace::LatencyTrace;
 use crate::types::side::Side;
 
 #[derive(Debug, Clone, Copy)]
@@ -7,6 +8,7 @@ pub struct CancelOrderRequest {
     pub order_side: Side,
     pub order_id: u32,
     pub instrument: [u8; 16],
+    pub trace: LatencyTrace,
 }
 
 #[derive(Debug, Clone, Copy)]
@@ -18,6 +20,7 @@ pub struct CancelledOrderAck {
     pub cancel_order_status: CancelOrderStatus,
     pub reason: Reason,
     pub ack_time: u64,
+    pub trace: LatencyTrace,
 }
 
 #[derive(Debug, Clone, Copy)]
@@ -25,6 +28,17 @@ pub struct CancelledOrderAck {
 pub enum CancelOrderStatus {
     Cancelled = 0,
     NotFound = 1,
+    Rejected = 2,
+}
+
+impl CancelOrderStatus {
+    pub fn val_to_str(status: CancelOrderStatus) -> String {
+        match status {
+            CancelOrderStatus::Cancelled => "cancelled".to_owned(),
+            CancelOrderStatus::NotFound => "not_found".to_owned(),
+            CancelOrderStatus::Rejected => "rejected".to_owned(),
+        }
+    }
 }
 
 #[derive(Debug, Clone, Copy)]
@@ -32,4 +46,23 @@ pub enum CancelOrderStatus {
 pub enum Reason {
     ClientRequested = 0,
     SelfMatchPrevention = 1,
+    AdminRequested = 2,
+    Throttled = 3,
+    /// The order belongs to another client, it stays on the book.
+    NotOwner = 4,
+    /// The cancel request could not be parsed, it never reached the engine.
+    InvalidCancel = 5,
+}
+
+impl Reason {
+    pub fn val_to_str(reason: Reason) -> String {
+        match reason {
+            Reason::ClientRequested => "client_requested".to_owned(),
+            Reason::SelfMatchPrevention => "self_match_prevention".to_owned(),
+            Reason::AdminRequested => "admin_requested".to_owned(),
+            Reason::Throttled => "throttled".to_owned(),
+            Reason::NotOwner => "not_owner".to_owned(),
+            Reason::InvalidCancel => "invalid_cancel".to_owned(),
+        }
+    }
 }
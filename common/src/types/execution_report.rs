@@ -1,3 +1,4 @@
+use crate::types::latency_trace::LatencyTrace;
 use std::fmt::Debug;
 
 #[derive(PartialEq, Debug)]
@@ -8,21 +9,24 @@ pub struct ExecutionReport {
 
     pub bid_client_id: u32,
     pub bid_order_id: u32,
-    pub bid_order_px: u32,
+    pub bid_client_order_id: u64,
+    pub bid_order_px: u64,
     pub bid_fill_type: FillType,
 
     pub ask_client_id: u32,
     pub ask_order_id: u32,
-    pub ask_order_px: u32,
+    pub ask_client_order_id: u64,
+    pub ask_order_px: u64,
     pub ask_fill_type: FillType,
 
     pub instrument: [u8; 16],
 
-    pub exec_px: u32,
-    pub exec_qty: u32,
+    pub exec_px: u64,
+    pub exec_qty: u64,
     pub exec_type: ExecType,
 
     pub exec_ns: u64,
+    pub trace: LatencyTrace,
 }
 
 #[derive(PartialEq, Debug, Copy, Clone)]
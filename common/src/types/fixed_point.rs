@@ -0,0 +1,94 @@
+use serde::Deserialize;
+
+/// Decimal places of an instrument's fixed-point prices and quantities. Engine messages carry raw
+/// `u64` ticks and lots; decimal strings only exist at the JSON boundaries.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct FixedPointScale {
+    pub px_decimals: u8,
+    pub qty_decimals: u8,
+}
+
+/// The most decimal places a `u64` can scale by, 10^20 doesn't fit.
+pub const MAX_DECIMALS: u8 = 19;
+
+impl Default for FixedPointScale {
+    fn default() -> Self {
+        FixedPointScale {
+            px_decimals: 2,
+            qty_decimals: 8,
+        }
+    }
+}
+
+impl FixedPointScale {
+    pub fn px_to_str(&self, px: u64) -> String {
+        to_decimal_str(px, self.px_decimals)
+    }
+
+    pub fn qty_to_str(&self, qty: u64) -> String {
+        to_decimal_str(qty, self.qty_decimals)
+    }
+
+    pub fn str_to_px(&self, px: &str) -> Result<u64, String> {
+        from_decimal_str(px, self.px_decimals)
+    }
+
+    pub fn str_to_qty(&self, qty: &str) -> Result<u64, String> {
+        from_decimal_str(qty, self.qty_decimals)
+    }
+}
+
+pub fn to_decimal_str(value: u64, decimals: u8) -> String {
+    if decimals == 0 {
+        return value.to_string();
+    }
+
+    let unit = 10u64.pow(decimals as u32);
+    format!(
+        "{}.{:0width$}",
+        value / unit,
+        value % unit,
+        width = decimals as usize
+    )
+}
+
+/// Parses a non-negative decimal string into raw units, rejecting more precision than `decimals`.
+pub fn from_decimal_str(value: &str, decimals: u8) -> Result<u64, String> {
+    let (int_part, frac_part) = value.split_once('.').unwrap_or((value, ""));
+
+    if (int_part.is_empty() && frac_part.is_empty())
+        || !int_part.bytes().all(|b| b.is_ascii_digit())
+        || !frac_part.bytes().all(|b| b.is_ascii_digit())
+    {
+        return Err(format!("'{}' is not a decimal", value));
+    }
+
+    let frac_digits = frac_part.trim_end_matches('0');
+    if frac_digits.len() > decimals as usize {
+        return Err(format!(
+            "'{}' has more than {} decimal places",
+            value, decimals
+        ));
+    }
+
+    let unit = 10u64.pow(decimals as u32);
+    let int_value = if int_part.is_empty() {
+        0
+    } else {
+        int_part
+            .parse::<u64>()
+            .map_err(|_| format!("'{}' is out of range", value))?
+    };
+    let frac_value = if frac_digits.is_empty() {
+        0
+    } else {
+        frac_digits.parse::<u64>().unwrap()
+            * 10u64.pow((decimals as usize - frac_digits.len()) as u32)
+    };
+
+    int_value
+        .checked_mul(unit)
+        .and_then(|raw| raw.checked_add(frac_value))
+        .ok_or_else(|| format!("'{}' is out of range", value))
+}
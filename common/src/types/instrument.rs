@@ -13,4 +13,9 @@ impl Instrument {
         buf[..n].copy_from_slice(&bytes[..n]);
         buf
     }
+
+    pub fn fixed_char_buffer_to_str(buf: &[u8; 16]) -> String {
+        let len = buf.iter().position(|&b| b == 0).unwrap_or(buf.len());
+        String::from_utf8_lossy(&buf[..len]).into_owned()
+    }
 }
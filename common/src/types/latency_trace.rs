@@ -0,0 +1,65 @@
+/// Hop timestamps (unix nanos) carried with an order from entry to engine publish.
+/// A zero timestamp means the message did not pass through that hop.
+#[repr(C)]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct LatencyTrace {
+    pub oe_api_recv_ns: u64,
+    pub gateway_recv_ns: u64,
+    pub gateway_send_ns: u64,
+    pub engine_recv_ns: u64,
+    pub match_ns: u64,
+    /// When msg_out added the message to a multicast batch, which goes out once full, overdue or
+    /// msg_out runs idle.
+    pub engine_batch_ns: u64,
+    /// When the datagram carrying the message was sent, stamped into the encoded batch so it has
+    /// to stay the last field on the wire.
+    pub engine_publish_ns: u64,
+}
+
+impl LatencyTrace {
+    pub const HOP_NAMES: [&'static str; 6] = [
+        "oe_api_recv -> gateway_recv",
+        "gateway_recv -> gateway_send",
+        "gateway_send -> engine_recv",
+        "engine_recv -> match",
+        "match -> engine_batch",
+        "engine_batch -> engine_publish",
+    ];
+
+    /// Per-hop latencies in the order of [`LatencyTrace::HOP_NAMES`], `None` where either end is missing.
+    pub fn hops(&self) -> [Option<u64>; 6] {
+        let stamps = self.stamps();
+        let mut hops = [None; 6];
+
+        for (idx, hop) in hops.iter_mut().enumerate() {
+            *hop = hop_latency(stamps[idx], stamps[idx + 1]);
+        }
+
+        hops
+    }
+
+    /// Latency from the earliest recorded hop to engine publish.
+    pub fn end_to_end(&self) -> Option<u64> {
+        let first = self.stamps().into_iter().find(|&stamp| stamp != 0)?;
+        hop_latency(first, self.engine_publish_ns)
+    }
+
+    fn stamps(&self) -> [u64; 7] {
+        [
+            self.oe_api_recv_ns,
+            self.gateway_recv_ns,
+            self.gateway_send_ns,
+            self.engine_recv_ns,
+            self.match_ns,
+            self.engine_batch_ns,
+            self.engine_publish_ns,
+        ]
+    }
+}
+
+fn hop_latency(from: u64, to: u64) -> Option<u64> {
+    if from == 0 || to == 0 {
+        return None;
+    }
+    Some(to.saturating_sub(from))
+}
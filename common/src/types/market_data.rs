@@ -0,0 +1,83 @@
+use crate::types::side::Side;
+
+/// One message of the order-by-order market data feed. `instrument_id` is the engine's
+/// `instrument_id` and `timestamp` the engine time of the event in nanoseconds.
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[repr(C)]
+pub struct MarketDataMessage {
+    pub instrument_id: u32,
+    pub timestamp: u64,
+    pub body: MarketDataBody,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[repr(C)]
+pub enum MarketDataBody {
+    AddOrder(AddOrder),
+    OrderExecuted(OrderExecuted),
+    OrderCancelled(OrderCancelled),
+    OrderReplaced(OrderReplaced),
+    Trade(Trade),
+    TradingState(TradingState),
+}
+
+/// A new order resting on the book.
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[repr(C)]
+pub struct AddOrder {
+    pub order_id: u32,
+    pub side: Side,
+    pub px: u64,
+    pub qty: u64,
+}
+
+/// A resting order was (partly) filled at its own price.
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[repr(C)]
+pub struct OrderExecuted {
+    pub order_id: u32,
+    pub executed_qty: u64,
+    pub match_id: u64,
+}
+
+/// `cancelled_qty` left the book, the order is gone once its whole open quantity is cancelled.
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[repr(C)]
+pub struct OrderCancelled {
+    pub order_id: u32,
+    pub cancelled_qty: u64,
+}
+
+/// A resting order replaced in place by a new order id, losing its time priority.
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[repr(C)]
+pub struct OrderReplaced {
+    pub orig_order_id: u32,
+    pub order_id: u32,
+    pub px: u64,
+    pub qty: u64,
+}
+
+/// Every execution, `side` is the aggressor's and `match_id` matches the OrderExecuted of the
+/// resting order it filled.
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[repr(C)]
+pub struct Trade {
+    pub match_id: u64,
+    pub side: Side,
+    pub px: u64,
+    pub qty: u64,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[repr(C)]
+pub struct TradingState {
+    pub state: TradingStatus,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[repr(C)]
+pub enum TradingStatus {
+    Trading = 0,
+    Halted = 1,
+}
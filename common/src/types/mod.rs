@@ -1,6 +1,12 @@
+pub mod admin;
 pub mod cancel_order;
 pub mod engine;
 pub mod execution_report;
+pub mod fixed_point;
 pub mod instrument;
+pub mod latency_trace;
+pub mod market_data;
 pub mod order;
+pub mod position;
+pub mod risk;
 pub mod side;
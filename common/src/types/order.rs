@@ -1,29 +1,83 @@
+use crate::types::latency_trace::LatencyTrace;
 use crate::types::side::Side;
 
 #[repr(C)]
 #[derive(Debug, Clone, Copy)]
 pub struct OrderRequest {
     pub client_id: u32,
+    /// Opaque id chosen by the submitting session, echoed on every report about the order.
+    pub client_order_id: u64,
     pub instrument: [u8; 16],
     pub order_side: Side,
-    pub px: u32,
-    pub qty: u32,
+    pub px: u64,
+    pub qty: u64,
     pub time_in_force: TimeInForce,
     pub timestamp: u64,
+    pub trace: LatencyTrace,
 }
 
 #[repr(C)]
 #[derive(Debug, Clone, Copy)]
 pub struct NewOrderAck {
     pub client_id: u32,
+    pub client_order_id: u64,
     pub order_id: u32,
     pub instrument: [u8; 16],
     pub side: Side,
-    pub px: u32,
-    pub qty: u32,
-    pub qty_rem: u32,
+    pub px: u64,
+    pub qty: u64,
+    pub qty_rem: u64,
     pub time_in_force: TimeInForce,
     pub ack_time: u64,
+    pub trace: LatencyTrace,
+}
+
+#[repr(C)]
+#[derive(Debug, Clone, Copy)]
+pub struct OrderReject {
+    pub client_id: u32,
+    pub client_order_id: u64,
+    pub instrument: [u8; 16],
+    pub side: Side,
+    pub px: u64,
+    pub qty: u64,
+    pub reason: RejectReason,
+    pub reject_time: u64,
+    pub trace: LatencyTrace,
+}
+
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum RejectReason {
+    InstrumentHalted = 0,
+    MaxOrderQty = 1,
+    MaxOrderNotional = 2,
+    MaxOpenOrders = 3,
+    MaxOpenNotional = 4,
+    PriceDeviation = 5,
+    MaxPosition = 6,
+    Throttled = 7,
+    /// Zero quantity or price.
+    InvalidOrder = 8,
+    /// A trusted session entering an order for a client id outside its range.
+    UnknownClient = 9,
+}
+
+impl RejectReason {
+    pub fn val_to_str(reason: RejectReason) -> String {
+        match reason {
+            RejectReason::InstrumentHalted => "instrument_halted".to_owned(),
+            RejectReason::MaxOrderQty => "max_order_qty".to_owned(),
+            RejectReason::MaxOrderNotional => "max_order_notional".to_owned(),
+            RejectReason::MaxOpenOrders => "max_open_orders".to_owned(),
+            RejectReason::MaxOpenNotional => "max_open_notional".to_owned(),
+            RejectReason::PriceDeviation => "price_deviation".to_owned(),
+            RejectReason::MaxPosition => "max_position".to_owned(),
+            RejectReason::Throttled => "throttled".to_owned(),
+            RejectReason::InvalidOrder => "invalid_order".to_owned(),
+            RejectReason::UnknownClient => "unknown_client".to_owned(),
+        }
+    }
 }
 
 #[repr(C)]
@@ -0,0 +1,17 @@
+use serde::{Deserialize, Serialize};
+
+/// Post-trade position of one client in one instrument, as served by the position keeper.
+/// Prices are engine ticks, PnL is `qty * ticks` marked against the last trade.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct ClientPosition {
+    pub client_id: u32,
+    pub instrument: String,
+    pub net_qty: i64,
+    pub avg_entry_px: f64,
+    pub realized_pnl: f64,
+    pub unrealized_pnl: f64,
+    pub open_buy_qty: u64,
+    pub open_sell_qty: u64,
+    pub open_buy_notional: u64,
+    pub open_sell_notional: u64,
+}
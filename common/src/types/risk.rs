@@ -0,0 +1,68 @@
+use serde::Deserialize;
+
+/// Pre-trade limits applied by the gateway, in raw fixed-point units. Notionals are `px * qty`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(deny_unknown_fields, default)]
+#[repr(C)]
+pub struct RiskLimits {
+    pub max_order_qty: u64,
+    pub max_order_notional: u64,
+    pub max_open_orders: u64,
+    pub max_open_notional: u64,
+    /// Allowed distance of a limit price from the last trade, in basis points.
+    pub max_price_deviation_bps: u64,
+    /// Largest absolute net position a client may reach if all its open orders fill. Only
+    /// enforced when the gateway polls a position keeper.
+    pub max_position: u64,
+}
+
+impl Default for RiskLimits {
+    fn default() -> Self {
+        RiskLimits {
+            max_order_qty: 10_000_000_000,
+            max_order_notional: 100_000_000_000_000_000,
+            max_open_orders: 10_000,
+            max_open_notional: 1_000_000_000_000_000_000,
+            max_price_deviation_bps: 1_000,
+            max_position: 100_000_000_000,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[repr(C)]
+pub enum RiskLimitKind {
+    MaxOrderQty,
+    MaxOrderNotional,
+    MaxOpenOrders,
+    MaxOpenNotional,
+    MaxPriceDeviationBps,
+    MaxPosition,
+}
+
+impl RiskLimitKind {
+    pub fn str_to_val(limit: &str) -> Result<RiskLimitKind, String> {
+        match limit {
+            "max_order_qty" => Ok(RiskLimitKind::MaxOrderQty),
+            "max_order_notional" => Ok(RiskLimitKind::MaxOrderNotional),
+            "max_open_orders" => Ok(RiskLimitKind::MaxOpenOrders),
+            "max_open_notional" => Ok(RiskLimitKind::MaxOpenNotional),
+            "max_price_deviation_bps" => Ok(RiskLimitKind::MaxPriceDeviationBps),
+            "max_position" => Ok(RiskLimitKind::MaxPosition),
+            _ => Err(format!("Unknown risk limit {}", limit)),
+        }
+    }
+}
+
+impl RiskLimits {
+    pub fn set(&mut self, limit: RiskLimitKind, value: u64) {
+        match limit {
+            RiskLimitKind::MaxOrderQty => self.max_order_qty = value,
+            RiskLimitKind::MaxOrderNotional => self.max_order_notional = value,
+            RiskLimitKind::MaxOpenOrders => self.max_open_orders = value,
+            RiskLimitKind::MaxOpenNotional => self.max_open_notional = value,
+            RiskLimitKind::MaxPriceDeviationBps => self.max_price_deviation_bps = value,
+            RiskLimitKind::MaxPosition => self.max_position = value,
+        }
+    }
+}
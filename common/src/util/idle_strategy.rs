@@ -0,0 +1,83 @@
+use serde::Deserialize;
+use std::hint::spin_loop;
+use std::thread;
+use std::time::Duration;
+
+const BACKOFF_SPINS: u32 = 100;
+const BACKOFF_YIELDS: u32 = 100;
+const BACKOFF_MIN_SLEEP_MICROS: u64 = 1;
+
+/// What a polling thread does when it finds no work.
+///
+/// In config either a bare name (`"spin"`, `"yield"`, `"backoff"`) or, to tune it,
+/// `{"sleep": {"micros": 50}}` / `{"backoff": {"max_sleep_micros": 500}}`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum IdleStrategy {
+    /// Burns the core, lowest latency; only sensible on a pinned, isolated core.
+    Spin,
+    Yield,
+    Sleep {
+        micros: u64,
+    },
+    /// Spins, then yields, then sleeps with a doubling interval up to `max_sleep_micros`.
+    Backoff {
+        #[serde(default = "default_max_sleep_micros")]
+        max_sleep_micros: u64,
+    },
+}
+
+fn default_max_sleep_micros() -> u64 {
+    100
+}
+
+impl Default for IdleStrategy {
+    fn default() -> Self {
+        IdleStrategy::Backoff {
+            max_sleep_micros: default_max_sleep_micros(),
+        }
+    }
+}
+
+impl IdleStrategy {
+    pub fn idler(self) -> Idler {
+        Idler {
+            strategy: self,
+            idle_count: 0,
+        }
+    }
+}
+
+pub struct Idler {
+    strategy: IdleStrategy,
+    idle_count: u32,
+}
+
+impl Idler {
+    #[inline(always)]
+    pub fn idle(&mut self) {
+        match self.strategy {
+            IdleStrategy::Spin => spin_loop(),
+            IdleStrategy::Yield => thread::yield_now(),
+            IdleStrategy::Sleep { micros } => thread::sleep(Duration::from_micros(micros)),
+            IdleStrategy::Backoff { max_sleep_micros } => {
+                if self.idle_count < BACKOFF_SPINS {
+                    spin_loop();
+                } else if self.idle_count < BACKOFF_SPINS + BACKOFF_YIELDS {
+                    thread::yield_now();
+                } else {
+                    let doublings = (self.idle_count - BACKOFF_SPINS - BACKOFF_YIELDS).min(16);
+                    let micros = (BACKOFF_MIN_SLEEP_MICROS << doublings).min(max_sleep_micros);
+                    thread::sleep(Duration::from_micros(micros));
+                }
+                self.idle_count = self.idle_count.saturating_add(1);
+            }
+        }
+    }
+
+    /// Call after doing work so backoff starts from spinning again.
+    #[inline(always)]
+    pub fn reset(&mut self) {
+        self.idle_count = 0;
+    }
+}
@@ -1,2 +1,6 @@
-pub mod limit_spin;
+pub mod idle_strategy;
+pub mod secret;
+pub mod thread_affinity;
+pub mod throttle;
 pub mod time;
+pub mod token_bucket;
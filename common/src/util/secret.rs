@@ -0,0 +1,8 @@
+/// Compares every byte so the time taken doesn't depend on where the secrets differ, only the
+/// length of `actual` leaks.
+pub fn secrets_match(expected: &[u8], actual: &[u8]) -> bool {
+    let diff = (0..actual.len()).fold(0u8, |diff, idx| {
+        diff | (expected.get(idx).copied().unwrap_or(0) ^ actual[idx])
+    });
+    diff == 0 && expected.len() == actual.len()
+}
@@ -0,0 +1,19 @@
+/// Pins the calling thread to `core` if it is one of the cores available to the process.
+/// An unavailable core is reported and the thread is left unpinned rather than failing.
+pub fn pin_current_thread(thread_name: &str, core: Option<usize>) {
+    let Some(core) = core else {
+        return;
+    };
+
+    let available = core_affinity::get_core_ids().unwrap_or_default();
+    match available.into_iter().find(|core_id| core_id.id == core) {
+        Some(core_id) if core_affinity::set_for_current(core_id) => {
+            println!("Pinned {} thread to core {}", thread_name, core);
+        }
+        Some(_) => eprintln!("Failed to pin {} thread to core {}", thread_name, core),
+        None => eprintln!(
+            "Core {} is not available, {} thread left unpinned",
+            core, thread_name
+        ),
+    }
+}
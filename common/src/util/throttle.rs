@@ -0,0 +1,142 @@
+use crate::metrics::counter::Counter;
+use crate::metrics::metrics_registry::MetricsRegistry;
+use crate::transport::sequenced_message::EngineMessage;
+use crate::util::time::system_nanos;
+use crate::util::token_bucket::{RateLimit, TokenBucket};
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+
+/// New order and cancel budgets for one session and for one client id across all its sessions.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields, default)]
+pub struct ThrottleConfig {
+    pub session_new_orders: RateLimit,
+    pub session_cancels: RateLimit,
+    pub client_new_orders: RateLimit,
+    pub client_cancels: RateLimit,
+}
+
+impl Default for ThrottleConfig {
+    fn default() -> Self {
+        ThrottleConfig {
+            session_new_orders: RateLimit::new(50_000, 100_000),
+            session_cancels: RateLimit::new(50_000, 100_000),
+            client_new_orders: RateLimit::new(10_000, 20_000),
+            client_cancels: RateLimit::new(10_000, 20_000),
+        }
+    }
+}
+
+struct OrderBuckets {
+    new_orders: TokenBucket,
+    cancels: TokenBucket,
+}
+
+impl OrderBuckets {
+    fn new(new_orders: RateLimit, cancels: RateLimit, now_ns: u64) -> Self {
+        OrderBuckets {
+            new_orders: TokenBucket::new(new_orders, now_ns),
+            cancels: TokenBucket::new(cancels, now_ns),
+        }
+    }
+
+    fn bucket(&mut self, message: &EngineMessage) -> Option<&mut TokenBucket> {
+        match message {
+            EngineMessage::NewOrder(_) => Some(&mut self.new_orders),
+            EngineMessage::CancelOrder(_) => Some(&mut self.cancels),
+            _ => None,
+        }
+    }
+}
+
+/// Client buckets and throttle counters shared by every session of a process.
+pub struct Throttle {
+    config: ThrottleConfig,
+    clients: Mutex<HashMap<u32, OrderBuckets>>,
+    throttled_new_orders: Arc<Counter>,
+    throttled_cancels: Arc<Counter>,
+}
+
+impl Throttle {
+    pub fn new(config: ThrottleConfig, registry: &MetricsRegistry, prefix: &str) -> Self {
+        Throttle {
+            config,
+            clients: Mutex::new(HashMap::new()),
+            throttled_new_orders: registry.counter(
+                &format!("{}_throttled_new_orders_total", prefix),
+                "New orders rejected by the session or client rate limit",
+            ),
+            throttled_cancels: registry.counter(
+                &format!("{}_throttled_cancels_total", prefix),
+                "Cancels rejected by the session or client rate limit",
+            ),
+        }
+    }
+
+    pub fn session(self: &Arc<Self>) -> SessionThrottle {
+        SessionThrottle {
+            buckets: OrderBuckets::new(
+                self.config.session_new_orders,
+                self.config.session_cancels,
+                system_nanos(),
+            ),
+            throttle: self.clone(),
+        }
+    }
+
+    fn try_acquire_client(&self, client_id: u32, message: &EngineMessage, now_ns: u64) -> bool {
+        let mut clients = self.clients.lock().unwrap();
+        let buckets = clients.entry(client_id).or_insert_with(|| {
+            OrderBuckets::new(
+                self.config.client_new_orders,
+                self.config.client_cancels,
+                now_ns,
+            )
+        });
+
+        buckets
+            .bucket(message)
+            .is_none_or(|bucket| bucket.try_acquire(now_ns))
+    }
+}
+
+pub struct SessionThrottle {
+    buckets: OrderBuckets,
+    throttle: Arc<Throttle>,
+}
+
+impl SessionThrottle {
+    /// Takes a token from the session and client budgets for new orders and cancels, other
+    /// messages always pass. Returns false, and counts it, if either budget is exhausted, in
+    /// which case neither is charged.
+    pub fn try_acquire(&mut self, message: &EngineMessage) -> bool {
+        let client_id = match message {
+            EngineMessage::NewOrder(order) => order.client_id,
+            EngineMessage::CancelOrder(cancel) => cancel.client_id,
+            _ => return true,
+        };
+
+        let now_ns = system_nanos();
+        let mut session = self.buckets.bucket(message);
+        let mut allowed = session
+            .as_mut()
+            .is_none_or(|bucket| bucket.try_acquire(now_ns));
+        if allowed && !self.throttle.try_acquire_client(client_id, message, now_ns) {
+            // a message the client budget refuses doesn't use up the session's
+            if let Some(bucket) = session {
+                bucket.refund();
+            }
+            allowed = false;
+        }
+
+        if !allowed {
+            match message {
+                EngineMessage::NewOrder(_) => self.throttle.throttled_new_orders.inc(),
+                _ => self.throttle.throttled_cancels.inc(),
+            }
+        }
+
+        allowed
+    }
+}
@@ -0,0 +1,58 @@
+use serde::Deserialize;
+
+/// Sustained `per_sec` with bursts of up to `burst`. A `per_sec` of 0 disables the limit.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct RateLimit {
+    pub per_sec: u32,
+    pub burst: u32,
+}
+
+impl RateLimit {
+    pub fn new(per_sec: u32, burst: u32) -> Self {
+        RateLimit { per_sec, burst }
+    }
+}
+
+pub struct TokenBucket {
+    limit: RateLimit,
+    tokens: f64,
+    last_refill_ns: u64,
+}
+
+impl TokenBucket {
+    pub fn new(limit: RateLimit, now_ns: u64) -> Self {
+        TokenBucket {
+            limit,
+            tokens: limit.burst as f64,
+            last_refill_ns: now_ns,
+        }
+    }
+
+    pub fn try_acquire(&mut self, now_ns: u64) -> bool {
+        if self.limit.per_sec == 0 {
+            return true;
+        }
+
+        let elapsed_ns = now_ns.saturating_sub(self.last_refill_ns);
+        self.last_refill_ns = now_ns.max(self.last_refill_ns);
+        self.tokens = (self.tokens + elapsed_ns as f64 * self.limit.per_sec as f64 / 1e9)
+            .min(self.limit.burst.max(1) as f64);
+
+        if self.tokens >= 1.0 {
+            self.tokens -= 1.0;
+            true
+        } else {
+            false
+        }
+    }
+
+    /// Returns a token taken for a message that was refused elsewhere after all.
+    pub fn refund(&mut self) {
+        if self.limit.per_sec == 0 {
+            return;
+        }
+
+        self.tokens = (self.tokens + 1.0).min(self.limit.burst.max(1) as f64);
+    }
+}
@@ -0,0 +1,170 @@
+use common::config::app_config::AppConfig;
+use common::config::config_error::ConfigError;
+use common::config::config_loader::load_config;
+use std::env;
+use std::sync::Mutex;
+
+const CONFIG_PATH: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/../config/engine_btc_usd.json");
+
+/// Every load reads the whole environment, tests setting overrides take turns.
+static ENV: Mutex<()> = Mutex::new(());
+
+/// Loads the repo's config with `args` after `--config`, under `env_overrides` set for the
+/// duration of the load.
+fn load(
+    env_overrides: &[(&str, &str)],
+    args: &[&str],
+) -> Result<(AppConfig, Vec<String>), ConfigError> {
+    let _env = ENV.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+    for (key, value) in env_overrides {
+        env::set_var(key, value);
+    }
+
+    let args = ["--config", CONFIG_PATH]
+        .iter()
+        .chain(args)
+        .map(|arg| arg.to_string());
+    let loaded = load_config(args);
+
+    for (key, _) in env_overrides {
+        env::remove_var(key);
+    }
+    loaded
+}
+
+fn config(env_overrides: &[(&str, &str)], args: &[&str]) -> AppConfig {
+    load(env_overrides, args).unwrap().0
+}
+
+#[test]
+fn the_file_is_used_without_overrides() {
+    let (config, remaining) = load(&[], &[]).unwrap();
+
+    assert_eq!(config.gateway.metrics_port, 9101);
+    assert_eq!(config.oe_api.port, 8080);
+    assert!(remaining.is_empty());
+}
+
+#[test]
+fn env_overrides_replace_file_values() {
+    let config = config(
+        &[
+            ("ME__GATEWAY__METRICS_PORT", "9201"),
+            ("ME__TRANSPORT__BATCHING__BATCH_SIZE", "8"),
+        ],
+        &[],
+    );
+
+    assert_eq!(config.gateway.metrics_port, 9201);
+    assert_eq!(config.transport.batching.batch_size, 8);
+}
+
+#[test]
+fn env_overrides_find_dashed_sections() {
+    let config = config(&[("ME__OE_API__PORT", "8181")], &[]);
+
+    assert_eq!(config.oe_api.port, 8181);
+}
+
+#[test]
+fn cli_overrides_replace_file_values() {
+    let config = config(
+        &[],
+        &["--gateway.metrics_port", "9301", "--market-data.port=7001"],
+    );
+
+    assert_eq!(config.gateway.metrics_port, 9301);
+    assert_eq!(config.market_data.port, 7001);
+}
+
+#[test]
+fn cli_overrides_win_over_env_overrides() {
+    let config = config(
+        &[
+            ("ME__GATEWAY__METRICS_PORT", "9201"),
+            ("ME__GATEWAY__ADMIN_PORT", "3102"),
+        ],
+        &["--gateway.metrics_port", "9301"],
+    );
+
+    assert_eq!(config.gateway.metrics_port, 9301);
+    assert_eq!(config.gateway.admin_port, 3102);
+}
+
+#[test]
+fn string_values_stay_strings() {
+    let config = config(&[], &["--engine.instrument", "1234"]);
+
+    assert_eq!(config.engine.instrument, "1234");
+}
+
+#[test]
+fn other_arguments_are_returned() {
+    let (config, remaining) = load(
+        &[],
+        &["status", "--verbose", "--gateway.metrics_port", "9301"],
+    )
+    .unwrap();
+
+    assert_eq!(config.gateway.metrics_port, 9301);
+    assert_eq!(remaining, vec!["status", "--verbose"]);
+}
+
+#[test]
+fn an_unknown_key_is_rejected() {
+    assert!(matches!(
+        load(&[], &["--gateway.no_such_key", "1"]),
+        Err(ConfigError::Parse { .. })
+    ));
+    assert!(matches!(
+        load(&[("ME__NO_SUCH_SECTION__PORT", "1")], &[]),
+        Err(ConfigError::Parse { .. })
+    ));
+}
+
+#[test]
+fn an_override_through_a_value_is_rejected() {
+    let loaded = load(&[], &["--gateway.metrics_port.number", "1"]);
+
+    assert!(
+        matches!(loaded, Err(ConfigError::Override { key, .. }) if key == "gateway.metrics_port.number")
+    );
+}
+
+#[test]
+fn a_flag_without_a_value_is_rejected() {
+    assert!(matches!(
+        load(&[], &["--gateway.metrics_port"]),
+        Err(ConfigError::Argument(_))
+    ));
+}
+
+#[test]
+fn a_missing_file_is_rejected() {
+    assert!(matches!(
+        load(&[], &["--config", "no/such/config.json"]),
+        Err(ConfigError::Read { .. })
+    ));
+}
+
+#[test]
+fn overrides_are_validated() {
+    let invalid_key =
+        |env_overrides: &[(&str, &str)], args: &[&str]| match load(env_overrides, args) {
+            Err(ConfigError::Invalid { key, .. }) => key,
+            other => panic!("expected a validation error, got {:?}", other.map(|_| ())),
+        };
+
+    assert_eq!(
+        invalid_key(&[], &["--transport.batching.batch_size", "0"]),
+        "transport.batching.batch_size"
+    );
+    assert_eq!(
+        invalid_key(&[("ME__TRANSPORT__BATCHING__MTU", "100")], &[]),
+        "transport.batching.mtu"
+    );
+    assert_eq!(
+        invalid_key(&[], &["--transport.retransmit_ring_len", "0"]),
+        "transport.retransmit_ring_len"
+    );
+}
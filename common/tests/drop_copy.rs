@@ -0,0 +1,85 @@
+use common::codec::codec_error::CodecError;
+use common::codec::message_codec::encode_engine_message;
+use common::drop_copy::drop_copy_codec::{
+    decode_copy, decode_request, encode_copy, encode_request,
+};
+use common::drop_copy::drop_copy_message::{DropCopy, DropCopyRequest};
+use common::transport::sequenced_message::EngineMessage;
+use common::types::instrument::Instrument;
+use common::types::latency_trace::LatencyTrace;
+use common::types::order::{NewOrderAck, TimeInForce};
+use common::types::side::Side;
+
+fn ack() -> EngineMessage {
+    EngineMessage::NewOrderAck(NewOrderAck {
+        client_id: 11,
+        client_order_id: 42,
+        order_id: 1000,
+        instrument: Instrument::str_to_fixed_char_buffer("BTC-USD"),
+        side: Side::Buy,
+        px: 6_500_012,
+        qty: 100,
+        qty_rem: 100,
+        time_in_force: TimeInForce::GTC,
+        ack_time: 1_700_000_000_000_000_000,
+        trace: LatencyTrace::default(),
+    })
+}
+
+#[test]
+fn requests_round_trip() {
+    for request in [
+        DropCopyRequest::ReplayFromSequence(0),
+        DropCopyRequest::ReplayFromSequence(1_234),
+        DropCopyRequest::ReplayFromTime(1_700_000_000_000_000_000),
+    ] {
+        let mut buf = Vec::new();
+        encode_request(&request, &mut buf);
+        assert_eq!(buf.len(), 9);
+        assert_eq!(decode_request(&buf).unwrap(), request);
+    }
+}
+
+#[test]
+fn copies_round_trip_behind_a_sequence_and_timestamp() {
+    let copy = DropCopy {
+        sequence: 258,
+        timestamp: 7,
+        message: ack(),
+    };
+    let mut buf = Vec::new();
+    encode_copy(&copy, &mut buf);
+
+    assert_eq!(&buf[..8], &[0, 0, 0, 0, 0, 0, 1, 2]);
+    assert_eq!(&buf[8..16], &[0, 0, 0, 0, 0, 0, 0, 7]);
+    let mut message = Vec::new();
+    encode_engine_message(&copy.message, &mut message);
+    assert_eq!(&buf[16..], message.as_slice());
+
+    let decoded = decode_copy(&buf).unwrap();
+    assert_eq!((decoded.sequence, decoded.timestamp), (258, 7));
+    assert_eq!(
+        format!("{:?}", decoded.message),
+        format!("{:?}", copy.message)
+    );
+}
+
+#[test]
+fn malformed_requests_and_copies_are_rejected() {
+    assert_eq!(
+        decode_request(b"Q"),
+        Err(CodecError::UnknownMessageType(b'Q'))
+    );
+    assert!(matches!(
+        decode_request(b"S\0\0\0"),
+        Err(CodecError::Truncated { .. })
+    ));
+    assert!(matches!(
+        decode_request(b"T\0\0\0\0\0\0\0\0\0"),
+        Err(CodecError::LengthMismatch { .. })
+    ));
+    assert!(matches!(
+        decode_copy(&[0u8; 12]),
+        Err(CodecError::Truncated { .. })
+    ));
+}
@@ -0,0 +1,61 @@
+use common::types::fixed_point::{from_decimal_str, to_decimal_str, FixedPointScale, MAX_DECIMALS};
+
+#[test]
+fn parses_and_formats_at_the_scale() {
+    let scale = FixedPointScale {
+        px_decimals: 2,
+        qty_decimals: 8,
+    };
+
+    assert_eq!(scale.str_to_px("101.25"), Ok(10_125));
+    assert_eq!(scale.str_to_qty("0.5"), Ok(50_000_000));
+    assert_eq!(scale.px_to_str(10_125), "101.25");
+    assert_eq!(scale.qty_to_str(50_000_000), "0.50000000");
+    assert_eq!(scale.px_to_str(7), "0.07");
+}
+
+#[test]
+fn missing_parts_are_zero() {
+    assert_eq!(from_decimal_str("12", 2), Ok(1_200));
+    assert_eq!(from_decimal_str("12.", 2), Ok(1_200));
+    assert_eq!(from_decimal_str(".5", 2), Ok(50));
+    assert_eq!(from_decimal_str("0", 0), Ok(0));
+    assert_eq!(to_decimal_str(42, 0), "42");
+}
+
+#[test]
+fn trailing_zeros_do_not_count_as_precision() {
+    assert_eq!(from_decimal_str("1.2500000", 2), Ok(125));
+    assert_eq!(from_decimal_str("3.000", 0), Ok(3));
+}
+
+#[test]
+fn too_many_fraction_digits_are_rejected_not_rounded() {
+    assert_eq!(
+        from_decimal_str("1.005", 2),
+        Err("'1.005' has more than 2 decimal places".to_string())
+    );
+    assert!(from_decimal_str("1.1", 0).is_err());
+}
+
+#[test]
+fn malformed_values_are_rejected() {
+    for value in ["", ".", "-1", "1.2.3", "1e5", " 1", "abc"] {
+        assert!(from_decimal_str(value, 2).is_err(), "{:?}", value);
+    }
+}
+
+#[test]
+fn values_beyond_u64_are_rejected() {
+    assert_eq!(from_decimal_str(&u64::MAX.to_string(), 0), Ok(u64::MAX));
+    assert!(from_decimal_str("18446744073709551616", 0).is_err());
+    assert_eq!(from_decimal_str("184467440737095516.15", 2), Ok(u64::MAX));
+    assert!(from_decimal_str("184467440737095516.16", 2).is_err());
+}
+
+#[test]
+fn max_decimals_round_trips() {
+    let raw = from_decimal_str("1.0000000000000000001", MAX_DECIMALS).unwrap();
+    assert_eq!(raw, 10u64.pow(MAX_DECIMALS as u32) + 1);
+    assert_eq!(to_decimal_str(raw, MAX_DECIMALS), "1.0000000000000000001");
+}
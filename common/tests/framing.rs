@@ -0,0 +1,128 @@
+use common::codec::frame_error::FrameError;
+use common::codec::framing::{
+    read_frame, read_message, read_message_async, write_frame, write_message, write_message_async,
+    MAX_FRAME_LEN,
+};
+use common::transport::sequenced_message::EngineMessage;
+use common::types::engine::EngineCommand;
+use std::io::{ErrorKind, Read};
+
+/// Hands out at most one byte per read, like a slow socket.
+struct Trickle<'a>(&'a [u8]);
+
+impl Read for Trickle<'_> {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        if self.0.is_empty() || buf.is_empty() {
+            return Ok(0);
+        }
+        buf[0] = self.0[0];
+        self.0 = &self.0[1..];
+        Ok(1)
+    }
+}
+
+#[test]
+fn prefix_is_a_big_endian_u32() {
+    let mut out = Vec::new();
+    write_frame(&mut out, b"abc").unwrap();
+
+    assert_eq!(out, [0, 0, 0, 3, b'a', b'b', b'c']);
+}
+
+#[test]
+fn frames_survive_partial_reads() {
+    let mut out = Vec::new();
+    let mut scratch = Vec::new();
+    write_message(
+        &mut out,
+        &EngineMessage::EngineCommand(EngineCommand::Start),
+        &mut scratch,
+    )
+    .unwrap();
+    write_frame(&mut out, b"token").unwrap();
+
+    let mut reader = Trickle(&out);
+    let mut buffer = [0u8; MAX_FRAME_LEN];
+    assert!(matches!(
+        read_message(&mut reader, &mut buffer).unwrap(),
+        EngineMessage::EngineCommand(EngineCommand::Start)
+    ));
+    assert_eq!(read_frame(&mut reader, &mut buffer).unwrap(), b"token");
+    assert!(matches!(
+        read_frame(&mut reader, &mut buffer),
+        Err(FrameError::Closed)
+    ));
+}
+
+#[test]
+fn oversized_frames_are_refused() {
+    let mut out = Vec::new();
+    write_frame(&mut out, &[0u8; 64]).unwrap();
+
+    let mut buffer = [0u8; 16];
+    assert!(matches!(
+        read_frame(&mut out.as_slice(), &mut buffer),
+        Err(FrameError::TooLarge { len: 64, max: 16 })
+    ));
+    assert!(matches!(
+        write_frame(&mut Vec::new(), &vec![0u8; MAX_FRAME_LEN + 1]),
+        Err(FrameError::TooLarge { .. })
+    ));
+}
+
+#[test]
+fn eof_inside_a_frame_is_an_error() {
+    let mut out = Vec::new();
+    write_frame(&mut out, b"abcdef").unwrap();
+    let mut buffer = [0u8; MAX_FRAME_LEN];
+
+    for len in 1..out.len() {
+        match read_frame(&mut &out[..len], &mut buffer) {
+            Err(FrameError::Io(e)) => assert_eq!(e.kind(), ErrorKind::UnexpectedEof),
+            other => panic!("unexpected {:?}", other),
+        }
+    }
+}
+
+#[test]
+fn garbage_payloads_are_codec_errors() {
+    let mut out = Vec::new();
+    write_frame(&mut out, &[0xff; 8]).unwrap();
+    let mut buffer = [0u8; MAX_FRAME_LEN];
+
+    assert!(matches!(
+        read_message(&mut out.as_slice(), &mut buffer),
+        Err(FrameError::Codec(_))
+    ));
+}
+
+#[tokio::test]
+async fn async_frames_round_trip() {
+    let (mut client, mut server) = tokio::io::duplex(8);
+    let writer = tokio::spawn(async move {
+        let mut scratch = Vec::new();
+        for _ in 0..10 {
+            write_message_async(
+                &mut client,
+                &EngineMessage::EngineCommand(EngineCommand::Shutdown),
+                &mut scratch,
+            )
+            .await
+            .unwrap();
+        }
+    });
+
+    let mut buffer = [0u8; MAX_FRAME_LEN];
+    for _ in 0..10 {
+        assert!(matches!(
+            read_message_async(&mut server, &mut buffer).await.unwrap(),
+            EngineMessage::EngineCommand(EngineCommand::Shutdown)
+        ));
+    }
+    writer.await.unwrap();
+
+    assert!(matches!(
+        read_message_async(&mut server, &mut buffer).await,
+        Err(FrameError::Closed)
+    ));
+}
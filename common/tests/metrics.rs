@@ -0,0 +1,100 @@
+use common::metrics::latency_histogram::LatencyHistogram;
+use common::metrics::metrics_registry::MetricsRegistry;
+use std::sync::Arc;
+
+#[test]
+fn recordings_are_only_shared_once_flushed() {
+    let histogram = Arc::new(LatencyHistogram::new());
+    let mut recorder = histogram.recorder();
+
+    for nanos in 1..=1000 {
+        recorder.record(nanos * 1_000);
+    }
+    assert_eq!(histogram.snapshot().len(), 0);
+
+    recorder.flush();
+    let snapshot = histogram.snapshot();
+    assert_eq!(snapshot.len(), 1000);
+
+    // 3 significant figures
+    let p50 = snapshot.value_at_quantile(0.5);
+    assert!((499_000..=501_000).contains(&p50), "p50 {}", p50);
+    let max = snapshot.max();
+    assert!((999_000..=1_001_000).contains(&max), "max {}", max);
+}
+
+#[test]
+fn flushes_accumulate_and_reset_the_recorder() {
+    let histogram = Arc::new(LatencyHistogram::new());
+    let mut recorder = histogram.recorder();
+
+    recorder.record(100);
+    recorder.flush();
+    recorder.flush();
+    recorder.record(200);
+    recorder.flush();
+
+    assert_eq!(histogram.snapshot().len(), 2);
+}
+
+#[test]
+fn out_of_range_values_are_clamped() {
+    let histogram = Arc::new(LatencyHistogram::new());
+    let mut recorder = histogram.recorder();
+
+    recorder.record(0);
+    recorder.record(u64::MAX);
+    recorder.flush();
+
+    let snapshot = histogram.snapshot();
+    assert_eq!(snapshot.len(), 2);
+    assert_eq!(snapshot.min(), 1);
+}
+
+#[test]
+fn render_uses_the_prometheus_text_format() {
+    let registry = MetricsRegistry::new();
+    let orders = registry.counter("me_orders_total", "Orders received");
+    let depth = registry.gauge("me_book_depth", "Orders on the book");
+    let latency = registry.histogram("me_latency_ns", "Match latency");
+
+    orders.add(3);
+    depth.set(-2);
+    let mut recorder = latency.recorder();
+    recorder.record(1_000);
+    recorder.record(3_000);
+    recorder.flush();
+
+    let rendered = registry.render();
+    let lines: Vec<&str> = rendered.lines().collect();
+
+    assert_eq!(
+        lines[..6],
+        [
+            "# HELP me_orders_total Orders received",
+            "# TYPE me_orders_total counter",
+            "me_orders_total 3",
+            "# HELP me_book_depth Orders on the book",
+            "# TYPE me_book_depth gauge",
+            "me_book_depth -2",
+        ]
+    );
+    assert_eq!(lines[6], "# HELP me_latency_ns Match latency");
+    assert_eq!(lines[7], "# TYPE me_latency_ns summary");
+    // quantiles and the sum come from HDR buckets, 3000 lands in a 2ns wide one
+    assert_eq!(lines[8], "me_latency_ns{quantile=\"0.5\"} 1000");
+    assert_eq!(lines[12], "me_latency_ns{quantile=\"1\"} 3001");
+    assert_eq!(lines[13], "me_latency_ns_sum 4001");
+    assert_eq!(lines[14], "me_latency_ns_count 2");
+    assert_eq!(lines.len(), 15);
+}
+
+#[test]
+fn empty_histograms_render_zeroes() {
+    let registry = MetricsRegistry::new();
+    registry.histogram("me_latency_ns", "Match latency");
+
+    let rendered = registry.render();
+    assert!(rendered.contains("me_latency_ns{quantile=\"0.99\"} 0\n"));
+    assert!(rendered.contains("me_latency_ns_count 0\n"));
+}
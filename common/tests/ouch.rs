@@ -0,0 +1,165 @@
+use common::codec::codec_error::CodecError;
+use common::ouch::ouch_codec::{decode_inbound, decode_outbound, encode_inbound, encode_outbound};
+use common::ouch::ouch_message::{
+    Accepted, CancelOrder, CancelRejected, Canceled, CanceledReason, EnterOrder, Executed,
+    Liquidity, OuchInbound, OuchOutbound, Rejected, RejectedReason, ReplaceOrder, Replaced,
+};
+use common::types::instrument::Instrument;
+use common::types::order::TimeInForce;
+use common::types::side::Side;
+
+fn instrument() -> [u8; 16] {
+    Instrument::str_to_fixed_char_buffer("BTC-USD")
+}
+
+fn enter_order() -> OuchInbound {
+    OuchInbound::EnterOrder(EnterOrder {
+        token: 7,
+        side: Side::Sell,
+        qty: 150_000_000,
+        instrument: instrument(),
+        px: 6_500_012,
+        time_in_force: TimeInForce::IOC,
+    })
+}
+
+fn inbound_messages() -> Vec<OuchInbound> {
+    vec![
+        enter_order(),
+        OuchInbound::ReplaceOrder(ReplaceOrder {
+            existing_token: 7,
+            replacement_token: 8,
+            qty: 100,
+            px: 6_400_000,
+        }),
+        OuchInbound::CancelOrder(CancelOrder { token: u64::MAX }),
+    ]
+}
+
+fn outbound_messages() -> Vec<OuchOutbound> {
+    vec![
+        OuchOutbound::Accepted(Accepted {
+            timestamp: 1,
+            token: 7,
+            side: Side::Buy,
+            qty: 100,
+            instrument: instrument(),
+            px: 6_500_012,
+            time_in_force: TimeInForce::GTC,
+            order_ref: 1000,
+        }),
+        OuchOutbound::Replaced(Replaced {
+            timestamp: 2,
+            token: 8,
+            side: Side::Sell,
+            qty: 50,
+            instrument: instrument(),
+            px: 6_400_000,
+            time_in_force: TimeInForce::FOK,
+            order_ref: 1001,
+            previous_token: 7,
+        }),
+        OuchOutbound::Executed(Executed {
+            timestamp: 3,
+            token: 8,
+            executed_qty: 25,
+            px: 6_400_000,
+            liquidity: Liquidity::Added,
+        }),
+        OuchOutbound::Canceled(Canceled {
+            timestamp: 4,
+            token: 8,
+            decrement_qty: 25,
+            reason: CanceledReason::SelfMatchPrevention,
+        }),
+        OuchOutbound::Rejected(Rejected {
+            timestamp: 5,
+            token: 9,
+            reason: RejectedReason::DuplicateToken,
+        }),
+        OuchOutbound::CancelRejected(CancelRejected {
+            timestamp: 6,
+            token: 10,
+        }),
+    ]
+}
+
+#[test]
+fn inbound_messages_round_trip() {
+    for message in inbound_messages() {
+        let mut buf = Vec::new();
+        encode_inbound(&message, &mut buf);
+        assert_eq!(decode_inbound(&buf).unwrap(), message);
+    }
+}
+
+#[test]
+fn outbound_messages_round_trip() {
+    for message in outbound_messages() {
+        let mut buf = Vec::new();
+        encode_outbound(&message, &mut buf);
+        assert_eq!(decode_outbound(&buf).unwrap(), message);
+    }
+}
+
+#[test]
+fn enter_order_layout_is_fixed_and_big_endian() {
+    let mut buf = Vec::new();
+    encode_inbound(&enter_order(), &mut buf);
+
+    assert_eq!(buf.len(), 43);
+    assert_eq!(buf[0], b'O');
+    assert_eq!(buf[1..9], 7u64.to_be_bytes());
+    assert_eq!(buf[9], b'S');
+    assert_eq!(buf[10..18], 150_000_000u64.to_be_bytes());
+    assert_eq!(&buf[18..25], b"BTC-USD");
+    assert_eq!(buf[34..42], 6_500_012u64.to_be_bytes());
+    assert_eq!(buf[42], b'I');
+}
+
+#[test]
+fn every_truncation_is_rejected() {
+    for message in inbound_messages() {
+        let mut buf = Vec::new();
+        encode_inbound(&message, &mut buf);
+        for len in 0..buf.len() {
+            assert!(matches!(
+                decode_inbound(&buf[..len]),
+                Err(CodecError::Truncated { .. })
+            ));
+        }
+    }
+}
+
+#[test]
+fn malformed_messages_are_rejected() {
+    let mut buf = Vec::new();
+    encode_inbound(&enter_order(), &mut buf);
+
+    let mut trailing = buf.clone();
+    trailing.push(0);
+    assert_eq!(
+        decode_inbound(&trailing),
+        Err(CodecError::LengthMismatch {
+            declared: 44,
+            actual: 43
+        })
+    );
+
+    let mut bad_side = buf.clone();
+    bad_side[9] = b'x';
+    assert_eq!(
+        decode_inbound(&bad_side),
+        Err(CodecError::InvalidValue {
+            field: "side",
+            value: b'x'
+        })
+    );
+
+    let mut unknown = buf;
+    unknown[0] = b'Q';
+    assert_eq!(
+        decode_inbound(&unknown),
+        Err(CodecError::UnknownMessageType(b'Q'))
+    );
+}
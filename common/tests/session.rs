@@ -0,0 +1,114 @@
+use common::codec::codec_error::CodecError;
+use common::session::session_codec::{decode_client, decode_server, encode_client, encode_server};
+use common::session::session_message::{
+    credential, ClientPacket, LoginAccepted, LoginRejectReason, LoginRequest, ServerPacket,
+};
+
+fn client_packets() -> Vec<ClientPacket<'static>> {
+    vec![
+        ClientPacket::LoginRequest(LoginRequest::new("trader1", "secret")),
+        ClientPacket::LoginRequest(LoginRequest::new("trader1", "secret").resume(1_234)),
+        ClientPacket::UnsequencedData(b"O payload"),
+        ClientPacket::UnsequencedData(b""),
+        ClientPacket::ClientHeartbeat,
+        ClientPacket::LogoutRequest,
+    ]
+}
+
+fn server_packets() -> Vec<ServerPacket<'static>> {
+    vec![
+        ServerPacket::LoginAccepted(LoginAccepted {
+            client_id: 42,
+            sequence: 7,
+        }),
+        ServerPacket::LoginRejected(LoginRejectReason::NotAuthorized),
+        ServerPacket::LoginRejected(LoginRejectReason::SessionUnavailable),
+        ServerPacket::SequencedData(b"A payload"),
+        ServerPacket::ServerHeartbeat,
+        ServerPacket::EndOfSession,
+    ]
+}
+
+#[test]
+fn client_packets_round_trip() {
+    for packet in client_packets() {
+        let mut buf = Vec::new();
+        encode_client(&packet, &mut buf);
+        assert_eq!(decode_client(&buf).unwrap(), packet);
+    }
+}
+
+#[test]
+fn server_packets_round_trip() {
+    for packet in server_packets() {
+        let mut buf = Vec::new();
+        encode_server(&packet, &mut buf);
+        assert_eq!(decode_server(&buf).unwrap(), packet);
+    }
+}
+
+#[test]
+fn login_request_layout_is_fixed() {
+    let mut buf = Vec::new();
+    encode_client(
+        &ClientPacket::LoginRequest(LoginRequest::new("trader1", "secret").resume(258)),
+        &mut buf,
+    );
+
+    assert_eq!(buf.len(), 41);
+    assert_eq!(buf[0], b'L');
+    assert_eq!(&buf[1..8], b"trader1");
+    assert!(buf[8..17].iter().all(|&b| b == 0));
+    assert_eq!(&buf[17..23], b"secret");
+    assert_eq!(&buf[33..41], &[0, 0, 0, 0, 0, 0, 1, 2]);
+}
+
+#[test]
+fn data_packets_carry_the_rest_of_the_frame() {
+    assert_eq!(
+        decode_server(b"Shello").unwrap(),
+        ServerPacket::SequencedData(b"hello")
+    );
+    assert_eq!(
+        decode_client(b"Uhello").unwrap(),
+        ClientPacket::UnsequencedData(b"hello")
+    );
+}
+
+#[test]
+fn credentials_are_cut_to_sixteen_bytes() {
+    assert_eq!(&credential("a-very-long-username"), b"a-very-long-user");
+}
+
+#[test]
+fn malformed_packets_are_rejected() {
+    assert!(matches!(
+        decode_client(b"Ltrader1"),
+        Err(CodecError::Truncated { .. })
+    ));
+    assert_eq!(
+        decode_client(b"R!"),
+        Err(CodecError::LengthMismatch {
+            declared: 2,
+            actual: 1
+        })
+    );
+    assert_eq!(
+        decode_server(b"JX"),
+        Err(CodecError::InvalidValue {
+            field: "login_reject_reason",
+            value: b'X'
+        })
+    );
+    assert_eq!(
+        decode_server(b""),
+        Err(CodecError::Truncated {
+            needed: 1,
+            remaining: 0
+        })
+    );
+    assert_eq!(
+        decode_client(b"Q"),
+        Err(CodecError::UnknownMessageType(b'Q'))
+    );
+}
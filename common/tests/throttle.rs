@@ -0,0 +1,179 @@
+use common::metrics::metrics_registry::MetricsRegistry;
+use common::transport::sequenced_message::EngineMessage;
+use common::types::admin::{AdminCommand, AdminRequest};
+use common::types::cancel_order::CancelOrderRequest;
+use common::types::latency_trace::LatencyTrace;
+use common::types::order::{OrderRequest, TimeInForce};
+use common::types::side::Side;
+use common::util::throttle::{Throttle, ThrottleConfig};
+use common::util::token_bucket::{RateLimit, TokenBucket};
+use std::sync::Arc;
+
+const SECOND_NS: u64 = 1_000_000_000;
+
+fn new_order(client_id: u32) -> EngineMessage {
+    EngineMessage::NewOrder(OrderRequest {
+        client_id,
+        client_order_id: 1,
+        instrument: [0; 16],
+        order_side: Side::Buy,
+        px: 100,
+        qty: 1,
+        time_in_force: TimeInForce::GTC,
+        timestamp: 0,
+        trace: LatencyTrace::default(),
+    })
+}
+
+fn cancel(client_id: u32) -> EngineMessage {
+    EngineMessage::CancelOrder(CancelOrderRequest {
+        client_id,
+        order_side: Side::Buy,
+        order_id: 1,
+        instrument: [0; 16],
+        trace: LatencyTrace::default(),
+    })
+}
+
+/// Rates low enough that nothing refills while a test runs.
+fn config(session: RateLimit, client: RateLimit) -> ThrottleConfig {
+    ThrottleConfig {
+        session_new_orders: session,
+        session_cancels: session,
+        client_new_orders: client,
+        client_cancels: client,
+    }
+}
+
+#[test]
+fn bucket_allows_a_burst_then_refills_at_the_rate() {
+    let mut bucket = TokenBucket::new(RateLimit::new(10, 3), 0);
+
+    assert!((0..3).all(|_| bucket.try_acquire(0)));
+    assert!(!bucket.try_acquire(0));
+
+    // one token every 100ms
+    assert!(!bucket.try_acquire(SECOND_NS / 20));
+    assert!(bucket.try_acquire(SECOND_NS / 10));
+    assert!(!bucket.try_acquire(SECOND_NS / 10));
+}
+
+#[test]
+fn bucket_refill_is_capped_at_the_burst() {
+    let mut bucket = TokenBucket::new(RateLimit::new(1_000, 2), 0);
+    assert!(bucket.try_acquire(0));
+
+    let later = 60 * SECOND_NS;
+    assert!((0..2).all(|_| bucket.try_acquire(later)));
+    assert!(!bucket.try_acquire(later));
+}
+
+#[test]
+fn bucket_ignores_the_clock_going_backwards() {
+    let mut bucket = TokenBucket::new(RateLimit::new(1, 1), SECOND_NS);
+    assert!(bucket.try_acquire(SECOND_NS));
+
+    assert!(!bucket.try_acquire(0));
+    assert!(!bucket.try_acquire(SECOND_NS));
+    assert!(bucket.try_acquire(2 * SECOND_NS));
+}
+
+#[test]
+fn zero_rate_disables_the_bucket() {
+    let mut bucket = TokenBucket::new(RateLimit::new(0, 0), 0);
+    assert!((0..1_000).all(|_| bucket.try_acquire(0)));
+}
+
+#[test]
+fn sessions_have_their_own_budget() {
+    let registry = MetricsRegistry::new();
+    let throttle = Arc::new(Throttle::new(
+        config(RateLimit::new(1, 2), RateLimit::new(0, 0)),
+        &registry,
+        "test",
+    ));
+    let mut first = throttle.session();
+    let mut second = throttle.session();
+
+    assert!(first.try_acquire(&new_order(1)));
+    assert!(first.try_acquire(&new_order(1)));
+    assert!(!first.try_acquire(&new_order(1)));
+    assert!(second.try_acquire(&new_order(1)));
+
+    // cancels are budgeted separately
+    assert!(first.try_acquire(&cancel(1)));
+}
+
+#[test]
+fn clients_share_a_budget_across_sessions() {
+    let registry = MetricsRegistry::new();
+    let throttle = Arc::new(Throttle::new(
+        config(RateLimit::new(0, 0), RateLimit::new(1, 2)),
+        &registry,
+        "test",
+    ));
+    let mut first = throttle.session();
+    let mut second = throttle.session();
+
+    assert!(first.try_acquire(&new_order(7)));
+    assert!(second.try_acquire(&new_order(7)));
+    assert!(!first.try_acquire(&new_order(7)));
+    assert!(!second.try_acquire(&new_order(7)));
+    assert!(first.try_acquire(&new_order(8)));
+
+    assert!(second.try_acquire(&cancel(7)));
+    assert!(second.try_acquire(&cancel(7)));
+    assert!(!first.try_acquire(&cancel(7)));
+
+    let rendered = registry.render();
+    assert!(rendered.contains("test_throttled_new_orders_total 2\n"));
+    assert!(rendered.contains("test_throttled_cancels_total 1\n"));
+}
+
+#[test]
+fn a_message_the_client_budget_refuses_keeps_its_session_token() {
+    let registry = MetricsRegistry::new();
+    let throttle = Arc::new(Throttle::new(
+        config(RateLimit::new(1, 2), RateLimit::new(1, 1)),
+        &registry,
+        "test",
+    ));
+    let mut session = throttle.session();
+
+    assert!(session.try_acquire(&new_order(7)));
+    assert!(!session.try_acquire(&new_order(7)));
+
+    // the session's second token is still there for another client
+    assert!(session.try_acquire(&new_order(8)));
+    assert!(!session.try_acquire(&new_order(9)));
+}
+
+#[test]
+fn bucket_refund_is_capped_at_the_burst() {
+    let mut bucket = TokenBucket::new(RateLimit::new(1, 1), 0);
+    bucket.refund();
+
+    assert!(bucket.try_acquire(0));
+    assert!(!bucket.try_acquire(0));
+
+    bucket.refund();
+    assert!(bucket.try_acquire(0));
+}
+
+#[test]
+fn other_messages_are_never_throttled() {
+    let registry = MetricsRegistry::new();
+    let throttle = Arc::new(Throttle::new(
+        config(RateLimit::new(1, 1), RateLimit::new(1, 1)),
+        &registry,
+        "test",
+    ));
+    let mut session = throttle.session();
+    let admin = EngineMessage::AdminRequest(AdminRequest {
+        request_id: 1,
+        instrument: [0; 16],
+        command: AdminCommand::Status,
+    });
+
+    assert!((0..100).all(|_| session.try_acquire(&admin)));
+}
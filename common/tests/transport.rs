@@ -0,0 +1,527 @@
+use common::codec::message_codec::{
+    decode_datagram, decode_range_nack, encode_heartbeat, Datagram,
+};
+use common::config::app_config::BatchingConfig;
+use common::network::mutlicast::multicast_receiver;
+use common::transport::nack_sequenced_multicast_receiver::{
+    NackSequencedMulticastReceiver, TransportEvent,
+};
+use common::transport::nack_sequenced_multicast_sender::{
+    resendable, NackSequencedMulticastSender,
+};
+use common::transport::sequenced_message::{
+    EngineMessage, SenderEpoch, SenderId, SequenceNumber, SequencedEngineMessage,
+    SequencedHeartbeat,
+};
+use common::transport::transport_constants::SENDER_SILENT_TIMEOUT;
+use common::transport::zero_alloc::RawWireMessage;
+use common::types::engine::EngineCommand;
+use std::net::{SocketAddr, UdpSocket};
+use std::time::{Duration, Instant};
+
+const SENDER_ID: SenderId = 3;
+const EPOCH: SenderEpoch = 1_000;
+
+fn message(epoch: SenderEpoch, sequence_number: SequenceNumber) -> SequencedEngineMessage {
+    SequencedEngineMessage {
+        sender_id: SENDER_ID,
+        epoch,
+        sequence_number,
+        message: EngineMessage::EngineCommand(EngineCommand::Start),
+        sent_time: sequence_number as u64,
+    }
+}
+
+fn batch(
+    epoch: SenderEpoch,
+    sequence_numbers: impl IntoIterator<Item = SequenceNumber>,
+) -> Vec<u8> {
+    let mut batch = RawWireMessage::new(1472);
+    for sequence_number in sequence_numbers {
+        assert!(batch.push(&message(epoch, sequence_number)));
+    }
+    batch.as_bytes().to_vec()
+}
+
+fn heartbeat(epoch: SenderEpoch, sequence_number: SequenceNumber) -> Vec<u8> {
+    let mut heartbeat = Vec::new();
+    encode_heartbeat(
+        &SequencedHeartbeat {
+            sender_id: SENDER_ID,
+            epoch,
+            sequence_number,
+            // messages are sent at their sequence number
+            watermark_ns: sequence_number as u64 + 1,
+        },
+        &mut heartbeat,
+    );
+    heartbeat
+}
+
+/// A receiver reading a unicast socket, with the socket a test sender writes to it from.
+fn receiver(nack_port: u16) -> (NackSequencedMulticastReceiver, UdpSocket, SocketAddr) {
+    let recv_socket = UdpSocket::bind("127.0.0.1:0").unwrap();
+    let recv_addr = recv_socket.local_addr().unwrap();
+    let receiver = NackSequencedMulticastReceiver::new(recv_socket, nack_port, 64);
+    (receiver, UdpSocket::bind("127.0.0.1:0").unwrap(), recv_addr)
+}
+
+/// A sender writing to a unicast socket the test reads its batches from.
+fn sender(nack_port: u16, batching: BatchingConfig) -> (NackSequencedMulticastSender, UdpSocket) {
+    let socket = UdpSocket::bind("127.0.0.1:0").unwrap();
+    let sender = NackSequencedMulticastSender::new(
+        UdpSocket::bind("127.0.0.1:0").unwrap(),
+        socket.local_addr().unwrap(),
+        nack_port,
+        SENDER_ID,
+        batching,
+        64,
+    );
+    (sender, socket)
+}
+
+/// The sequence numbers in the next batch on `socket`, skipping heartbeats, `None` if no batch
+/// arrives within 50ms.
+fn next_batch(socket: &UdpSocket) -> Option<Vec<SequenceNumber>> {
+    let deadline = Instant::now() + Duration::from_millis(50);
+    let mut buf = [0u8; 1500];
+    loop {
+        let wait = deadline.checked_duration_since(Instant::now())?;
+        socket
+            .set_read_timeout(Some(wait.max(Duration::from_millis(1))))
+            .unwrap();
+        let len = socket.recv(&mut buf).ok()?;
+        if let Datagram::Batch(batch) = decode_datagram(&buf[..len]).unwrap() {
+            return Some(batch.map(|msg| msg.unwrap().sequence_number).collect());
+        }
+    }
+}
+
+/// Polls `poll` until it returns something, for up to `timeout`.
+fn poll_for<T>(timeout: Duration, mut poll: impl FnMut() -> Option<T>) -> Option<T> {
+    let deadline = Instant::now() + timeout;
+    while Instant::now() < deadline {
+        if let Some(value) = poll() {
+            return Some(value);
+        }
+        std::thread::sleep(Duration::from_micros(100));
+    }
+    None
+}
+
+#[test]
+fn resendable_keeps_a_range_within_what_was_sent() {
+    assert_eq!(resendable(3, 7, 10, 100), Some((3, 7)));
+    assert_eq!(resendable(1, 10, 10, 100), Some((1, 10)));
+}
+
+#[test]
+fn resendable_drops_a_range_past_the_last_sent() {
+    assert_eq!(resendable(11, 20, 10, 100), None);
+    assert_eq!(resendable(1, 5, 0, 100), None);
+}
+
+#[test]
+fn resendable_drops_a_range_older_than_the_ring() {
+    // the ring of 5 holds 6 to 10
+    assert_eq!(resendable(1, 5, 10, 5), None);
+    assert_eq!(resendable(6, 6, 10, 5), Some((6, 6)));
+}
+
+#[test]
+fn resendable_clamps_a_range_straddling_either_end() {
+    assert_eq!(resendable(8, 20, 10, 100), Some((8, 10)));
+    assert_eq!(resendable(1, 8, 10, 5), Some((6, 8)));
+    assert_eq!(resendable(0, SequenceNumber::MAX, 10, 5), Some((6, 10)));
+}
+
+#[test]
+fn resendable_caps_a_ring_larger_than_the_sequence_space() {
+    assert_eq!(resendable(1, 10, 10, usize::MAX), Some((1, 10)));
+}
+
+#[test]
+fn a_heartbeat_gets_a_lost_tail_nacked_and_resent() {
+    let nack_port = 19_401;
+    let nack_listener = multicast_receiver(nack_port);
+    nack_listener
+        .set_read_timeout(Some(Duration::from_millis(100)))
+        .unwrap();
+    let (mut receiver, sender, recv_addr) = receiver(nack_port);
+
+    // 1 and 2 were lost, only the heartbeat saying 2 went out arrives
+    sender.send_to(&heartbeat(EPOCH, 2), recv_addr).unwrap();
+
+    let mut nack_buf = [0u8; 1500];
+    let (nack, nack_source) = poll_for(Duration::from_secs(5), || {
+        assert!(receiver.try_recv().is_none());
+        let (len, source) = nack_listener.recv_from(&mut nack_buf).ok()?;
+        Some((decode_range_nack(&nack_buf[..len]).unwrap(), source))
+    })
+    .expect("no NACK for the tail");
+    assert_eq!(nack.sender_id, SENDER_ID);
+    assert_eq!(nack.epoch, EPOCH);
+    assert_eq!(nack.start, 1);
+
+    // the retransmission goes back to the port the NACK came from
+    sender.send_to(&batch(EPOCH, 1..=2), nack_source).unwrap();
+
+    let first = poll_for(Duration::from_secs(5), || receiver.try_recv()).unwrap();
+    let second = poll_for(Duration::from_secs(5), || receiver.try_recv()).unwrap();
+    assert_eq!(first.sequence_number, 1);
+    assert_eq!(second.sequence_number, 2);
+}
+
+#[test]
+fn a_caught_up_receiver_nacks_nothing() {
+    let nack_port = 19_402;
+    let nack_listener = multicast_receiver(nack_port);
+    nack_listener
+        .set_read_timeout(Some(Duration::from_millis(100)))
+        .unwrap();
+    let (mut receiver, sender, recv_addr) = receiver(nack_port);
+
+    sender.send_to(&batch(EPOCH, 1..=2), recv_addr).unwrap();
+    sender.send_to(&heartbeat(EPOCH, 2), recv_addr).unwrap();
+
+    let first = poll_for(Duration::from_secs(5), || receiver.try_recv()).unwrap();
+    let second = poll_for(Duration::from_secs(5), || receiver.try_recv()).unwrap();
+    assert_eq!(first.sequence_number, 1);
+    assert_eq!(second.sequence_number, 2);
+
+    for _ in 0..100 {
+        assert!(receiver.try_recv().is_none());
+    }
+    let mut nack_buf = [0u8; 1500];
+    assert!(nack_listener.recv_from(&mut nack_buf).is_err());
+}
+
+#[test]
+fn a_sender_is_reported_silent_and_then_resumed() {
+    let (mut receiver, sender, recv_addr) = receiver(19_403);
+
+    sender.send_to(&heartbeat(EPOCH, 0), recv_addr).unwrap();
+    // nothing is reported while the sender is heard from
+    std::thread::sleep(SENDER_SILENT_TIMEOUT / 2);
+    assert_eq!(receiver.poll_event(), None);
+
+    let silent = poll_for(SENDER_SILENT_TIMEOUT * 3, || receiver.poll_event());
+    assert_eq!(silent, Some(TransportEvent::SenderSilent(SENDER_ID)));
+
+    sender.send_to(&heartbeat(EPOCH, 0), recv_addr).unwrap();
+    let resumed = poll_for(Duration::from_secs(5), || receiver.poll_event());
+    assert_eq!(resumed, Some(TransportEvent::SenderResumed(SENDER_ID)));
+}
+
+#[test]
+fn flush_sends_a_partial_batch() {
+    let batching = BatchingConfig {
+        batch_size: 32,
+        max_flush_delay_micros: 60_000_000,
+        mtu: 1500,
+    };
+    let (mut sender, socket) = sender(19_404, batching);
+
+    assert_eq!(
+        sender.send(EngineMessage::EngineCommand(EngineCommand::Start)),
+        Some(1)
+    );
+    assert_eq!(next_batch(&socket), None);
+    assert_eq!(sender.last_published(), 0);
+
+    sender.flush();
+    assert_eq!(next_batch(&socket), Some(vec![1]));
+    assert_eq!(sender.last_published(), 1);
+}
+
+#[test]
+fn a_full_batch_is_sent_without_a_flush() {
+    let batching = BatchingConfig {
+        batch_size: 3,
+        max_flush_delay_micros: 60_000_000,
+        mtu: 1500,
+    };
+    let (mut sender, socket) = sender(19_405, batching);
+
+    for _ in 0..4 {
+        sender.send(EngineMessage::EngineCommand(EngineCommand::Start));
+    }
+    assert_eq!(next_batch(&socket), Some(vec![1, 2, 3]));
+    assert_eq!(next_batch(&socket), None);
+    assert_eq!(sender.last_published(), 3);
+}
+
+#[test]
+fn a_send_after_the_flush_delay_sends_the_batch() {
+    let batching = BatchingConfig {
+        batch_size: 32,
+        max_flush_delay_micros: 10_000,
+        mtu: 1500,
+    };
+    let (mut sender, socket) = sender(19_406, batching);
+
+    sender.send(EngineMessage::EngineCommand(EngineCommand::Start));
+    assert_eq!(next_batch(&socket), None);
+
+    std::thread::sleep(Duration::from_millis(20));
+    sender.send(EngineMessage::EngineCommand(EngineCommand::Start));
+    assert_eq!(next_batch(&socket), Some(vec![1, 2]));
+    assert_eq!(sender.last_published(), 2);
+}
+
+#[test]
+fn a_message_larger_than_a_datagram_is_dropped() {
+    // short of what config validation allows, no message fits
+    let batching = BatchingConfig {
+        batch_size: 32,
+        max_flush_delay_micros: 0,
+        mtu: 40,
+    };
+    let (mut sender, socket) = sender(19_407, batching);
+
+    assert_eq!(
+        sender.send(EngineMessage::EngineCommand(EngineCommand::Start)),
+        None
+    );
+    sender.flush();
+    assert_eq!(next_batch(&socket), None);
+    assert_eq!(sender.last_published(), 0);
+}
+
+/// Receives the next message, `None` if none is delivered within 50ms.
+fn recv(receiver: &mut NackSequencedMulticastReceiver) -> Option<SequencedEngineMessage> {
+    poll_for(Duration::from_millis(50), || receiver.try_recv())
+}
+
+/// The epoch and sequence number of the next message, waiting for it up to 5s.
+fn recv_next(receiver: &mut NackSequencedMulticastReceiver) -> (SenderEpoch, SequenceNumber) {
+    let msg = poll_for(Duration::from_secs(5), || receiver.try_recv()).expect("no message");
+    (msg.epoch, msg.sequence_number)
+}
+
+/// Events reported so far.
+fn events(receiver: &mut NackSequencedMulticastReceiver) -> Vec<TransportEvent> {
+    std::iter::from_fn(|| receiver.poll_event()).collect()
+}
+
+#[test]
+fn a_newer_epoch_restarts_the_stream() {
+    let (mut receiver, sender, recv_addr) = receiver(19_408);
+
+    sender.send_to(&batch(EPOCH, 1..=3), recv_addr).unwrap();
+    for sequence_number in 1..=3 {
+        assert_eq!(recv_next(&mut receiver), (EPOCH, sequence_number));
+    }
+
+    // numbered from 1 again, not taken as a repeat of the old epoch's 1
+    sender.send_to(&batch(EPOCH + 1, 1..=2), recv_addr).unwrap();
+    assert_eq!(recv_next(&mut receiver), (EPOCH + 1, 1));
+    assert_eq!(
+        events(&mut receiver),
+        vec![TransportEvent::SenderRestarted(SENDER_ID)]
+    );
+    assert_eq!(recv_next(&mut receiver), (EPOCH + 1, 2));
+}
+
+#[test]
+fn an_older_epoch_is_ignored_while_the_stream_is_live() {
+    let (mut receiver, sender, recv_addr) = receiver(19_409);
+
+    sender.send_to(&batch(EPOCH, 1..=2), recv_addr).unwrap();
+    assert_eq!(recv_next(&mut receiver), (EPOCH, 1));
+    assert_eq!(recv_next(&mut receiver), (EPOCH, 2));
+
+    sender.send_to(&batch(EPOCH - 1, 1..=3), recv_addr).unwrap();
+    sender.send_to(&heartbeat(EPOCH - 1, 3), recv_addr).unwrap();
+    assert!(recv(&mut receiver).is_none());
+
+    sender.send_to(&batch(EPOCH, 3..=3), recv_addr).unwrap();
+    assert_eq!(recv_next(&mut receiver), (EPOCH, 3));
+    assert_eq!(events(&mut receiver), vec![]);
+}
+
+#[test]
+fn an_older_epoch_is_followed_once_the_stream_goes_silent() {
+    let (mut receiver, sender, recv_addr) = receiver(19_410);
+
+    sender.send_to(&batch(EPOCH, 1..=2), recv_addr).unwrap();
+    assert_eq!(recv_next(&mut receiver), (EPOCH, 1));
+    assert_eq!(recv_next(&mut receiver), (EPOCH, 2));
+
+    // a restart whose clock stepped back
+    std::thread::sleep(SENDER_SILENT_TIMEOUT + Duration::from_millis(100));
+    sender.send_to(&batch(EPOCH - 1, 1..=1), recv_addr).unwrap();
+    assert_eq!(recv_next(&mut receiver), (EPOCH - 1, 1));
+    assert!(events(&mut receiver).contains(&TransportEvent::SenderRestarted(SENDER_ID)));
+}
+
+#[test]
+fn stale_retransmits_from_the_old_epoch_are_not_delivered() {
+    let (mut receiver, sender, recv_addr) = receiver(19_411);
+
+    sender.send_to(&batch(EPOCH, 1..=1), recv_addr).unwrap();
+    assert_eq!(recv_next(&mut receiver), (EPOCH, 1));
+
+    sender.send_to(&heartbeat(EPOCH + 1, 0), recv_addr).unwrap();
+    assert!(recv(&mut receiver).is_none());
+    assert_eq!(
+        events(&mut receiver),
+        vec![TransportEvent::SenderRestarted(SENDER_ID)]
+    );
+
+    // answers to NACKs sent before the restart
+    sender.send_to(&batch(EPOCH, 1..=3), recv_addr).unwrap();
+    assert!(recv(&mut receiver).is_none());
+
+    sender.send_to(&batch(EPOCH + 1, 1..=1), recv_addr).unwrap();
+    assert_eq!(recv_next(&mut receiver), (EPOCH + 1, 1));
+}
+
+/// A batch from `sender_id` of messages with the given sequence numbers and sent times.
+fn sender_batch(sender_id: SenderId, messages: &[(SequenceNumber, u64)]) -> Vec<u8> {
+    let mut batch = RawWireMessage::new(1472);
+    for &(sequence_number, sent_time) in messages {
+        let mut message = message(EPOCH, sequence_number);
+        message.sender_id = sender_id;
+        message.sent_time = sent_time;
+        assert!(batch.push(&message));
+    }
+    batch.as_bytes().to_vec()
+}
+
+fn sender_heartbeat(
+    sender_id: SenderId,
+    sequence_number: SequenceNumber,
+    watermark_ns: u64,
+) -> Vec<u8> {
+    let mut heartbeat = Vec::new();
+    encode_heartbeat(
+        &SequencedHeartbeat {
+            sender_id,
+            epoch: EPOCH,
+            sequence_number,
+            watermark_ns,
+        },
+        &mut heartbeat,
+    );
+    heartbeat
+}
+
+/// Sends `datagrams` to a fresh receiver in the given order, taking whatever it delivers as they
+/// arrive, and returns the `(sent_time, sender_id)` of the first `count` messages.
+fn merged(nack_port: u16, datagrams: &[&[u8]], count: usize) -> Vec<(u64, SenderId)> {
+    let (mut receiver, sender, recv_addr) = receiver(nack_port);
+    let mut merged = Vec::new();
+
+    for datagram in datagrams {
+        sender.send_to(datagram, recv_addr).unwrap();
+        while let Some(msg) = recv(&mut receiver) {
+            merged.push((msg.sent_time, msg.sender_id));
+        }
+    }
+    while merged.len() < count {
+        let msg = poll_for(Duration::from_secs(5), || receiver.try_recv()).expect("merge stalled");
+        merged.push((msg.sent_time, msg.sender_id));
+    }
+    merged
+}
+
+#[test]
+fn senders_merge_in_the_same_order_whatever_order_they_arrive_in() {
+    let joined_1 = sender_heartbeat(1, 0, 0);
+    let joined_2 = sender_heartbeat(2, 0, 0);
+    let sent_1 = sender_batch(1, &[(1, 10), (2, 30), (3, 50)]);
+    let first_sent_2 = sender_batch(2, &[(1, 20), (2, 30)]);
+    let then_sent_2 = sender_batch(2, &[(3, 40)]);
+    let idle_1 = sender_heartbeat(1, 3, 60);
+    let idle_2 = sender_heartbeat(2, 3, 60);
+
+    let expected = vec![(10, 1), (20, 2), (30, 1), (30, 2), (40, 2), (50, 1)];
+    let arrival_orders: [&[&[u8]]; 3] = [
+        &[
+            &joined_1,
+            &joined_2,
+            &sent_1,
+            &idle_1,
+            &first_sent_2,
+            &then_sent_2,
+            &idle_2,
+        ],
+        &[
+            &joined_2,
+            &joined_1,
+            &first_sent_2,
+            &then_sent_2,
+            &idle_2,
+            &sent_1,
+            &idle_1,
+        ],
+        // sender 2's second batch overtakes its first
+        &[
+            &joined_1,
+            &joined_2,
+            &then_sent_2,
+            &sent_1,
+            &first_sent_2,
+            &idle_1,
+            &idle_2,
+        ],
+    ];
+
+    for (i, datagrams) in arrival_orders.iter().enumerate() {
+        let merged = merged(19_412 + i as u16, datagrams, expected.len());
+        assert_eq!(merged, expected, "arrival order {}", i);
+    }
+}
+
+/// The next heartbeat on `socket`, skipping batches.
+fn next_heartbeat(socket: &UdpSocket) -> SequencedHeartbeat {
+    socket
+        .set_read_timeout(Some(Duration::from_secs(5)))
+        .unwrap();
+    let mut buf = [0u8; 1500];
+    loop {
+        let len = socket.recv(&mut buf).expect("no heartbeat");
+        if let Datagram::Heartbeat(heartbeat) = decode_datagram(&buf[..len]).unwrap() {
+            return heartbeat;
+        }
+    }
+}
+
+#[test]
+fn a_heartbeat_watermark_never_passes_an_unflushed_message() {
+    let batching = BatchingConfig {
+        batch_size: 32,
+        max_flush_delay_micros: 60_000_000,
+        mtu: 1500,
+    };
+    let (mut sender, socket) = sender(19_415, batching);
+
+    sender.send(EngineMessage::EngineCommand(EngineCommand::Start));
+    // skip the heartbeats sent before it
+    std::thread::sleep(Duration::from_millis(5));
+    socket.set_nonblocking(true).unwrap();
+    while socket.recv(&mut [0u8; 1500]).is_ok() {}
+    socket.set_nonblocking(false).unwrap();
+    let pending = next_heartbeat(&socket);
+    assert_eq!(pending.sequence_number, 0);
+
+    sender.flush();
+    let mut buf = [0u8; 1500];
+    let sent_time = loop {
+        let len = socket.recv(&mut buf).unwrap();
+        if let Datagram::Batch(mut batch) = decode_datagram(&buf[..len]).unwrap() {
+            break batch.next().unwrap().unwrap().sent_time;
+        }
+    };
+    assert!(pending.watermark_ns <= sent_time);
+
+    // once flushed the watermark moves past it
+    let idle = loop {
+        let heartbeat = next_heartbeat(&socket);
+        if heartbeat.sequence_number == 1 {
+            break heartbeat;
+        }
+    };
+    assert!(idle.watermark_ns >= sent_time);
+}
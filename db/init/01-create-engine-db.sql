@@ -9,9 +9,9 @@ CREATE TABLE IF NOT EXISTS orders
     client_id     INTEGER     NOT NULL,
     instrument    VARCHAR(16) NOT NULL,
     side          SMALLINT    NOT NULL,
-    px            INTEGER     NOT NULL,
-    qty           INTEGER     NOT NULL,
-    qty_rem       INTEGER     NOT NULL,
+    px            BIGINT      NOT NULL,
+    qty           BIGINT      NOT NULL,
+    qty_rem       BIGINT      NOT NULL,
     time_in_force SMALLINT    NOT NULL,
     ack_time      BIGINT      NOT NULL
 );
@@ -23,15 +23,15 @@ CREATE TABLE IF NOT EXISTS trades
     trade_seq     INTEGER  NOT NULL,
     bid_client_id INTEGER  NOT NULL,
     bid_order_id  INTEGER  NOT NULL,
-    bid_order_px  INTEGER  NOT NULL,
+    bid_order_px  BIGINT   NOT NULL,
     bid_fill_type SMALLINT NOT NULL,
     ask_client_id INTEGER  NOT NULL,
     ask_order_id  INTEGER  NOT NULL,
-    ask_order_px  INTEGER  NOT NULL,
+    ask_order_px  BIGINT   NOT NULL,
     ask_fill_type SMALLINT NOT NULL,
     instrument    CHAR(16) NOT NULL,
-    exec_px       INTEGER  NOT NULL,
-    exec_qty      INTEGER  NOT NULL,
+    exec_px       BIGINT   NOT NULL,
+    exec_qty      BIGINT   NOT NULL,
     exec_type     SMALLINT NOT NULL,
     exec_ns       BIGINT   NOT NULL
 );
\ No newline at end of file
@@ -1,6 +1,7 @@
 use crate::book::order_book::Price;
 use crate::domain::order::LimitOrder;
 use common::types::execution_report::{ExecType, ExecutionReport, FillType};
+use common::types::latency_trace::LatencyTrace;
 use common::types::side::Side;
 use common::util::time::system_nanos;
 
@@ -12,6 +13,13 @@ pub fn best_prices_cross(order: &LimitOrder, best_px: Price) -> bool {
     px_cross
 }
 
+pub fn opposite_side(side: Side) -> Side {
+    match side {
+        Side::Buy => Side::Sell,
+        Side::Sell => Side::Buy,
+    }
+}
+
 pub fn traders_will_self_match(order: &LimitOrder, resting_order: &LimitOrder) -> bool {
     order.client_id == resting_order.client_id
 }
@@ -19,8 +27,8 @@ pub fn traders_will_self_match(order: &LimitOrder, resting_order: &LimitOrder) -
 pub fn build_fill_execution(
     order: &mut LimitOrder,
     resting_order: &mut LimitOrder,
-    exec_px: u32,
-    exec_qty: u32,
+    exec_px: u64,
+    exec_qty: u64,
 ) -> ExecutionReport {
     let bid;
     let ask;
@@ -52,10 +60,12 @@ pub fn build_fill_execution(
         trade_id: 0,
         bid_client_id: bid.client_id,
         bid_order_id: bid.id,
+        bid_client_order_id: bid.client_order_id,
         bid_order_px: bid.px,
         bid_fill_type,
         ask_client_id: ask.client_id,
         ask_order_id: ask.id,
+        ask_client_order_id: ask.client_order_id,
         ask_order_px: ask.px,
         ask_fill_type,
         instrument: [0; 16],
@@ -63,6 +73,7 @@ pub fn build_fill_execution(
         exec_qty,
         exec_type: ExecType::MatchEvent,
         exec_ns: system_nanos(),
+        trace: LatencyTrace::default(),
     }
 }
 
@@ -72,10 +83,12 @@ pub fn build_self_match_prevention_execution(resting_order: &mut LimitOrder) ->
             trade_id: 0,
             bid_client_id: resting_order.client_id,
             bid_order_id: resting_order.id,
+            bid_client_order_id: resting_order.client_order_id,
             bid_order_px: resting_order.px,
             bid_fill_type: FillType::NoFill,
             ask_client_id: 0,
             ask_order_id: 0,
+            ask_client_order_id: 0,
             ask_order_px: 0,
             ask_fill_type: FillType::NoFill,
             instrument: [0; 16],
@@ -83,15 +96,18 @@ pub fn build_self_match_prevention_execution(resting_order: &mut LimitOrder) ->
             exec_qty: resting_order.qty,
             exec_type: ExecType::SelfMatchPrevented,
             exec_ns: system_nanos(),
+            trace: LatencyTrace::default(),
         },
         Side::Sell => ExecutionReport {
             trade_id: 0,
             bid_client_id: 0,
             bid_order_id: 0,
+            bid_client_order_id: 0,
             bid_order_px: 0,
             bid_fill_type: FillType::NoFill,
             ask_client_id: resting_order.client_id,
             ask_order_id: resting_order.id,
+            ask_client_order_id: resting_order.client_order_id,
             ask_order_px: resting_order.px,
             ask_fill_type: FillType::NoFill,
             instrument: [0; 16],
@@ -99,6 +115,7 @@ pub fn build_self_match_prevention_execution(resting_order: &mut LimitOrder) ->
             exec_type: ExecType::SelfMatchPrevented,
             exec_px: resting_order.px,
             exec_ns: system_nanos(),
+            trace: LatencyTrace::default(),
         },
     }
 }
@@ -1,12 +1,11 @@
 use crate::algorithm::algo_utils::{
     best_prices_cross, build_fill_execution, build_self_match_prevention_execution,
-    traders_will_self_match,
+    opposite_side, traders_will_self_match,
 };
 use crate::algorithm::match_strategy::MatchStrategy;
-use crate::book::order_book::LimitOrderBook;
+use crate::book::book::Book;
 use crate::domain::order::LimitOrder;
 use common::types::execution_report::ExecutionReport;
-use common::types::side::Side;
 
 #[derive(Debug)]
 pub struct FifoMatchStrategy;
@@ -16,94 +15,55 @@ impl FifoMatchStrategy {
         FifoMatchStrategy {}
     }
 }
-impl MatchStrategy for FifoMatchStrategy {
-
+impl<B: Book> MatchStrategy<B> for FifoMatchStrategy {
     fn match_orders(
         &mut self,
-        order_book: &mut LimitOrderBook,
+        order_book: &mut B,
         order: &mut LimitOrder,
         executions_buffer: &mut Vec<ExecutionReport>,
     ) -> usize {
-        let (_book_side, opposite_book_side) = match order.side {
-            Side::Buy => (&mut order_book.bids, &mut order_book.asks),
-            Side::Sell => (&mut order_book.asks, &mut order_book.bids),
-        };
-
-        loop {
-            let Some(best_px) = opposite_book_side.best_price() else {
-                break; // No liquidity - skip match, add to orderbook
-            };
-
-            if best_prices_cross(order, best_px) {
-                let opposite_price_level =
-                    match opposite_book_side.price_level_map.get_mut(&best_px) {
-                        Some(l) => l,
-                        None => break,
-                    };
-
-                // Match loop
-                while order.qty > 0 && !opposite_price_level.is_empty() {
-                    let resting_id = opposite_price_level.front().copied().unwrap();
-
-                    let resting_order = match opposite_book_side.order_map.get_mut(&resting_id) {
-                        Some(o) => o,
-                        None => {
-                            opposite_price_level.pop_front(); // cleanup orphan id
-                            continue;
-                        }
-                    };
-
-                    //SMP
-                    if traders_will_self_match(order, resting_order) {
-                        executions_buffer
-                            .push(build_self_match_prevention_execution(resting_order));
-
-                        opposite_price_level.pop_front();
-                        opposite_price_level.total_qty -= resting_order.qty;
-                        opposite_book_side.total_qty -= resting_order.qty;
-                        opposite_book_side.num_orders -= 1;
-                        continue;
-                    }
-
-                    let exec_qty = order.qty.min(resting_order.qty);
-                    if exec_qty == 0 {
-                        opposite_price_level.pop_front();
-                        opposite_book_side.order_map.remove(&resting_id);
-                        continue;
-                    }
-
-                    // Record execution
-                    executions_buffer.push(build_fill_execution(
-                        order,
-                        resting_order,
-                        best_px,
-                        exec_qty,
-                    ));
-
-                    // Adjust quantities
-                    // This needs abstracted
-                    order.qty -= exec_qty;
-                    resting_order.qty -= exec_qty;
-                    opposite_price_level.total_qty -= exec_qty;
-                    opposite_book_side.total_qty -= exec_qty;
-
-                    // Remove fully filled book order
-                    if resting_order.qty == 0 {
-                        opposite_book_side.order_map.remove(&resting_id);
-                        opposite_price_level.pop_front();
-                        opposite_book_side.num_orders -= 1;
-                    }
-                }
+        let opposite_side = opposite_side(order.side);
 
-                // Remove empty price level
-                if opposite_price_level.is_empty() {
-                    opposite_book_side.price_level_map.remove(&best_px);
-                }
+        // No liquidity - skip match, add to orderbook
+        while let Some(best_px) = order_book.best_price(opposite_side) {
+            if !best_prices_cross(order, best_px) {
+                break;
+            }
 
-                if order.qty == 0 {
-                    break;
+            // Match loop
+            while order.qty > 0 {
+                let Some(mut resting_order) = order_book
+                    .orders_at_level(opposite_side, best_px)
+                    .next()
+                    .copied()
+                else {
+                    break; // Level exhausted
+                };
+
+                //SMP
+                if traders_will_self_match(order, &resting_order) {
+                    executions_buffer
+                        .push(build_self_match_prevention_execution(&mut resting_order));
+                    order_book.remove_order_by_id(opposite_side, resting_order.id);
+                    continue;
                 }
-            } else {
+
+                let exec_qty = order.qty.min(resting_order.qty);
+
+                // Record execution
+                executions_buffer.push(build_fill_execution(
+                    order,
+                    &mut resting_order,
+                    best_px,
+                    exec_qty,
+                ));
+
+                // Adjust quantities
+                order.qty -= exec_qty;
+                order_book.fill_order(opposite_side, resting_order.id, exec_qty);
+            }
+
+            if order.qty == 0 {
                 break;
             }
         }
@@ -111,3 +71,116 @@ impl MatchStrategy for FifoMatchStrategy {
         executions_buffer.len()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::book::order_book::LimitOrderBook;
+    use common::types::execution_report::{ExecType, FillType};
+    use common::types::latency_trace::LatencyTrace;
+    use common::types::order::TimeInForce;
+    use common::types::side::Side;
+
+    fn limit(client_id: u32, id: u32, side: Side, px: u64, qty: u64) -> LimitOrder {
+        LimitOrder {
+            client_id,
+            id,
+            client_order_id: id as u64,
+            side,
+            px,
+            qty,
+            time_in_force: TimeInForce::GTC,
+            placed_time: 0,
+            trace: LatencyTrace::default(),
+        }
+    }
+
+    fn match_order(book: &mut LimitOrderBook, order: &mut LimitOrder) -> Vec<ExecutionReport> {
+        let mut executions = Vec::new();
+        FifoMatchStrategy::new().match_orders(book, order, &mut executions);
+        executions
+    }
+
+    #[test]
+    fn fills_the_best_price_then_the_oldest_order() {
+        let mut book = LimitOrderBook::new();
+        book.add_order(limit(1, 1, Side::Sell, 101, 5));
+        book.add_order(limit(2, 2, Side::Sell, 100, 5));
+        book.add_order(limit(3, 3, Side::Sell, 100, 5));
+
+        let mut buy = limit(4, 4, Side::Buy, 101, 12);
+        let executions = match_order(&mut book, &mut buy);
+
+        let fills: Vec<(u32, u64, u64)> = executions
+            .iter()
+            .map(|e| (e.ask_order_id, e.exec_px, e.exec_qty))
+            .collect();
+        assert_eq!(fills, vec![(2, 100, 5), (3, 100, 5), (1, 101, 2)]);
+        assert_eq!(buy.qty, 0);
+        assert_eq!(book.ask_volume(), 3);
+        assert_eq!(book.orders_on_book(), 1);
+    }
+
+    #[test]
+    fn partially_fills_a_larger_resting_order() {
+        let mut book = LimitOrderBook::new();
+        book.add_order(limit(1, 1, Side::Buy, 100, 10));
+
+        let mut sell = limit(2, 2, Side::Sell, 99, 4);
+        let executions = match_order(&mut book, &mut sell);
+
+        assert_eq!(executions.len(), 1);
+        assert_eq!(executions[0].exec_px, 100);
+        assert_eq!(executions[0].exec_qty, 4);
+        assert_eq!(executions[0].bid_fill_type, FillType::PartialFill);
+        assert_eq!(executions[0].ask_fill_type, FillType::FullFill);
+        assert_eq!(sell.qty, 0);
+        assert_eq!(book.orders_at_level(Side::Buy, 100).next().unwrap().qty, 6);
+    }
+
+    #[test]
+    fn leaves_the_aggressor_remainder_once_prices_stop_crossing() {
+        let mut book = LimitOrderBook::new();
+        book.add_order(limit(1, 1, Side::Sell, 100, 3));
+        book.add_order(limit(1, 2, Side::Sell, 102, 3));
+
+        let mut buy = limit(2, 3, Side::Buy, 101, 5);
+        let executions = match_order(&mut book, &mut buy);
+
+        assert_eq!(executions.len(), 1);
+        assert_eq!(executions[0].bid_fill_type, FillType::PartialFill);
+        assert_eq!(buy.qty, 2);
+        assert_eq!(book.best_ask(), Some(102));
+    }
+
+    #[test]
+    fn does_not_match_when_prices_do_not_cross() {
+        let mut book = LimitOrderBook::new();
+        book.add_order(limit(1, 1, Side::Sell, 100, 3));
+
+        let mut buy = limit(2, 2, Side::Buy, 99, 3);
+
+        assert!(match_order(&mut book, &mut buy).is_empty());
+        assert_eq!(buy.qty, 3);
+        assert_eq!(book.ask_volume(), 3);
+    }
+
+    #[test]
+    fn cancels_the_resting_order_of_the_same_client_and_keeps_matching() {
+        let mut book = LimitOrderBook::new();
+        book.add_order(limit(1, 1, Side::Sell, 100, 5));
+        book.add_order(limit(2, 2, Side::Sell, 100, 5));
+
+        let mut buy = limit(1, 3, Side::Buy, 100, 5);
+        let executions = match_order(&mut book, &mut buy);
+
+        assert_eq!(executions.len(), 2);
+        assert_eq!(executions[0].exec_type, ExecType::SelfMatchPrevented);
+        assert_eq!(executions[0].ask_order_id, 1);
+        assert_eq!(executions[0].exec_qty, 5);
+        assert_eq!(executions[1].exec_type, ExecType::MatchEvent);
+        assert_eq!(executions[1].ask_order_id, 2);
+        assert_eq!(buy.qty, 0);
+        assert_eq!(book.orders_on_book(), 0);
+    }
+}
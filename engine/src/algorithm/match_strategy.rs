@@ -1,11 +1,11 @@
-use crate::book::order_book::LimitOrderBook;
+use crate::book::book::Book;
 use crate::domain::order::LimitOrder;
 use common::types::execution_report::ExecutionReport;
 
-pub trait MatchStrategy: std::fmt::Debug {
+pub trait MatchStrategy<B: Book>: std::fmt::Debug {
     fn match_orders(
         &mut self,
-        order_book: &mut LimitOrderBook,
+        order_book: &mut B,
         order: &mut LimitOrder,
         mutable_execution_buffer: &mut Vec<ExecutionReport>,
     ) -> usize;
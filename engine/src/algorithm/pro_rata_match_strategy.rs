@@ -1,11 +1,9 @@
-use crate::algorithm::algo_utils::{best_prices_cross, build_fill_execution};
+use crate::algorithm::algo_utils::{best_prices_cross, build_fill_execution, opposite_side};
 use crate::algorithm::match_strategy::MatchStrategy;
-use crate::book::order_book::LimitOrderBook;
-use crate::book::price_level::PriceLevel;
+use crate::book::book::Book;
 use crate::domain::order::LimitOrder;
 use common::types::execution_report::ExecutionReport;
-use common::types::side::Side;
-use std::collections::HashMap;
+use std::cmp::Reverse;
 
 #[derive(Debug)]
 pub struct ProRataMatchStrategy;
@@ -15,97 +13,56 @@ impl ProRataMatchStrategy {
         ProRataMatchStrategy {}
     }
 }
-impl MatchStrategy for ProRataMatchStrategy {
+impl<B: Book> MatchStrategy<B> for ProRataMatchStrategy {
     fn match_orders(
         &mut self,
-        order_book: &mut LimitOrderBook,
+        order_book: &mut B,
         order: &mut LimitOrder,
         executions_buffer: &mut Vec<ExecutionReport>,
     ) -> usize {
-        let mut num_executions: usize = 0;
-
-        let (book_side, opposite_book_side) = match order.side {
-            Side::Buy => (&mut order_book.bids, &mut order_book.asks),
-            Side::Sell => (&mut order_book.asks, &mut order_book.bids),
-        };
-
-        loop {
-            let Some(best_px) = opposite_book_side.best_price() else {
-                break; // No liquidity - skip match, add to orderbook
-            };
-
-            if best_prices_cross(order, best_px) {
-                let opposite_price_level =
-                    match opposite_book_side.price_level_map.get_mut(&best_px) {
-                        Some(l) => l,
-                        None => break,
-                    };
-
-                let price_level_total_qty = opposite_price_level.total_qty;
-                let matched_qty = order.qty.min(price_level_total_qty);
-                let pro_rata_allocations = Self::pro_rata_allocate_fills(
-                    opposite_price_level,
-                    &opposite_book_side.order_map,
-                    matched_qty,
-                );
-
-                let mut remaining_qty = matched_qty;
-                let mut pro_rata_allocation_idx = 0;
-                // Match loop
-                while remaining_qty > 0 && !opposite_price_level.is_empty() {
-                    let resting_id = opposite_price_level.front().copied().unwrap();
-
-                    let resting_order = match opposite_book_side.order_map.get_mut(&resting_id) {
-                        Some(o) => o,
-                        None => {
-                            opposite_price_level.pop_front(); // cleanup orphan id
-                            continue;
-                        }
-                    };
-
-                    let fill_qty = pro_rata_allocations[pro_rata_allocation_idx];
-                    if fill_qty == 0 {
-                        opposite_price_level.pop_front();
-                        opposite_book_side.order_map.remove(&resting_id);
-                        continue;
-                    }
-
-                    // Adjust quantities
-                    // This needs abstracted
-                    order.qty -= fill_qty;
-                    resting_order.qty -= fill_qty;
-                    opposite_price_level.total_qty -= fill_qty;
-                    opposite_book_side.total_qty -= fill_qty;
-
-                    // Record execution
-                    executions_buffer.push(build_fill_execution(
-                        order,
-                        resting_order,
-                        best_px,
-                        fill_qty,
-                    ));
-
-                    remaining_qty -= fill_qty;
-                    pro_rata_allocation_idx += 1;
-                    num_executions += 1;
-
-                    // Remove fully filled book order
-                    if resting_order.qty == 0 {
-                        opposite_book_side.order_map.remove(&resting_id);
-                        opposite_price_level.pop_front();
-                        opposite_book_side.num_orders -= 1;
-                    }
-                }
+        let opposite_side = opposite_side(order.side);
 
-                // Remove empty price level
-                if opposite_price_level.is_empty() {
-                    opposite_book_side.price_level_map.remove(&best_px);
-                }
+        // No liquidity - skip match, add to orderbook
+        while let Some(best_px) = order_book.best_price(opposite_side) {
+            if !best_prices_cross(order, best_px) {
+                break;
+            }
+
+            let resting_orders: Vec<LimitOrder> = order_book
+                .orders_at_level(opposite_side, best_px)
+                .copied()
+                .collect();
 
-                if order.qty == 0 {
-                    break;
+            if resting_orders.is_empty() {
+                break;
+            }
+
+            let price_level_total_qty = resting_orders.iter().map(|o| o.qty).sum::<u64>();
+            let matched_qty = order.qty.min(price_level_total_qty);
+            let pro_rata_allocations =
+                Self::pro_rata_allocate_fills(&resting_orders, price_level_total_qty, matched_qty);
+
+            // Match loop
+            for (mut resting_order, fill_qty) in resting_orders.into_iter().zip(pro_rata_allocations)
+            {
+                if fill_qty == 0 {
+                    continue;
                 }
-            } else {
+
+                // Record execution
+                executions_buffer.push(build_fill_execution(
+                    order,
+                    &mut resting_order,
+                    best_px,
+                    fill_qty,
+                ));
+
+                // Adjust quantities
+                order.qty -= fill_qty;
+                order_book.fill_order(opposite_side, resting_order.id, fill_qty);
+            }
+
+            if order.qty == 0 {
                 break;
             }
         }
@@ -116,40 +73,134 @@ impl MatchStrategy for ProRataMatchStrategy {
 
 impl ProRataMatchStrategy {
     fn pro_rata_allocate_fills(
-        price_level: &PriceLevel,
-        order_map: &HashMap<u32, LimitOrder>,
-        matched_qty: u32,
-    ) -> Vec<u32> {
-        let mut allocations: Vec<u32> = price_level
-            .order_ids()
-            .map(|order_id| {
-                (order_map.get(order_id).unwrap().qty as f64 * matched_qty as f64
-                    / price_level.total_qty as f64)
-                    .floor() as u32
+        resting_orders: &[LimitOrder],
+        price_level_total_qty: u64,
+        matched_qty: u64,
+    ) -> Vec<u64> {
+        let mut allocations: Vec<u64> = resting_orders
+            .iter()
+            .map(|resting_order| {
+                (resting_order.qty as u128 * matched_qty as u128 / price_level_total_qty as u128)
+                    as u64
             })
             .collect();
 
-        let allocated_qty = allocations.iter().sum::<u32>();
+        // Rounding down leaves less than one unit per order unallocated, hand it out one unit at a
+        // time to the largest orders first (time priority among equals), never past an order's qty
+        let mut by_size: Vec<usize> = (0..resting_orders.len()).collect();
+        by_size.sort_by_key(|&i| Reverse(resting_orders[i].qty));
+
+        let mut remainder = matched_qty - allocations.iter().sum::<u64>();
+        for i in by_size {
+            if remainder == 0 {
+                break;
+            }
+            if allocations[i] < resting_orders[i].qty {
+                allocations[i] += 1;
+                remainder -= 1;
+            }
+        }
 
-        if allocated_qty != matched_qty {
-            let adjustment = matched_qty - allocated_qty;
-            // println!(
-            //     "Allocations require adjustment: expected: {}, actual: {} = adjustment: {}",
-            //     matched_qty, allocated_qty, adjustment
-            // );
+        assert_eq!(allocations.iter().sum::<u64>(), matched_qty);
 
-            let max_allocation_idx = allocations
-                .iter()
-                .enumerate()
-                .max_by_key(|(_i, &val)| val)
-                .map(|(i, _val)| i)
-                .unwrap();
+        allocations
+    }
+}
 
-            allocations[max_allocation_idx] += adjustment;
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::book::order_book::LimitOrderBook;
+    use common::types::latency_trace::LatencyTrace;
+    use common::types::order::TimeInForce;
+    use common::types::side::Side;
+
+    fn limit(client_id: u32, id: u32, side: Side, px: u64, qty: u64) -> LimitOrder {
+        LimitOrder {
+            client_id,
+            id,
+            client_order_id: id as u64,
+            side,
+            px,
+            qty,
+            time_in_force: TimeInForce::GTC,
+            placed_time: 0,
+            trace: LatencyTrace::default(),
         }
+    }
 
-        assert_eq!(allocations.iter().sum::<u32>(), matched_qty);
+    fn match_order(book: &mut LimitOrderBook, order: &mut LimitOrder) -> Vec<ExecutionReport> {
+        let mut executions = Vec::new();
+        ProRataMatchStrategy::new().match_orders(book, order, &mut executions);
+        executions
+    }
 
-        allocations
+    fn fills(executions: &[ExecutionReport]) -> Vec<(u32, u64)> {
+        executions
+            .iter()
+            .map(|e| (e.ask_order_id, e.exec_qty))
+            .collect()
+    }
+
+    #[test]
+    fn allocates_a_level_in_proportion_to_resting_qty() {
+        let mut book = LimitOrderBook::new();
+        book.add_order(limit(1, 1, Side::Sell, 100, 30));
+        book.add_order(limit(2, 2, Side::Sell, 100, 10));
+
+        let mut buy = limit(3, 3, Side::Buy, 100, 20);
+        let executions = match_order(&mut book, &mut buy);
+
+        assert_eq!(fills(&executions), vec![(1, 15), (2, 5)]);
+        assert_eq!(buy.qty, 0);
+        assert_eq!(book.ask_volume(), 20);
+    }
+
+    #[test]
+    fn spreads_the_rounding_remainder_without_overfilling_an_order() {
+        let mut book = LimitOrderBook::new();
+        for id in 1..=3 {
+            book.add_order(limit(id, id, Side::Sell, 100, 1));
+        }
+
+        let mut buy = limit(4, 4, Side::Buy, 100, 2);
+        let executions = match_order(&mut book, &mut buy);
+
+        assert_eq!(fills(&executions), vec![(1, 1), (2, 1)]);
+        assert_eq!(buy.qty, 0);
+        assert_eq!(book.ask_volume(), 1);
+        assert_eq!(book.orders_on_book(), 1);
+    }
+
+    #[test]
+    fn gives_the_remainder_to_the_largest_orders_first() {
+        let resting = [
+            limit(1, 1, Side::Sell, 100, 2),
+            limit(2, 2, Side::Sell, 100, 3),
+            limit(3, 3, Side::Sell, 100, 3),
+        ];
+
+        let allocations = ProRataMatchStrategy::pro_rata_allocate_fills(&resting, 8, 4);
+
+        assert_eq!(allocations, vec![1, 2, 1]);
+    }
+
+    #[test]
+    fn sweeps_the_next_level_once_a_level_is_filled() {
+        let mut book = LimitOrderBook::new();
+        book.add_order(limit(1, 1, Side::Buy, 100, 4));
+        book.add_order(limit(2, 2, Side::Buy, 100, 4));
+        book.add_order(limit(3, 3, Side::Buy, 99, 10));
+
+        let mut sell = limit(4, 4, Side::Sell, 99, 12);
+        let executions = match_order(&mut book, &mut sell);
+
+        let fills: Vec<(u32, u64, u64)> = executions
+            .iter()
+            .map(|e| (e.bid_order_id, e.exec_px, e.exec_qty))
+            .collect();
+        assert_eq!(fills, vec![(1, 100, 4), (2, 100, 4), (3, 99, 4)]);
+        assert_eq!(sell.qty, 0);
+        assert_eq!(book.bid_volume(), 6);
     }
 }
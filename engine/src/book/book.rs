@@ -1,11 +1,51 @@
-use common::types::cancel_order::CancelOrderRequest;
+use crate::book::order_book::Price;
 use crate::domain::order::LimitOrder;
+use common::types::cancel_order::{CancelOrderRequest, CancelOrderStatus};
+use common::types::side::Side;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DepthLevel {
+    pub px: Price,
+    pub qty: u64,
+    pub num_orders: u32,
+}
 
 pub trait Book {
+    type LevelOrders<'a>: Iterator<Item = &'a LimitOrder>
+    where
+        Self: 'a;
+
     fn add_order(&mut self, order: LimitOrder);
-    fn remove_order(&mut self, cancel_order: &CancelOrderRequest) -> bool;
+    /// Removes a resting order for the client that entered it, an order of another client stays
+    /// on the book and the cancel is `Rejected`.
+    fn remove_order(&mut self, cancel_order: &CancelOrderRequest) -> CancelOrderStatus;
     fn orders_on_book(&mut self) -> usize;
-    fn bid_volume(&self) -> u32;
-    fn ask_volume(&self) -> u32;
-    fn total_volume(&self) -> u32;
+    fn bid_volume(&self) -> u64;
+    fn ask_volume(&self) -> u64;
+    fn total_volume(&self) -> u64;
+
+    fn best_bid(&self) -> Option<Price>;
+    fn best_ask(&self) -> Option<Price>;
+
+    /// Number of price levels on a side, without aggregating them like [`Book::depth`].
+    fn level_count(&self, side: Side) -> usize;
+
+    /// Aggregated levels for a side, best price first.
+    fn depth(&self, side: Side, levels: usize) -> Vec<DepthLevel>;
+
+    /// Resting orders at a price level in priority (time) order.
+    fn orders_at_level(&self, side: Side, px: Price) -> Self::LevelOrders<'_>;
+
+    /// Reduces a resting order by `qty`, removing it (and an emptied level) once fully filled.
+    /// Returns the order as it stands after the fill.
+    fn fill_order(&mut self, side: Side, order_id: u32, qty: u64) -> Option<LimitOrder>;
+
+    fn remove_order_by_id(&mut self, side: Side, order_id: u32) -> Option<LimitOrder>;
+
+    fn best_price(&self, side: Side) -> Option<Price> {
+        match side {
+            Side::Buy => self.best_bid(),
+            Side::Sell => self.best_ask(),
+        }
+    }
 }
@@ -1,8 +1,10 @@
+use crate::book::book::DepthLevel;
 use crate::book::order_book::Price;
 use crate::book::price_level::PriceLevel;
 use crate::domain::order::LimitOrder;
 use common::types::side::Side;
 use common::types::side::Side::{Buy, Sell};
+use std::collections::vec_deque;
 use std::collections::{BTreeMap, HashMap};
 
 pub struct BookSide {
@@ -10,7 +12,7 @@ pub struct BookSide {
     pub order_map: HashMap<u32, LimitOrder>,
     pub side: Side,
 
-    pub total_qty: u32,
+    pub total_qty: u64,
     pub num_orders: u32,
 }
 impl BookSide {
@@ -36,14 +38,48 @@ impl BookSide {
         self.num_orders += 1;
     }
 
-    pub fn remove_order(&mut self, id: u32) {
-        if let Some(order) = self.order_map.remove(&id) {
-            if let Some(level) = self.price_level_map.get_mut(&order.px) {
-                level.remove_order(order.id, order.qty);
-                self.total_qty -= order.qty;
-                self.num_orders -= 1;
+    pub fn remove_order(&mut self, id: u32) -> Option<LimitOrder> {
+        let order = self.order_map.remove(&id)?;
+
+        if let Some(level) = self.price_level_map.get_mut(&order.px) {
+            level.remove_order(order.id, order.qty);
+            self.total_qty -= order.qty;
+            self.num_orders -= 1;
+
+            if level.is_empty() {
+                self.price_level_map.remove(&order.px);
             }
         }
+
+        Some(order)
+    }
+
+    pub fn fill_order(&mut self, id: u32, qty: u64) -> Option<LimitOrder> {
+        let order = self.order_map.get_mut(&id)?;
+        let qty = qty.min(order.qty);
+        let px = order.px;
+
+        order.qty -= qty;
+        let filled = *order;
+        self.total_qty -= qty;
+
+        if let Some(level) = self.price_level_map.get_mut(&px) {
+            level.adjust_qty(qty);
+
+            if filled.qty == 0 {
+                level.remove_order(id, 0);
+                if level.is_empty() {
+                    self.price_level_map.remove(&px);
+                }
+            }
+        }
+
+        if filled.qty == 0 {
+            self.order_map.remove(&id);
+            self.num_orders -= 1;
+        }
+
+        Some(filled)
     }
 
     pub fn best_price(&self) -> Option<Price> {
@@ -53,11 +89,60 @@ impl BookSide {
         }
     }
 
+    pub fn level_count(&self) -> usize {
+        self.price_level_map.len()
+    }
+
+    pub fn depth(&self, levels: usize) -> Vec<DepthLevel> {
+        let to_depth_level = |(px, level): (&Price, &PriceLevel)| DepthLevel {
+            px: *px,
+            qty: level.volume(),
+            num_orders: level.num_orders(),
+        };
+
+        match self.side {
+            Buy => self
+                .price_level_map
+                .iter()
+                .rev()
+                .take(levels)
+                .map(to_depth_level)
+                .collect(),
+            Sell => self
+                .price_level_map
+                .iter()
+                .take(levels)
+                .map(to_depth_level)
+                .collect(),
+        }
+    }
+
+    pub fn orders_at_level(&self, px: Price) -> LevelOrderIter<'_> {
+        LevelOrderIter {
+            order_ids: self.price_level_map.get(&px).map(|level| level.order_ids()),
+            order_map: &self.order_map,
+        }
+    }
+
     pub fn num_orders(&self) -> u32 {
         self.num_orders
     }
 
-    pub fn volume(&self) -> u32 {
+    pub fn volume(&self) -> u64 {
         self.total_qty
     }
 }
+
+pub struct LevelOrderIter<'a> {
+    order_ids: Option<vec_deque::Iter<'a, u32>>,
+    order_map: &'a HashMap<u32, LimitOrder>,
+}
+
+impl<'a> Iterator for LevelOrderIter<'a> {
+    type Item = &'a LimitOrder;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let order_ids = self.order_ids.as_mut()?;
+        order_ids.find_map(|id| self.order_map.get(id))
+    }
+}
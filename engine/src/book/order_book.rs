@@ -1,10 +1,11 @@
-use crate::book::book::Book;
-use crate::book::book_side::BookSide;
+use crate::book::book::{Book, DepthLevel};
+use crate::book::book_side::{BookSide, LevelOrderIter};
 use crate::domain::order::LimitOrder;
-use common::types::cancel_order::CancelOrderRequest;
+use common::types::cancel_order::{CancelOrderRequest, CancelOrderStatus};
+use common::types::side::Side;
 use common::types::side::Side::{Buy, Sell};
 
-pub type Price = u32;
+pub type Price = u64;
 pub struct LimitOrderBook {
     pub asks: BookSide,
     pub bids: BookSide,
@@ -17,46 +18,135 @@ impl LimitOrderBook {
             bids: BookSide::new(Buy),
         }
     }
+
+    fn side(&self, side: Side) -> &BookSide {
+        match side {
+            Buy => &self.bids,
+            Sell => &self.asks,
+        }
+    }
+
+    fn side_mut(&mut self, side: Side) -> &mut BookSide {
+        match side {
+            Buy => &mut self.bids,
+            Sell => &mut self.asks,
+        }
+    }
 }
 
 impl Book for LimitOrderBook {
+    type LevelOrders<'a> = LevelOrderIter<'a>;
+
     fn add_order(&mut self, order: LimitOrder) {
-        match order.side {
-            Buy => {
-                self.bids.add_order(order);
-            }
-            Sell => {
-                self.asks.add_order(order);
-            }
-        };
+        self.side_mut(order.side).add_order(order);
     }
 
-    fn remove_order(&mut self, order: &CancelOrderRequest) -> bool {
-        match order.order_side {
-            Buy => {
-                self.bids.remove_order(order.order_id);
-            }
-            Sell => {
-                self.asks.remove_order(order.order_id);
+    fn remove_order(&mut self, order: &CancelOrderRequest) -> CancelOrderStatus {
+        let side = self.side_mut(order.order_side);
+        match side.order_map.get(&order.order_id) {
+            None => CancelOrderStatus::NotFound,
+            Some(resting) if resting.client_id != order.client_id => CancelOrderStatus::Rejected,
+            Some(_) => {
+                side.remove_order(order.order_id);
+                CancelOrderStatus::Cancelled
             }
-        };
-
-        true
+        }
     }
 
     fn orders_on_book(&mut self) -> usize {
         (self.asks.num_orders() + self.bids.num_orders()) as usize
     }
 
-    fn bid_volume(&self) -> u32 {
+    fn bid_volume(&self) -> u64 {
         self.bids.volume()
     }
 
-    fn ask_volume(&self) -> u32 {
+    fn ask_volume(&self) -> u64 {
         self.asks.volume()
     }
 
-    fn total_volume(&self) -> u32 {
+    fn total_volume(&self) -> u64 {
         self.bid_volume() + self.ask_volume()
     }
+
+    fn best_bid(&self) -> Option<Price> {
+        self.bids.best_price()
+    }
+
+    fn best_ask(&self) -> Option<Price> {
+        self.asks.best_price()
+    }
+
+    fn level_count(&self, side: Side) -> usize {
+        self.side(side).level_count()
+    }
+
+    fn depth(&self, side: Side, levels: usize) -> Vec<DepthLevel> {
+        self.side(side).depth(levels)
+    }
+
+    fn orders_at_level(&self, side: Side, px: Price) -> LevelOrderIter<'_> {
+        self.side(side).orders_at_level(px)
+    }
+
+    fn fill_order(&mut self, side: Side, order_id: u32, qty: u64) -> Option<LimitOrder> {
+        self.side_mut(side).fill_order(order_id, qty)
+    }
+
+    fn remove_order_by_id(&mut self, side: Side, order_id: u32) -> Option<LimitOrder> {
+        self.side_mut(side).remove_order(order_id)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use common::types::latency_trace::LatencyTrace;
+    use common::types::order::TimeInForce;
+
+    fn resting(client_id: u32, id: u32) -> LimitOrder {
+        LimitOrder {
+            client_id,
+            id,
+            client_order_id: 1,
+            side: Buy,
+            px: 100,
+            qty: 5,
+            time_in_force: TimeInForce::GTC,
+            placed_time: 0,
+            trace: LatencyTrace::default(),
+        }
+    }
+
+    fn cancel(client_id: u32, order_id: u32) -> CancelOrderRequest {
+        CancelOrderRequest {
+            client_id,
+            order_side: Buy,
+            order_id,
+            instrument: [0; 16],
+            trace: LatencyTrace::default(),
+        }
+    }
+
+    #[test]
+    fn only_the_owner_cancels_an_order() {
+        let mut book = LimitOrderBook::new();
+        book.add_order(resting(1, 7));
+
+        assert!(matches!(
+            book.remove_order(&cancel(2, 7)),
+            CancelOrderStatus::Rejected
+        ));
+        assert_eq!(book.bid_volume(), 5);
+
+        assert!(matches!(
+            book.remove_order(&cancel(1, 7)),
+            CancelOrderStatus::Cancelled
+        ));
+        assert!(matches!(
+            book.remove_order(&cancel(1, 7)),
+            CancelOrderStatus::NotFound
+        ));
+        assert_eq!(book.orders_on_book(), 0);
+    }
 }
@@ -1,9 +1,9 @@
-use std::collections::VecDeque;
+use std::collections::{vec_deque, VecDeque};
 
 #[derive(Debug, Default)]
 pub struct PriceLevel {
     order_ids: VecDeque<u32>,
-    pub total_qty: u32,
+    pub total_qty: u64,
     pub num_orders: u32,
 }
 
@@ -16,17 +16,17 @@ impl PriceLevel {
         }
     }
 
-    pub fn add_order(&mut self, order_id: u32, qty: u32) {
+    pub fn add_order(&mut self, order_id: u32, qty: u64) {
         self.order_ids.push_back(order_id);
         self.total_qty += qty;
         self.num_orders += 1;
     }
 
-    pub fn adjust_qty(&mut self, qty: u32) {
+    pub fn adjust_qty(&mut self, qty: u64) {
         self.total_qty = self.total_qty.saturating_sub(qty);
     }
 
-    pub fn remove_order(&mut self, order_id: u32, qty: u32) {
+    pub fn remove_order(&mut self, order_id: u32, qty: u64) {
         if let Some(pos) = self.order_ids.iter().position(|&id| id == order_id) {
             self.order_ids.remove(pos);
             self.total_qty = self.total_qty.saturating_sub(qty);
@@ -38,19 +38,11 @@ impl PriceLevel {
         self.order_ids.is_empty()
     }
 
-    pub fn front(&self) -> Option<&u32> {
-        self.order_ids.front()
-    }
-
-    pub fn pop_front(&mut self) -> Option<u32> {
-        self.order_ids.pop_front()
-    }
-
-    pub fn order_ids(&self) -> impl Iterator<Item = &u32> {
+    pub fn order_ids(&self) -> vec_deque::Iter<'_, u32> {
         self.order_ids.iter()
     }
     
-    pub fn volume(&self) -> u32 {
+    pub fn volume(&self) -> u64 {
         self.total_qty
     }
 
@@ -1,3 +1,4 @@
+use common::types::latency_trace::LatencyTrace;
 use common::types::order::TimeInForce;
 use common::types::side::Side;
 use std::cmp::Ordering;
@@ -6,11 +7,13 @@ use std::cmp::Ordering;
 pub struct LimitOrder {
     pub client_id: u32,
     pub id: u32,
+    pub client_order_id: u64,
     pub side: Side,
-    pub px: u32,
-    pub qty: u32,
+    pub px: u64,
+    pub qty: u64,
     pub time_in_force: TimeInForce,
     pub placed_time: u64,
+    pub trace: LatencyTrace,
 }
 
 impl LimitOrder {
@@ -1,7 +1,9 @@
 pub(crate) use crate::domain::limit_order::LimitOrder;
+use common::types::admin::AdminRequest;
 use common::types::cancel_order::CancelOrderRequest;
 
 pub enum Order {
     LimitOrder(LimitOrder),
     Cancel(CancelOrderRequest),
+    Admin(AdminRequest),
 }
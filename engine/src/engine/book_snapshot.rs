@@ -0,0 +1,59 @@
+use crate::book::book::Book;
+use crate::domain::order::LimitOrder;
+use common::types::side::Side;
+use common::util::time::system_nanos;
+use serde::Serialize;
+use std::fs;
+use std::io;
+
+#[derive(Serialize)]
+pub struct SnapshotOrder {
+    pub order_id: u32,
+    pub client_id: u32,
+    pub px: u64,
+    pub qty: u64,
+    pub placed_time: u64,
+}
+
+/// Resting orders per side, best price first and in time priority within a level.
+#[derive(Serialize)]
+pub struct BookSnapshot {
+    pub taken_at: u64,
+    pub bids: Vec<SnapshotOrder>,
+    pub asks: Vec<SnapshotOrder>,
+}
+
+impl BookSnapshot {
+    pub fn capture<B: Book>(book: &B) -> Self {
+        BookSnapshot {
+            taken_at: system_nanos(),
+            bids: Self::capture_side(book, Side::Buy),
+            asks: Self::capture_side(book, Side::Sell),
+        }
+    }
+
+    pub fn write(&self, path: &str) -> io::Result<()> {
+        let json = serde_json::to_string_pretty(self)?;
+        fs::write(path, json)
+    }
+
+    fn capture_side<B: Book>(book: &B, side: Side) -> Vec<SnapshotOrder> {
+        book.depth(side, usize::MAX)
+            .iter()
+            .flat_map(|level| book.orders_at_level(side, level.px))
+            .map(SnapshotOrder::from)
+            .collect()
+    }
+}
+
+impl From<&LimitOrder> for SnapshotOrder {
+    fn from(order: &LimitOrder) -> Self {
+        SnapshotOrder {
+            order_id: order.id,
+            client_id: order.client_id,
+            px: order.px,
+            qty: order.qty,
+            placed_time: order.placed_time,
+        }
+    }
+}
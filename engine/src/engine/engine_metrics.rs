@@ -0,0 +1,57 @@
+use common::metrics::counter::Counter;
+use common::metrics::gauge::Gauge;
+use common::metrics::latency_histogram::LatencyHistogram;
+use common::metrics::metrics_registry::MetricsRegistry;
+use std::sync::Arc;
+
+pub struct EngineMetrics {
+    pub orders: Arc<Counter>,
+    pub cancels: Arc<Counter>,
+    pub executions: Arc<Counter>,
+    pub rejects: Arc<Counter>,
+
+    pub orders_on_book: Arc<Gauge>,
+    pub bid_levels: Arc<Gauge>,
+    pub ask_levels: Arc<Gauge>,
+    pub bid_volume: Arc<Gauge>,
+    pub ask_volume: Arc<Gauge>,
+    pub order_entry_queue_len: Arc<Gauge>,
+    pub msg_out_queue_len: Arc<Gauge>,
+
+    pub inbound_to_match_ns: Arc<LatencyHistogram>,
+    pub match_to_publish_ns: Arc<LatencyHistogram>,
+}
+
+impl EngineMetrics {
+    pub fn new(registry: &MetricsRegistry) -> Self {
+        EngineMetrics {
+            orders: registry.counter("engine_orders_total", "New orders received by the engine"),
+            cancels: registry.counter("engine_cancels_total", "Cancel requests received by the engine"),
+            executions: registry.counter("engine_executions_total", "Execution reports generated"),
+            rejects: registry.counter("engine_rejects_total", "Requests rejected by the engine"),
+
+            orders_on_book: registry.gauge("engine_orders_on_book", "Resting orders on the book"),
+            bid_levels: registry.gauge("engine_bid_levels", "Bid price levels on the book"),
+            ask_levels: registry.gauge("engine_ask_levels", "Ask price levels on the book"),
+            bid_volume: registry.gauge("engine_bid_volume", "Resting bid quantity"),
+            ask_volume: registry.gauge("engine_ask_volume", "Resting ask quantity"),
+            order_entry_queue_len: registry.gauge(
+                "engine_order_entry_queue_len",
+                "Orders queued between msg_in and the match thread",
+            ),
+            msg_out_queue_len: registry.gauge(
+                "engine_msg_out_queue_len",
+                "Messages queued between the match thread and msg_out",
+            ),
+
+            inbound_to_match_ns: registry.histogram(
+                "engine_inbound_to_match_ns",
+                "Nanoseconds from msg_in receipt of a limit order to match completion",
+            ),
+            match_to_publish_ns: registry.histogram(
+                "engine_match_to_publish_ns",
+                "Nanoseconds from match completion to multicast publish",
+            ),
+        }
+    }
+}
@@ -3,28 +3,52 @@ use crate::algorithm::match_strategy::MatchStrategy;
 use crate::book::book::Book;
 use crate::book::order_book::LimitOrderBook;
 use crate::domain::order::{LimitOrder, Order};
+use crate::engine::book_snapshot::BookSnapshot;
+use crate::engine::engine_metrics::EngineMetrics;
 use common::transport::sequenced_message::EngineMessage;
-use common::types::cancel_order::Reason::ClientRequested;
+use common::types::admin::{
+    AdminBookLevel, AdminCommand, AdminError, AdminRequest, AdminResponse, AdminResponseBody,
+    EngineStatus,
+};
+use common::types::cancel_order::Reason::{AdminRequested, ClientRequested, NotOwner};
 use common::types::cancel_order::{CancelOrderStatus, CancelledOrderAck};
+use common::types::engine::EngineCommand;
 use common::types::execution_report::ExecutionReport;
 use common::types::instrument::Instrument;
-use common::types::order::{NewOrderAck, TimeInForce};
+use common::types::latency_trace::LatencyTrace;
+use common::types::market_data::{MarketDataBody, MarketDataMessage, TradingState, TradingStatus};
+use common::types::order::{NewOrderAck, OrderReject, RejectReason, TimeInForce};
+use common::types::side::Side;
+use common::util::idle_strategy::IdleStrategy;
 use common::util::time::system_nanos;
 use std::sync::mpsc::Receiver;
 use std::sync::mpsc::{Sender, TryRecvError};
+use std::sync::Arc;
+
+const METRICS_PUBLISH_INTERVAL_NS: u64 = 1_000_000_000;
 
 pub struct MatchEngine {
     book: LimitOrderBook,
     match_strategy: FifoMatchStrategy,
     cycle_executions_buffer: Vec<ExecutionReport>,
-    
+
     initial_order_id: u32,
     initial_execution_id: u32,
-    
+
+    instrument: [u8; 16],
+    instrument_id: u32,
+    halted: bool,
+    snapshot_path: Option<String>,
+    metrics: Arc<EngineMetrics>,
 }
 
 impl MatchEngine {
-    pub fn new() -> Self {
+    pub fn new(
+        instrument: &str,
+        instrument_id: u32,
+        snapshot_path: Option<String>,
+        metrics: Arc<EngineMetrics>,
+    ) -> Self {
         let book = LimitOrderBook::new();
 
         println!("--- Initializing engine instance ---",);
@@ -34,6 +58,11 @@ impl MatchEngine {
             cycle_executions_buffer: Vec::with_capacity(100_000),
             initial_order_id: 0,
             initial_execution_id: 0,
+            instrument: Instrument::str_to_fixed_char_buffer(instrument),
+            instrument_id,
+            halted: false,
+            snapshot_path,
+            metrics,
         }
     }
 
@@ -41,117 +70,379 @@ impl MatchEngine {
         &mut self,
         order_tx: Receiver<Order>,
         engine_msg_out_tx: Sender<EngineMessage>,
-    ) -> ! {
+        idle_strategy: IdleStrategy,
+    ) {
+        let mut idler = idle_strategy.idler();
         let match_cycle_msg_out_tx = engine_msg_out_tx.clone();
 
         let mut engine_msg_out_seq_num: u32 = 1;
         let mut engine_execution_seq_num = 1;
 
-        // Per second statistics
-        let mut statistic_print_per_second_clock = system_nanos();
-        let mut orders_per_second = 0;
-        let mut executions_per_second = 0;
-        let mut cycles_per_second = 0;
+        let mut inbound_to_match_ns = self.metrics.inbound_to_match_ns.recorder();
+        let mut metrics_publish_clock = system_nanos();
 
         loop {
-            let cycle_start_epoch_statistic = system_nanos();
-
             // oe phase
-            if let Some(inbound_order) = self.receive_inbound_order(&order_tx) {
-                match inbound_order {
-                    Order::LimitOrder(mut limit_order) => {
-                        orders_per_second += 1;
-
-                        // match phase
-                        let executions = self.match_inbound_order(
-                            &mut engine_msg_out_seq_num,
-                            &mut engine_execution_seq_num,
-                            &mut limit_order,
-                            &match_cycle_msg_out_tx,
-                        );
+            match order_tx.try_recv() {
+                Ok(inbound_order) => {
+                    idler.reset();
+                    self.metrics.order_entry_queue_len.dec();
+
+                    match inbound_order {
+                        Order::LimitOrder(limit_order) if self.halted => {
+                            self.metrics.orders.inc();
+                            self.metrics.rejects.inc();
 
-                        // add & ack full / remainder order
-                        if limit_order.qty > 0 {
-                            self.book.add_order(limit_order);
-                            let ack = NewOrderAck {
+                            let reject_time = system_nanos();
+                            let out = EngineMessage::OrderReject(OrderReject {
                                 client_id: limit_order.client_id,
-                                order_id: limit_order.id,
-                                instrument: Instrument::str_to_fixed_char_buffer("BTC-USD"),
+                                client_order_id: limit_order.client_order_id,
+                                instrument: self.instrument,
                                 side: limit_order.side,
                                 px: limit_order.px,
                                 qty: limit_order.qty,
-                                qty_rem: limit_order.qty,
-                                time_in_force: TimeInForce::GTC,
-                                ack_time: system_nanos(),
-                            };
-                            let out = EngineMessage::NewOrderAck(ack);
+                                reason: RejectReason::InstrumentHalted,
+                                reject_time,
+                                trace: LatencyTrace {
+                                    match_ns: reject_time,
+                                    ..limit_order.trace
+                                },
+                            });
 
-                            engine_msg_out_tx.send(out).unwrap();
+                            self.publish(&engine_msg_out_tx, out);
                             engine_msg_out_seq_num += 1;
                         }
+                        Order::LimitOrder(mut limit_order) => {
+                            self.metrics.orders.inc();
 
-                        executions_per_second += executions;
-                    }
-                    Order::Cancel(cancel_order) => {
-                        let cancel_order_status = match self.book.remove_order(&cancel_order) {
-                            true => CancelOrderStatus::Cancelled,
-                            false => CancelOrderStatus::NotFound,
-                        };
-
-                        let out = EngineMessage::CancelOrderAck(CancelledOrderAck {
-                            client_id: cancel_order.client_id,
-                            order_id: cancel_order.order_id,
-                            instrument: [0; 16],
-                            cancel_order_status,
-                            reason: ClientRequested,
-                            ack_time: system_nanos(),
-                        });
-
-                        engine_msg_out_tx.send(out).unwrap();
-                        engine_msg_out_seq_num += 1;
-                        orders_per_second += 1;
+                            // match phase
+                            let executions = self.match_inbound_order(
+                                &mut engine_msg_out_seq_num,
+                                &mut engine_execution_seq_num,
+                                &mut limit_order,
+                                &match_cycle_msg_out_tx,
+                            );
+
+                            // add & ack full / remainder order
+                            if limit_order.qty > 0 {
+                                self.book.add_order(limit_order);
+                                let ack_time = system_nanos();
+                                let ack = NewOrderAck {
+                                    client_id: limit_order.client_id,
+                                    client_order_id: limit_order.client_order_id,
+                                    order_id: limit_order.id,
+                                    instrument: self.instrument,
+                                    side: limit_order.side,
+                                    px: limit_order.px,
+                                    qty: limit_order.qty,
+                                    qty_rem: limit_order.qty,
+                                    time_in_force: TimeInForce::GTC,
+                                    ack_time,
+                                    trace: LatencyTrace {
+                                        match_ns: ack_time,
+                                        ..limit_order.trace
+                                    },
+                                };
+                                let out = EngineMessage::NewOrderAck(ack);
+
+                                self.publish(&engine_msg_out_tx, out);
+                                engine_msg_out_seq_num += 1;
+                            }
+
+                            inbound_to_match_ns.record(system_nanos() - limit_order.placed_time);
+                            self.metrics.executions.add(executions as u64);
+                        }
+                        Order::Cancel(cancel_order) => {
+                            self.metrics.cancels.inc();
+
+                            let cancel_order_status = self.book.remove_order(&cancel_order);
+                            let reason = match cancel_order_status {
+                                CancelOrderStatus::Cancelled => ClientRequested,
+                                CancelOrderStatus::NotFound => {
+                                    self.metrics.rejects.inc();
+                                    ClientRequested
+                                }
+                                CancelOrderStatus::Rejected => {
+                                    self.metrics.rejects.inc();
+                                    NotOwner
+                                }
+                            };
+
+                            let ack_time = system_nanos();
+                            let out = EngineMessage::CancelOrderAck(CancelledOrderAck {
+                                client_id: cancel_order.client_id,
+                                order_id: cancel_order.order_id,
+                                instrument: self.instrument,
+                                cancel_order_status,
+                                reason,
+                                ack_time,
+                                trace: LatencyTrace {
+                                    match_ns: ack_time,
+                                    ..cancel_order.trace
+                                },
+                            });
+
+                            self.publish(&engine_msg_out_tx, out);
+                            engine_msg_out_seq_num += 1;
+                        }
+                        Order::Admin(admin_request) => {
+                            self.process_admin_request(&admin_request, &engine_msg_out_tx);
+                        }
                     }
                 }
+                Err(TryRecvError::Empty) => idler.idle(),
+                // msg_in has stopped and every queued order has been matched
+                Err(TryRecvError::Disconnected) => break,
             }
 
-            cycles_per_second += 1;
-
-            if system_nanos() - statistic_print_per_second_clock > 1000 * 1000 * 1000 {
-                let nanos = system_nanos();
-                println!(
-                    "nanos: {} ord: {} exe: {} book: {} bid_v: {} ask_v: {} volume: {}",
-                    nanos - cycle_start_epoch_statistic,
-                    // cycles_per_second,
-                    orders_per_second,
-                    executions_per_second,
-                    self.book.orders_on_book(),
-                    self.book.bid_volume(),
-                    self.book.ask_volume(),
-                    self.book.total_volume()
-                );
+            if system_nanos() - metrics_publish_clock > METRICS_PUBLISH_INTERVAL_NS {
+                inbound_to_match_ns.flush();
+                self.publish_book_metrics();
+                metrics_publish_clock = system_nanos();
+            }
+        }
+
+        println!("--- Order entry drained, shutting down engine instance ---");
+        inbound_to_match_ns.flush();
+        self.publish_book_metrics();
 
-                statistic_print_per_second_clock = nanos;
-                orders_per_second = 0;
-                executions_per_second = 0;
-                cycles_per_second = 0;
+        if let Some(snapshot_path) = &self.snapshot_path {
+            match BookSnapshot::capture(&self.book).write(snapshot_path) {
+                Ok(()) => println!("Wrote final book snapshot to {}", snapshot_path),
+                Err(e) => eprintln!("Failed to write book snapshot {}: {}", snapshot_path, e),
             }
         }
+
+        self.publish(
+            &engine_msg_out_tx,
+            EngineMessage::EngineCommand(EngineCommand::Shutdown),
+        );
+    }
+
+    fn publish(&self, engine_msg_out_tx: &Sender<EngineMessage>, out: EngineMessage) {
+        engine_msg_out_tx.send(out).unwrap();
+        self.metrics.msg_out_queue_len.inc();
     }
 
-    fn receive_inbound_order(&mut self, order_tx: &Receiver<Order>) -> Option<Order> {
-        let inbound_order = order_tx.try_recv();
+    fn process_admin_request(
+        &mut self,
+        request: &AdminRequest,
+        engine_msg_out_tx: &Sender<EngineMessage>,
+    ) {
+        println!(
+            "Admin request {} {}",
+            request.request_id,
+            request.command.name()
+        );
+
+        if request.command != AdminCommand::Status && request.instrument != self.instrument {
+            self.respond(
+                engine_msg_out_tx,
+                request.request_id,
+                AdminResponseBody::Error(AdminError::UnknownInstrument),
+                true,
+            );
+            return;
+        }
 
-        match inbound_order {
-            Ok(order) => Some(order),
-            Err(err) => match err {
-                TryRecvError::Disconnected => {
-                    panic!("Error order recv disconnected!")
+        match request.command {
+            AdminCommand::Status => {
+                let status = self.status();
+                self.respond(
+                    engine_msg_out_tx,
+                    request.request_id,
+                    AdminResponseBody::Status(status),
+                    true,
+                );
+            }
+            AdminCommand::HaltInstrument => {
+                self.halted = true;
+                self.publish_trading_state(engine_msg_out_tx, TradingStatus::Halted);
+                self.respond(
+                    engine_msg_out_tx,
+                    request.request_id,
+                    AdminResponseBody::Done,
+                    true,
+                );
+            }
+            AdminCommand::ResumeInstrument => {
+                self.halted = false;
+                self.publish_trading_state(engine_msg_out_tx, TradingStatus::Trading);
+                self.respond(
+                    engine_msg_out_tx,
+                    request.request_id,
+                    AdminResponseBody::Done,
+                    true,
+                );
+            }
+            AdminCommand::DumpBook => {
+                for side in [Side::Buy, Side::Sell] {
+                    for level in self.book.depth(side, usize::MAX) {
+                        self.respond(
+                            engine_msg_out_tx,
+                            request.request_id,
+                            AdminResponseBody::BookLevel(AdminBookLevel {
+                                side,
+                                px: level.px,
+                                qty: level.qty,
+                                num_orders: level.num_orders,
+                            }),
+                            false,
+                        );
+                    }
                 }
-                _ => None,
-            },
+                self.respond(
+                    engine_msg_out_tx,
+                    request.request_id,
+                    AdminResponseBody::Done,
+                    true,
+                );
+            }
+            AdminCommand::CancelClientOrders(client_id) => {
+                let cancelled = self.cancel_client_orders(client_id, engine_msg_out_tx);
+                self.respond(
+                    engine_msg_out_tx,
+                    request.request_id,
+                    AdminResponseBody::OrdersCancelled(cancelled),
+                    true,
+                );
+            }
+            AdminCommand::Snapshot => {
+                let body = match &self.snapshot_path {
+                    None => AdminResponseBody::Error(AdminError::SnapshotNotConfigured),
+                    Some(snapshot_path) => {
+                        match BookSnapshot::capture(&self.book).write(snapshot_path) {
+                            Ok(()) => AdminResponseBody::Done,
+                            Err(e) => {
+                                eprintln!("Failed to write book snapshot {}: {}", snapshot_path, e);
+                                AdminResponseBody::Error(AdminError::SnapshotFailed)
+                            }
+                        }
+                    }
+                };
+                self.respond(engine_msg_out_tx, request.request_id, body, true);
+            }
+            AdminCommand::Shutdown => {
+                // msg_in has stopped, the engine exits once the orders queued ahead are matched
+                self.respond(
+                    engine_msg_out_tx,
+                    request.request_id,
+                    AdminResponseBody::Done,
+                    true,
+                );
+            }
+            AdminCommand::GetRiskLimits | AdminCommand::SetRiskLimit(_, _) => {
+                self.respond(
+                    engine_msg_out_tx,
+                    request.request_id,
+                    AdminResponseBody::Error(AdminError::UnsupportedCommand),
+                    true,
+                );
+            }
         }
     }
 
+    fn respond(
+        &self,
+        engine_msg_out_tx: &Sender<EngineMessage>,
+        request_id: u32,
+        body: AdminResponseBody,
+        last: bool,
+    ) {
+        self.publish(
+            engine_msg_out_tx,
+            EngineMessage::AdminResponse(AdminResponse {
+                request_id,
+                last,
+                body,
+            }),
+        );
+    }
+
+    /// Tells market data subscribers the instrument was halted or resumed.
+    fn publish_trading_state(
+        &self,
+        engine_msg_out_tx: &Sender<EngineMessage>,
+        state: TradingStatus,
+    ) {
+        self.publish(
+            engine_msg_out_tx,
+            EngineMessage::MarketData(MarketDataMessage {
+                instrument_id: self.instrument_id,
+                timestamp: system_nanos(),
+                body: MarketDataBody::TradingState(TradingState { state }),
+            }),
+        );
+    }
+
+    fn status(&mut self) -> EngineStatus {
+        EngineStatus {
+            instrument: self.instrument,
+            halted: self.halted,
+            orders_on_book: self.book.orders_on_book() as u32,
+            bid_levels: self.book.level_count(Side::Buy) as u32,
+            ask_levels: self.book.level_count(Side::Sell) as u32,
+            best_bid: self.book.best_bid().unwrap_or(0),
+            best_ask: self.book.best_ask().unwrap_or(0),
+            orders_processed: self.metrics.orders.get(),
+            executions: self.metrics.executions.get(),
+        }
+    }
+
+    fn cancel_client_orders(
+        &mut self,
+        client_id: u32,
+        engine_msg_out_tx: &Sender<EngineMessage>,
+    ) -> u32 {
+        let client_orders: Vec<(Side, u32)> = [Side::Buy, Side::Sell]
+            .into_iter()
+            .flat_map(|side| {
+                self.book
+                    .depth(side, usize::MAX)
+                    .into_iter()
+                    .map(move |level| (side, level.px))
+            })
+            .flat_map(|(side, px)| self.book.orders_at_level(side, px))
+            .filter(|order| order.client_id == client_id)
+            .map(|order| (order.side, order.id))
+            .collect();
+
+        let mut cancelled = 0;
+        for (side, order_id) in client_orders {
+            if let Some(order) = self.book.remove_order_by_id(side, order_id) {
+                let ack_time = system_nanos();
+                let out = EngineMessage::CancelOrderAck(CancelledOrderAck {
+                    client_id: order.client_id,
+                    order_id: order.id,
+                    instrument: self.instrument,
+                    cancel_order_status: CancelOrderStatus::Cancelled,
+                    reason: AdminRequested,
+                    ack_time,
+                    trace: LatencyTrace::default(),
+                });
+
+                self.publish(engine_msg_out_tx, out);
+                cancelled += 1;
+            }
+        }
+
+        cancelled
+    }
+
+    fn publish_book_metrics(&mut self) {
+        self.metrics
+            .orders_on_book
+            .set(self.book.orders_on_book() as i64);
+        self.metrics
+            .bid_levels
+            .set(self.book.level_count(Side::Buy) as i64);
+        self.metrics
+            .ask_levels
+            .set(self.book.level_count(Side::Sell) as i64);
+        self.metrics.bid_volume.set(self.book.bid_volume() as i64);
+        self.metrics.ask_volume.set(self.book.ask_volume() as i64);
+    }
+
     fn match_inbound_order(
         &mut self,
         engine_msg_out_seq_num: &mut u32,
@@ -167,10 +458,17 @@ impl MatchEngine {
             &mut self.cycle_executions_buffer,
         );
 
-        for execution_report in self.cycle_executions_buffer.drain(..) {
+        for mut execution_report in self.cycle_executions_buffer.drain(..) {
+            execution_report.instrument = self.instrument;
+            execution_report.trace = LatencyTrace {
+                match_ns: execution_report.exec_ns,
+                ..order.trace
+            };
+
             let outbound_execution_message = EngineMessage::TradeExecution(execution_report);
 
             engine_msg_out_tx.send(outbound_execution_message).unwrap();
+            self.metrics.msg_out_queue_len.inc();
 
             *engine_msg_out_seq_num += 1;
             *execution_seq_num += 1;
@@ -179,3 +477,106 @@ impl MatchEngine {
         num_executions as u32
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use common::metrics::metrics_registry::MetricsRegistry;
+    use std::fs;
+    use std::sync::mpsc;
+    use std::thread;
+
+    fn limit(client_id: u32, id: u32, side: Side, px: u64, qty: u64) -> Order {
+        Order::LimitOrder(LimitOrder {
+            client_id,
+            id,
+            client_order_id: id as u64,
+            side,
+            px,
+            qty,
+            time_in_force: TimeInForce::GTC,
+            placed_time: system_nanos(),
+            trace: LatencyTrace::default(),
+        })
+    }
+
+    fn snapshot_orders(snapshot: &serde_json::Value, side: &str) -> Vec<(u64, u64)> {
+        snapshot[side]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|order| {
+                (
+                    order["order_id"].as_u64().unwrap(),
+                    order["qty"].as_u64().unwrap(),
+                )
+            })
+            .collect()
+    }
+
+    #[test]
+    fn shutdown_drains_the_queue_and_writes_the_snapshot_before_the_notice() {
+        let snapshot_path = std::env::temp_dir()
+            .join(format!("engine_shutdown_{}.json", std::process::id()))
+            .to_string_lossy()
+            .into_owned();
+        let _ = fs::remove_file(&snapshot_path);
+
+        let metrics = Arc::new(EngineMetrics::new(&MetricsRegistry::new()));
+        let mut engine = MatchEngine::new("BTC-USD", 1, Some(snapshot_path.clone()), metrics);
+
+        let (order_tx, order_rx) = mpsc::channel();
+        let (engine_msg_out_tx, engine_msg_out_rx) = mpsc::channel();
+
+        order_tx.send(limit(1, 1000, Side::Sell, 100, 5)).unwrap();
+        order_tx.send(limit(2, 1001, Side::Buy, 100, 3)).unwrap();
+        order_tx.send(limit(2, 1002, Side::Buy, 99, 4)).unwrap();
+        order_tx
+            .send(Order::Admin(AdminRequest {
+                request_id: 7,
+                instrument: Instrument::str_to_fixed_char_buffer("BTC-USD"),
+                command: AdminCommand::Shutdown,
+            }))
+            .unwrap();
+        // msg_in stops reading once it has passed the shutdown on
+        drop(order_tx);
+
+        let engine_thread = thread::spawn(move || {
+            engine.run(order_rx, engine_msg_out_tx, IdleStrategy::Yield);
+        });
+
+        let mut published = Vec::new();
+        for message in engine_msg_out_rx.iter() {
+            if let EngineMessage::EngineCommand(EngineCommand::Shutdown) = message {
+                let snapshot: serde_json::Value =
+                    serde_json::from_str(&fs::read_to_string(&snapshot_path).unwrap()).unwrap();
+                assert_eq!(snapshot_orders(&snapshot, "bids"), vec![(1002, 4)]);
+                assert_eq!(snapshot_orders(&snapshot, "asks"), vec![(1000, 2)]);
+            }
+            published.push(message);
+        }
+        engine_thread.join().unwrap();
+        fs::remove_file(&snapshot_path).unwrap();
+
+        assert_eq!(published.len(), 5);
+        assert!(matches!(&published[0], EngineMessage::NewOrderAck(ack) if ack.order_id == 1000));
+        assert!(matches!(
+            &published[1],
+            EngineMessage::TradeExecution(execution)
+                if execution.bid_order_id == 1001 && execution.exec_qty == 3
+        ));
+        assert!(matches!(&published[2], EngineMessage::NewOrderAck(ack) if ack.order_id == 1002));
+        assert!(matches!(
+            &published[3],
+            EngineMessage::AdminResponse(AdminResponse {
+                request_id: 7,
+                last: true,
+                body: AdminResponseBody::Done,
+            })
+        ));
+        assert!(matches!(
+            &published[4],
+            EngineMessage::EngineCommand(EngineCommand::Shutdown)
+        ));
+    }
+}
@@ -1,19 +1,25 @@
 use crate::domain::order::Order;
 use crate::engine::engine_config::EngineConfig;
+use crate::engine::engine_metrics::EngineMetrics;
 use crate::engine::match_engine::MatchEngine;
 use crate::process::match_thread::match_thread;
 use crate::process::msg_in_thread::msg_in_thread;
 use crate::process::msg_out_thread::msg_out_thread;
+use common::metrics::metrics_registry::MetricsRegistry;
+use common::metrics::metrics_server::spawn_metrics_server;
 use common::transport::sequenced_message::EngineMessage;
+use common::types::instrument::Instrument;
 use common::util::time::wait_50_milli;
+use signal_hook::consts::{SIGINT, SIGTERM};
+use std::net::{Ipv4Addr, SocketAddr, SocketAddrV4};
 use std::sync::atomic::AtomicBool;
 use std::sync::mpsc;
 use std::sync::mpsc::{Receiver, Sender};
+use std::sync::Arc;
 use std::thread::JoinHandle;
 
 pub struct MatchServer {
     engine_config: EngineConfig,
-    shutdown: AtomicBool,
 
     msg_in_thread: Option<JoinHandle<()>>,
     msg_out_thread: Option<JoinHandle<()>>,
@@ -30,20 +36,25 @@ impl MatchServer {
         ) = mpsc::channel();
         let (order_entry_tx, order_entry_rx): (Sender<Order>, Receiver<Order>) = mpsc::channel();
 
-        let core_ids = core_affinity::get_core_ids()
-            .unwrap()
-            .into_iter()
-            .collect::<Vec<_>>();
-        let pinned_match_core = core_ids[0];
-        let pinned_msg_in_core = core_ids[1];
-        let pinned_msg_out_core = core_ids[2];
+        let shutdown = Arc::new(AtomicBool::new(false));
+        for signal in [SIGTERM, SIGINT] {
+            signal_hook::flag::register(signal, shutdown.clone()).unwrap();
+        }
+
+        let metrics_registry = Arc::new(MetricsRegistry::new());
+        let metrics = Arc::new(EngineMetrics::new(&metrics_registry));
 
         // OE and Match Thread
-        let match_engine = MatchEngine::new();
+        let match_engine = MatchEngine::new(
+            &engine_config.instrument,
+            engine_config.instrument_id,
+            engine_config.snapshot_path.clone(),
+            metrics.clone(),
+        );
         let match_thread = match_thread(
             engine_msg_out_tx,
             order_entry_rx,
-            pinned_match_core,
+            engine_config.threads.match_thread.clone(),
             match_engine,
         );
 
@@ -52,8 +63,13 @@ impl MatchServer {
         // MULTICAST -> ENGINE MSG_IN
         let msg_in_thread = msg_in_thread(
             engine_config.msg_in_port,
+            engine_config.msg_in_nack_port,
+            engine_config.receive_ring_len,
+            Instrument::str_to_fixed_char_buffer(&engine_config.instrument),
             order_entry_tx,
-            pinned_msg_in_core,
+            engine_config.threads.msg_in.clone(),
+            shutdown.clone(),
+            metrics.clone(),
         );
 
         wait_50_milli();
@@ -61,13 +77,25 @@ impl MatchServer {
         // ENGINE MSG_OUT -> MULTICAST
         let msg_out_thread = msg_out_thread(
             engine_config.msg_out_port,
+            engine_config.msg_out_nack_port,
+            engine_config.retransmit_ring_len,
+            engine_config.batching,
             engine_msg_out_rx,
-            pinned_msg_out_core,
+            engine_config.threads.msg_out.clone(),
+            metrics,
+        );
+
+        // METRICS -> HTTP
+        spawn_metrics_server(
+            SocketAddr::V4(SocketAddrV4::new(
+                Ipv4Addr::LOCALHOST,
+                engine_config.metrics_port,
+            )),
+            metrics_registry,
         );
 
         MatchServer {
             engine_config,
-            shutdown: AtomicBool::new(false),
             msg_in_thread: Some(msg_in_thread),
             msg_out_thread: Some(msg_out_thread),
             match_thread: Some(match_thread),
@@ -75,8 +103,9 @@ impl MatchServer {
     }
 
     pub fn run(&mut self) {
+        // joined in pipeline order, each stage exits once its upstream has drained
         self.msg_in_thread.take().unwrap().join().unwrap();
-        self.msg_out_thread.take().unwrap().join().unwrap();
         self.match_thread.take().unwrap().join().unwrap();
+        self.msg_out_thread.take().unwrap().join().unwrap();
     }
 }
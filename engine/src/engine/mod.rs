@@ -1,3 +1,5 @@
 pub(crate) mod match_engine;
 pub(crate) mod match_server;
 pub(crate) mod engine_config;
+pub(crate) mod engine_metrics;
+pub(crate) mod book_snapshot;
@@ -1,9 +1,6 @@
-use crate::algorithm::match_strategy::MatchStrategy;
 use crate::engine::engine_config::EngineConfig;
 use crate::engine::match_server::MatchServer;
-use lazy_static::lazy_static;
-use std::env;
-use std::error::Error;
+use common::config::config_loader::load_config_or_exit;
 
 mod algorithm;
 mod book;
@@ -11,23 +8,14 @@ mod domain;
 mod engine;
 mod process;
 
-lazy_static! {
-    pub static ref ENGINE_MSG_IN_PORT: u16 = env::var("ENGINE_PORT")
-        .unwrap_or("3000".to_owned())
-        .parse::<u16>()
-        .unwrap();
-    pub static ref ENGINE_MSG_OUT_PORT: u16 = env::var("ENGINE_PORT")
-        .unwrap_or("3500".to_owned())
-        .parse::<u16>()
-        .unwrap();
-}
-
 fn main() {
     println!("--- Initializing Match Engine ---");
 
-    let engine_config =
-        EngineConfig::load("/home/robert/dev/match-engine/config/engine_btc_usd.json");
+    let app_config = load_config_or_exit();
+    let engine_config = EngineConfig::from_app_config(&app_config);
 
     let mut match_server = MatchServer::new(engine_config);
     match_server.run();
+
+    println!("--- Match Engine stopped ---");
 }
@@ -1,7 +1,8 @@
 use crate::domain::order::Order;
 use crate::engine::match_engine::MatchEngine;
+use common::config::app_config::ThreadConfig;
 use common::transport::sequenced_message::EngineMessage;
-use core_affinity::CoreId;
+use common::util::thread_affinity::pin_current_thread;
 use std::sync::mpsc::{Receiver, Sender};
 use std::thread;
 use std::thread::JoinHandle;
@@ -9,11 +10,11 @@ use std::thread::JoinHandle;
 pub fn match_thread(
     engine_msg_out_tx: Sender<EngineMessage>,
     order_entry_rx: Receiver<Order>,
-    pinned_match_core: CoreId,
+    thread_config: ThreadConfig,
     mut match_engine: MatchEngine
 ) -> JoinHandle<()> {
     thread::spawn(move || {
-        core_affinity::set_for_current(pinned_match_core);
-        match_engine.run(order_entry_rx, engine_msg_out_tx);
+        pin_current_thread("match", thread_config.core);
+        match_engine.run(order_entry_rx, engine_msg_out_tx, thread_config.idle);
     })
 }
@@ -1,69 +1,139 @@
 use crate::domain::limit_order::LimitOrder;
 use crate::domain::order::Order;
-use common::network::mutlicast::multicast_receiver;
-use common::transport::nack_sequenced_multicast_receiver::NackSequencedMulticastReceiver;
+use crate::engine::engine_metrics::EngineMetrics;
+use common::config::app_config::ThreadConfig;
+use common::network::mutlicast::multicast_receiver_with_busy_poll;
+use common::transport::nack_sequenced_multicast_receiver::{
+    NackSequencedMulticastReceiver, TransportEvent,
+};
 use common::transport::sequenced_message::EngineMessage;
+use common::transport::transport_constants::MAX_SENDERS;
+use common::types::admin::AdminCommand;
 use common::types::cancel_order::CancelOrderRequest;
+use common::types::latency_trace::LatencyTrace;
+use common::util::thread_affinity::pin_current_thread;
 use common::util::time::system_nanos;
-use core_affinity::CoreId;
+use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::mpsc::Sender;
+use std::sync::Arc;
 use std::thread;
 use std::thread::JoinHandle;
 
+#[allow(clippy::too_many_arguments)]
 pub fn msg_in_thread(
     msg_in_port: u16,
+    nack_port: u16,
+    ring_len: usize,
+    instrument: [u8; 16],
     order_entry_tx: Sender<Order>,
-    pinned_msg_in_core: CoreId,
+    thread_config: ThreadConfig,
+    shutdown: Arc<AtomicBool>,
+    metrics: Arc<EngineMetrics>,
 ) -> JoinHandle<()> {
     thread::spawn(move || {
-        core_affinity::set_for_current(pinned_msg_in_core);
-        let msg_in_socket = multicast_receiver(msg_in_port);
+        pin_current_thread("msg_in", thread_config.core);
+        let msg_in_socket =
+            multicast_receiver_with_busy_poll(msg_in_port, thread_config.busy_poll_micros);
+        let mut idler = thread_config.idle.idler();
 
-        let mut last_seen_seq = 0;
+        // one sequence space per gateway, the receiver interleaves them
+        let mut last_seen_seqs = [0; MAX_SENDERS];
 
-        let mut multicast_receiver = NackSequencedMulticastReceiver::new(msg_in_socket, 9000);
+        let mut multicast_receiver =
+            NackSequencedMulticastReceiver::new(msg_in_socket, nack_port, ring_len);
 
+        // Order ids are assigned in merge order, by the gateways' sent times, so re-sending the
+        // same input from the same gateways reproduces them.
         let mut init_oe_seq = 1000;
 
-        loop {
-            if let Some(inbound_engine_message) = multicast_receiver.try_recv() {
-                if inbound_engine_message.sequence_number != last_seen_seq + 1 {
-                    eprintln!(
-                        "Received out of order message actual: {} expected: {}",
-                        inbound_engine_message.sequence_number, last_seen_seq
-                    );
+        while !shutdown.load(Ordering::Acquire) {
+            let Some(inbound_engine_message) = multicast_receiver.try_recv() else {
+                while let Some(event) = multicast_receiver.poll_event() {
+                    eprintln!("Gateway stream: {}", event);
+                    if let TransportEvent::SenderRestarted(sender_id) = event {
+                        last_seen_seqs[sender_id as usize] = 0;
+                    }
                 }
+                idler.idle();
+                continue;
+            };
+            idler.reset();
 
-                last_seen_seq += 1;
+            let recv_ns = system_nanos();
 
-                match inbound_engine_message.message {
-                    EngineMessage::NewOrder(new) => {
-                        order_entry_tx
-                            .send(Order::LimitOrder(LimitOrder {
-                                client_id: new.client_id,
-                                id: init_oe_seq,
-                                side: new.order_side,
-                                px: new.px,
-                                qty: new.qty,
-                                time_in_force: new.time_in_force,
-                                placed_time: system_nanos(),
-                            }))
-                            .unwrap();
-                        init_oe_seq += 1;
-                    }
-                    EngineMessage::CancelOrder(cancel) => order_entry_tx
+            let last_seen_seq = &mut last_seen_seqs[inbound_engine_message.sender_id as usize];
+            if inbound_engine_message.sequence_number != *last_seen_seq + 1 {
+                eprintln!(
+                    "Received out of order message from sender {} actual: {} expected: {}",
+                    inbound_engine_message.sender_id,
+                    inbound_engine_message.sequence_number,
+                    *last_seen_seq + 1
+                );
+            }
+
+            *last_seen_seq = inbound_engine_message.sequence_number;
+
+            match inbound_engine_message.message {
+                EngineMessage::NewOrder(new) => {
+                    order_entry_tx
+                        .send(Order::LimitOrder(LimitOrder {
+                            client_id: new.client_id,
+                            id: init_oe_seq,
+                            client_order_id: new.client_order_id,
+                            side: new.order_side,
+                            px: new.px,
+                            qty: new.qty,
+                            time_in_force: new.time_in_force,
+                            placed_time: recv_ns,
+                            trace: LatencyTrace {
+                                engine_recv_ns: recv_ns,
+                                ..new.trace
+                            },
+                        }))
+                        .unwrap();
+                    init_oe_seq += 1;
+                    metrics.order_entry_queue_len.inc();
+                }
+                EngineMessage::CancelOrder(cancel) => {
+                    order_entry_tx
                         .send(Order::Cancel(CancelOrderRequest {
                             client_id: cancel.client_id,
                             order_side: cancel.order_side,
                             order_id: cancel.order_id,
                             instrument: [0; 16],
+                            trace: LatencyTrace {
+                                engine_recv_ns: recv_ns,
+                                ..cancel.trace
+                            },
                         }))
-                        .unwrap(),
-                    _ => {
-                        unimplemented!()
+                        .unwrap();
+                    metrics.order_entry_queue_len.inc();
+                }
+                EngineMessage::AdminRequest(admin_request) => {
+                    // nothing sequenced after a shutdown reaches the engine, what came before drains
+                    let stop = admin_request.command == AdminCommand::Shutdown
+                        && admin_request.instrument == instrument;
+
+                    order_entry_tx.send(Order::Admin(admin_request)).unwrap();
+                    metrics.order_entry_queue_len.inc();
+
+                    if stop {
+                        println!(
+                            "Shutdown requested, admin request {}",
+                            admin_request.request_id
+                        );
+                        shutdown.store(true, Ordering::Release);
                     }
                 }
+                // only the engine publishes these
+                EngineMessage::EngineCommand(_) => {}
+                _ => {
+                    unimplemented!()
+                }
             }
         }
+
+        // dropping order_entry_tx lets the match thread drain and exit
+        println!("--- Order entry stopped ---");
     })
 }
@@ -1,19 +1,33 @@
+use crate::engine::engine_metrics::EngineMetrics;
+use common::config::app_config::{BatchingConfig, ThreadConfig};
+use common::metrics::latency_histogram::LatencyRecorder;
 use common::network::mutlicast::multicast_sender;
 use common::transport::nack_sequenced_multicast_sender::NackSequencedMulticastSender;
-use common::transport::sequenced_message::EngineMessage;
-use core_affinity::CoreId;
+use common::transport::sequenced_message::{EngineMessage, SequenceNumber};
+use common::transport::transport_constants::SOLE_SENDER_ID;
+use common::util::thread_affinity::pin_current_thread;
+use common::util::time::{system_nanos, wait_50_milli};
+use std::collections::VecDeque;
 use std::net::{Ipv4Addr, SocketAddr, SocketAddrV4};
-use std::sync::mpsc::Receiver;
+use std::sync::mpsc::{Receiver, TryRecvError};
+use std::sync::Arc;
 use std::thread;
 use std::thread::JoinHandle;
 
+const METRICS_FLUSH_INTERVAL_NS: u64 = 100_000_000;
+
 pub fn msg_out_thread(
     msg_out_port: u16,
+    nack_port: u16,
+    ring_len: usize,
+    batching: BatchingConfig,
     engine_msg_out_rx: Receiver<EngineMessage>,
-    pinned_msg_out_core: CoreId,
+    thread_config: ThreadConfig,
+    metrics: Arc<EngineMetrics>,
 ) -> JoinHandle<()> {
     thread::spawn(move || {
-        core_affinity::set_for_current(pinned_msg_out_core);
+        // msg_out blocks on its channel so only placement applies
+        pin_current_thread("msg_out", thread_config.core);
         let msg_out_socket = multicast_sender();
 
         let send_addr = SocketAddr::V4(SocketAddrV4::new(
@@ -21,11 +35,163 @@ pub fn msg_out_thread(
             msg_out_port,
         ));
 
-        let mut multicast_sender =
-            NackSequencedMulticastSender::new(msg_out_socket, send_addr, 9001);
+        let mut multicast_sender = NackSequencedMulticastSender::new(
+            msg_out_socket,
+            send_addr,
+            nack_port,
+            SOLE_SENDER_ID,
+            batching,
+            ring_len,
+        )
+        .stamping_engine_publish();
+
+        let mut match_to_publish_ns = metrics.match_to_publish_ns.recorder();
+        // match times of the messages still waiting in the batch, by sequence number
+        let mut unpublished: VecDeque<(SequenceNumber, u64)> =
+            VecDeque::with_capacity(batching.batch_size as usize);
+        let mut last_metrics_flush = system_nanos();
+
+        loop {
+            let mut outbound_engine_message = match engine_msg_out_rx.try_recv() {
+                Ok(engine_message) => engine_message,
+                Err(TryRecvError::Empty) => {
+                    // nothing queued behind the partial batch, don't hold it back
+                    multicast_sender.flush();
+                    record_published(
+                        &multicast_sender,
+                        &mut unpublished,
+                        &mut match_to_publish_ns,
+                    );
+                    // nothing may arrive for a while, /metrics should show the burst just sent
+                    match_to_publish_ns.flush();
+                    last_metrics_flush = system_nanos();
+
+                    match engine_msg_out_rx.recv() {
+                        Ok(engine_message) => engine_message,
+                        Err(_) => break,
+                    }
+                }
+                Err(TryRecvError::Disconnected) => break,
+            };
+            metrics.msg_out_queue_len.dec();
+            let match_time = match_time(&outbound_engine_message);
+
+            if let Some(trace) = outbound_engine_message.trace_mut() {
+                trace.engine_batch_ns = system_nanos();
+            }
+
+            let seq = multicast_sender.send(outbound_engine_message);
+            if let (Some(seq), Some(match_time)) = (seq, match_time) {
+                unpublished.push_back((seq, match_time));
+            }
+            record_published(
+                &multicast_sender,
+                &mut unpublished,
+                &mut match_to_publish_ns,
+            );
 
-        while let Ok(outbound_engine_message) = engine_msg_out_rx.recv() {
-            multicast_sender.send(outbound_engine_message)
+            let now = system_nanos();
+            if now - last_metrics_flush > METRICS_FLUSH_INTERVAL_NS {
+                match_to_publish_ns.flush();
+                last_metrics_flush = now;
+            }
         }
+
+        // match thread has exited, push out the partial batch and linger for NACKs
+        multicast_sender.flush();
+        record_published(
+            &multicast_sender,
+            &mut unpublished,
+            &mut match_to_publish_ns,
+        );
+        match_to_publish_ns.flush();
+        wait_50_milli();
+        println!("--- Engine msg out flushed ---");
     })
 }
+
+/// Records match to publish for the messages whose batch has gone out since the last call.
+fn record_published(
+    multicast_sender: &NackSequencedMulticastSender,
+    unpublished: &mut VecDeque<(SequenceNumber, u64)>,
+    match_to_publish_ns: &mut LatencyRecorder,
+) {
+    let last_published = multicast_sender.last_published();
+    let now = system_nanos();
+
+    while let Some(&(seq, match_time)) = unpublished.front() {
+        if seq > last_published {
+            break;
+        }
+        match_to_publish_ns.record(now.saturating_sub(match_time));
+        unpublished.pop_front();
+    }
+}
+
+fn match_time(engine_message: &EngineMessage) -> Option<u64> {
+    match engine_message {
+        EngineMessage::NewOrderAck(ack) => Some(ack.ack_time),
+        EngineMessage::CancelOrderAck(ack) => Some(ack.ack_time),
+        EngineMessage::TradeExecution(execution) => Some(execution.exec_ns),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use common::codec::message_codec::{decode_datagram, Datagram};
+    use common::metrics::metrics_registry::MetricsRegistry;
+    use common::network::mutlicast::multicast_receiver;
+    use common::types::engine::EngineCommand;
+    use std::sync::mpsc;
+    use std::time::Duration;
+
+    #[test]
+    fn the_partial_batch_goes_out_when_the_match_thread_exits() {
+        let msg_out_port = 19_501;
+        let feed = multicast_receiver(msg_out_port);
+        feed.set_read_timeout(Some(Duration::from_secs(5))).unwrap();
+
+        // queued and disconnected before it starts, so nothing but the exit flushes the batch
+        let (tx, rx) = mpsc::channel();
+        tx.send(EngineMessage::EngineCommand(EngineCommand::Start))
+            .unwrap();
+        tx.send(EngineMessage::EngineCommand(EngineCommand::Shutdown))
+            .unwrap();
+        drop(tx);
+
+        let batching = BatchingConfig {
+            batch_size: 32,
+            max_flush_delay_micros: 60_000_000,
+            mtu: 1500,
+        };
+        let metrics = Arc::new(EngineMetrics::new(&MetricsRegistry::new()));
+        msg_out_thread(
+            msg_out_port,
+            19_502,
+            64,
+            batching,
+            rx,
+            ThreadConfig::default(),
+            metrics,
+        )
+        .join()
+        .unwrap();
+
+        let mut buf = [0u8; 1500];
+        let published: Vec<EngineMessage> = loop {
+            let len = feed.recv(&mut buf).expect("partial batch never sent");
+            if let Datagram::Batch(batch) = decode_datagram(&buf[..len]).unwrap() {
+                break batch.map(|msg| msg.unwrap().message).collect();
+            }
+        };
+        assert!(matches!(
+            published[..],
+            [
+                EngineMessage::EngineCommand(EngineCommand::Start),
+                EngineMessage::EngineCommand(EngineCommand::Shutdown)
+            ]
+        ));
+    }
+}
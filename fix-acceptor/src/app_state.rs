@@ -0,0 +1,13 @@
+use crate::session::fix_session::FixSession;
+use common::transport::sequenced_message::EngineMessage;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use tokio::sync::mpsc;
+
+pub struct AppState {
+    pub comp_id: String,
+    pub tx_gateway_queue: mpsc::Sender<EngineMessage>,
+    /// Sessions by the counterparty's SenderCompID.
+    pub sessions: HashMap<String, Arc<Mutex<FixSession>>>,
+    pub sessions_by_client: HashMap<u32, Arc<Mutex<FixSession>>>,
+}
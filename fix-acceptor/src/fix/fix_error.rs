@@ -0,0 +1,21 @@
+use std::error::Error;
+use std::fmt::{Display, Formatter};
+
+#[derive(Debug)]
+pub enum FixError {
+    /// The framing is intact but the checksum or the fields are not, the message is skipped.
+    Garbled { consumed: usize, reason: String },
+    /// The stream can't be framed any further and the connection has to be dropped.
+    Malformed(String),
+}
+
+impl Display for FixError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            FixError::Garbled { reason, .. } => write!(f, "garbled message: {}", reason),
+            FixError::Malformed(reason) => write!(f, "malformed message: {}", reason),
+        }
+    }
+}
+
+impl Error for FixError {}
@@ -0,0 +1,203 @@
+use crate::fix::fix_error::FixError;
+use crate::fix::tags;
+use std::fmt::Display;
+
+pub const SOH: u8 = 0x01;
+/// Longest message accepted from a counterparty, anything longer is treated as garbage.
+pub const MAX_MESSAGE_LEN: usize = 8192;
+/// `10=nnn<SOH>`
+const TRAILER_LEN: usize = 7;
+
+type Field = (u32, String);
+
+/// A tag=value message without BeginString, BodyLength and CheckSum, which are added and
+/// checked on the wire. MsgType(35) is always the first field.
+#[derive(Debug, Clone)]
+pub struct FixMessage {
+    fields: Vec<Field>,
+}
+
+impl FixMessage {
+    pub fn new(msg_type: &str) -> FixMessage {
+        FixMessage {
+            fields: vec![(tags::MSG_TYPE, msg_type.to_owned())],
+        }
+    }
+
+    pub fn with(mut self, tag: u32, value: impl Display) -> FixMessage {
+        self.set(tag, value);
+        self
+    }
+
+    /// Replaces the first occurrence of `tag` or appends it.
+    pub fn set(&mut self, tag: u32, value: impl Display) {
+        let value = value.to_string();
+        match self.fields.iter_mut().find(|(t, _)| *t == tag) {
+            Some(field) => field.1 = value,
+            None => self.fields.push((tag, value)),
+        }
+    }
+
+    pub fn get(&self, tag: u32) -> Option<&str> {
+        self.fields
+            .iter()
+            .find(|(t, _)| *t == tag)
+            .map(|(_, value)| value.as_str())
+    }
+
+    pub fn msg_type(&self) -> &str {
+        &self.fields[0].1
+    }
+
+    pub fn seq_num(&self) -> Option<u32> {
+        self.get(tags::MSG_SEQ_NUM)?.parse().ok()
+    }
+
+    pub fn flag(&self, tag: u32) -> bool {
+        self.get(tag) == Some("Y")
+    }
+
+    /// Writes the full message, header fields first, with BodyLength and CheckSum.
+    pub fn encode(&self, out: &mut Vec<u8>) {
+        let mut body = Vec::with_capacity(256);
+        let header = self.fields.iter().filter(|(tag, _)| is_header_tag(*tag));
+        let rest = self.fields.iter().filter(|(tag, _)| !is_header_tag(*tag));
+        for (tag, value) in header.chain(rest) {
+            push_field(&mut body, *tag, value);
+        }
+
+        let start = out.len();
+        push_field(out, tags::BEGIN_STRING, tags::FIX_4_4);
+        push_field(out, tags::BODY_LENGTH, body.len());
+        out.extend_from_slice(&body);
+        let checksum = checksum(&out[start..]);
+        push_field(out, tags::CHECK_SUM, format!("{:03}", checksum));
+    }
+
+    /// Takes the first complete message off the front of `buf`, returning it with the number of
+    /// bytes it used, or `None` if more bytes are needed.
+    pub fn decode(buf: &[u8]) -> Result<Option<(FixMessage, usize)>, FixError> {
+        let Some((begin_string, begin_end)) = next_field(buf, 0)? else {
+            return incomplete(buf);
+        };
+        if begin_string.0 != tags::BEGIN_STRING || begin_string.1 != tags::FIX_4_4 {
+            return Err(FixError::Malformed(format!(
+                "expected 8={} first, found {}={}",
+                tags::FIX_4_4,
+                begin_string.0,
+                begin_string.1
+            )));
+        }
+
+        let Some((body_length, body_start)) = next_field(buf, begin_end)? else {
+            return incomplete(buf);
+        };
+        if body_length.0 != tags::BODY_LENGTH {
+            return Err(FixError::Malformed(format!(
+                "expected BodyLength(9) second, found tag {}",
+                body_length.0
+            )));
+        }
+        let body_len: usize = body_length
+            .1
+            .parse()
+            .map_err(|_| FixError::Malformed(format!("invalid BodyLength {}", body_length.1)))?;
+
+        let body_end = body_start + body_len;
+        let consumed = body_end + TRAILER_LEN;
+        if consumed > MAX_MESSAGE_LEN {
+            return Err(FixError::Malformed(format!(
+                "message of {} bytes exceeds {}",
+                consumed, MAX_MESSAGE_LEN
+            )));
+        }
+        if buf.len() < consumed {
+            return Ok(None);
+        }
+
+        let trailer = &buf[body_end..consumed];
+        if !trailer.starts_with(b"10=") || trailer[TRAILER_LEN - 1] != SOH {
+            return Err(FixError::Malformed(
+                "CheckSum(10) does not follow BodyLength bytes".to_owned(),
+            ));
+        }
+        let declared = std::str::from_utf8(&trailer[3..6])
+            .ok()
+            .and_then(|checksum| checksum.parse::<u8>().ok());
+        if declared != Some(checksum(&buf[..body_end])) {
+            return Err(garbled(consumed, "invalid CheckSum(10)"));
+        }
+
+        let mut fields = Vec::new();
+        let mut pos = body_start;
+        while pos < body_end {
+            match next_field(&buf[..body_end], pos) {
+                Ok(Some((field, next))) => {
+                    fields.push(field);
+                    pos = next;
+                }
+                Ok(None) => return Err(garbled(consumed, "body does not end with SOH")),
+                Err(e) => return Err(garbled(consumed, &e.to_string())),
+            }
+        }
+
+        if fields.first().map(|(tag, _)| *tag) != Some(tags::MSG_TYPE) {
+            return Err(garbled(consumed, "MsgType(35) is not the first body field"));
+        }
+
+        Ok(Some((FixMessage { fields }, consumed)))
+    }
+}
+
+fn is_header_tag(tag: u32) -> bool {
+    matches!(
+        tag,
+        tags::MSG_TYPE
+            | tags::SENDER_COMP_ID
+            | tags::TARGET_COMP_ID
+            | tags::MSG_SEQ_NUM
+            | tags::POSS_DUP_FLAG
+            | tags::SENDING_TIME
+            | tags::ORIG_SENDING_TIME
+    )
+}
+
+fn push_field(out: &mut Vec<u8>, tag: u32, value: impl Display) {
+    out.extend_from_slice(format!("{}={}", tag, value).as_bytes());
+    out.push(SOH);
+}
+
+fn checksum(bytes: &[u8]) -> u8 {
+    bytes.iter().fold(0u8, |sum, b| sum.wrapping_add(*b))
+}
+
+/// Parses the `tag=value<SOH>` starting at `pos`, `None` if the SOH hasn't arrived yet.
+fn next_field(buf: &[u8], pos: usize) -> Result<Option<(Field, usize)>, FixError> {
+    let Some(len) = buf[pos..].iter().position(|b| *b == SOH) else {
+        return Ok(None);
+    };
+    let raw = &buf[pos..pos + len];
+    let field = std::str::from_utf8(raw)
+        .ok()
+        .and_then(|field| field.split_once('='))
+        .and_then(|(tag, value)| Some((tag.parse::<u32>().ok()?, value.to_owned())))
+        .ok_or_else(|| {
+            FixError::Malformed(format!("invalid field '{}'", String::from_utf8_lossy(raw)))
+        })?;
+
+    Ok(Some((field, pos + len + 1)))
+}
+
+fn garbled(consumed: usize, reason: &str) -> FixError {
+    FixError::Garbled {
+        consumed,
+        reason: reason.to_owned(),
+    }
+}
+
+fn incomplete(buf: &[u8]) -> Result<Option<(FixMessage, usize)>, FixError> {
+    if buf.len() > MAX_MESSAGE_LEN {
+        return Err(FixError::Malformed("no message header found".to_owned()));
+    }
+    Ok(None)
+}
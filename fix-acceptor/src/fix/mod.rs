@@ -0,0 +1,4 @@
+pub mod fix_error;
+pub mod fix_message;
+pub mod tags;
+pub mod utc_timestamp;
@@ -0,0 +1,128 @@
+use crate::app_state::AppState;
+use crate::fix::fix_error::FixError;
+use crate::fix::fix_message::{FixMessage, MAX_MESSAGE_LEN};
+use crate::fix::tags;
+use crate::fix::tags::msg_type;
+use crate::session::fix_session::{FixSession, SessionOutput};
+use std::net::SocketAddr;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::TcpStream;
+use tokio::sync::mpsc;
+
+/// Runs one counterparty connection: the first message has to be a Logon for a configured
+/// session, everything after it is handed to that session.
+pub async fn fix_connection(stream: TcpStream, addr: SocketAddr, state: Arc<AppState>) {
+    let (mut reader, mut writer) = stream.into_split();
+    let (tx_outbound, mut rx_outbound) = mpsc::unbounded_channel::<Vec<u8>>();
+
+    tokio::spawn(async move {
+        while let Some(bytes) = rx_outbound.recv().await {
+            if writer.write_all(&bytes).await.is_err() {
+                break;
+            }
+        }
+    });
+
+    let mut session: Option<Arc<Mutex<FixSession>>> = None;
+    let mut buffer = Vec::with_capacity(MAX_MESSAGE_LEN);
+    let mut chunk = [0u8; 4096];
+    let mut timer = tokio::time::interval(Duration::from_secs(1));
+
+    'connection: loop {
+        tokio::select! {
+            read = reader.read(&mut chunk) => match read {
+                Ok(0) | Err(_) => break,
+                Ok(n) => buffer.extend_from_slice(&chunk[..n]),
+            },
+            _ = timer.tick() => {
+                let Some(session) = &session else {
+                    continue;
+                };
+                if session.lock().unwrap().on_timer().disconnect {
+                    break;
+                }
+                continue;
+            }
+        }
+
+        loop {
+            let message = match FixMessage::decode(&buffer) {
+                Ok(Some((message, consumed))) => {
+                    buffer.drain(..consumed);
+                    message
+                }
+                Ok(None) => break,
+                Err(FixError::Garbled { consumed, reason }) => {
+                    eprintln!("{} sent a garbled message: {}", addr, reason);
+                    buffer.drain(..consumed);
+                    continue;
+                }
+                Err(e) => {
+                    eprintln!("Closing FIX connection {}: {}", addr, e);
+                    break 'connection;
+                }
+            };
+
+            let output = match &session {
+                Some(session) => session.lock().unwrap().on_message(message),
+                None => match logon(&state, &message, tx_outbound.clone()) {
+                    Some((logged_on, output)) => {
+                        session = Some(logged_on);
+                        output
+                    }
+                    None => break 'connection,
+                },
+            };
+
+            for message in output.to_gateway {
+                if state.tx_gateway_queue.send(message).await.is_err() {
+                    break 'connection;
+                }
+            }
+            if output.disconnect {
+                break 'connection;
+            }
+        }
+    }
+
+    if let Some(session) = session {
+        session.lock().unwrap().disconnect();
+    }
+}
+
+fn logon(
+    state: &AppState,
+    message: &FixMessage,
+    tx_outbound: mpsc::UnboundedSender<Vec<u8>>,
+) -> Option<(Arc<Mutex<FixSession>>, SessionOutput)> {
+    let sender_comp_id = message.get(tags::SENDER_COMP_ID).unwrap_or("");
+    if message.msg_type() != msg_type::LOGON {
+        eprintln!(
+            "{} sent MsgType {} before logging on",
+            sender_comp_id,
+            message.msg_type()
+        );
+        return None;
+    }
+    if message.get(tags::TARGET_COMP_ID) != Some(state.comp_id.as_str()) {
+        eprintln!("{} logged on to the wrong TargetCompID", sender_comp_id);
+        return None;
+    }
+    let Some(session) = state.sessions.get(sender_comp_id) else {
+        eprintln!("Logon from unknown SenderCompID '{}'", sender_comp_id);
+        return None;
+    };
+
+    let output = {
+        let mut locked = session.lock().unwrap();
+        if locked.is_connected() {
+            eprintln!("{} is already logged on", locked.counterparty());
+            return None;
+        }
+        locked.logon(message, tx_outbound)
+    };
+
+    Some((session.clone(), output))
+}
@@ -0,0 +1,68 @@
+use crate::app_state::AppState;
+use common::codec::frame_error::FrameError;
+use common::codec::framing::MAX_FRAME_LEN;
+use common::codec::message_codec::decode_engine_message;
+use common::session::session_client::read_data_async;
+use common::session::session_error::SessionError;
+use common::transport::sequenced_message::EngineMessage;
+use std::sync::Arc;
+use tokio::net::tcp::OwnedReadHalf;
+
+pub async fn gateway_event_stream(mut rx_gateway_stream: OwnedReadHalf, state: Arc<AppState>) {
+    println!("Initialized GW -> FIX");
+
+    let mut buffer = [0u8; MAX_FRAME_LEN];
+
+    loop {
+        let msg = match read_data_async(&mut rx_gateway_stream, &mut buffer)
+            .await
+            .and_then(|payload| Ok(decode_engine_message(payload)?))
+        {
+            Ok(msg) => msg,
+            Err(SessionError::Frame(FrameError::Closed)) => {
+                eprintln!("Gateway closed the connection");
+                return;
+            }
+            Err(e) => {
+                eprintln!("Closing gateway stream: {}", e);
+                return;
+            }
+        };
+
+        let (client_id, other_client) = match &msg {
+            EngineMessage::NewOrderAck(ack) => (ack.client_id, 0),
+            EngineMessage::CancelOrderAck(ack) => (ack.client_id, 0),
+            EngineMessage::OrderReject(reject) => (reject.client_id, 0),
+            EngineMessage::TradeExecution(execution) => {
+                (execution.bid_client_id, execution.ask_client_id)
+            }
+            EngineMessage::EngineCommand(command) => {
+                println!("Received engine command {:?}", command);
+                continue;
+            }
+            _ => continue,
+        };
+
+        // a session sees both sides of its own trade in one call
+        let other_client = if other_client == client_id {
+            0
+        } else {
+            other_client
+        };
+        for client_id in [client_id, other_client] {
+            if client_id == 0 {
+                continue;
+            }
+            let Some(session) = state.sessions_by_client.get(&client_id) else {
+                continue;
+            };
+
+            let to_gateway = session.lock().unwrap().on_engine_message(&msg);
+            for message in to_gateway {
+                if state.tx_gateway_queue.send(message).await.is_err() {
+                    return;
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,36 @@
+use common::codec::message_codec::encode_engine_message;
+use common::session::session_client::{send_data_async, send_packet_async};
+use common::session::session_message::{ClientPacket, HEARTBEAT_INTERVAL};
+use common::transport::sequenced_message::EngineMessage;
+use tokio::net::tcp::OwnedWriteHalf;
+use tokio::sync::mpsc;
+use tokio::time::timeout;
+
+pub async fn gateway_order_entry(
+    mut tx_gateway_stream: OwnedWriteHalf,
+    mut rx: mpsc::Receiver<EngineMessage>,
+) {
+    println!("Initialized FIX -> GW");
+    let mut serialized = Vec::new();
+
+    loop {
+        let sent = match timeout(HEARTBEAT_INTERVAL, rx.recv()).await {
+            Ok(Some(msg_for_engine)) => {
+                send_data_async(&mut tx_gateway_stream, &mut serialized, |out| {
+                    encode_engine_message(&msg_for_engine, out)
+                })
+                .await
+            }
+            Ok(None) => break,
+            Err(_) => {
+                let heartbeat = ClientPacket::ClientHeartbeat;
+                send_packet_async(&mut tx_gateway_stream, &heartbeat, &mut serialized).await
+            }
+        };
+
+        if let Err(err) = sent {
+            println!("Gateway write error: {}", err);
+            break;
+        }
+    }
+}
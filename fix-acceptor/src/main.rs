@@ -0,0 +1,105 @@
+mod app_state;
+mod fix;
+mod fix_connection;
+mod gateway_event_stream;
+mod gateway_order_entry;
+mod orders;
+mod session;
+
+use crate::app_state::AppState;
+use crate::fix_connection::fix_connection;
+use crate::gateway_event_stream::gateway_event_stream;
+use crate::gateway_order_entry::gateway_order_entry;
+use crate::session::fix_session::FixSession;
+use common::config::config_loader::load_config_or_exit;
+use common::session::session_client::logon_async;
+use common::session::session_message::LoginRequest;
+use common::transport::sequenced_message::EngineMessage;
+use common::util::time::system_nanos;
+use std::collections::HashMap;
+use std::path::Path;
+use std::process::exit;
+use std::sync::{Arc, Mutex};
+use tokio::net::{TcpListener, TcpStream};
+use tokio::sync::mpsc;
+
+#[tokio::main]
+async fn main() {
+    let config = load_config_or_exit();
+    let fix_config = &config.fix_acceptor;
+
+    // client order ids only have to differ from those of earlier runs still on the book
+    let first_client_order_id = system_nanos();
+
+    let mut sessions = HashMap::new();
+    let mut sessions_by_client = HashMap::new();
+    for session_config in &fix_config.sessions {
+        let session = FixSession::new(
+            &fix_config.comp_id,
+            &session_config.sender_comp_id,
+            session_config.client_id,
+            &config.engine.instrument,
+            config.engine.scale,
+            Path::new(&fix_config.store_dir),
+            first_client_order_id,
+        )
+        .unwrap_or_else(|e| {
+            eprintln!(
+                "Cannot open session store for {}: {}",
+                session_config.sender_comp_id, e
+            );
+            exit(1);
+        });
+
+        let session = Arc::new(Mutex::new(session));
+        sessions.insert(session_config.sender_comp_id.clone(), session.clone());
+        sessions_by_client.insert(session_config.client_id, session);
+    }
+
+    let (tx_gateway_queue, rx_gateway_queue) = mpsc::channel::<EngineMessage>(10_000);
+
+    let mut gateway_stream =
+        TcpStream::connect((fix_config.gateway_host.as_str(), fix_config.gateway_port))
+            .await
+            .expect("cannot connect to TCP market-gateway");
+    let login = LoginRequest::new(&fix_config.gateway_username, &fix_config.gateway_password);
+    if let Err(e) = logon_async(&mut gateway_stream, &login).await {
+        eprintln!("Cannot log on to market-gateway: {}", e);
+        exit(1);
+    }
+    let (rx_gw_stream, tx_gateway_stream) = gateway_stream.into_split();
+
+    let state = Arc::new(AppState {
+        comp_id: fix_config.comp_id.clone(),
+        tx_gateway_queue,
+        sessions,
+        sessions_by_client,
+    });
+
+    tokio::spawn(gateway_order_entry(tx_gateway_stream, rx_gateway_queue));
+    tokio::spawn(gateway_event_stream(rx_gw_stream, state.clone()));
+
+    println!("Connected to market-gateway");
+
+    let listener = TcpListener::bind(("0.0.0.0", fix_config.port))
+        .await
+        .unwrap();
+    println!(
+        "FIX acceptor {} listening on 0.0.0.0:{} with {} sessions",
+        fix_config.comp_id,
+        fix_config.port,
+        fix_config.sessions.len()
+    );
+
+    loop {
+        let (stream, addr) = match listener.accept().await {
+            Ok(accepted) => accepted,
+            Err(e) => {
+                eprintln!("Accept failed: {}", e);
+                continue;
+            }
+        };
+        stream.set_nodelay(true).ok();
+        tokio::spawn(fix_connection(stream, addr, state.clone()));
+    }
+}
@@ -0,0 +1,83 @@
+use crate::fix::fix_message::FixMessage;
+use crate::fix::tags;
+use crate::fix::tags::{msg_type, ord_status};
+use crate::fix::utc_timestamp::utc_timestamp;
+use crate::orders::order_entry::{side_to_fix, time_in_force_to_fix};
+use crate::orders::order_tracker::TrackedOrder;
+use common::types::fixed_point::FixedPointScale;
+use common::util::time::system_nanos;
+
+/// ExecutionReport(8) for `order` as it stands. Callers add LastPx/LastQty, Text and
+/// OrdRejReason where they apply.
+pub fn execution_report(
+    order: &TrackedOrder,
+    symbol: &str,
+    scale: &FixedPointScale,
+    exec_id: u64,
+    exec_type: char,
+    status: char,
+) -> FixMessage {
+    let leaves_qty = match status {
+        ord_status::FILLED | ord_status::CANCELED | ord_status::REJECTED => 0,
+        _ => order.leaves_qty(),
+    };
+
+    let mut report = FixMessage::new(msg_type::EXECUTION_REPORT)
+        .with(tags::ORDER_ID, order_id(order))
+        .with(tags::CL_ORD_ID, &order.cl_ord_id);
+    if let Some(orig_cl_ord_id) = &order.orig_cl_ord_id {
+        report.set(tags::ORIG_CL_ORD_ID, orig_cl_ord_id);
+    }
+
+    report
+        .with(tags::EXEC_ID, exec_id)
+        .with(tags::EXEC_TYPE, exec_type)
+        .with(tags::ORD_STATUS, status)
+        .with(tags::SYMBOL, symbol)
+        .with(tags::SIDE, side_to_fix(order.side))
+        .with(tags::ORDER_QTY, scale.qty_to_str(order.order_qty))
+        .with(tags::ORD_TYPE, '2')
+        .with(tags::PRICE, scale.px_to_str(order.px))
+        .with(
+            tags::TIME_IN_FORCE,
+            time_in_force_to_fix(order.time_in_force),
+        )
+        .with(tags::LEAVES_QTY, scale.qty_to_str(leaves_qty))
+        .with(tags::CUM_QTY, scale.qty_to_str(order.cum_qty))
+        .with(tags::AVG_PX, scale.px_to_str(order.avg_px()))
+        .with(tags::TRANSACT_TIME, utc_timestamp(system_nanos()))
+}
+
+/// OrderCancelReject(9) answering the cancel or replace `cl_ord_id` of `orig_cl_ord_id`.
+pub fn order_cancel_reject(
+    cl_ord_id: &str,
+    orig_cl_ord_id: &str,
+    order: Option<&TrackedOrder>,
+    response_to: char,
+    reason: u32,
+    text: &str,
+) -> FixMessage {
+    FixMessage::new(msg_type::ORDER_CANCEL_REJECT)
+        .with(
+            tags::ORDER_ID,
+            order.map(order_id).unwrap_or("NONE".to_owned()),
+        )
+        .with(tags::CL_ORD_ID, cl_ord_id)
+        .with(tags::ORIG_CL_ORD_ID, orig_cl_ord_id)
+        .with(
+            tags::ORD_STATUS,
+            order
+                .map(|order| order.status())
+                .unwrap_or(ord_status::REJECTED),
+        )
+        .with(tags::CXL_REJ_RESPONSE_TO, response_to)
+        .with(tags::CXL_REJ_REASON, reason)
+        .with(tags::TEXT, text)
+}
+
+fn order_id(order: &TrackedOrder) -> String {
+    match order.order_id {
+        Some(order_id) => order_id.to_string(),
+        None => "NONE".to_owned(),
+    }
+}
@@ -0,0 +1,3 @@
+pub mod execution_reports;
+pub mod order_entry;
+pub mod order_tracker;
@@ -0,0 +1,146 @@
+use crate::fix::fix_message::FixMessage;
+use crate::fix::tags;
+use crate::fix::tags::session_reject_reason;
+use common::types::fixed_point::FixedPointScale;
+use common::types::order::TimeInForce;
+use common::types::side::Side;
+
+/// A field that fails session level validation, answered with a Reject(3).
+pub struct FieldError {
+    pub tag: u32,
+    pub reason: u32,
+}
+
+/// NewOrderSingle(D). Order types other than limit and unsupported TimeInForce values parse, so
+/// they can be rejected with an ExecutionReport rather than a session Reject.
+pub struct NewOrder {
+    pub cl_ord_id: String,
+    pub symbol: String,
+    pub side: Side,
+    pub order_qty: u64,
+    /// `None` unless OrdType(40) is limit.
+    pub px: Option<u64>,
+    pub time_in_force: Option<TimeInForce>,
+}
+
+/// OrderCancelRequest(F)
+pub struct CancelRequest {
+    pub cl_ord_id: String,
+    pub orig_cl_ord_id: String,
+    pub symbol: String,
+    pub side: Side,
+}
+
+/// OrderCancelReplaceRequest(G)
+pub struct ReplaceRequest {
+    pub cancel: CancelRequest,
+    pub order_qty: u64,
+    pub px: Option<u64>,
+    pub time_in_force: Option<TimeInForce>,
+}
+
+impl NewOrder {
+    pub fn parse(message: &FixMessage, scale: &FixedPointScale) -> Result<NewOrder, FieldError> {
+        Ok(NewOrder {
+            cl_ord_id: required(message, tags::CL_ORD_ID)?.to_owned(),
+            symbol: required(message, tags::SYMBOL)?.to_owned(),
+            side: side(message)?,
+            order_qty: order_qty(message, scale)?,
+            px: limit_px(message, scale)?,
+            time_in_force: time_in_force(message),
+        })
+    }
+}
+
+impl CancelRequest {
+    pub fn parse(message: &FixMessage) -> Result<CancelRequest, FieldError> {
+        Ok(CancelRequest {
+            cl_ord_id: required(message, tags::CL_ORD_ID)?.to_owned(),
+            orig_cl_ord_id: required(message, tags::ORIG_CL_ORD_ID)?.to_owned(),
+            symbol: required(message, tags::SYMBOL)?.to_owned(),
+            side: side(message)?,
+        })
+    }
+}
+
+impl ReplaceRequest {
+    pub fn parse(
+        message: &FixMessage,
+        scale: &FixedPointScale,
+    ) -> Result<ReplaceRequest, FieldError> {
+        Ok(ReplaceRequest {
+            cancel: CancelRequest::parse(message)?,
+            order_qty: order_qty(message, scale)?,
+            px: limit_px(message, scale)?,
+            time_in_force: time_in_force(message),
+        })
+    }
+}
+
+pub fn side_to_fix(side: Side) -> char {
+    match side {
+        Side::Buy => '1',
+        Side::Sell => '2',
+    }
+}
+
+pub fn time_in_force_to_fix(time_in_force: TimeInForce) -> char {
+    match time_in_force {
+        TimeInForce::GTC => '1',
+        TimeInForce::IOC => '3',
+        TimeInForce::FOK => '4',
+    }
+}
+
+fn required(message: &FixMessage, tag: u32) -> Result<&str, FieldError> {
+    match message.get(tag) {
+        Some(value) if !value.is_empty() => Ok(value),
+        _ => Err(FieldError {
+            tag,
+            reason: session_reject_reason::REQUIRED_TAG_MISSING,
+        }),
+    }
+}
+
+fn incorrect(tag: u32) -> FieldError {
+    FieldError {
+        tag,
+        reason: session_reject_reason::VALUE_INCORRECT,
+    }
+}
+
+fn side(message: &FixMessage) -> Result<Side, FieldError> {
+    match required(message, tags::SIDE)? {
+        "1" => Ok(Side::Buy),
+        "2" => Ok(Side::Sell),
+        _ => Err(incorrect(tags::SIDE)),
+    }
+}
+
+fn order_qty(message: &FixMessage, scale: &FixedPointScale) -> Result<u64, FieldError> {
+    match scale.str_to_qty(required(message, tags::ORDER_QTY)?) {
+        Ok(qty) if qty > 0 => Ok(qty),
+        _ => Err(incorrect(tags::ORDER_QTY)),
+    }
+}
+
+fn limit_px(message: &FixMessage, scale: &FixedPointScale) -> Result<Option<u64>, FieldError> {
+    if required(message, tags::ORD_TYPE)? != "2" {
+        return Ok(None);
+    }
+
+    match scale.str_to_px(required(message, tags::PRICE)?) {
+        Ok(px) if px > 0 => Ok(Some(px)),
+        _ => Err(incorrect(tags::PRICE)),
+    }
+}
+
+/// Day orders rest until cancelled, the engine has no trading session to expire them at.
+fn time_in_force(message: &FixMessage) -> Option<TimeInForce> {
+    match message.get(tags::TIME_IN_FORCE).unwrap_or("0") {
+        "0" | "1" => Some(TimeInForce::GTC),
+        "3" => Some(TimeInForce::IOC),
+        "4" => Some(TimeInForce::FOK),
+        _ => None,
+    }
+}
@@ -0,0 +1,130 @@
+use crate::fix::tags::ord_status;
+use common::types::order::TimeInForce;
+use common::types::side::Side;
+use std::collections::HashMap;
+
+/// A cancel or cancel/replace waiting on the engine's cancel ack.
+pub struct PendingCancel {
+    pub cl_ord_id: String,
+    pub replace: Option<Replacement>,
+}
+
+#[derive(Clone, Copy)]
+pub struct Replacement {
+    pub px: u64,
+    pub order_qty: u64,
+    pub time_in_force: TimeInForce,
+}
+
+/// The FIX view of one live order. Quantities are the FIX ones, so a replacement carries the
+/// executed quantity of the order it replaced and only `order_qty - cum_qty` is in the engine.
+pub struct TrackedOrder {
+    pub cl_ord_id: String,
+    /// Set on a replacement, reported as OrigClOrdID(41) on its Replaced report.
+    pub orig_cl_ord_id: Option<String>,
+    pub order_id: Option<u32>,
+    pub side: Side,
+    pub px: u64,
+    pub order_qty: u64,
+    pub time_in_force: TimeInForce,
+    pub cum_qty: u64,
+    pub cum_notional: u128,
+    /// Whether the New or Replaced report has gone out, the engine reports fills of an
+    /// aggressive order before its ack.
+    pub reported: bool,
+    pub pending: Option<PendingCancel>,
+}
+
+impl TrackedOrder {
+    pub fn leaves_qty(&self) -> u64 {
+        self.order_qty - self.cum_qty
+    }
+
+    /// Average price in raw price units, 0 before the first fill.
+    pub fn avg_px(&self) -> u64 {
+        match self.cum_qty {
+            0 => 0,
+            cum_qty => (self.cum_notional / cum_qty as u128) as u64,
+        }
+    }
+
+    pub fn status(&self) -> char {
+        match self.cum_qty {
+            0 => ord_status::NEW,
+            cum_qty if cum_qty < self.order_qty => ord_status::PARTIALLY_FILLED,
+            _ => ord_status::FILLED,
+        }
+    }
+}
+
+/// Live orders of a session keyed by the `client_order_id` they were entered with, which the
+/// engine echoes on every ack, reject and execution. Only kept in memory: after a restart the
+/// engine's reports for orders entered before it match nothing here and are dropped.
+pub struct OrderTracker {
+    orders: HashMap<u64, TrackedOrder>,
+    by_cl_ord_id: HashMap<String, u64>,
+    by_order_id: HashMap<u32, u64>,
+    next_client_order_id: u64,
+}
+
+impl OrderTracker {
+    /// `first_client_order_id` should not repeat across restarts so reports for orders entered
+    /// before a restart are never taken for new ones.
+    pub fn new(first_client_order_id: u64) -> OrderTracker {
+        OrderTracker {
+            orders: HashMap::new(),
+            by_cl_ord_id: HashMap::new(),
+            by_order_id: HashMap::new(),
+            next_client_order_id: first_client_order_id,
+        }
+    }
+
+    pub fn contains_cl_ord_id(&self, cl_ord_id: &str) -> bool {
+        self.by_cl_ord_id.contains_key(cl_ord_id)
+    }
+
+    pub fn insert(&mut self, order: TrackedOrder) -> u64 {
+        let client_order_id = self.next_client_order_id;
+        self.next_client_order_id += 1;
+        self.by_cl_ord_id
+            .insert(order.cl_ord_id.clone(), client_order_id);
+        self.orders.insert(client_order_id, order);
+        client_order_id
+    }
+
+    pub fn get_mut(&mut self, client_order_id: u64) -> Option<&mut TrackedOrder> {
+        self.orders.get_mut(&client_order_id)
+    }
+
+    pub fn by_cl_ord_id(&mut self, cl_ord_id: &str) -> Option<&mut TrackedOrder> {
+        let client_order_id = self.by_cl_ord_id.get(cl_ord_id)?;
+        self.orders.get_mut(client_order_id)
+    }
+
+    pub fn by_order_id(&mut self, order_id: u32) -> Option<&mut TrackedOrder> {
+        let client_order_id = self.by_order_id.get(&order_id)?;
+        self.orders.get_mut(client_order_id)
+    }
+
+    pub fn set_order_id(&mut self, client_order_id: u64, order_id: u32) {
+        if let Some(order) = self.orders.get_mut(&client_order_id) {
+            order.order_id = Some(order_id);
+            self.by_order_id.insert(order_id, client_order_id);
+        }
+    }
+
+    /// Forgets an order once it is filled, cancelled or rejected.
+    pub fn remove_by_order_id(&mut self, order_id: u32) -> Option<TrackedOrder> {
+        let client_order_id = self.by_order_id.remove(&order_id)?;
+        self.remove(client_order_id)
+    }
+
+    pub fn remove(&mut self, client_order_id: u64) -> Option<TrackedOrder> {
+        let order = self.orders.remove(&client_order_id)?;
+        self.by_cl_ord_id.remove(&order.cl_ord_id);
+        if let Some(order_id) = order.order_id {
+            self.by_order_id.remove(&order_id);
+        }
+        Some(order)
+    }
+}
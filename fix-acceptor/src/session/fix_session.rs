@@ -0,0 +1,1180 @@
+use crate::fix::fix_message::FixMessage;
+use crate::fix::tags;
+use crate::fix::tags::{
+    cxl_rej_reason, cxl_rej_response_to, exec_type, msg_type, ord_rej_reason, ord_status,
+    session_reject_reason,
+};
+use crate::fix::utc_timestamp::utc_timestamp;
+use crate::orders::execution_reports::{execution_report, order_cancel_reject};
+use crate::orders::order_entry::{CancelRequest, FieldError, NewOrder, ReplaceRequest};
+use crate::orders::order_tracker::{OrderTracker, PendingCancel, Replacement, TrackedOrder};
+use common::transport::sequenced_message::EngineMessage;
+use common::types::cancel_order::{
+    CancelOrderRequest, CancelOrderStatus, CancelledOrderAck, Reason,
+};
+use common::types::execution_report::{ExecType, ExecutionReport, FillType};
+use common::types::fixed_point::FixedPointScale;
+use common::types::instrument::Instrument;
+use common::types::latency_trace::LatencyTrace;
+use common::types::order::{OrderRequest, RejectReason, TimeInForce};
+use common::types::side::Side;
+use common::util::time::system_nanos;
+use std::io;
+use std::path::Path;
+use std::time::{Duration, Instant};
+use tokio::sync::mpsc::UnboundedSender;
+
+use crate::session::session_store::SessionStore;
+
+/// What the connection has to do after the session handled an event.
+#[derive(Default)]
+pub struct SessionOutput {
+    pub to_gateway: Vec<EngineMessage>,
+    pub disconnect: bool,
+}
+
+impl SessionOutput {
+    fn disconnect() -> SessionOutput {
+        SessionOutput {
+            to_gateway: Vec::new(),
+            disconnect: true,
+        }
+    }
+
+    fn to_gateway(to_gateway: Vec<EngineMessage>) -> SessionOutput {
+        SessionOutput {
+            to_gateway,
+            disconnect: false,
+        }
+    }
+}
+
+/// One configured counterparty. The session outlives its connections: engine reports for its
+/// orders are sequenced and stored while it is disconnected and can be resent after it logs
+/// back on.
+pub struct FixSession {
+    comp_id: String,
+    counterparty: String,
+    client_id: u32,
+    symbol: String,
+    instrument: [u8; 16],
+    scale: FixedPointScale,
+    store: SessionStore,
+    orders: OrderTracker,
+    next_exec_id: u64,
+    outbound: Option<UnboundedSender<Vec<u8>>>,
+    heartbeat_interval: Duration,
+    last_sent: Instant,
+    last_recv: Instant,
+    test_request_sent: Option<Instant>,
+    /// Highest sequence number seen ahead of a gap we asked to have resent.
+    resend_up_to: Option<u32>,
+}
+
+impl FixSession {
+    pub fn new(
+        comp_id: &str,
+        counterparty: &str,
+        client_id: u32,
+        symbol: &str,
+        scale: FixedPointScale,
+        store_dir: &Path,
+        first_client_order_id: u64,
+    ) -> io::Result<FixSession> {
+        let store = SessionStore::open(store_dir, &format!("{}-{}", comp_id, counterparty))?;
+
+        Ok(FixSession {
+            comp_id: comp_id.to_owned(),
+            counterparty: counterparty.to_owned(),
+            client_id,
+            symbol: symbol.to_owned(),
+            instrument: Instrument::str_to_fixed_char_buffer(symbol),
+            scale,
+            store,
+            orders: OrderTracker::new(first_client_order_id),
+            next_exec_id: first_client_order_id,
+            outbound: None,
+            heartbeat_interval: Duration::from_secs(30),
+            last_sent: Instant::now(),
+            last_recv: Instant::now(),
+            test_request_sent: None,
+            resend_up_to: None,
+        })
+    }
+
+    pub fn counterparty(&self) -> &str {
+        &self.counterparty
+    }
+
+    pub fn is_connected(&self) -> bool {
+        self.outbound.is_some()
+    }
+
+    /// Handles the Logon(A) that opened a connection, `outbound` receives everything the
+    /// session writes until it disconnects.
+    pub fn logon(
+        &mut self,
+        logon: &FixMessage,
+        outbound: UnboundedSender<Vec<u8>>,
+    ) -> SessionOutput {
+        self.outbound = Some(outbound);
+        self.last_recv = Instant::now();
+        self.test_request_sent = None;
+        self.resend_up_to = None;
+
+        let heartbeat_secs = match logon.get(tags::HEART_BT_INT).map(str::parse::<u64>) {
+            Some(Ok(secs)) if secs > 0 => secs,
+            _ => return self.logout("HeartBtInt(108) must be a positive number of seconds"),
+        };
+        self.heartbeat_interval = Duration::from_secs(heartbeat_secs);
+
+        let reset = logon.flag(tags::RESET_SEQ_NUM_FLAG);
+        if reset && let Err(e) = self.store.reset() {
+            eprintln!("{}: cannot reset session store: {}", self.counterparty, e);
+            return self.logout("cannot reset sequence numbers");
+        }
+
+        let Some(seq) = logon.seq_num() else {
+            return self.logout("MsgSeqNum(34) missing");
+        };
+        let expected = self.store.next_target_seq();
+        if seq < expected {
+            return self.logout(&format!(
+                "MsgSeqNum too low, expecting {} but received {}",
+                expected, seq
+            ));
+        }
+
+        let mut reply = FixMessage::new(msg_type::LOGON)
+            .with(tags::ENCRYPT_METHOD, 0)
+            .with(tags::HEART_BT_INT, heartbeat_secs);
+        if reset {
+            reply.set(tags::RESET_SEQ_NUM_FLAG, 'Y');
+        }
+        self.send(reply);
+
+        println!(
+            "{} logged on, next inbound {} next outbound {}",
+            self.counterparty,
+            expected,
+            self.store.next_sender_seq()
+        );
+
+        if seq > expected {
+            self.request_resend(expected, seq);
+        } else {
+            self.set_next_target_seq(seq + 1);
+        }
+
+        SessionOutput::default()
+    }
+
+    pub fn disconnect(&mut self) {
+        if self.outbound.take().is_some() {
+            println!("{} disconnected", self.counterparty);
+        }
+    }
+
+    pub fn on_message(&mut self, message: FixMessage) -> SessionOutput {
+        self.last_recv = Instant::now();
+        self.test_request_sent = None;
+
+        if message.get(tags::SENDER_COMP_ID) != Some(self.counterparty.as_str())
+            || message.get(tags::TARGET_COMP_ID) != Some(self.comp_id.as_str())
+        {
+            return self.logout("CompID problem");
+        }
+
+        let Some(seq) = message.seq_num() else {
+            return self.logout("MsgSeqNum(34) missing");
+        };
+
+        // a SequenceReset in reset mode applies whatever its own sequence number
+        if message.msg_type() == msg_type::SEQUENCE_RESET && !message.flag(tags::GAP_FILL_FLAG) {
+            let expected = self.store.next_target_seq();
+            self.sequence_reset(&message, expected);
+            return SessionOutput::default();
+        }
+
+        let expected = self.store.next_target_seq();
+        if seq > expected {
+            match message.msg_type() {
+                msg_type::LOGOUT => return self.logout("Logout acknowledged"),
+                msg_type::RESEND_REQUEST => self.resend(&message),
+                _ => {}
+            }
+            self.request_resend(expected, seq);
+            return SessionOutput::default();
+        }
+
+        if seq < expected {
+            if message.flag(tags::POSS_DUP_FLAG) {
+                return SessionOutput::default();
+            }
+            return self.logout(&format!(
+                "MsgSeqNum too low, expecting {} but received {}",
+                expected, seq
+            ));
+        }
+
+        self.set_next_target_seq(seq + 1);
+        if self.resend_up_to.is_some_and(|up_to| seq >= up_to) {
+            self.resend_up_to = None;
+        }
+
+        match message.msg_type() {
+            msg_type::HEARTBEAT | msg_type::LOGON => SessionOutput::default(),
+            msg_type::TEST_REQUEST => {
+                let mut heartbeat = FixMessage::new(msg_type::HEARTBEAT);
+                if let Some(test_req_id) = message.get(tags::TEST_REQ_ID) {
+                    heartbeat.set(tags::TEST_REQ_ID, test_req_id);
+                }
+                self.send(heartbeat);
+                SessionOutput::default()
+            }
+            msg_type::RESEND_REQUEST => {
+                self.resend(&message);
+                SessionOutput::default()
+            }
+            msg_type::SEQUENCE_RESET => {
+                self.sequence_reset(&message, seq + 1);
+                SessionOutput::default()
+            }
+            msg_type::LOGOUT => self.logout("Logout acknowledged"),
+            msg_type::NEW_ORDER_SINGLE => match NewOrder::parse(&message, &self.scale) {
+                Ok(order) => SessionOutput::to_gateway(self.new_order(order)),
+                Err(e) => self.reject(&message, e),
+            },
+            msg_type::ORDER_CANCEL_REQUEST => match CancelRequest::parse(&message) {
+                Ok(cancel) => SessionOutput::to_gateway(self.cancel(cancel, None)),
+                Err(e) => self.reject(&message, e),
+            },
+            msg_type::ORDER_CANCEL_REPLACE_REQUEST => {
+                match ReplaceRequest::parse(&message, &self.scale) {
+                    Ok(replace) => SessionOutput::to_gateway(self.replace(replace)),
+                    Err(e) => self.reject(&message, e),
+                }
+            }
+            _ => {
+                let reason = session_reject_reason::INVALID_MSG_TYPE;
+                self.reject(
+                    &message,
+                    FieldError {
+                        tag: tags::MSG_TYPE,
+                        reason,
+                    },
+                )
+            }
+        }
+    }
+
+    /// Called about once a second while connected, sends heartbeats and test requests and
+    /// gives up on a counterparty that stopped answering.
+    pub fn on_timer(&mut self) -> SessionOutput {
+        if !self.is_connected() {
+            return SessionOutput::default();
+        }
+
+        if let Some(sent_at) = self.test_request_sent {
+            if sent_at.elapsed() >= self.heartbeat_interval {
+                eprintln!("{} did not answer a TestRequest", self.counterparty);
+                return SessionOutput::disconnect();
+            }
+        } else if self.last_recv.elapsed() >= self.heartbeat_interval + self.heartbeat_interval / 5
+        {
+            let test_req_id = format!("TEST-{}", self.store.next_sender_seq());
+            self.send(FixMessage::new(msg_type::TEST_REQUEST).with(tags::TEST_REQ_ID, test_req_id));
+            self.test_request_sent = Some(Instant::now());
+        }
+
+        if self.last_sent.elapsed() >= self.heartbeat_interval {
+            self.send(FixMessage::new(msg_type::HEARTBEAT));
+        }
+
+        SessionOutput::default()
+    }
+
+    /// Turns engine output for this session's client into ExecutionReports and cancel rejects,
+    /// returning the new order of a replace whose cancel went through.
+    pub fn on_engine_message(&mut self, message: &EngineMessage) -> Vec<EngineMessage> {
+        match message {
+            EngineMessage::NewOrderAck(ack) if ack.client_id == self.client_id => {
+                self.orders.set_order_id(ack.client_order_id, ack.order_id);
+                self.report_accepted(ack.client_order_id);
+            }
+            EngineMessage::OrderReject(reject) if reject.client_id == self.client_id => {
+                if let Some(order) = self.orders.remove(reject.client_order_id) {
+                    let report = self
+                        .report(&order, exec_type::REJECTED, ord_status::REJECTED)
+                        .with(tags::ORD_REJ_REASON, ord_rej_reason::OTHER)
+                        .with(tags::TEXT, RejectReason::val_to_str(reject.reason));
+                    self.send(report);
+                }
+            }
+            EngineMessage::TradeExecution(execution) => self.execution(execution),
+            EngineMessage::CancelOrderAck(ack) if ack.client_id == self.client_id => {
+                return self.cancel_ack(ack);
+            }
+            _ => {}
+        }
+
+        Vec::new()
+    }
+
+    fn new_order(&mut self, order: NewOrder) -> Vec<EngineMessage> {
+        let tracked = TrackedOrder {
+            cl_ord_id: order.cl_ord_id,
+            orig_cl_ord_id: None,
+            order_id: None,
+            side: order.side,
+            px: order.px.unwrap_or(0),
+            order_qty: order.order_qty,
+            time_in_force: order.time_in_force.unwrap_or(TimeInForce::GTC),
+            cum_qty: 0,
+            cum_notional: 0,
+            reported: true,
+            pending: None,
+        };
+
+        let rejection = if order.symbol != self.symbol {
+            Some((ord_rej_reason::UNKNOWN_SYMBOL, "unknown symbol"))
+        } else if order.px.is_none() {
+            Some((
+                ord_rej_reason::UNSUPPORTED_ORDER_CHARACTERISTIC,
+                "only limit orders are supported",
+            ))
+        } else if order.time_in_force.is_none() {
+            Some((
+                ord_rej_reason::UNSUPPORTED_ORDER_CHARACTERISTIC,
+                "unsupported TimeInForce",
+            ))
+        } else if self.orders.contains_cl_ord_id(&tracked.cl_ord_id) {
+            Some((ord_rej_reason::DUPLICATE_ORDER, "duplicate ClOrdID"))
+        } else {
+            None
+        };
+
+        if let Some((reason, text)) = rejection {
+            let report = self
+                .report(&tracked, exec_type::REJECTED, ord_status::REJECTED)
+                .with(tags::ORD_REJ_REASON, reason)
+                .with(tags::TEXT, text);
+            self.send(report);
+            return Vec::new();
+        }
+
+        let (side, px, qty, time_in_force) = (
+            tracked.side,
+            tracked.px,
+            tracked.order_qty,
+            tracked.time_in_force,
+        );
+        let client_order_id = self.orders.insert(TrackedOrder {
+            reported: false,
+            ..tracked
+        });
+        vec![self.order_request(client_order_id, side, px, qty, time_in_force)]
+    }
+
+    fn replace(&mut self, replace: ReplaceRequest) -> Vec<EngineMessage> {
+        let (Some(px), Some(time_in_force)) = (replace.px, replace.time_in_force) else {
+            self.cancel_reject(
+                &replace.cancel,
+                cxl_rej_response_to::REPLACE,
+                cxl_rej_reason::OTHER,
+                "only limit orders with a supported TimeInForce can be replaced",
+            );
+            return Vec::new();
+        };
+
+        let replacement = Replacement {
+            px,
+            order_qty: replace.order_qty,
+            time_in_force,
+        };
+        self.cancel(replace.cancel, Some(replacement))
+    }
+
+    /// Cancels are sent straight to the engine, a replace is a cancel followed by a new order
+    /// for the quantity left once the cancel is acked.
+    fn cancel(
+        &mut self,
+        cancel: CancelRequest,
+        replace: Option<Replacement>,
+    ) -> Vec<EngineMessage> {
+        let response_to = match replace {
+            Some(_) => cxl_rej_response_to::REPLACE,
+            None => cxl_rej_response_to::CANCEL,
+        };
+
+        let duplicate = self.orders.contains_cl_ord_id(&cancel.cl_ord_id);
+        let symbol_ok = cancel.symbol == self.symbol;
+        let checked = match self.orders.by_cl_ord_id(&cancel.orig_cl_ord_id) {
+            None => Err((cxl_rej_reason::UNKNOWN_ORDER, "unknown order")),
+            Some(_) if !symbol_ok => Err((cxl_rej_reason::OTHER, "unknown symbol")),
+            Some(_) if duplicate => Err((cxl_rej_reason::OTHER, "duplicate ClOrdID")),
+            Some(order) if order.pending.is_some() => Err((
+                cxl_rej_reason::PENDING_CANCEL_OR_REPLACE,
+                "a cancel or replace is already pending",
+            )),
+            Some(order) if order.side != cancel.side => {
+                Err((cxl_rej_reason::OTHER, "side does not match the order"))
+            }
+            Some(order) if replace.is_some_and(|r| r.order_qty <= order.cum_qty) => Err((
+                cxl_rej_reason::OTHER,
+                "OrderQty must be above the executed quantity",
+            )),
+            Some(order) => match order.order_id {
+                None => Err((cxl_rej_reason::OTHER, "order not yet acknowledged")),
+                Some(order_id) => {
+                    order.pending = Some(PendingCancel {
+                        cl_ord_id: cancel.cl_ord_id.clone(),
+                        replace,
+                    });
+                    Ok((order_id, order.side))
+                }
+            },
+        };
+
+        match checked {
+            Ok((order_id, side)) => vec![EngineMessage::CancelOrder(CancelOrderRequest {
+                client_id: self.client_id,
+                order_side: side,
+                order_id,
+                instrument: self.instrument,
+                trace: LatencyTrace::default(),
+            })],
+            Err((reason, text)) => {
+                self.cancel_reject(&cancel, response_to, reason, text);
+                Vec::new()
+            }
+        }
+    }
+
+    fn cancel_reject(
+        &mut self,
+        cancel: &CancelRequest,
+        response_to: char,
+        reason: u32,
+        text: &str,
+    ) {
+        let order = self.orders.by_cl_ord_id(&cancel.orig_cl_ord_id);
+        let reject = order_cancel_reject(
+            &cancel.cl_ord_id,
+            &cancel.orig_cl_ord_id,
+            order.as_deref(),
+            response_to,
+            reason,
+            text,
+        );
+        self.send(reject);
+    }
+
+    fn cancel_ack(&mut self, ack: &CancelledOrderAck) -> Vec<EngineMessage> {
+        match ack.cancel_order_status {
+            CancelOrderStatus::Cancelled => {
+                let Some(order) = self.orders.remove_by_order_id(ack.order_id) else {
+                    return Vec::new();
+                };
+
+                match &order.pending {
+                    Some(PendingCancel {
+                        cl_ord_id,
+                        replace: Some(replacement),
+                    }) => {
+                        let replacement = TrackedOrder {
+                            cl_ord_id: cl_ord_id.clone(),
+                            orig_cl_ord_id: Some(order.cl_ord_id.clone()),
+                            order_id: None,
+                            side: order.side,
+                            px: replacement.px,
+                            order_qty: replacement.order_qty,
+                            time_in_force: replacement.time_in_force,
+                            cum_qty: order.cum_qty,
+                            cum_notional: order.cum_notional,
+                            reported: false,
+                            pending: None,
+                        };
+                        let (side, px, qty, time_in_force) = (
+                            replacement.side,
+                            replacement.px,
+                            replacement.leaves_qty(),
+                            replacement.time_in_force,
+                        );
+                        let client_order_id = self.orders.insert(replacement);
+                        return vec![self.order_request(
+                            client_order_id,
+                            side,
+                            px,
+                            qty,
+                            time_in_force,
+                        )];
+                    }
+                    Some(PendingCancel {
+                        cl_ord_id,
+                        replace: None,
+                    }) => {
+                        let report = self
+                            .report(&order, exec_type::CANCELED, ord_status::CANCELED)
+                            .with(tags::CL_ORD_ID, cl_ord_id)
+                            .with(tags::ORIG_CL_ORD_ID, &order.cl_ord_id);
+                        self.send(report);
+                    }
+                    None => {
+                        // cancelled by the exchange rather than the client
+                        let report = self
+                            .report(&order, exec_type::CANCELED, ord_status::CANCELED)
+                            .with(tags::TEXT, Reason::val_to_str(ack.reason));
+                        self.send(report);
+                    }
+                }
+            }
+            CancelOrderStatus::NotFound | CancelOrderStatus::Rejected => {
+                let Some(order) = self.orders.by_order_id(ack.order_id) else {
+                    return Vec::new();
+                };
+                let Some(pending) = order.pending.take() else {
+                    return Vec::new();
+                };
+
+                let response_to = match pending.replace {
+                    Some(_) => cxl_rej_response_to::REPLACE,
+                    None => cxl_rej_response_to::CANCEL,
+                };
+                let (reason, text) = match ack.cancel_order_status {
+                    CancelOrderStatus::NotFound => (
+                        cxl_rej_reason::TOO_LATE_TO_CANCEL,
+                        "order is no longer on the book".to_owned(),
+                    ),
+                    _ => (cxl_rej_reason::OTHER, Reason::val_to_str(ack.reason)),
+                };
+                let reject = order_cancel_reject(
+                    &pending.cl_ord_id,
+                    &order.cl_ord_id,
+                    Some(order),
+                    response_to,
+                    reason,
+                    &text,
+                );
+                self.send(reject);
+            }
+        }
+
+        Vec::new()
+    }
+
+    fn execution(&mut self, execution: &ExecutionReport) {
+        let sides = [
+            (
+                execution.bid_client_id,
+                execution.bid_client_order_id,
+                execution.bid_order_id,
+                execution.bid_fill_type,
+            ),
+            (
+                execution.ask_client_id,
+                execution.ask_client_order_id,
+                execution.ask_order_id,
+                execution.ask_fill_type,
+            ),
+        ];
+
+        for (client_id, client_order_id, order_id, fill_type) in sides {
+            if client_id != self.client_id || order_id == 0 {
+                continue;
+            }
+
+            match execution.exec_type {
+                ExecType::MatchEvent => self.fill(
+                    client_order_id,
+                    order_id,
+                    fill_type,
+                    execution.exec_px,
+                    execution.exec_qty,
+                ),
+                // only the resting order is reported, and it has left the book
+                ExecType::SelfMatchPrevented => {
+                    if let Some(order) = self.orders.remove(client_order_id) {
+                        let report = self
+                            .report(&order, exec_type::CANCELED, ord_status::CANCELED)
+                            .with(tags::TEXT, Reason::val_to_str(Reason::SelfMatchPrevention));
+                        self.send(report);
+                    }
+                }
+            }
+        }
+    }
+
+    fn fill(
+        &mut self,
+        client_order_id: u64,
+        order_id: u32,
+        fill_type: FillType,
+        px: u64,
+        qty: u64,
+    ) {
+        self.orders.set_order_id(client_order_id, order_id);
+        self.report_accepted(client_order_id);
+
+        let Some(order) = self.orders.get_mut(client_order_id) else {
+            return;
+        };
+        order.cum_qty += qty;
+        order.cum_notional += px as u128 * qty as u128;
+
+        if fill_type != FillType::FullFill {
+            let report = self
+                .report_by_id(
+                    client_order_id,
+                    exec_type::TRADE,
+                    ord_status::PARTIALLY_FILLED,
+                )
+                .with(tags::LAST_QTY, self.scale.qty_to_str(qty))
+                .with(tags::LAST_PX, self.scale.px_to_str(px));
+            self.send(report);
+            return;
+        }
+
+        let Some(order) = self.orders.remove(client_order_id) else {
+            return;
+        };
+        let report = self
+            .report(&order, exec_type::TRADE, ord_status::FILLED)
+            .with(tags::LAST_QTY, self.scale.qty_to_str(qty))
+            .with(tags::LAST_PX, self.scale.px_to_str(px));
+        self.send(report);
+
+        // the engine will not find the order to cancel any more
+        if let Some(pending) = &order.pending {
+            let response_to = match pending.replace {
+                Some(_) => cxl_rej_response_to::REPLACE,
+                None => cxl_rej_response_to::CANCEL,
+            };
+            let reject = order_cancel_reject(
+                &pending.cl_ord_id,
+                &order.cl_ord_id,
+                Some(&order),
+                response_to,
+                cxl_rej_reason::TOO_LATE_TO_CANCEL,
+                "order is filled",
+            );
+            self.send(reject);
+        }
+    }
+
+    /// Sends the New, or Replaced, report the first time the engine mentions an order.
+    fn report_accepted(&mut self, client_order_id: u64) {
+        let Some(order) = self.orders.get_mut(client_order_id) else {
+            return;
+        };
+        if order.reported {
+            return;
+        }
+        order.reported = true;
+
+        let exec_type = match order.orig_cl_ord_id {
+            Some(_) => exec_type::REPLACED,
+            None => exec_type::NEW,
+        };
+        let status = order.status();
+        let report = self.report_by_id(client_order_id, exec_type, status);
+        self.send(report);
+    }
+
+    fn report_by_id(&mut self, client_order_id: u64, exec_type: char, status: char) -> FixMessage {
+        let exec_id = self.exec_id();
+        let order = self
+            .orders
+            .get_mut(client_order_id)
+            .expect("reported order is tracked");
+        execution_report(order, &self.symbol, &self.scale, exec_id, exec_type, status)
+    }
+
+    fn report(&mut self, order: &TrackedOrder, exec_type: char, status: char) -> FixMessage {
+        let exec_id = self.exec_id();
+        execution_report(order, &self.symbol, &self.scale, exec_id, exec_type, status)
+    }
+
+    fn exec_id(&mut self) -> u64 {
+        self.next_exec_id += 1;
+        self.next_exec_id
+    }
+
+    fn order_request(
+        &self,
+        client_order_id: u64,
+        side: Side,
+        px: u64,
+        qty: u64,
+        time_in_force: TimeInForce,
+    ) -> EngineMessage {
+        EngineMessage::NewOrder(OrderRequest {
+            client_id: self.client_id,
+            client_order_id,
+            instrument: self.instrument,
+            order_side: side,
+            px,
+            qty,
+            time_in_force,
+            timestamp: system_nanos(),
+            trace: LatencyTrace::default(),
+        })
+    }
+
+    fn reject(&mut self, message: &FixMessage, error: FieldError) -> SessionOutput {
+        let mut reject = FixMessage::new(msg_type::REJECT)
+            .with(tags::REF_SEQ_NUM, message.seq_num().unwrap_or(0))
+            .with(tags::REF_MSG_TYPE, message.msg_type())
+            .with(tags::SESSION_REJECT_REASON, error.reason);
+        if error.tag != tags::MSG_TYPE {
+            reject.set(tags::REF_TAG_ID, error.tag);
+        }
+        self.send(reject);
+        SessionOutput::default()
+    }
+
+    fn logout(&mut self, text: &str) -> SessionOutput {
+        self.send(FixMessage::new(msg_type::LOGOUT).with(tags::TEXT, text));
+        SessionOutput::disconnect()
+    }
+
+    fn request_resend(&mut self, expected: u32, received: u32) {
+        if self.resend_up_to.is_none() {
+            self.send(
+                FixMessage::new(msg_type::RESEND_REQUEST)
+                    .with(tags::BEGIN_SEQ_NO, expected)
+                    .with(tags::END_SEQ_NO, 0),
+            );
+        }
+        self.resend_up_to = Some(self.resend_up_to.unwrap_or(0).max(received));
+    }
+
+    /// Moves the expected inbound sequence number to NewSeqNo(36), which may not go below
+    /// `min_seq`.
+    fn sequence_reset(&mut self, message: &FixMessage, min_seq: u32) {
+        let new_seq = message
+            .get(tags::NEW_SEQ_NO)
+            .and_then(|new_seq| new_seq.parse::<u32>().ok());
+        match new_seq {
+            Some(new_seq) if new_seq >= min_seq => self.set_next_target_seq(new_seq),
+            _ => {
+                let reason = session_reject_reason::VALUE_INCORRECT;
+                self.reject(
+                    message,
+                    FieldError {
+                        tag: tags::NEW_SEQ_NO,
+                        reason,
+                    },
+                );
+            }
+        }
+    }
+
+    /// Resends stored application messages in the requested range as possible duplicates and
+    /// gap fills over the session messages in between.
+    fn resend(&mut self, request: &FixMessage) {
+        let range = (
+            request.get(tags::BEGIN_SEQ_NO).map(str::parse::<u32>),
+            request.get(tags::END_SEQ_NO).map(str::parse::<u32>),
+        );
+        let (Some(Ok(begin)), Some(Ok(end))) = range else {
+            let reason = session_reject_reason::REQUIRED_TAG_MISSING;
+            self.reject(
+                request,
+                FieldError {
+                    tag: tags::BEGIN_SEQ_NO,
+                    reason,
+                },
+            );
+            return;
+        };
+
+        let last_sent = self.store.next_sender_seq() - 1;
+        let end = if end == 0 || end > last_sent {
+            last_sent
+        } else {
+            end
+        };
+        if begin == 0 || begin > end {
+            return;
+        }
+
+        let messages: Vec<(u32, FixMessage)> = self
+            .store
+            .sent_messages(begin..=end)
+            .map(|(seq, message)| (*seq, message.clone()))
+            .collect();
+        println!(
+            "{} resend {}..={}, {} application messages",
+            self.counterparty,
+            begin,
+            end,
+            messages.len()
+        );
+
+        let mut next = begin;
+        for (seq, mut message) in messages {
+            if seq > next {
+                self.gap_fill(next, seq);
+            }
+            let orig_sending_time = message.get(tags::SENDING_TIME).unwrap_or("").to_owned();
+            message.set(tags::POSS_DUP_FLAG, 'Y');
+            message.set(tags::ORIG_SENDING_TIME, orig_sending_time);
+            message.set(tags::SENDING_TIME, utc_timestamp(system_nanos()));
+            self.write(&message);
+            next = seq + 1;
+        }
+        if next <= end {
+            self.gap_fill(next, end + 1);
+        }
+    }
+
+    fn gap_fill(&mut self, seq: u32, new_seq: u32) {
+        let gap_fill = FixMessage::new(msg_type::SEQUENCE_RESET)
+            .with(tags::SENDER_COMP_ID, &self.comp_id)
+            .with(tags::TARGET_COMP_ID, &self.counterparty)
+            .with(tags::MSG_SEQ_NUM, seq)
+            .with(tags::POSS_DUP_FLAG, 'Y')
+            .with(tags::SENDING_TIME, utc_timestamp(system_nanos()))
+            .with(tags::GAP_FILL_FLAG, 'Y')
+            .with(tags::NEW_SEQ_NO, new_seq);
+        self.write(&gap_fill);
+    }
+
+    fn set_next_target_seq(&mut self, seq: u32) {
+        if let Err(e) = self.store.set_next_target_seq(seq) {
+            eprintln!(
+                "{}: cannot persist sequence numbers: {}",
+                self.counterparty, e
+            );
+        }
+    }
+
+    /// Stamps the header with the next sequence number, stores the message and writes it if
+    /// the counterparty is connected.
+    fn send(&mut self, message: FixMessage) {
+        let message = message
+            .with(tags::SENDER_COMP_ID, &self.comp_id)
+            .with(tags::TARGET_COMP_ID, &self.counterparty)
+            .with(tags::MSG_SEQ_NUM, self.store.next_sender_seq())
+            .with(tags::SENDING_TIME, utc_timestamp(system_nanos()));
+
+        let mut encoded = Vec::new();
+        message.encode(&mut encoded);
+        let keep = !msg_type::is_admin(message.msg_type());
+        if let Err(e) = self.store.sent(&message, &encoded, keep) {
+            eprintln!("{}: cannot persist sent message: {}", self.counterparty, e);
+        }
+        self.write_encoded(encoded);
+    }
+
+    fn write(&mut self, message: &FixMessage) {
+        let mut encoded = Vec::new();
+        message.encode(&mut encoded);
+        self.write_encoded(encoded);
+    }
+
+    fn write_encoded(&mut self, encoded: Vec<u8>) {
+        let Some(outbound) = &self.outbound else {
+            return;
+        };
+        if outbound.send(encoded).is_err() {
+            self.outbound = None;
+        }
+        self.last_sent = Instant::now();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use common::types::order::NewOrderAck;
+    use std::path::PathBuf;
+    use tokio::sync::mpsc::{UnboundedReceiver, unbounded_channel};
+
+    const SCALE: FixedPointScale = FixedPointScale {
+        px_decimals: 2,
+        qty_decimals: 0,
+    };
+
+    fn store_dir(name: &str) -> PathBuf {
+        let dir =
+            std::env::temp_dir().join(format!("fix-acceptor-{}-{}", std::process::id(), name));
+        let _ = std::fs::remove_dir_all(&dir);
+        dir
+    }
+
+    fn session(dir: &Path) -> FixSession {
+        FixSession::new("MATCH", "CLIENT1", 1, "BTC-USD", SCALE, dir, 1000).unwrap()
+    }
+
+    fn inbound(msg_type: &str, seq: u32) -> FixMessage {
+        FixMessage::new(msg_type)
+            .with(tags::SENDER_COMP_ID, "CLIENT1")
+            .with(tags::TARGET_COMP_ID, "MATCH")
+            .with(tags::MSG_SEQ_NUM, seq)
+    }
+
+    fn logon(session: &mut FixSession, seq: u32) -> (SessionOutput, UnboundedReceiver<Vec<u8>>) {
+        let (tx, rx) = unbounded_channel();
+        let output = session.logon(
+            &inbound(msg_type::LOGON, seq).with(tags::HEART_BT_INT, 30),
+            tx,
+        );
+        (output, rx)
+    }
+
+    fn new_order(seq: u32, cl_ord_id: &str, symbol: &str, qty: u64) -> FixMessage {
+        inbound(msg_type::NEW_ORDER_SINGLE, seq)
+            .with(tags::CL_ORD_ID, cl_ord_id)
+            .with(tags::SYMBOL, symbol)
+            .with(tags::SIDE, '1')
+            .with(tags::ORDER_QTY, qty)
+            .with(tags::ORD_TYPE, '2')
+            .with(tags::PRICE, "100.00")
+    }
+
+    fn written(rx: &mut UnboundedReceiver<Vec<u8>>) -> Vec<FixMessage> {
+        let mut messages = Vec::new();
+        while let Ok(bytes) = rx.try_recv() {
+            let (message, consumed) = FixMessage::decode(&bytes).unwrap().unwrap();
+            assert_eq!(consumed, bytes.len());
+            messages.push(message);
+        }
+        messages
+    }
+
+    fn summary(messages: &[FixMessage]) -> Vec<(String, u32)> {
+        messages
+            .iter()
+            .map(|m| (m.msg_type().to_owned(), m.seq_num().unwrap()))
+            .collect()
+    }
+
+    #[test]
+    fn logon_is_answered_and_sequence_numbers_persist() {
+        let dir = store_dir("logon");
+        let mut first = session(&dir);
+        let (output, mut rx) = logon(&mut first, 1);
+        assert!(!output.disconnect);
+
+        let reply = written(&mut rx);
+        assert_eq!(summary(&reply), [("A".to_owned(), 1)]);
+        assert_eq!(reply[0].get(tags::HEART_BT_INT), Some("30"));
+        first.on_message(inbound(msg_type::HEARTBEAT, 2));
+        drop(first);
+
+        // a restarted acceptor expects the counterparty to carry on at 3
+        let mut restarted = session(&dir);
+        let (output, mut rx) = logon(&mut restarted, 2);
+        assert!(output.disconnect);
+        let logout = written(&mut rx);
+        assert_eq!(summary(&logout), [("5".to_owned(), 2)]);
+        assert_eq!(
+            logout[0].get(tags::TEXT),
+            Some("MsgSeqNum too low, expecting 3 but received 2")
+        );
+
+        let (output, mut rx) = logon(&mut restarted, 3);
+        assert!(!output.disconnect);
+        assert_eq!(summary(&written(&mut rx)), [("A".to_owned(), 3)]);
+    }
+
+    #[test]
+    fn a_gap_is_requested_once_and_closed_by_a_gap_fill() {
+        let dir = store_dir("gap");
+        let mut session = session(&dir);
+        let (_, mut rx) = logon(&mut session, 1);
+        written(&mut rx);
+
+        session.on_message(inbound(msg_type::HEARTBEAT, 5));
+        session.on_message(inbound(msg_type::HEARTBEAT, 6));
+        let requests = written(&mut rx);
+        assert_eq!(summary(&requests), [("2".to_owned(), 2)]);
+        assert_eq!(requests[0].get(tags::BEGIN_SEQ_NO), Some("2"));
+        assert_eq!(requests[0].get(tags::END_SEQ_NO), Some("0"));
+
+        let gap_fill = inbound(msg_type::SEQUENCE_RESET, 2)
+            .with(tags::GAP_FILL_FLAG, 'Y')
+            .with(tags::NEW_SEQ_NO, 7);
+        session.on_message(gap_fill);
+        assert_eq!(session.store.next_target_seq(), 7);
+        assert!(written(&mut rx).is_empty());
+
+        // a gap fill may not move the sequence backwards
+        let backwards = inbound(msg_type::SEQUENCE_RESET, 7)
+            .with(tags::GAP_FILL_FLAG, 'Y')
+            .with(tags::NEW_SEQ_NO, 3);
+        session.on_message(backwards);
+        let reject = written(&mut rx);
+        assert_eq!(reject[0].msg_type(), msg_type::REJECT);
+        assert_eq!(reject[0].get(tags::REF_TAG_ID), Some("36"));
+        assert_eq!(session.store.next_target_seq(), 8);
+    }
+
+    #[test]
+    fn resend_replays_application_messages_and_gap_fills_the_rest() {
+        let dir = store_dir("resend");
+        let mut session = session(&dir);
+        let (_, mut rx) = logon(&mut session, 1);
+        // 1 Logon, 2 rejected order, 3 Heartbeat, 4 rejected order, 5 Heartbeat
+        session.on_message(new_order(2, "A", "ETH-USD", 1));
+        session.on_message(inbound(msg_type::TEST_REQUEST, 3).with(tags::TEST_REQ_ID, "T"));
+        session.on_message(new_order(4, "B", "ETH-USD", 1));
+        session.on_message(inbound(msg_type::TEST_REQUEST, 5).with(tags::TEST_REQ_ID, "T"));
+        let sent = written(&mut rx);
+        assert_eq!(sent.len(), 5);
+
+        let resend = inbound(msg_type::RESEND_REQUEST, 6)
+            .with(tags::BEGIN_SEQ_NO, 1)
+            .with(tags::END_SEQ_NO, 0);
+        session.on_message(resend);
+        let resent = written(&mut rx);
+        assert_eq!(
+            summary(&resent),
+            [
+                ("4".to_owned(), 1),
+                ("8".to_owned(), 2),
+                ("4".to_owned(), 3),
+                ("8".to_owned(), 4),
+                ("4".to_owned(), 5),
+            ]
+        );
+        assert_eq!(resent[0].get(tags::NEW_SEQ_NO), Some("2"));
+        assert_eq!(resent[2].get(tags::NEW_SEQ_NO), Some("4"));
+        assert_eq!(resent[4].get(tags::NEW_SEQ_NO), Some("6"));
+        assert!(resent.iter().all(|m| m.flag(tags::POSS_DUP_FLAG)));
+        assert_eq!(resent[1].get(tags::CL_ORD_ID), Some("A"));
+        assert_eq!(
+            resent[1].get(tags::ORIG_SENDING_TIME),
+            sent[1].get(tags::SENDING_TIME)
+        );
+
+        // the end is clamped to the last message sent
+        let resend = inbound(msg_type::RESEND_REQUEST, 7)
+            .with(tags::BEGIN_SEQ_NO, 4)
+            .with(tags::END_SEQ_NO, 99);
+        session.on_message(resend);
+        let resent = written(&mut rx);
+        assert_eq!(summary(&resent), [("8".to_owned(), 4), ("4".to_owned(), 5)]);
+        assert_eq!(session.store.next_sender_seq(), 6);
+    }
+
+    #[test]
+    fn replace_after_a_partial_fill_keeps_the_executed_quantity() {
+        let dir = store_dir("replace");
+        let mut session = session(&dir);
+        let (_, mut rx) = logon(&mut session, 1);
+        written(&mut rx);
+
+        let output = session.on_message(new_order(2, "A", "BTC-USD", 10));
+        let [EngineMessage::NewOrder(order)] = output.to_gateway.as_slice() else {
+            panic!("expected a new order");
+        };
+        assert_eq!(
+            (order.client_order_id, order.qty, order.px),
+            (1000, 10, 10_000)
+        );
+        session.on_engine_message(&ack(order, 7));
+        session.on_engine_message(&fill(1000, 7, FillType::PartialFill, 4));
+        let reports = written(&mut rx);
+        assert_eq!(reports[0].get(tags::EXEC_TYPE), Some("0"));
+        assert_eq!(reports[1].get(tags::EXEC_TYPE), Some("F"));
+        assert_eq!(reports[1].get(tags::LEAVES_QTY), Some("6"));
+
+        // the new quantity has to stay above what already executed
+        let replace = |seq: u32, cl_ord_id: &str, qty: u64| {
+            inbound(msg_type::ORDER_CANCEL_REPLACE_REQUEST, seq)
+                .with(tags::CL_ORD_ID, cl_ord_id)
+                .with(tags::ORIG_CL_ORD_ID, "A")
+                .with(tags::SYMBOL, "BTC-USD")
+                .with(tags::SIDE, '1')
+                .with(tags::ORDER_QTY, qty)
+                .with(tags::ORD_TYPE, '2')
+                .with(tags::PRICE, "101.00")
+        };
+        assert!(session.on_message(replace(3, "B", 4)).to_gateway.is_empty());
+        let reject = written(&mut rx);
+        assert_eq!(reject[0].msg_type(), msg_type::ORDER_CANCEL_REJECT);
+        assert_eq!(reject[0].get(tags::ORD_STATUS), Some("1"));
+
+        let output = session.on_message(replace(4, "C", 8));
+        let [EngineMessage::CancelOrder(cancel)] = output.to_gateway.as_slice() else {
+            panic!("expected a cancel");
+        };
+        assert_eq!(cancel.order_id, 7);
+
+        let cancelled = EngineMessage::CancelOrderAck(CancelledOrderAck {
+            client_id: 1,
+            order_id: 7,
+            instrument: session.instrument,
+            cancel_order_status: CancelOrderStatus::Cancelled,
+            reason: Reason::ClientRequested,
+            ack_time: 0,
+            trace: LatencyTrace::default(),
+        });
+        let to_gateway = session.on_engine_message(&cancelled);
+        let [EngineMessage::NewOrder(order)] = to_gateway.as_slice() else {
+            panic!("expected the replacement order");
+        };
+        assert_eq!(
+            (order.client_order_id, order.qty, order.px),
+            (1001, 4, 10_100)
+        );
+        assert!(written(&mut rx).is_empty());
+
+        session.on_engine_message(&ack(order, 8));
+        let replaced = written(&mut rx);
+        assert_eq!(replaced[0].get(tags::EXEC_TYPE), Some("5"));
+        assert_eq!(replaced[0].get(tags::ORD_STATUS), Some("1"));
+        assert_eq!(replaced[0].get(tags::CL_ORD_ID), Some("C"));
+        assert_eq!(replaced[0].get(tags::ORIG_CL_ORD_ID), Some("A"));
+        assert_eq!(replaced[0].get(tags::ORDER_QTY), Some("8"));
+        assert_eq!(replaced[0].get(tags::CUM_QTY), Some("4"));
+        assert_eq!(replaced[0].get(tags::LEAVES_QTY), Some("4"));
+        assert_eq!(replaced[0].get(tags::AVG_PX), Some("100.00"));
+
+        session.on_engine_message(&fill(1001, 8, FillType::FullFill, 4));
+        let filled = written(&mut rx);
+        assert_eq!(filled[0].get(tags::ORD_STATUS), Some("2"));
+        assert_eq!(filled[0].get(tags::CUM_QTY), Some("8"));
+    }
+
+    fn ack(order: &OrderRequest, order_id: u32) -> EngineMessage {
+        EngineMessage::NewOrderAck(NewOrderAck {
+            client_id: order.client_id,
+            client_order_id: order.client_order_id,
+            order_id,
+            instrument: order.instrument,
+            side: order.order_side,
+            px: order.px,
+            qty: order.qty,
+            qty_rem: order.qty,
+            time_in_force: order.time_in_force,
+            ack_time: 0,
+            trace: LatencyTrace::default(),
+        })
+    }
+
+    fn fill(client_order_id: u64, order_id: u32, fill_type: FillType, qty: u64) -> EngineMessage {
+        EngineMessage::TradeExecution(ExecutionReport {
+            trade_id: 1,
+            bid_client_id: 1,
+            bid_order_id: order_id,
+            bid_client_order_id: client_order_id,
+            bid_order_px: 10_000,
+            bid_fill_type: fill_type,
+            ask_client_id: 2,
+            ask_order_id: 99,
+            ask_client_order_id: 1,
+            ask_order_px: 10_000,
+            ask_fill_type: FillType::PartialFill,
+            instrument: [0; 16],
+            exec_px: 10_000,
+            exec_qty: qty,
+            exec_type: ExecType::MatchEvent,
+            exec_ns: 0,
+            trace: LatencyTrace::default(),
+        })
+    }
+}
@@ -0,0 +1,2 @@
+pub mod fix_session;
+pub mod session_store;
@@ -0,0 +1,230 @@
+use crate::fix::fix_error::FixError;
+use crate::fix::fix_message::FixMessage;
+use std::collections::BTreeMap;
+use std::fs::{self, File, OpenOptions};
+use std::io;
+use std::io::Write;
+use std::ops::RangeInclusive;
+use std::path::{Path, PathBuf};
+
+/// Sequence numbers and sent application messages of one session, kept on disk so a restarted
+/// acceptor picks the session up where it left off. `<id>.seqnums` holds the next outbound and
+/// expected inbound sequence numbers, `<id>.messages` every application message sent as it
+/// went on the wire.
+pub struct SessionStore {
+    seqnums_path: PathBuf,
+    messages_path: PathBuf,
+    messages_file: File,
+    next_sender_seq: u32,
+    next_target_seq: u32,
+    sent: BTreeMap<u32, FixMessage>,
+}
+
+impl SessionStore {
+    pub fn open(dir: &Path, session_id: &str) -> io::Result<SessionStore> {
+        fs::create_dir_all(dir)?;
+        let seqnums_path = dir.join(format!("{}.seqnums", session_id));
+        let messages_path = dir.join(format!("{}.messages", session_id));
+
+        let (next_sender_seq, next_target_seq) = match fs::read_to_string(&seqnums_path) {
+            Ok(seqnums) => parse_seqnums(&seqnums).ok_or_else(|| {
+                io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    format!("invalid {}", seqnums_path.display()),
+                )
+            })?,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => (1, 1),
+            Err(e) => return Err(e),
+        };
+
+        let (sent, valid_len) = match fs::read(&messages_path) {
+            Ok(bytes) => read_messages(&bytes).map_err(|e| {
+                io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    format!("{}: {}", messages_path.display(), e),
+                )
+            })?,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => (BTreeMap::new(), 0),
+            Err(e) => return Err(e),
+        };
+
+        let messages_file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&messages_path)?;
+        // drop a message cut short by a crash mid-write, it was never fully sent
+        messages_file.set_len(valid_len as u64)?;
+
+        Ok(SessionStore {
+            seqnums_path,
+            messages_path,
+            messages_file,
+            next_sender_seq,
+            next_target_seq,
+            sent,
+        })
+    }
+
+    pub fn next_sender_seq(&self) -> u32 {
+        self.next_sender_seq
+    }
+
+    pub fn next_target_seq(&self) -> u32 {
+        self.next_target_seq
+    }
+
+    pub fn set_next_target_seq(&mut self, seq: u32) -> io::Result<()> {
+        self.next_target_seq = seq;
+        self.write_seqnums()
+    }
+
+    /// Records a message going out under the next sender sequence number. Only application
+    /// messages are kept for resending.
+    pub fn sent(&mut self, message: &FixMessage, encoded: &[u8], keep: bool) -> io::Result<()> {
+        if keep {
+            self.messages_file.write_all(encoded)?;
+            self.sent.insert(self.next_sender_seq, message.clone());
+        }
+        self.next_sender_seq += 1;
+        self.write_seqnums()
+    }
+
+    pub fn sent_messages(
+        &self,
+        range: RangeInclusive<u32>,
+    ) -> impl Iterator<Item = (&u32, &FixMessage)> {
+        self.sent.range(range)
+    }
+
+    /// Starts both sequences over at 1 and drops the sent messages.
+    pub fn reset(&mut self) -> io::Result<()> {
+        self.next_sender_seq = 1;
+        self.next_target_seq = 1;
+        self.sent.clear();
+        self.messages_file = File::create(&self.messages_path)?;
+        self.write_seqnums()
+    }
+
+    fn write_seqnums(&self) -> io::Result<()> {
+        fs::write(
+            &self.seqnums_path,
+            format!("{} {}\n", self.next_sender_seq, self.next_target_seq),
+        )
+    }
+}
+
+fn parse_seqnums(seqnums: &str) -> Option<(u32, u32)> {
+    let (sender, target) = seqnums.trim().split_once(' ')?;
+    Some((sender.parse().ok()?, target.parse().ok()?))
+}
+
+/// Returns the stored messages and the length of the file they were read from.
+fn read_messages(bytes: &[u8]) -> Result<(BTreeMap<u32, FixMessage>, usize), FixError> {
+    let mut sent = BTreeMap::new();
+    let mut pos = 0;
+    while let Some((message, consumed)) = FixMessage::decode(&bytes[pos..])? {
+        if let Some(seq) = message.seq_num() {
+            sent.insert(seq, message);
+        }
+        pos += consumed;
+    }
+
+    Ok((sent, pos))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fix::tags;
+
+    fn store_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("fix-store-{}-{}", std::process::id(), name));
+        let _ = fs::remove_dir_all(&dir);
+        dir
+    }
+
+    fn send(store: &mut SessionStore, msg_type: &str, keep: bool) -> Vec<u8> {
+        let message = FixMessage::new(msg_type).with(tags::MSG_SEQ_NUM, store.next_sender_seq());
+        let mut encoded = Vec::new();
+        message.encode(&mut encoded);
+        store.sent(&message, &encoded, keep).unwrap();
+        encoded
+    }
+
+    fn stored_seqs(store: &SessionStore) -> Vec<u32> {
+        store
+            .sent_messages(1..=u32::MAX)
+            .map(|(seq, _)| *seq)
+            .collect()
+    }
+
+    #[test]
+    fn sequence_numbers_and_application_messages_survive_a_reopen() {
+        let dir = store_dir("reopen");
+        let mut store = SessionStore::open(&dir, "s").unwrap();
+        send(&mut store, "8", true);
+        send(&mut store, "0", false);
+        send(&mut store, "8", true);
+        store.set_next_target_seq(5).unwrap();
+        drop(store);
+
+        let store = SessionStore::open(&dir, "s").unwrap();
+        assert_eq!(store.next_sender_seq(), 4);
+        assert_eq!(store.next_target_seq(), 5);
+        assert_eq!(stored_seqs(&store), [1, 3]);
+    }
+
+    #[test]
+    fn a_message_cut_short_by_a_crash_is_dropped() {
+        let dir = store_dir("truncated");
+        let mut store = SessionStore::open(&dir, "s").unwrap();
+        send(&mut store, "8", true);
+        let second = send(&mut store, "8", true);
+        drop(store);
+
+        let messages_path = dir.join("s.messages");
+        let full_len = fs::metadata(&messages_path).unwrap().len();
+        let partial_len = full_len - second.len() as u64 / 2;
+        OpenOptions::new()
+            .write(true)
+            .open(&messages_path)
+            .unwrap()
+            .set_len(partial_len)
+            .unwrap();
+
+        let mut store = SessionStore::open(&dir, "s").unwrap();
+        assert_eq!(stored_seqs(&store), [1]);
+        let valid_len = full_len - second.len() as u64;
+        assert_eq!(fs::metadata(&messages_path).unwrap().len(), valid_len);
+
+        // later messages are appended after the last complete one
+        send(&mut store, "8", true);
+        drop(store);
+        let store = SessionStore::open(&dir, "s").unwrap();
+        assert_eq!(stored_seqs(&store), [1, 3]);
+    }
+
+    #[test]
+    fn unreadable_sequence_numbers_fail_the_open() {
+        let dir = store_dir("seqnums");
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(dir.join("s.seqnums"), "3\n").unwrap();
+
+        let error = SessionStore::open(&dir, "s").err().unwrap();
+        assert_eq!(error.kind(), io::ErrorKind::InvalidData);
+    }
+
+    #[test]
+    fn reset_starts_over() {
+        let dir = store_dir("reset");
+        let mut store = SessionStore::open(&dir, "s").unwrap();
+        send(&mut store, "8", true);
+        store.set_next_target_seq(9).unwrap();
+        store.reset().unwrap();
+        drop(store);
+
+        let store = SessionStore::open(&dir, "s").unwrap();
+        assert_eq!((store.next_sender_seq(), store.next_target_seq()), (1, 1));
+        assert!(stored_seqs(&store).is_empty());
+    }
+}
@@ -1,49 +1,67 @@
 mod market_data_book;
+mod market_data_feed;
 mod market_event;
 mod process;
 
 use crate::market_data_book::MarketDataBook;
 use crate::market_event::MarketEvent;
 use crate::process::engine_out_msg_thread::initialize_engine_msg_out_receiver;
+use crate::process::feed_thread::feed_thread;
 use axum::extract::ws::{Message, Utf8Bytes, WebSocket};
 use axum::extract::{State, WebSocketUpgrade};
 use axum::response::IntoResponse;
 use axum::routing::get;
 use axum::Router;
+use common::config::config_loader::load_config_or_exit;
 use common::transport::sequenced_message::SequencedEngineMessage;
-use lazy_static::lazy_static;
 use serde::{Deserialize, Serialize};
 use std::time::Duration;
 use tokio::sync::broadcast;
 
-lazy_static! {
-    pub static ref ENGINE_MSG_OUT_PORT: u16 = 3500;
-}
-
 #[tokio::main]
 async fn main() {
+    let config = load_config_or_exit();
+    let engine_msg_out_port = config.engine.msg_out_port;
+    let msg_out_nack_port = config.transport.msg_out_nack_port;
+    let receive_ring_len = config.transport.receive_ring_len;
+    let scale = config.engine.scale;
+
     // Init channels
     let (tx_mdd_processor_to_ws, _) = broadcast::channel::<MarketEvent>(4096);
     let (tx_multicast_to_mdd_processor, mut rx_udp_to_mdd_processor) =
         tokio::sync::mpsc::unbounded_channel::<SequencedEngineMessage>();
+    let (tx_feed, rx_feed) = std::sync::mpsc::channel::<SequencedEngineMessage>();
+
+    // Init MDD -> order-by-order multicast feed thread
+    let _feed_thread = feed_thread(
+        config.market_data.feed_port,
+        config.market_data.feed_nack_port,
+        config.engine.instrument_id,
+        config.transport.retransmit_ring_len,
+        config.transport.batching,
+        rx_feed,
+        config.market_data.threads.feed.clone(),
+    );
 
     // Init MSG_OUT -> MDD mc recv thread
-    let core_ids = core_affinity::get_core_ids()
-        .unwrap()
-        .into_iter()
-        .collect::<Vec<_>>();
-    let pinned_msg_out_core = core_ids[2];
-
+    let msg_out_thread_config = config.market_data.threads.msg_out.clone();
     let engine_msg_out_thread = std::thread::spawn(move || {
-        core_affinity::set_for_current(pinned_msg_out_core);
-        initialize_engine_msg_out_receiver(*ENGINE_MSG_OUT_PORT, tx_multicast_to_mdd_processor)
-            .unwrap();
+        initialize_engine_msg_out_receiver(
+            engine_msg_out_port,
+            msg_out_nack_port,
+            receive_ring_len,
+            msg_out_thread_config,
+            tx_multicast_to_mdd_processor,
+            tx_feed,
+        )
+        .unwrap();
     });
 
     // Init MDD processing thread
     let mdd_tx_mdd_processor_to_ws = tx_mdd_processor_to_ws.clone();
     tokio::spawn(async move {
-        let mut book = MarketDataBook::new();
+        let mut book = MarketDataBook::new(scale);
+        let mut engine_epoch = 0;
 
         let mut ticker = tokio::time::interval(Duration::from_millis(50));
         ticker.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Skip);
@@ -54,6 +72,11 @@ async fn main() {
                 maybe_msg = rx_udp_to_mdd_processor.recv() => {
                     match maybe_msg {
                         Some(msg) => {
+                            // a restarted engine starts with an empty book
+                            if msg.epoch != engine_epoch {
+                                book.clear_orders();
+                                engine_epoch = msg.epoch;
+                            }
                             book.update_from_engine(&msg.message);
                         }
                         None => break, // channel closed
@@ -74,8 +97,9 @@ async fn main() {
         .route("/ws/marketdata", get(ws_handler))
         .with_state(tx_mdd_processor_to_ws);
 
-    println!("Market Data Distributor running on http://127.0.0.1:7000");
-    let listener = tokio::net::TcpListener::bind("0.0.0.0:7000").await.unwrap();
+    let port = config.market_data.port;
+    println!("Market Data Distributor running on http://127.0.0.1:{}", port);
+    let listener = tokio::net::TcpListener::bind(("0.0.0.0", port)).await.unwrap();
     axum::serve(listener, app).await.unwrap();
 
     engine_msg_out_thread.join().unwrap();
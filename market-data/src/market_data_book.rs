@@ -1,6 +1,7 @@
 use crate::market_event::{L2Level, MarketEvent, Trade, L1, L2};
 use common::transport::sequenced_message::EngineMessage;
 use common::types::execution_report::{ExecType, ExecutionReport};
+use common::types::fixed_point::FixedPointScale;
 use common::types::order::NewOrderAck;
 use common::types::side::Side;
 use common::types::side::Side::Buy;
@@ -9,26 +10,27 @@ use std::collections::{BTreeMap, HashMap};
 
 #[derive(Debug)]
 struct PriceLevel {
-    px: u32,
-    qty: u32,
+    px: u64,
+    qty: u64,
 }
 
 #[derive(Debug)]
 struct OrderMetadata {
     pub side: Side,
-    pub px: u32,
-    pub qty: u32,
+    pub px: u64,
+    pub qty: u64,
 }
 
 impl OrderMetadata {
-    pub fn new(side: Side, px: u32, qty: u32) -> Self {
+    pub fn new(side: Side, px: u64, qty: u64) -> Self {
         OrderMetadata { side, px, qty }
     }
 }
 
+#[derive(Clone, Copy, Default)]
 struct TradeMetadata {
-    pub exec_px: u32,
-    pub exec_qty: u32,
+    pub exec_px: u64,
+    pub exec_qty: u64,
     pub exec_ns: u64,
 }
 
@@ -45,26 +47,28 @@ impl TradeMetadata {
 const MAX_MARKET_EVENT_DEPTH: usize = 10;
 
 pub struct MarketDataBook {
-    bids_levels: BTreeMap<u32, PriceLevel>,
-    asks_levels: BTreeMap<u32, PriceLevel>,
+    bids_levels: BTreeMap<u64, PriceLevel>,
+    asks_levels: BTreeMap<u64, PriceLevel>,
     order_metadata_map: HashMap<u32, OrderMetadata>,
     orders: u32,
+    scale: FixedPointScale,
 
     // stats
-    last_trade_px: u32,
-    last_trades: [Trade; MAX_MARKET_EVENT_DEPTH],
+    last_trade_px: u64,
+    last_trades: [TradeMetadata; MAX_MARKET_EVENT_DEPTH],
     trade_count: usize,
 }
 
 impl MarketDataBook {
-    pub fn new() -> Self {
+    pub fn new(scale: FixedPointScale) -> Self {
         Self {
             bids_levels: BTreeMap::new(),
             asks_levels: BTreeMap::new(),
             order_metadata_map: HashMap::new(),
             orders: 0,
+            scale,
             last_trade_px: 0,
-            last_trades: [Trade::default(); MAX_MARKET_EVENT_DEPTH],
+            last_trades: [TradeMetadata::default(); MAX_MARKET_EVENT_DEPTH],
             trade_count: 0,
         }
     }
@@ -91,43 +95,47 @@ impl MarketDataBook {
         let best_bid = self.bids_levels.keys().max().unwrap_or(&0);
         let best_ask = self.asks_levels.keys().min().unwrap_or(&0);
 
-        let mut l2_snapshot = L2 {
-            bids: [L2Level::default(); 10],
-            asks: [L2Level::default(); 10],
+        let l2_snapshot = L2 {
+            bids: self.l2_levels(self.bids_levels.values().rev()),
+            asks: self.l2_levels(self.asks_levels.values()),
         };
 
-        for (idx, level) in self
-            .bids_levels
-            .iter()
-            .rev()
-            .take(MAX_MARKET_EVENT_DEPTH)
-            .enumerate()
-        {
-            l2_snapshot.bids[idx].px = level.1.px;
-            l2_snapshot.bids[idx].qty = level.1.qty;
-        }
-        for (idx, level) in self
-            .asks_levels
-            .iter()
-            .take(MAX_MARKET_EVENT_DEPTH)
-            .enumerate()
-        {
-            l2_snapshot.asks[idx].px = level.1.px;
-            l2_snapshot.asks[idx].qty = level.1.qty;
-        }
-
         MarketEvent {
             l1: L1 {
-                best_bid: *best_bid,
-                best_ask: *best_ask,
-                last_price: self.last_trade_px,
+                best_bid: self.scale.px_to_str(*best_bid),
+                best_ask: self.scale.px_to_str(*best_ask),
+                last_price: self.scale.px_to_str(self.last_trade_px),
             },
             l2: l2_snapshot,
-            last_px: self.last_trade_px,
-            trades: self.last_trades,
+            last_px: self.scale.px_to_str(self.last_trade_px),
+            trades: self
+                .last_trades
+                .iter()
+                .map(|trade| Trade {
+                    px: self.scale.px_to_str(trade.exec_px),
+                    qty: self.scale.qty_to_str(trade.exec_qty),
+                    ts: trade.exec_ns,
+                })
+                .collect(),
         }
     }
 
+    // always MAX_MARKET_EVENT_DEPTH levels, empty ones are zero
+    fn l2_levels<'a>(&self, levels: impl Iterator<Item = &'a PriceLevel>) -> Vec<L2Level> {
+        let mut l2_levels: Vec<L2Level> = levels
+            .take(MAX_MARKET_EVENT_DEPTH)
+            .map(|level| L2Level {
+                px: self.scale.px_to_str(level.px),
+                qty: self.scale.qty_to_str(level.qty),
+            })
+            .collect();
+        l2_levels.resize_with(MAX_MARKET_EVENT_DEPTH, || L2Level {
+            px: self.scale.px_to_str(0),
+            qty: self.scale.qty_to_str(0),
+        });
+        l2_levels
+    }
+
     pub fn emit_l1(&self) {
         let best_bid = self.bids_levels.iter().rev().next();
         let best_ask = self.asks_levels.iter().next();
@@ -159,10 +167,21 @@ impl MarketDataBook {
         println!("Trades:");
         for i in 0..self.trade_count {
             let exec = &self.last_trades[i];
-            println!("px:{} @ qty:{} @ {} ", exec.px, exec.qty, exec.ts);
+            println!(
+                "px:{} @ qty:{} @ {} ",
+                exec.exec_px, exec.exec_qty, exec.exec_ns
+            );
         }
     }
 
+    /// Drops every resting order, the trade history is kept.
+    pub fn clear_orders(&mut self) {
+        self.bids_levels.clear();
+        self.asks_levels.clear();
+        self.order_metadata_map.clear();
+        self.orders = 0;
+    }
+
     pub fn order_count(&self) -> u32 {
         self.orders
     }
@@ -224,11 +243,7 @@ impl MarketDataBook {
         }
 
         self.last_trade_px = metadata.exec_px;
-        self.last_trades[self.trade_count % MAX_MARKET_EVENT_DEPTH] = Trade {
-            px: metadata.exec_px,
-            qty: metadata.exec_qty,
-            ts: metadata.exec_ns,
-        };
+        self.last_trades[self.trade_count % MAX_MARKET_EVENT_DEPTH] = metadata;
         self.trade_count += 1;
     }
 
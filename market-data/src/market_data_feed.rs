@@ -0,0 +1,352 @@
+use common::transport::sequenced_message::EngineMessage;
+use common::types::cancel_order::CancelOrderStatus;
+use common::types::execution_report::{ExecType, ExecutionReport};
+use common::types::market_data::{
+    AddOrder, MarketDataBody, MarketDataMessage, OrderCancelled, OrderExecuted, Trade,
+};
+use common::types::side::Side;
+use std::collections::HashMap;
+
+/// Turns the engine output into order-by-order feed messages. The engine reports an aggressive
+/// order's executions before its ack, so only orders that rest are added and an execution always
+/// fills the resting order, the one with the lower (earlier assigned) order id.
+pub struct MarketDataFeed {
+    instrument_id: u32,
+    open_qty: HashMap<u32, u64>,
+    next_match_id: u64,
+}
+
+impl MarketDataFeed {
+    pub fn new(instrument_id: u32) -> Self {
+        MarketDataFeed {
+            instrument_id,
+            open_qty: HashMap::new(),
+            next_match_id: 1,
+        }
+    }
+
+    pub fn on_engine_message(
+        &mut self,
+        engine_message: &EngineMessage,
+        out: &mut Vec<MarketDataMessage>,
+    ) {
+        match engine_message {
+            EngineMessage::NewOrderAck(ack) => {
+                self.open_qty.insert(ack.order_id, ack.qty);
+                out.push(self.message(
+                    ack.ack_time,
+                    MarketDataBody::AddOrder(AddOrder {
+                        order_id: ack.order_id,
+                        side: ack.side,
+                        px: ack.px,
+                        qty: ack.qty,
+                    }),
+                ));
+            }
+            EngineMessage::CancelOrderAck(ack) => {
+                if let CancelOrderStatus::Cancelled = ack.cancel_order_status {
+                    self.cancel(ack.order_id, ack.ack_time, out);
+                }
+            }
+            EngineMessage::TradeExecution(execution) => match execution.exec_type {
+                ExecType::MatchEvent => self.execute(execution, out),
+                // only the resting side is reported, the other id is 0
+                ExecType::SelfMatchPrevented => self.cancel(
+                    execution.bid_order_id.max(execution.ask_order_id),
+                    execution.exec_ns,
+                    out,
+                ),
+            },
+            // trading state changes are published by the engine as is
+            EngineMessage::MarketData(message) => out.push(*message),
+            _ => {}
+        }
+    }
+
+    /// The restarted engine starts with an empty book, so every order still open on the feed is
+    /// cancelled.
+    pub fn on_engine_restart(&mut self, timestamp: u64, out: &mut Vec<MarketDataMessage>) {
+        let mut order_ids: Vec<u32> = self.open_qty.keys().copied().collect();
+        order_ids.sort_unstable();
+        for order_id in order_ids {
+            self.cancel(order_id, timestamp, out);
+        }
+    }
+
+    fn execute(&mut self, execution: &ExecutionReport, out: &mut Vec<MarketDataMessage>) {
+        let (resting_order_id, aggressor_side) = if execution.bid_order_id < execution.ask_order_id
+        {
+            (execution.bid_order_id, Side::Sell)
+        } else {
+            (execution.ask_order_id, Side::Buy)
+        };
+
+        let match_id = self.next_match_id;
+        self.next_match_id += 1;
+
+        // an order added before the feed started can't be executed against
+        if let Some(open_qty) = self.open_qty.get_mut(&resting_order_id) {
+            *open_qty = open_qty.saturating_sub(execution.exec_qty);
+            if *open_qty == 0 {
+                self.open_qty.remove(&resting_order_id);
+            }
+
+            out.push(self.message(
+                execution.exec_ns,
+                MarketDataBody::OrderExecuted(OrderExecuted {
+                    order_id: resting_order_id,
+                    executed_qty: execution.exec_qty,
+                    match_id,
+                }),
+            ));
+        }
+
+        out.push(self.message(
+            execution.exec_ns,
+            MarketDataBody::Trade(Trade {
+                match_id,
+                side: aggressor_side,
+                px: execution.exec_px,
+                qty: execution.exec_qty,
+            }),
+        ));
+    }
+
+    fn cancel(&mut self, order_id: u32, timestamp: u64, out: &mut Vec<MarketDataMessage>) {
+        if let Some(cancelled_qty) = self.open_qty.remove(&order_id) {
+            out.push(self.message(
+                timestamp,
+                MarketDataBody::OrderCancelled(OrderCancelled {
+                    order_id,
+                    cancelled_qty,
+                }),
+            ));
+        }
+    }
+
+    fn message(&self, timestamp: u64, body: MarketDataBody) -> MarketDataMessage {
+        MarketDataMessage {
+            instrument_id: self.instrument_id,
+            timestamp,
+            body,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use common::types::cancel_order::{CancelledOrderAck, Reason};
+    use common::types::execution_report::FillType;
+    use common::types::latency_trace::LatencyTrace;
+    use common::types::market_data::{TradingState, TradingStatus};
+    use common::types::order::{NewOrderAck, TimeInForce};
+
+    fn ack(order_id: u32, side: Side, px: u64, qty: u64) -> EngineMessage {
+        EngineMessage::NewOrderAck(NewOrderAck {
+            client_id: 1,
+            client_order_id: order_id as u64,
+            order_id,
+            instrument: [0; 16],
+            side,
+            px,
+            qty,
+            qty_rem: qty,
+            time_in_force: TimeInForce::GTC,
+            ack_time: 10,
+            trace: LatencyTrace::default(),
+        })
+    }
+
+    fn execution(exec_type: ExecType, bid: u32, ask: u32, px: u64, qty: u64) -> EngineMessage {
+        EngineMessage::TradeExecution(ExecutionReport {
+            trade_id: 1,
+            bid_client_id: 1,
+            bid_order_id: bid,
+            bid_client_order_id: bid as u64,
+            bid_order_px: px,
+            bid_fill_type: FillType::PartialFill,
+            ask_client_id: 2,
+            ask_order_id: ask,
+            ask_client_order_id: ask as u64,
+            ask_order_px: px,
+            ask_fill_type: FillType::PartialFill,
+            instrument: [0; 16],
+            exec_px: px,
+            exec_qty: qty,
+            exec_type,
+            exec_ns: 20,
+            trace: LatencyTrace::default(),
+        })
+    }
+
+    fn cancelled(order_id: u32, status: CancelOrderStatus) -> EngineMessage {
+        EngineMessage::CancelOrderAck(CancelledOrderAck {
+            client_id: 1,
+            order_id,
+            instrument: [0; 16],
+            cancel_order_status: status,
+            reason: Reason::ClientRequested,
+            ack_time: 30,
+            trace: LatencyTrace::default(),
+        })
+    }
+
+    fn bodies(feed: &mut MarketDataFeed, messages: &[EngineMessage]) -> Vec<MarketDataBody> {
+        let mut out = Vec::new();
+        for message in messages {
+            feed.on_engine_message(message, &mut out);
+        }
+        assert!(out.iter().all(|message| message.instrument_id == 7));
+        out.into_iter().map(|message| message.body).collect()
+    }
+
+    fn executed(order_id: u32, executed_qty: u64, match_id: u64) -> MarketDataBody {
+        MarketDataBody::OrderExecuted(OrderExecuted {
+            order_id,
+            executed_qty,
+            match_id,
+        })
+    }
+
+    fn trade(match_id: u64, side: Side, px: u64, qty: u64) -> MarketDataBody {
+        MarketDataBody::Trade(Trade {
+            match_id,
+            side,
+            px,
+            qty,
+        })
+    }
+
+    fn cancel(order_id: u32, cancelled_qty: u64) -> MarketDataBody {
+        MarketDataBody::OrderCancelled(OrderCancelled {
+            order_id,
+            cancelled_qty,
+        })
+    }
+
+    #[test]
+    fn executions_fill_the_resting_order_under_sequential_match_ids() {
+        let mut feed = MarketDataFeed::new(7);
+        let out = bodies(
+            &mut feed,
+            &[
+                ack(1, Side::Sell, 100, 10),
+                // order 2 buys aggressively, its ack comes after its fills and it doesn't rest
+                execution(ExecType::MatchEvent, 2, 1, 100, 4),
+                execution(ExecType::MatchEvent, 2, 1, 100, 6),
+                cancelled(1, CancelOrderStatus::Cancelled),
+            ],
+        );
+
+        assert_eq!(
+            out,
+            [
+                MarketDataBody::AddOrder(AddOrder {
+                    order_id: 1,
+                    side: Side::Sell,
+                    px: 100,
+                    qty: 10,
+                }),
+                executed(1, 4, 1),
+                trade(1, Side::Buy, 100, 4),
+                executed(1, 6, 2),
+                trade(2, Side::Buy, 100, 6),
+            ]
+        );
+    }
+
+    #[test]
+    fn a_sell_aggressor_fills_the_resting_bid() {
+        let mut feed = MarketDataFeed::new(7);
+        let out = bodies(
+            &mut feed,
+            &[
+                ack(3, Side::Buy, 99, 5),
+                execution(ExecType::MatchEvent, 3, 4, 99, 2),
+            ],
+        );
+
+        assert_eq!(out[1..], [executed(3, 2, 1), trade(1, Side::Sell, 99, 2)]);
+    }
+
+    #[test]
+    fn cancels_report_the_open_quantity_once() {
+        let mut feed = MarketDataFeed::new(7);
+        let out = bodies(
+            &mut feed,
+            &[
+                ack(1, Side::Buy, 100, 10),
+                execution(ExecType::MatchEvent, 1, 2, 100, 3),
+                cancelled(1, CancelOrderStatus::NotFound),
+                cancelled(1, CancelOrderStatus::Cancelled),
+                cancelled(1, CancelOrderStatus::Cancelled),
+            ],
+        );
+
+        assert_eq!(out[3..], [cancel(1, 7)]);
+    }
+
+    #[test]
+    fn orders_from_before_the_feed_started_only_trade() {
+        let mut feed = MarketDataFeed::new(7);
+        let out = bodies(&mut feed, &[execution(ExecType::MatchEvent, 5, 6, 100, 1)]);
+
+        assert_eq!(out, [trade(1, Side::Sell, 100, 1)]);
+    }
+
+    #[test]
+    fn self_match_prevention_cancels_the_resting_order() {
+        let mut feed = MarketDataFeed::new(7);
+        let out = bodies(
+            &mut feed,
+            &[
+                ack(1, Side::Sell, 100, 10),
+                execution(ExecType::SelfMatchPrevented, 0, 1, 100, 10),
+            ],
+        );
+
+        assert_eq!(out[1..], [cancel(1, 10)]);
+    }
+
+    #[test]
+    fn an_engine_restart_cancels_every_open_order_in_order_id_order() {
+        let mut feed = MarketDataFeed::new(7);
+        bodies(
+            &mut feed,
+            &[
+                ack(9, Side::Buy, 99, 1),
+                ack(4, Side::Sell, 101, 2),
+                ack(6, Side::Buy, 98, 3),
+            ],
+        );
+
+        let mut out = Vec::new();
+        feed.on_engine_restart(50, &mut out);
+        let restart: Vec<_> = out.iter().map(|m| (m.timestamp, m.body)).collect();
+        assert_eq!(
+            restart,
+            [(50, cancel(4, 2)), (50, cancel(6, 3)), (50, cancel(9, 1))]
+        );
+
+        out.clear();
+        feed.on_engine_restart(60, &mut out);
+        assert!(out.is_empty());
+    }
+
+    #[test]
+    fn trading_state_passes_through() {
+        let halted = MarketDataMessage {
+            instrument_id: 7,
+            timestamp: 40,
+            body: MarketDataBody::TradingState(TradingState {
+                state: TradingStatus::Halted,
+            }),
+        };
+        let mut feed = MarketDataFeed::new(7);
+        let mut out = Vec::new();
+        feed.on_engine_message(&EngineMessage::MarketData(halted), &mut out);
+
+        assert_eq!(out, [halted]);
+    }
+}
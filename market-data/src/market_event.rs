@@ -1,70 +1,37 @@
 use serde::{Deserialize, Serialize};
 
-#[derive(Debug, Serialize, Deserialize, Clone, Copy)]
-pub struct L1 {
-    pub best_bid: u32,
-    pub best_ask: u32,
-    pub last_price: u32,
-}
+// Prices and quantities are decimal strings in the instrument's scale
 
-impl L1 {
-    pub fn default() -> L1 {
-        L1 {
-            best_bid: 0,
-            best_ask: 0,
-            last_price: 0,
-        }
-    }
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct L1 {
+    pub best_bid: String,
+    pub best_ask: String,
+    pub last_price: String,
 }
 
-#[derive(Debug, Serialize, Deserialize, Clone, Copy)]
+#[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct L2Level {
-    pub px: u32,
-    pub qty: u32,
-}
-
-impl L2Level {
-    pub fn default() -> L2Level {
-        L2Level { px: 0, qty: 0 }
-    }
+    pub px: String,
+    pub qty: String,
 }
 
-#[derive(Debug, Serialize, Deserialize, Clone, Copy)]
+#[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct L2 {
-    pub bids: [L2Level; 10],
-    pub asks: [L2Level; 10],
+    pub bids: Vec<L2Level>,
+    pub asks: Vec<L2Level>,
 }
 
-impl L2 {
-    pub fn default() -> L2 {
-        L2 {
-            bids: [L2Level::default(); 10],
-            asks: [L2Level::default(); 10],
-        }
-    }
-}
-
-#[derive(Debug, Serialize, Deserialize, Clone, Copy)]
+#[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct Trade {
-    pub px: u32,
-    pub qty: u32,
+    pub px: String,
+    pub qty: String,
     pub ts: u64,
 }
 
-impl Trade {
-    pub fn default() -> Trade {
-        Trade {
-            px: 0,
-            qty: 0,
-            ts: 0,
-        }
-    }
-}
-
-#[derive(Debug, Serialize, Deserialize, Clone, Copy)]
+#[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct MarketEvent {
     pub l1: L1,
     pub l2: L2, // full snapshot or diff
-    pub last_px: u32,
-    pub trades: [Trade; 10],
+    pub last_px: String,
+    pub trades: Vec<Trade>,
 }
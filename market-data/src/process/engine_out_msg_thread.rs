@@ -1,15 +1,26 @@
-use common::network::mutlicast::multicast_receiver;
+use common::config::app_config::ThreadConfig;
+use common::network::mutlicast::multicast_receiver_with_busy_poll;
 use common::transport::nack_sequenced_multicast_receiver::NackSequencedMulticastReceiver;
 use common::transport::sequenced_message::SequencedEngineMessage;
+use common::util::thread_affinity::pin_current_thread;
 use std::error::Error;
+use std::sync::mpsc::Sender;
 use tokio::sync::mpsc::UnboundedSender;
 
 pub fn initialize_engine_msg_out_receiver(
     engine_msg_out_port: u16,
+    nack_port: u16,
+    ring_len: usize,
+    thread_config: ThreadConfig,
     tx: UnboundedSender<SequencedEngineMessage>,
+    tx_feed: Sender<SequencedEngineMessage>,
 ) -> Result<(), Box<dyn Error>> {
-    let udp_socket = multicast_receiver(engine_msg_out_port);
-    let mut multicast_receiver = NackSequencedMulticastReceiver::new(udp_socket, 9001);
+    pin_current_thread("msg_out", thread_config.core);
+    let udp_socket =
+        multicast_receiver_with_busy_poll(engine_msg_out_port, thread_config.busy_poll_micros);
+    let mut idler = thread_config.idle.idler();
+    let mut multicast_receiver =
+        NackSequencedMulticastReceiver::new(udp_socket, nack_port, ring_len);
 
     println!(
         "Initialized MSG_OUT -> MDD multicast on port {}",
@@ -18,10 +29,20 @@ pub fn initialize_engine_msg_out_receiver(
 
     loop {
         if let Some(outbound_engine_message) = multicast_receiver.try_recv() {
+            idler.reset();
+            if tx_feed.send(outbound_engine_message.clone()).is_err() {
+                eprintln!("feed channel closed");
+                break;
+            }
             if tx.send(outbound_engine_message).is_err() {
                 eprintln!("mpsc channel closed");
                 break;
             }
+        } else {
+            while let Some(event) = multicast_receiver.poll_event() {
+                eprintln!("Engine stream: {}", event);
+            }
+            idler.idle();
         }
     }
 
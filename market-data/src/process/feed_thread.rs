@@ -0,0 +1,72 @@
+use crate::market_data_feed::MarketDataFeed;
+use common::config::app_config::{BatchingConfig, ThreadConfig};
+use common::network::mutlicast::multicast_sender;
+use common::transport::nack_sequenced_multicast_sender::NackSequencedMulticastSender;
+use common::transport::sequenced_message::{EngineMessage, SequencedEngineMessage};
+use common::transport::transport_constants::SOLE_SENDER_ID;
+use common::util::thread_affinity::pin_current_thread;
+use std::net::{Ipv4Addr, SocketAddr, SocketAddrV4};
+use std::sync::mpsc::{Receiver, TryRecvError};
+use std::thread;
+use std::thread::JoinHandle;
+
+pub fn feed_thread(
+    feed_port: u16,
+    nack_port: u16,
+    instrument_id: u32,
+    ring_len: usize,
+    batching: BatchingConfig,
+    engine_msg_out_rx: Receiver<SequencedEngineMessage>,
+    thread_config: ThreadConfig,
+) -> JoinHandle<()> {
+    thread::spawn(move || {
+        // feed blocks on its channel so only placement applies
+        pin_current_thread("feed", thread_config.core);
+        let send_addr = SocketAddr::V4(SocketAddrV4::new(Ipv4Addr::new(239, 255, 0, 1), feed_port));
+        let mut multicast_sender = NackSequencedMulticastSender::new(
+            multicast_sender(),
+            send_addr,
+            nack_port,
+            SOLE_SENDER_ID,
+            batching,
+            ring_len,
+        );
+
+        let mut feed = MarketDataFeed::new(instrument_id);
+        let mut feed_messages = Vec::new();
+        let mut engine_epoch = 0;
+
+        println!(
+            "Publishing market data feed on multicast port {}",
+            feed_port
+        );
+
+        loop {
+            let engine_message = match engine_msg_out_rx.try_recv() {
+                Ok(engine_message) => engine_message,
+                Err(TryRecvError::Empty) => {
+                    // nothing queued behind the partial batch, don't hold it back
+                    multicast_sender.flush();
+                    match engine_msg_out_rx.recv() {
+                        Ok(engine_message) => engine_message,
+                        Err(_) => break,
+                    }
+                }
+                Err(TryRecvError::Disconnected) => break,
+            };
+
+            if engine_message.epoch != engine_epoch {
+                if engine_epoch != 0 {
+                    feed.on_engine_restart(engine_message.sent_time, &mut feed_messages);
+                }
+                engine_epoch = engine_message.epoch;
+            }
+            feed.on_engine_message(&engine_message.message, &mut feed_messages);
+            for feed_message in feed_messages.drain(..) {
+                multicast_sender.send(EngineMessage::MarketData(feed_message));
+            }
+        }
+
+        multicast_sender.flush();
+    })
+}
@@ -1,2 +1,3 @@
 pub(crate) mod engine_out_msg_thread;
+pub(crate) mod feed_thread;
 mod mdd_process;
@@ -1,12 +1,50 @@
-use common::transport::sequenced_message::EngineMessage;
+use crate::drop_copy::drop_copy_log::DropCopyLog;
+use crate::risk::risk_checker::RiskChecker;
+use crate::session::user_store::UserStore;
+use common::transport::sequenced_message::{EngineMessage, SenderId};
+use common::util::throttle::Throttle;
+use common::util::time::system_nanos;
+use std::sync::atomic::{AtomicU32, AtomicU64, Ordering};
+use std::sync::Arc;
 use tokio::sync::mpsc;
 
 pub struct AppState {
     pub tx_oe_queue: mpsc::Sender<EngineMessage>,
+    /// Gateways share the engine's admin responses, so request ids carry the sender id in their
+    /// top byte.
+    sender_id: SenderId,
+    next_admin_request_id: AtomicU32,
+    /// Engine side ids of OUCH orders, seeded from the clock so they don't repeat across restarts.
+    pub next_client_order_id: AtomicU64,
+    pub risk: Arc<RiskChecker>,
+    pub throttle: Arc<Throttle>,
+    pub users: UserStore,
+    pub drop_copies: Arc<DropCopyLog>,
 }
 
 impl AppState {
-    pub fn new(tx_oe_queue: mpsc::Sender<EngineMessage>) -> AppState {
-        AppState { tx_oe_queue }
+    pub fn new(
+        tx_oe_queue: mpsc::Sender<EngineMessage>,
+        sender_id: SenderId,
+        risk: Arc<RiskChecker>,
+        throttle: Arc<Throttle>,
+        users: UserStore,
+        drop_copies: Arc<DropCopyLog>,
+    ) -> AppState {
+        AppState {
+            tx_oe_queue,
+            sender_id,
+            next_admin_request_id: AtomicU32::new(1),
+            next_client_order_id: AtomicU64::new(system_nanos()),
+            risk,
+            throttle,
+            users,
+            drop_copies,
+        }
+    }
+
+    pub fn next_admin_request_id(&self) -> u32 {
+        let sequence = self.next_admin_request_id.fetch_add(1, Ordering::Relaxed);
+        ((self.sender_id as u32) << 24) | (sequence & 0x00ff_ffff)
     }
 }
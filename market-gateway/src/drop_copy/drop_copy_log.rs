@@ -0,0 +1,381 @@
+use crate::session::session_registry::{owners, SessionRegistry};
+use common::drop_copy::drop_copy_codec::encode_copy;
+use common::drop_copy::drop_copy_message::DropCopy;
+use common::transport::sequenced_message::EngineMessage;
+use common::util::time::system_nanos;
+use std::collections::{HashMap, VecDeque};
+use std::sync::{Arc, Mutex};
+use tokio::sync::watch;
+
+/// Every firm's copies of its clients' acks, cancels, rejects and executions, numbered per firm
+/// from 1 and kept for replay.
+pub struct DropCopyLog {
+    sessions: Arc<SessionRegistry>,
+    retained: usize,
+    firms: Mutex<HashMap<String, Arc<FirmLog>>>,
+}
+
+pub struct FirmLog {
+    entries: Mutex<FirmEntries>,
+    /// The sequence number of the latest copy.
+    tx_appended: watch::Sender<u64>,
+}
+
+struct FirmEntries {
+    next_sequence: u64,
+    last_timestamp: u64,
+    /// Timestamp and encoded copy, oldest first.
+    retained: VecDeque<(u64, Vec<u8>)>,
+    capacity: usize,
+}
+
+impl DropCopyLog {
+    pub fn new(sessions: Arc<SessionRegistry>, retained: usize) -> DropCopyLog {
+        DropCopyLog {
+            sessions,
+            retained,
+            firms: Mutex::new(HashMap::new()),
+        }
+    }
+
+    pub fn firm(&self, firm: &str) -> Arc<FirmLog> {
+        let mut firms = self.firms.lock().unwrap();
+        if let Some(log) = firms.get(firm) {
+            return log.clone();
+        }
+
+        let log = Arc::new(FirmLog::new(self.retained));
+        firms.insert(firm.to_owned(), log.clone());
+        log
+    }
+
+    /// Logs a copy for the firm of each client the message reports on. On an execution between
+    /// two firms each firm's copy has the other side's client ids blanked.
+    pub fn record(&self, message: &EngineMessage) {
+        let Some(owners) = owners(message) else {
+            return;
+        };
+        let firms = owners.map(|client_id| match client_id {
+            0 => None,
+            client_id => self.sessions.firm_of(client_id),
+        });
+
+        if firms[0] == firms[1] {
+            if let Some(firm) = &firms[0] {
+                self.firm(firm).append(message.clone());
+            }
+            return;
+        }
+        for (side, firm) in firms.iter().enumerate() {
+            if let Some(firm) = firm {
+                self.firm(firm).append(own_side(message, side));
+            }
+        }
+    }
+}
+
+impl FirmLog {
+    fn new(capacity: usize) -> FirmLog {
+        FirmLog {
+            entries: Mutex::new(FirmEntries {
+                next_sequence: 1,
+                last_timestamp: 0,
+                retained: VecDeque::with_capacity(capacity),
+                capacity,
+            }),
+            tx_appended: watch::channel(0).0,
+        }
+    }
+
+    fn append(&self, message: EngineMessage) {
+        let mut entries = self.entries.lock().unwrap();
+        let sequence = entries.next_sequence;
+        // kept in order so a replay from a time can search for it
+        let timestamp = system_nanos().max(entries.last_timestamp);
+        let mut buffer = if entries.retained.len() == entries.capacity {
+            entries
+                .retained
+                .pop_front()
+                .map(|(_, buffer)| buffer)
+                .unwrap_or_default()
+        } else {
+            Vec::new()
+        };
+        buffer.clear();
+        encode_copy(
+            &DropCopy {
+                sequence,
+                timestamp,
+                message,
+            },
+            &mut buffer,
+        );
+        entries.retained.push_back((timestamp, buffer));
+        entries.next_sequence += 1;
+        entries.last_timestamp = timestamp;
+        drop(entries);
+
+        self.tx_appended.send_replace(sequence);
+    }
+
+    /// Changes whenever a copy is appended.
+    pub fn subscribe(&self) -> watch::Receiver<u64> {
+        self.tx_appended.subscribe()
+    }
+
+    /// Where a session asking for `requested` carries on, as for a gateway session's
+    /// retransmission store.
+    pub fn resume_from(&self, requested: u64) -> u64 {
+        let entries = self.entries.lock().unwrap();
+        if requested == 0 || requested > entries.next_sequence {
+            return entries.next_sequence;
+        }
+        requested.max(entries.first_retained())
+    }
+
+    /// The first retained copy logged at or after `timestamp`, the next one if there is none.
+    pub fn sequence_at(&self, timestamp: u64) -> u64 {
+        let entries = self.entries.lock().unwrap();
+        let index = entries
+            .retained
+            .partition_point(|(logged, _)| *logged < timestamp);
+        entries.first_retained() + index as u64
+    }
+
+    /// Appends up to `max` encoded copies from `sequence` on to `out`. False if `sequence` is no
+    /// longer retained.
+    pub fn read(&self, sequence: u64, max: usize, out: &mut Vec<Vec<u8>>) -> bool {
+        let entries = self.entries.lock().unwrap();
+        let first = entries.first_retained();
+        if sequence < first {
+            return false;
+        }
+
+        let skip = (sequence - first) as usize;
+        out.extend(
+            entries
+                .retained
+                .iter()
+                .skip(skip)
+                .take(max)
+                .map(|(_, copy)| copy.clone()),
+        );
+        true
+    }
+}
+
+impl FirmEntries {
+    fn first_retained(&self) -> u64 {
+        self.next_sequence - self.retained.len() as u64
+    }
+}
+
+/// An execution as seen by the firm of one side, 0 for the bid and 1 for the ask.
+fn own_side(message: &EngineMessage, side: usize) -> EngineMessage {
+    let mut message = message.clone();
+    if let EngineMessage::TradeExecution(execution) = &mut message {
+        if side == 0 {
+            execution.ask_client_id = 0;
+            execution.ask_client_order_id = 0;
+        } else {
+            execution.bid_client_id = 0;
+            execution.bid_client_order_id = 0;
+        }
+    }
+    message
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::session::user_store::SessionIdentity;
+    use common::config::app_config::{ClientIdRange, GatewayUser};
+    use common::drop_copy::drop_copy_codec::decode_copy;
+    use common::types::execution_report::{ExecType, ExecutionReport, FillType};
+    use common::types::latency_trace::LatencyTrace;
+    use common::types::order::{NewOrderAck, TimeInForce};
+    use common::types::side::Side;
+
+    fn user(username: &str, firm: &str, client_id: u32, trusted: bool) -> GatewayUser {
+        GatewayUser {
+            username: username.to_owned(),
+            password: "secret".to_owned(),
+            firm: firm.to_owned(),
+            client_id,
+            trusted,
+            client_ids: trusted.then_some(ClientIdRange {
+                first: 1000,
+                last: 1999,
+            }),
+            drop_copy: false,
+        }
+    }
+
+    /// Two ACME traders, one of them an order router, and a BETA trader.
+    fn log(retained: usize) -> (DropCopyLog, Arc<SessionRegistry>, Vec<GatewayUser>) {
+        let users = vec![
+            user("acme1", "ACME", 11, false),
+            user("router", "ACME", 900, true),
+            user("beta1", "BETA", 21, false),
+        ];
+        let sessions = Arc::new(SessionRegistry::new(&users));
+        (
+            DropCopyLog::new(sessions.clone(), retained),
+            sessions,
+            users,
+        )
+    }
+
+    fn ack(client_id: u32) -> EngineMessage {
+        EngineMessage::NewOrderAck(NewOrderAck {
+            client_id,
+            client_order_id: 1,
+            order_id: 1,
+            instrument: [0; 16],
+            side: Side::Buy,
+            px: 100,
+            qty: 1,
+            qty_rem: 1,
+            time_in_force: TimeInForce::GTC,
+            ack_time: 0,
+            trace: LatencyTrace::default(),
+        })
+    }
+
+    fn execution(bid_client_id: u32, ask_client_id: u32) -> EngineMessage {
+        EngineMessage::TradeExecution(ExecutionReport {
+            trade_id: 1,
+            bid_client_id,
+            bid_order_id: 1,
+            bid_client_order_id: 5,
+            bid_order_px: 100,
+            bid_fill_type: FillType::FullFill,
+            ask_client_id,
+            ask_order_id: 2,
+            ask_client_order_id: 6,
+            ask_order_px: 100,
+            ask_fill_type: FillType::FullFill,
+            instrument: [0; 16],
+            exec_px: 100,
+            exec_qty: 1,
+            exec_type: ExecType::MatchEvent,
+            exec_ns: 0,
+            trace: LatencyTrace::default(),
+        })
+    }
+
+    /// Every copy the firm has retained.
+    fn copies(log: &DropCopyLog, firm: &str) -> Vec<DropCopy> {
+        let log = log.firm(firm);
+        let mut encoded = Vec::new();
+        assert!(log.read(log.resume_from(1), usize::MAX, &mut encoded));
+        encoded
+            .iter()
+            .map(|copy| decode_copy(copy).unwrap())
+            .collect()
+    }
+
+    #[test]
+    fn copies_go_to_the_firm_of_the_client() {
+        let (log, _, _) = log(10);
+
+        log.record(&ack(11));
+        log.record(&ack(21));
+        log.record(&ack(11));
+        // not a configured client or one entered through a trusted session
+        log.record(&ack(77));
+
+        let acme = copies(&log, "ACME");
+        assert_eq!(acme.len(), 2);
+        assert_eq!(acme[0].sequence, 1);
+        assert_eq!(acme[1].sequence, 2);
+        assert!(matches!(&acme[0].message, EngineMessage::NewOrderAck(a) if a.client_id == 11));
+        let beta = copies(&log, "BETA");
+        assert_eq!(beta.len(), 1);
+        assert!(matches!(&beta[0].message, EngineMessage::NewOrderAck(a) if a.client_id == 21));
+    }
+
+    #[test]
+    fn clients_of_a_trusted_session_belong_to_its_firm() {
+        let (log, sessions, users) = log(10);
+        let identity = SessionIdentity {
+            username: users[1].username.clone(),
+            firm: users[1].firm.clone(),
+            client_id: users[1].client_id,
+            trusted: true,
+            client_ids: users[1].client_ids,
+            drop_copy: false,
+        };
+        let (route, _rx) = sessions.register(&identity);
+        assert!(route.add_client(1001));
+
+        log.record(&ack(1001));
+
+        assert_eq!(copies(&log, "ACME").len(), 1);
+        assert!(copies(&log, "BETA").is_empty());
+    }
+
+    #[test]
+    fn each_firm_only_sees_its_own_side_of_an_execution() {
+        let (log, _, _) = log(10);
+
+        log.record(&execution(11, 21));
+
+        let acme = copies(&log, "ACME");
+        let beta = copies(&log, "BETA");
+        assert_eq!((acme.len(), beta.len()), (1, 1));
+        let EngineMessage::TradeExecution(bid) = &acme[0].message else {
+            panic!("{:?}", acme[0].message);
+        };
+        assert_eq!((bid.bid_client_id, bid.bid_client_order_id), (11, 5));
+        assert_eq!((bid.ask_client_id, bid.ask_client_order_id), (0, 0));
+        let EngineMessage::TradeExecution(ask) = &beta[0].message else {
+            panic!("{:?}", beta[0].message);
+        };
+        assert_eq!((ask.bid_client_id, ask.bid_client_order_id), (0, 0));
+        assert_eq!((ask.ask_client_id, ask.ask_client_order_id), (21, 6));
+    }
+
+    #[test]
+    fn an_execution_within_a_firm_is_copied_once_with_both_sides() {
+        let (log, _, _) = log(10);
+
+        log.record(&execution(11, 900));
+
+        let acme = copies(&log, "ACME");
+        assert_eq!(acme.len(), 1);
+        let EngineMessage::TradeExecution(execution) = &acme[0].message else {
+            panic!("{:?}", acme[0].message);
+        };
+        assert_eq!(
+            (execution.bid_client_id, execution.ask_client_id),
+            (11, 900)
+        );
+    }
+
+    #[test]
+    fn replay_from_a_sequence_or_a_time() {
+        let (log, _, _) = log(3);
+        for _ in 0..5 {
+            log.record(&ack(11));
+        }
+        let firm = log.firm("ACME");
+        let retained = copies(&log, "ACME");
+
+        // 1 and 2 were dropped to keep 3
+        let mut out = Vec::new();
+        assert!(!firm.read(2, usize::MAX, &mut out));
+        assert_eq!(firm.resume_from(1), 3);
+        assert_eq!(firm.resume_from(4), 4);
+        assert_eq!(firm.resume_from(0), 6);
+        assert!(firm.read(4, 1, &mut out));
+        assert_eq!(decode_copy(&out[0]).unwrap().sequence, 4);
+        assert_eq!(out.len(), 1);
+
+        assert_eq!(firm.sequence_at(0), 3);
+        // the first copy logged at that time, copies logged within a nanosecond share it
+        let at = firm.sequence_at(retained[2].timestamp);
+        assert!(at <= 5 && retained[at as usize - 3].timestamp == retained[2].timestamp);
+        assert_eq!(firm.sequence_at(u64::MAX), 6);
+    }
+}
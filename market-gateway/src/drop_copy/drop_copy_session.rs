@@ -0,0 +1,125 @@
+use crate::drop_copy::drop_copy_log::DropCopyLog;
+use crate::session::gateway_session::{read_login, read_session, write_packet, SessionWriter};
+use crate::session::user_store::credentials_match;
+use common::config::app_config::DropCopyUser;
+use common::drop_copy::drop_copy_codec::decode_request;
+use common::drop_copy::drop_copy_message::DropCopyRequest;
+use common::session::session_message::{
+    credential, LoginAccepted, LoginRejectReason, LoginRequest, ServerPacket, CREDENTIAL_LEN,
+    HEARTBEAT_INTERVAL,
+};
+use std::collections::HashMap;
+use std::error::Error;
+use std::net::SocketAddr;
+use std::sync::Arc;
+use tokio::net::TcpStream;
+use tokio::sync::mpsc;
+use tokio::time::interval;
+
+/// Copies sent before the session checks for requests and new copies again.
+const REPLAY_BATCH: usize = 1_000;
+
+pub struct DropCopyUsers {
+    users: HashMap<[u8; CREDENTIAL_LEN], DropCopyUser>,
+}
+
+impl DropCopyUsers {
+    pub fn new(users: &[DropCopyUser]) -> DropCopyUsers {
+        DropCopyUsers {
+            users: users
+                .iter()
+                .map(|user| (credential(&user.username), user.clone()))
+                .collect(),
+        }
+    }
+
+    fn authenticate(&self, login: &LoginRequest) -> Option<&DropCopyUser> {
+        self.users
+            .get(&login.username)
+            .filter(|user| credentials_match(&credential(&user.password), &login.password))
+    }
+}
+
+/// Streams the copies logged for the user's firm from the sequence asked for at logon, and from
+/// wherever a later replay request points it. A user may have any number of sessions, none of
+/// them keeps state once it ends.
+pub async fn drop_copy_session(
+    mut socket: TcpStream,
+    addr: SocketAddr,
+    users: Arc<DropCopyUsers>,
+    log: Arc<DropCopyLog>,
+) -> Result<(), Box<dyn Error>> {
+    let login = read_login(&mut socket).await?;
+
+    let mut scratch = Vec::new();
+    let Some(user) = users.authenticate(&login) else {
+        let reply = ServerPacket::LoginRejected(LoginRejectReason::NotAuthorized);
+        write_packet(&mut socket, &reply, &mut scratch).await?;
+        println!("Drop copy session {} logon refused", addr);
+        return Ok(());
+    };
+
+    let firm_log = log.firm(&user.firm);
+    let mut rx_appended = firm_log.subscribe();
+    let mut sequence = firm_log.resume_from(login.sequence);
+    let accepted = LoginAccepted {
+        client_id: 0,
+        sequence,
+    };
+    write_packet(
+        &mut socket,
+        &ServerPacket::LoginAccepted(accepted),
+        &mut scratch,
+    )
+    .await?;
+    println!(
+        "Drop copy session {} logged on as {} ({}) at sequence {}",
+        addr, user.username, user.firm, sequence
+    );
+
+    let (rx_client, tx_client) = socket.into_split();
+    let (tx_requests, mut rx_requests) = mpsc::channel::<DropCopyRequest>(16);
+    tokio::spawn(read_session(rx_client, addr, decode_request, tx_requests));
+
+    let mut writer = SessionWriter::without_store(tx_client);
+    let mut heartbeat = interval(HEARTBEAT_INTERVAL);
+    let mut batch = Vec::with_capacity(REPLAY_BATCH);
+    loop {
+        rx_appended.borrow_and_update();
+        if !firm_log.read(sequence, REPLAY_BATCH, &mut batch) {
+            println!(
+                "Ending drop copy session {}, copies from {} are no longer retained",
+                addr, sequence
+            );
+            writer.end_session().await?;
+            return Ok(());
+        }
+
+        let sent = batch.len();
+        for copy in batch.drain(..) {
+            writer.send_data(|out| out.extend_from_slice(&copy)).await?;
+        }
+        sequence += sent as u64;
+        if sent == REPLAY_BATCH {
+            continue;
+        }
+
+        tokio::select! {
+            request = rx_requests.recv() => match request {
+                Some(DropCopyRequest::ReplayFromSequence(requested)) => {
+                    sequence = firm_log.resume_from(requested);
+                }
+                Some(DropCopyRequest::ReplayFromTime(timestamp)) => {
+                    sequence = firm_log.sequence_at(timestamp);
+                }
+                None => return Ok(()),
+            },
+            changed = rx_appended.changed() => {
+                if changed.is_err() {
+                    return Ok(());
+                }
+            }
+            _ = heartbeat.tick() => writer.heartbeat().await?,
+        }
+    }
+}
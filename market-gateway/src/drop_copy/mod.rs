@@ -0,0 +1,2 @@
+pub mod drop_copy_log;
+pub mod drop_copy_session;
@@ -1,16 +1,33 @@
 mod app_state;
+mod drop_copy;
 mod message;
+mod ouch;
 mod process;
+mod risk;
+mod session;
 
 use crate::app_state::AppState;
 
+use crate::drop_copy::drop_copy_log::DropCopyLog;
+use crate::drop_copy::drop_copy_session::{drop_copy_session, DropCopyUsers};
+
+use crate::ouch::ouch_connection::ouch_connection;
+use crate::process::admin_session::admin_session_handler;
+use crate::process::client_session::client_session;
 use crate::process::engine_msg_in_thread::msg_in_thread;
 use crate::process::engine_msg_out_thread::msg_out_thread;
-use crate::process::gateway_to_oe_api::gateway_to_oe_api_handler;
-use crate::process::oe_api_to_gateway::oe_api_to_gateway_handler;
+use crate::risk::position_poller::position_poller_task;
+use crate::risk::risk_checker::RiskChecker;
+use crate::session::session_registry::SessionRegistry;
+use crate::session::user_store::UserStore;
+use common::config::config_loader::load_config_or_exit;
+use common::metrics::metrics_registry::MetricsRegistry;
+use common::metrics::metrics_server::spawn_metrics_server;
 use common::transport::sequenced_message::EngineMessage;
-use core_affinity::CoreId;
+use common::util::throttle::Throttle;
+use std::env;
 use std::error::Error;
+use std::net::{Ipv4Addr, SocketAddr, SocketAddrV4};
 use std::sync::Arc;
 use tokio::net::TcpListener;
 use tokio::sync::{broadcast, mpsc};
@@ -19,44 +36,212 @@ use tokio::sync::{broadcast, mpsc};
 async fn main() -> Result<(), Box<dyn Error>> {
     println!("--- Initializing Gateway ---");
 
-    let listener = TcpListener::bind("0.0.0.0:3001").await?;
-    println!("Gateway listening on 3001");
+    let config = load_config_or_exit();
+    let gateway_config = &config.gateway;
+
+    let listener = TcpListener::bind(("0.0.0.0", gateway_config.port)).await?;
+    println!("Gateway listening on {}", gateway_config.port);
 
     let (tx_gw_queue, rx_gw_queue) = mpsc::channel::<EngineMessage>(1_000_000);
     let (tx_engine_queue, _) = broadcast::channel::<EngineMessage>(1_000_000);
 
-    let state = Arc::new(AppState::new(tx_gw_queue));
+    let metrics_registry = Arc::new(MetricsRegistry::new());
+    let throttle = Arc::new(Throttle::new(
+        gateway_config.throttle.clone(),
+        &metrics_registry,
+        "gateway",
+    ));
+    spawn_metrics_server(
+        SocketAddr::V4(SocketAddrV4::new(
+            Ipv4Addr::LOCALHOST,
+            gateway_config.metrics_port,
+        )),
+        metrics_registry,
+    );
+
+    let risk = Arc::new(RiskChecker::new(gateway_config.risk));
+    let sessions = Arc::new(SessionRegistry::new(&gateway_config.users));
+    let users = UserStore::new(
+        &gateway_config.users,
+        sessions.clone(),
+        gateway_config.retransmit_len,
+    );
+    let drop_copies = Arc::new(DropCopyLog::new(
+        sessions.clone(),
+        gateway_config.drop_copy.retained,
+    ));
+    let state = Arc::new(AppState::new(
+        tx_gw_queue,
+        gateway_config.sender_id,
+        risk.clone(),
+        throttle,
+        users,
+        drop_copies.clone(),
+    ));
+    tokio::spawn(session_router_task(
+        tx_engine_queue.subscribe(),
+        sessions,
+        drop_copies.clone(),
+    ));
+
+    msg_in_thread(
+        gateway_config.msg_in_port,
+        config.transport.msg_in_nack_port,
+        gateway_config.sender_id,
+        config.transport.retransmit_ring_len,
+        config.transport.batching,
+        gateway_config.threads.msg_in.clone(),
+        rx_gw_queue,
+    );
+    msg_out_thread(
+        gateway_config.msg_out_port,
+        config.transport.msg_out_nack_port,
+        config.transport.receive_ring_len,
+        gateway_config.threads.msg_out.clone(),
+        risk.clone(),
+        tx_engine_queue.clone(),
+    );
+
+    match &gateway_config.position_keeper_url {
+        Some(position_keeper_url) => {
+            tokio::spawn(position_poller_task(
+                position_keeper_url.clone(),
+                gateway_config.position_poll_millis,
+                risk,
+            ));
+        }
+        None => println!("position_keeper_url not set, position limit disabled"),
+    }
 
-    let core_ids = core_affinity::get_core_ids()
-        .unwrap()
-        .into_iter()
-        .collect::<Vec<_>>();
+    let ouch_listener = TcpListener::bind(("0.0.0.0", gateway_config.ouch_port)).await?;
+    println!("Gateway OUCH listening on {}", gateway_config.ouch_port);
+    tokio::spawn(ouch_listener_task(ouch_listener, state.clone()));
 
-    let pinned_match_core = core_ids[0];
-    let pinned_msg_in_core = core_ids[1];
-    let pinned_msg_out_core = core_ids[2];
+    let drop_copy_listener = TcpListener::bind(("0.0.0.0", gateway_config.drop_copy.port)).await?;
+    println!(
+        "Gateway drop copy listening on {}",
+        gateway_config.drop_copy.port
+    );
+    tokio::spawn(drop_copy_listener_task(
+        drop_copy_listener,
+        Arc::new(DropCopyUsers::new(&gateway_config.drop_copy.users)),
+        drop_copies,
+    ));
 
-    msg_in_thread(3000, pinned_msg_in_core, rx_gw_queue);
-    msg_out_thread(3500, pinned_msg_out_core, tx_engine_queue.clone());
+    match env::var("ME_ADMIN_TOKEN") {
+        Ok(admin_token) => {
+            let admin_listener =
+                TcpListener::bind(("127.0.0.1", gateway_config.admin_port)).await?;
+            println!("Gateway admin listening on {}", gateway_config.admin_port);
+            tokio::spawn(admin_listener_task(
+                admin_listener,
+                admin_token,
+                state.clone(),
+                tx_engine_queue.clone(),
+            ));
+        }
+        Err(_) => println!("ME_ADMIN_TOKEN not set, admin port disabled"),
+    }
 
     loop {
         let (socket, addr) = listener.accept().await?;
-        let state = state.clone();
-
-        let (rx_oe_api, tx_oe_api) = socket.into_split();
 
+        let session = client_session(socket, addr, state.clone());
         tokio::spawn(async move {
-            if let Err(e) = oe_api_to_gateway_handler(rx_oe_api, state).await {
+            if let Err(e) = session.await {
                 eprintln!("Connection {} error: {}", addr, e);
             }
         });
+    }
+}
+
+/// Hands each engine message to the sessions it belongs to and logs the drop copies of it.
+async fn session_router_task(
+    mut rx_engine_queue: broadcast::Receiver<EngineMessage>,
+    sessions: Arc<SessionRegistry>,
+    drop_copies: Arc<DropCopyLog>,
+) {
+    loop {
+        match rx_engine_queue.recv().await {
+            Ok(message) => {
+                sessions.dispatch(&message);
+                drop_copies.record(&message);
+            }
+            Err(broadcast::error::RecvError::Lagged(skipped)) => {
+                eprintln!("Session router lagged, {} engine messages lost", skipped);
+            }
+            Err(broadcast::error::RecvError::Closed) => return,
+        }
+    }
+}
+
+async fn ouch_listener_task(ouch_listener: TcpListener, state: Arc<AppState>) {
+    loop {
+        let (socket, addr) = match ouch_listener.accept().await {
+            Ok(accepted) => accepted,
+            Err(e) => {
+                eprintln!("OUCH accept error: {}", e);
+                continue;
+            }
+        };
+
+        let session = ouch_connection(socket, addr, state.clone());
+        tokio::spawn(async move {
+            if let Err(e) = session.await {
+                eprintln!("OUCH session {} error: {}", addr, e);
+            }
+        });
+    }
+}
 
-        let tx_engine_queue_task = tx_engine_queue.clone();
+async fn drop_copy_listener_task(
+    drop_copy_listener: TcpListener,
+    users: Arc<DropCopyUsers>,
+    log: Arc<DropCopyLog>,
+) {
+    loop {
+        let (socket, addr) = match drop_copy_listener.accept().await {
+            Ok(accepted) => accepted,
+            Err(e) => {
+                eprintln!("Drop copy accept error: {}", e);
+                continue;
+            }
+        };
+
+        let session = drop_copy_session(socket, addr, users.clone(), log.clone());
         tokio::spawn(async move {
-            if let Err(e) =
-                gateway_to_oe_api_handler(tx_oe_api, tx_engine_queue_task.subscribe()).await
-            {
-                eprintln!("Connection {} error: {}", addr, e);
+            if let Err(e) = session.await {
+                eprintln!("Drop copy session {} error: {}", addr, e);
+            }
+        });
+    }
+}
+
+async fn admin_listener_task(
+    admin_listener: TcpListener,
+    admin_token: String,
+    state: Arc<AppState>,
+    tx_engine_queue: broadcast::Sender<EngineMessage>,
+) {
+    loop {
+        let (socket, addr) = match admin_listener.accept().await {
+            Ok(accepted) => accepted,
+            Err(e) => {
+                eprintln!("Admin accept error: {}", e);
+                continue;
+            }
+        };
+
+        let session = admin_session_handler(
+            socket,
+            addr,
+            admin_token.clone(),
+            state.clone(),
+            tx_engine_queue.clone(),
+        );
+        tokio::spawn(async move {
+            if let Err(e) = session.await {
+                eprintln!("Admin session {} error: {}", addr, e);
             }
         });
     }
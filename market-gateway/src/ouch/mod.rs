@@ -0,0 +1,2 @@
+pub mod ouch_connection;
+pub mod ouch_session;
@@ -0,0 +1,82 @@
+use crate::app_state::AppState;
+use crate::ouch::ouch_session::OuchSession;
+use crate::process::order_admission::admit;
+use crate::session::gateway_session::{accept_logon, read_session, SessionWriter};
+use crate::session::session_state::SessionKind;
+use common::ouch::ouch_codec::{decode_inbound, encode_outbound};
+use common::ouch::ouch_message::OuchInbound;
+use common::session::session_message::HEARTBEAT_INTERVAL;
+use std::error::Error;
+use std::net::SocketAddr;
+use std::sync::Arc;
+use tokio::net::TcpStream;
+use tokio::sync::mpsc;
+use tokio::time::interval;
+
+/// One OUCH client. Packets are read on their own task so a partly read frame is never lost to
+/// the select below.
+pub async fn ouch_connection(
+    mut socket: TcpStream,
+    addr: SocketAddr,
+    state: Arc<AppState>,
+) -> Result<(), Box<dyn Error>> {
+    let Some(mut lease) = accept_logon(&mut socket, addr, &state.users, SessionKind::Ouch).await?
+    else {
+        return Ok(());
+    };
+    let identity = lease.identity.clone();
+
+    let (rx_client, tx_client) = socket.into_split();
+    let (tx_inbound, mut rx_inbound) = mpsc::channel::<OuchInbound>(1024);
+    tokio::spawn(read_session(rx_client, addr, decode_inbound, tx_inbound));
+
+    let user_session = lease.state();
+    let route = &user_session.route;
+    let rx_engine_queue = &mut user_session.rx_engine_queue;
+    let session = user_session
+        .ouch
+        .get_or_insert_with(|| OuchSession::new(identity.client_id));
+    let mut writer = SessionWriter::new(tx_client, &mut user_session.store);
+    let mut throttle = state.throttle.session();
+    let mut heartbeat = interval(HEARTBEAT_INTERVAL);
+    let mut outbound = Vec::new();
+
+    loop {
+        let submit = tokio::select! {
+            inbound = rx_inbound.recv() => match inbound {
+                Some(message) => {
+                    session.on_inbound(message, &state.next_client_order_id, &mut outbound)
+                }
+                None => break,
+            },
+            engine_message = rx_engine_queue.recv() => match engine_message {
+                Some(message) => session.on_engine_message(&message, &mut outbound),
+                None => {
+                    writer.end_session().await?;
+                    break;
+                }
+            },
+            _ = heartbeat.tick() => {
+                writer.heartbeat().await?;
+                continue;
+            }
+        };
+
+        if let Some(message) = submit {
+            if let Some(reject) = admit(&state, &identity, route, &mut throttle, message).await? {
+                session.on_engine_message(&reject, &mut outbound);
+            }
+        }
+
+        // every message is stored for replay, even once a write has failed
+        let mut sent = Ok(());
+        for message in outbound.drain(..) {
+            let result = writer.send_data(|out| encode_outbound(&message, out)).await;
+            sent = sent.and(result);
+        }
+        sent?;
+    }
+
+    println!("OUCH session {} ({}) closed", addr, identity.username);
+    Ok(())
+}
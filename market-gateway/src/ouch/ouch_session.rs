@@ -0,0 +1,486 @@
+use common::ouch::ouch_message::{
+    Accepted, CancelOrder, CancelRejected, Canceled, CanceledReason, EnterOrder, Executed,
+    Liquidity, OrderToken, OuchInbound, OuchOutbound, Rejected, RejectedReason, ReplaceOrder,
+    Replaced,
+};
+use common::transport::sequenced_message::EngineMessage;
+use common::types::cancel_order::{
+    CancelOrderRequest, CancelOrderStatus, CancelledOrderAck, Reason,
+};
+use common::types::execution_report::{ExecType, ExecutionReport};
+use common::types::latency_trace::LatencyTrace;
+use common::types::order::{OrderReject, OrderRequest, RejectReason, TimeInForce};
+use common::types::side::Side;
+use common::util::time::system_nanos;
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicU64, Ordering};
+
+/// A cancel or replace of an order waiting on the engine's cancel ack.
+enum Pending {
+    Cancel,
+    /// Token of the replacement, entered once the cancel is acked.
+    Replace(OrderToken),
+}
+
+struct OuchOrder {
+    /// The engine side id of the order, unique across every session of the gateway.
+    client_order_id: u64,
+    client_id: u32,
+    side: Side,
+    instrument: [u8; 16],
+    px: u64,
+    qty: u64,
+    time_in_force: TimeInForce,
+    leaves_qty: u64,
+    /// Set once Accepted (or Replaced) has been sent.
+    order_id: Option<u32>,
+    /// The order this one replaces and its open quantity when it was cancelled.
+    replaces: Option<(OrderToken, u64)>,
+    pending: Option<Pending>,
+}
+
+/// Translates between the OUCH messages of one user's sessions and engine messages. Orders are
+/// identified by the client's token and by an internal `client_order_id` in the engine, so
+/// tokens only have to be unique per user. The engine has no replace, a Replace
+/// cancels the order and enters the replacement once the cancel is acked.
+pub struct OuchSession {
+    /// The logged on client, every order of the session is entered under it.
+    client_id: u32,
+    orders: HashMap<OrderToken, OuchOrder>,
+    by_client_order_id: HashMap<u64, OrderToken>,
+    by_order_id: HashMap<u32, OrderToken>,
+}
+
+impl OuchSession {
+    pub fn new(client_id: u32) -> OuchSession {
+        OuchSession {
+            client_id,
+            orders: HashMap::new(),
+            by_client_order_id: HashMap::new(),
+            by_order_id: HashMap::new(),
+        }
+    }
+
+    /// Returns the engine message to submit for a client message, if any.
+    pub fn on_inbound(
+        &mut self,
+        message: OuchInbound,
+        client_order_ids: &AtomicU64,
+        out: &mut Vec<OuchOutbound>,
+    ) -> Option<EngineMessage> {
+        match message {
+            OuchInbound::EnterOrder(enter) => self.enter(enter, client_order_ids, out),
+            OuchInbound::ReplaceOrder(replace) => self.replace(replace, client_order_ids, out),
+            OuchInbound::CancelOrder(cancel) => self.cancel(cancel, out),
+        }
+    }
+
+    /// Translates engine output for this session's orders and ignores the rest. Returns the
+    /// replacement order to submit when a replace's cancel is acked.
+    pub fn on_engine_message(
+        &mut self,
+        message: &EngineMessage,
+        out: &mut Vec<OuchOutbound>,
+    ) -> Option<EngineMessage> {
+        match message {
+            EngineMessage::NewOrderAck(ack) => {
+                let token = self.token(ack.client_id, ack.client_order_id)?;
+                self.accept(token, ack.order_id, ack.ack_time, out);
+            }
+            EngineMessage::OrderReject(reject) => self.on_reject(reject, out),
+            EngineMessage::TradeExecution(execution) => match execution.exec_type {
+                ExecType::MatchEvent => {
+                    self.on_execution(execution, Side::Buy, out);
+                    self.on_execution(execution, Side::Sell, out);
+                }
+                ExecType::SelfMatchPrevented => self.on_self_match_prevented(execution, out),
+            },
+            EngineMessage::CancelOrderAck(ack) => return self.on_cancel_ack(ack, out),
+            _ => {}
+        }
+        None
+    }
+
+    fn enter(
+        &mut self,
+        enter: EnterOrder,
+        client_order_ids: &AtomicU64,
+        out: &mut Vec<OuchOutbound>,
+    ) -> Option<EngineMessage> {
+        if let Err(reason) = self.check_new(enter.token, enter.qty, enter.px) {
+            out.push(rejected(enter.token, reason));
+            return None;
+        }
+
+        let order = OuchOrder {
+            client_order_id: client_order_ids.fetch_add(1, Ordering::Relaxed),
+            client_id: self.client_id,
+            side: enter.side,
+            instrument: enter.instrument,
+            px: enter.px,
+            qty: enter.qty,
+            time_in_force: enter.time_in_force,
+            leaves_qty: enter.qty,
+            order_id: None,
+            replaces: None,
+            pending: None,
+        };
+        let new_order = new_order(&order);
+        self.insert(enter.token, order);
+        Some(new_order)
+    }
+
+    fn replace(
+        &mut self,
+        replace: ReplaceOrder,
+        client_order_ids: &AtomicU64,
+        out: &mut Vec<OuchOutbound>,
+    ) -> Option<EngineMessage> {
+        if let Err(reason) = self.check_new(replace.replacement_token, replace.qty, replace.px) {
+            out.push(rejected(replace.replacement_token, reason));
+            return None;
+        }
+
+        let Some(existing) = self.cancellable(replace.existing_token) else {
+            out.push(rejected(
+                replace.replacement_token,
+                RejectedReason::ReplaceRefused,
+            ));
+            return None;
+        };
+        existing.pending = Some(Pending::Replace(replace.replacement_token));
+        let cancel = cancel_order(existing);
+
+        // entered when the cancel is acked, inserted now to hold the token
+        let replacement = OuchOrder {
+            client_order_id: client_order_ids.fetch_add(1, Ordering::Relaxed),
+            client_id: existing.client_id,
+            side: existing.side,
+            instrument: existing.instrument,
+            px: replace.px,
+            qty: replace.qty,
+            time_in_force: existing.time_in_force,
+            leaves_qty: replace.qty,
+            order_id: None,
+            replaces: Some((replace.existing_token, 0)),
+            pending: None,
+        };
+        self.insert(replace.replacement_token, replacement);
+        Some(cancel)
+    }
+
+    fn cancel(
+        &mut self,
+        cancel: CancelOrder,
+        out: &mut Vec<OuchOutbound>,
+    ) -> Option<EngineMessage> {
+        let Some(order) = self.cancellable(cancel.token) else {
+            out.push(cancel_rejected(cancel.token));
+            return None;
+        };
+        order.pending = Some(Pending::Cancel);
+        Some(cancel_order(order))
+    }
+
+    fn check_new(&self, token: OrderToken, qty: u64, px: u64) -> Result<(), RejectedReason> {
+        if self.orders.contains_key(&token) {
+            return Err(RejectedReason::DuplicateToken);
+        }
+        if qty == 0 || px == 0 {
+            return Err(RejectedReason::InvalidOrder);
+        }
+        Ok(())
+    }
+
+    /// An accepted order without a cancel or replace in flight.
+    fn cancellable(&mut self, token: OrderToken) -> Option<&mut OuchOrder> {
+        self.orders
+            .get_mut(&token)
+            .filter(|order| order.order_id.is_some() && order.pending.is_none())
+    }
+
+    fn accept(
+        &mut self,
+        token: OrderToken,
+        order_id: u32,
+        timestamp: u64,
+        out: &mut Vec<OuchOutbound>,
+    ) {
+        let Some(order) = self.orders.get_mut(&token) else {
+            return;
+        };
+        if order.order_id.is_some() {
+            return;
+        }
+        order.order_id = Some(order_id);
+        self.by_order_id.insert(order_id, token);
+
+        out.push(match order.replaces {
+            None => OuchOutbound::Accepted(Accepted {
+                timestamp,
+                token,
+                side: order.side,
+                qty: order.qty,
+                instrument: order.instrument,
+                px: order.px,
+                time_in_force: order.time_in_force,
+                order_ref: order_id,
+            }),
+            Some((previous_token, _)) => OuchOutbound::Replaced(Replaced {
+                timestamp,
+                token,
+                side: order.side,
+                qty: order.qty,
+                instrument: order.instrument,
+                px: order.px,
+                time_in_force: order.time_in_force,
+                order_ref: order_id,
+                previous_token,
+            }),
+        });
+    }
+
+    fn on_reject(&mut self, reject: &OrderReject, out: &mut Vec<OuchOutbound>) {
+        let Some(token) = self.token(reject.client_id, reject.client_order_id) else {
+            return;
+        };
+        let Some(order) = self.remove(token) else {
+            return;
+        };
+
+        // the replaced order is already gone
+        if let Some((previous_token, decrement_qty)) = order.replaces {
+            out.push(OuchOutbound::Canceled(Canceled {
+                timestamp: reject.reject_time,
+                token: previous_token,
+                decrement_qty,
+                reason: CanceledReason::UserRequested,
+            }));
+        }
+        out.push(rejected(token, rejected_reason(reject.reason)));
+    }
+
+    fn on_execution(
+        &mut self,
+        execution: &ExecutionReport,
+        side: Side,
+        out: &mut Vec<OuchOutbound>,
+    ) {
+        let (client_id, client_order_id, order_id, other_order_id) = match side {
+            Side::Buy => (
+                execution.bid_client_id,
+                execution.bid_client_order_id,
+                execution.bid_order_id,
+                execution.ask_order_id,
+            ),
+            Side::Sell => (
+                execution.ask_client_id,
+                execution.ask_client_order_id,
+                execution.ask_order_id,
+                execution.bid_order_id,
+            ),
+        };
+        let Some(token) = self.token(client_id, client_order_id) else {
+            return;
+        };
+
+        // an aggressive order's fills come before its ack
+        self.accept(token, order_id, execution.exec_ns, out);
+
+        // order ids are assigned in arrival order, the resting order has the lower one
+        let liquidity = if order_id < other_order_id {
+            Liquidity::Added
+        } else {
+            Liquidity::Removed
+        };
+        out.push(OuchOutbound::Executed(Executed {
+            timestamp: execution.exec_ns,
+            token,
+            executed_qty: execution.exec_qty,
+            px: execution.exec_px,
+            liquidity,
+        }));
+
+        let Some(order) = self.orders.get_mut(&token) else {
+            return;
+        };
+        order.leaves_qty = order.leaves_qty.saturating_sub(execution.exec_qty);
+        if order.leaves_qty == 0 {
+            self.done(token, out);
+        }
+    }
+
+    fn on_self_match_prevented(
+        &mut self,
+        execution: &ExecutionReport,
+        out: &mut Vec<OuchOutbound>,
+    ) {
+        // only the resting side is reported
+        let (client_id, client_order_id) = if execution.bid_order_id != 0 {
+            (execution.bid_client_id, execution.bid_client_order_id)
+        } else {
+            (execution.ask_client_id, execution.ask_client_order_id)
+        };
+        let Some(token) = self.token(client_id, client_order_id) else {
+            return;
+        };
+
+        out.push(OuchOutbound::Canceled(Canceled {
+            timestamp: execution.exec_ns,
+            token,
+            decrement_qty: execution.exec_qty,
+            reason: CanceledReason::SelfMatchPrevention,
+        }));
+        self.done(token, out);
+    }
+
+    fn on_cancel_ack(
+        &mut self,
+        ack: &CancelledOrderAck,
+        out: &mut Vec<OuchOutbound>,
+    ) -> Option<EngineMessage> {
+        let token = *self.by_order_id.get(&ack.order_id)?;
+        let order = self.orders.get_mut(&token)?;
+        if order.client_id != ack.client_id {
+            return None;
+        }
+
+        if let CancelOrderStatus::Cancelled = ack.cancel_order_status {
+            let order = self.remove(token)?;
+            if let Some(Pending::Replace(replacement_token)) = order.pending {
+                let replacement = self.orders.get_mut(&replacement_token)?;
+                replacement.replaces = Some((token, order.leaves_qty));
+                return Some(new_order(replacement));
+            }
+
+            out.push(OuchOutbound::Canceled(Canceled {
+                timestamp: ack.ack_time,
+                token,
+                decrement_qty: order.leaves_qty,
+                reason: canceled_reason(ack.reason),
+            }));
+            return None;
+        }
+
+        // not found or throttled, the order stays live
+        let reason = match ack.reason {
+            Reason::Throttled => RejectedReason::Throttled,
+            _ => RejectedReason::ReplaceRefused,
+        };
+        if let Some(pending) = order.pending.take() {
+            self.refuse(token, pending, reason, out);
+        }
+        None
+    }
+
+    /// Forgets a filled or cancelled order, refusing a cancel or replace still in flight for it.
+    fn done(&mut self, token: OrderToken, out: &mut Vec<OuchOutbound>) {
+        let Some(order) = self.remove(token) else {
+            return;
+        };
+        if let Some(pending) = order.pending {
+            self.refuse(token, pending, RejectedReason::ReplaceRefused, out);
+        }
+    }
+
+    fn refuse(
+        &mut self,
+        token: OrderToken,
+        pending: Pending,
+        reason: RejectedReason,
+        out: &mut Vec<OuchOutbound>,
+    ) {
+        match pending {
+            Pending::Cancel => out.push(cancel_rejected(token)),
+            Pending::Replace(replacement_token) => {
+                self.remove(replacement_token);
+                out.push(rejected(replacement_token, reason));
+            }
+        }
+    }
+
+    fn token(&self, client_id: u32, client_order_id: u64) -> Option<OrderToken> {
+        let token = *self.by_client_order_id.get(&client_order_id)?;
+        match self.orders.get(&token) {
+            Some(order) if order.client_id == client_id => Some(token),
+            _ => None,
+        }
+    }
+
+    fn insert(&mut self, token: OrderToken, order: OuchOrder) {
+        self.by_client_order_id.insert(order.client_order_id, token);
+        self.orders.insert(token, order);
+    }
+
+    fn remove(&mut self, token: OrderToken) -> Option<OuchOrder> {
+        let order = self.orders.remove(&token)?;
+        self.by_client_order_id.remove(&order.client_order_id);
+        if let Some(order_id) = order.order_id {
+            self.by_order_id.remove(&order_id);
+        }
+        Some(order)
+    }
+}
+
+fn new_order(order: &OuchOrder) -> EngineMessage {
+    EngineMessage::NewOrder(OrderRequest {
+        client_id: order.client_id,
+        client_order_id: order.client_order_id,
+        instrument: order.instrument,
+        order_side: order.side,
+        px: order.px,
+        qty: order.qty,
+        time_in_force: order.time_in_force,
+        timestamp: system_nanos(),
+        trace: LatencyTrace::default(),
+    })
+}
+
+fn cancel_order(order: &OuchOrder) -> EngineMessage {
+    EngineMessage::CancelOrder(CancelOrderRequest {
+        client_id: order.client_id,
+        order_side: order.side,
+        order_id: order.order_id.unwrap_or_default(),
+        instrument: order.instrument,
+        trace: LatencyTrace::default(),
+    })
+}
+
+fn rejected(token: OrderToken, reason: RejectedReason) -> OuchOutbound {
+    OuchOutbound::Rejected(Rejected {
+        timestamp: system_nanos(),
+        token,
+        reason,
+    })
+}
+
+fn cancel_rejected(token: OrderToken) -> OuchOutbound {
+    OuchOutbound::CancelRejected(CancelRejected {
+        timestamp: system_nanos(),
+        token,
+    })
+}
+
+fn rejected_reason(reason: RejectReason) -> RejectedReason {
+    match reason {
+        RejectReason::InstrumentHalted => RejectedReason::InstrumentHalted,
+        RejectReason::MaxOrderQty => RejectedReason::MaxOrderQty,
+        RejectReason::MaxOrderNotional => RejectedReason::MaxOrderNotional,
+        RejectReason::MaxOpenOrders => RejectedReason::MaxOpenOrders,
+        RejectReason::MaxOpenNotional => RejectedReason::MaxOpenNotional,
+        RejectReason::PriceDeviation => RejectedReason::PriceDeviation,
+        RejectReason::MaxPosition => RejectedReason::MaxPosition,
+        RejectReason::Throttled => RejectedReason::Throttled,
+        RejectReason::InvalidOrder | RejectReason::UnknownClient => RejectedReason::InvalidOrder,
+    }
+}
+
+fn canceled_reason(reason: Reason) -> CanceledReason {
+    match reason {
+        Reason::SelfMatchPrevention => CanceledReason::SelfMatchPrevention,
+        Reason::AdminRequested => CanceledReason::Supervisory,
+        Reason::ClientRequested | Reason::Throttled | Reason::NotOwner | Reason::InvalidCancel => {
+            CanceledReason::UserRequested
+        }
+    }
+}
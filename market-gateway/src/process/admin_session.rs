@@ -0,0 +1,126 @@
+use crate::app_state::AppState;
+use common::codec::frame_error::FrameError;
+use common::codec::framing::{read_frame_async, read_message_async, write_message_async};
+use common::transport::sequenced_message::EngineMessage;
+use common::types::admin::{AdminCommand, AdminResponse, AdminResponseBody};
+use common::util::secret::secrets_match;
+use std::collections::HashSet;
+use std::net::SocketAddr;
+use std::sync::{Arc, Mutex};
+use tokio::net::tcp::OwnedWriteHalf;
+use tokio::net::TcpStream;
+use tokio::sync::broadcast::error::RecvError;
+use tokio::sync::broadcast::{Receiver, Sender};
+
+const MAX_ADMIN_FRAME: usize = 256;
+
+/// An admin session authenticates with a single token frame, then sends `AdminRequest` frames.
+/// Requests are re-numbered under this gateway's sender id and only responses to this session's
+/// requests are returned.
+pub async fn admin_session_handler(
+    socket: TcpStream,
+    addr: SocketAddr,
+    admin_token: String,
+    state: Arc<AppState>,
+    tx_engine_queue: Sender<EngineMessage>,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let (mut rx_admin, tx_admin) = socket.into_split();
+
+    let mut buffer = [0u8; MAX_ADMIN_FRAME];
+    let token = read_frame_async(&mut rx_admin, &mut buffer).await?;
+    if !secrets_match(admin_token.as_bytes(), token) {
+        eprintln!("Admin session {} failed authentication", addr);
+        return Ok(());
+    }
+    println!("Admin session {} authenticated", addr);
+
+    let pending = Arc::new(Mutex::new(HashSet::new()));
+
+    let responses = tokio::spawn(admin_responses(
+        tx_admin,
+        tx_engine_queue.subscribe(),
+        pending.clone(),
+    ));
+
+    loop {
+        let mut request = match read_message_async(&mut rx_admin, &mut buffer).await {
+            Ok(EngineMessage::AdminRequest(request)) => request,
+            Err(FrameError::Closed) => break, // disconnected
+            _ => {
+                eprintln!("Admin session {} sent malformed request", addr);
+                break;
+            }
+        };
+
+        request.request_id = state.next_admin_request_id();
+        pending.lock().unwrap().insert(request.request_id);
+        if request.command == AdminCommand::Shutdown {
+            println!(
+                "Admin session {} requested engine shutdown, request {}",
+                addr, request.request_id
+            );
+        }
+
+        // risk limits live in the gateway, answer through the same response path
+        let local_response = match request.command {
+            AdminCommand::GetRiskLimits => Some(state.risk.limits()),
+            AdminCommand::SetRiskLimit(limit, value) => Some(state.risk.set_limit(limit, value)),
+            _ => None,
+        };
+        if let Some(limits) = local_response {
+            let _ = tx_engine_queue.send(EngineMessage::AdminResponse(AdminResponse {
+                request_id: request.request_id,
+                last: true,
+                body: AdminResponseBody::RiskLimits(limits),
+            }));
+            continue;
+        }
+
+        state
+            .tx_oe_queue
+            .send(EngineMessage::AdminRequest(request))
+            .await?;
+    }
+
+    responses.abort();
+    println!("Admin session {} closed", addr);
+    Ok(())
+}
+
+async fn admin_responses(
+    mut tx_admin: OwnedWriteHalf,
+    mut rx_engine_queue: Receiver<EngineMessage>,
+    pending: Arc<Mutex<HashSet<u32>>>,
+) {
+    let mut serialized = Vec::new();
+    loop {
+        let message = match rx_engine_queue.recv().await {
+            Ok(message) => message,
+            Err(RecvError::Lagged(skipped)) => {
+                eprintln!("Admin responses lagged, {} engine messages lost", skipped);
+                continue;
+            }
+            Err(RecvError::Closed) => break,
+        };
+        let EngineMessage::AdminResponse(response) = &message else {
+            continue;
+        };
+
+        {
+            let mut pending = pending.lock().unwrap();
+            if !pending.contains(&response.request_id) {
+                continue;
+            }
+            if response.last {
+                pending.remove(&response.request_id);
+            }
+        }
+
+        if write_message_async(&mut tx_admin, &message, &mut serialized)
+            .await
+            .is_err()
+        {
+            break;
+        }
+    }
+}
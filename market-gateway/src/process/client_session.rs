@@ -0,0 +1,57 @@
+use crate::app_state::AppState;
+use crate::process::gateway_to_oe_api::gateway_to_oe_api_handler;
+use crate::process::oe_api_to_gateway::oe_api_to_gateway_handler;
+use crate::session::gateway_session::{accept_logon, read_session, SessionWriter};
+use crate::session::session_state::SessionKind;
+use common::codec::message_codec::decode_engine_message;
+use common::transport::sequenced_message::EngineMessage;
+use std::error::Error;
+use std::net::SocketAddr;
+use std::sync::Arc;
+use tokio::net::TcpStream;
+use tokio::sync::mpsc;
+
+/// An engine message session on the client port, used by oe-api and fix-acceptor.
+pub async fn client_session(
+    mut socket: TcpStream,
+    addr: SocketAddr,
+    state: Arc<AppState>,
+) -> Result<(), Box<dyn Error>> {
+    let Some(mut lease) =
+        accept_logon(&mut socket, addr, &state.users, SessionKind::Engine).await?
+    else {
+        return Ok(());
+    };
+
+    let (rx_oe_api, tx_oe_api) = socket.into_split();
+    let (tx_inbound, rx_inbound) = mpsc::channel::<EngineMessage>(1024);
+    // gateway generated responses (risk rejects) for this session only
+    let (tx_session, rx_session) = mpsc::channel::<EngineMessage>(1024);
+
+    tokio::spawn(read_session(
+        rx_oe_api,
+        addr,
+        decode_engine_message,
+        tx_inbound,
+    ));
+
+    let identity = lease.identity.clone();
+    let session = lease.state();
+    let route = session.route.clone();
+    tokio::spawn(async move {
+        let handler = oe_api_to_gateway_handler(rx_inbound, state, identity, route, tx_session);
+        if let Err(e) = handler.await {
+            eprintln!("Connection {} error: {}", addr, e);
+        }
+    });
+
+    gateway_to_oe_api_handler(
+        SessionWriter::new(tx_oe_api, &mut session.store),
+        &mut session.rx_engine_queue,
+        rx_session,
+    )
+    .await?;
+
+    println!("Session {} ({}) closed", addr, lease.identity.username);
+    Ok(())
+}
@@ -1,7 +1,9 @@
+use common::config::app_config::{BatchingConfig, ThreadConfig};
 use common::network::mutlicast::multicast_sender;
 use common::transport::nack_sequenced_multicast_sender::NackSequencedMulticastSender;
-use common::transport::sequenced_message::EngineMessage;
-use core_affinity::CoreId;
+use common::transport::sequenced_message::{EngineMessage, SenderId};
+use common::util::thread_affinity::pin_current_thread;
+use common::util::time::system_nanos;
 use std::net::{Ipv4Addr, SocketAddr, SocketAddrV4};
 use std::thread;
 use std::thread::JoinHandle;
@@ -9,24 +11,43 @@ use tokio::sync::mpsc::Receiver;
 
 pub fn msg_in_thread(
     msg_in_port: u16,
-    pinned_msg_in_core: CoreId,
+    nack_port: u16,
+    sender_id: SenderId,
+    ring_len: usize,
+    batching: BatchingConfig,
+    thread_config: ThreadConfig,
     mut rx_oe_queue: Receiver<EngineMessage>,
 ) -> JoinHandle<()> {
     thread::spawn(move || {
-        // core_affinity::set_for_current(pinned_msg_in_core);
+        pin_current_thread("msg_in", thread_config.core);
+        let mut idler = thread_config.idle.idler();
         let msg_in_socket = multicast_sender();
         let send_addr = SocketAddr::V4(SocketAddrV4::new(
             Ipv4Addr::new(239, 255, 0, 1),
             msg_in_port,
         ));
 
-        let mut multicast_sender =
-            NackSequencedMulticastSender::new(msg_in_socket, send_addr, 9000);
+        let mut multicast_sender = NackSequencedMulticastSender::new(
+            msg_in_socket,
+            send_addr,
+            nack_port,
+            sender_id,
+            batching,
+            ring_len,
+        );
 
         loop {
-            while let Ok(inbound_engine_message) = rx_oe_queue.try_recv() {
-                multicast_sender.send(inbound_engine_message);
+            let Ok(mut inbound_engine_message) = rx_oe_queue.try_recv() else {
+                multicast_sender.flush();
+                idler.idle();
+                continue;
+            };
+            idler.reset();
+
+            if let Some(trace) = inbound_engine_message.trace_mut() {
+                trace.gateway_send_ns = system_nanos();
             }
+            multicast_sender.send(inbound_engine_message);
         }
     })
 }
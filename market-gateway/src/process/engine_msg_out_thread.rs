@@ -1,27 +1,49 @@
-use common::network::mutlicast::multicast_receiver;
-use common::transport::nack_sequenced_multicast_receiver::NackSequencedMulticastReceiver;
+use crate::risk::risk_checker::RiskChecker;
+use common::config::app_config::ThreadConfig;
+use common::network::mutlicast::multicast_receiver_with_busy_poll;
+use common::transport::nack_sequenced_multicast_receiver::{
+    NackSequencedMulticastReceiver, TransportEvent,
+};
 use common::transport::sequenced_message::EngineMessage;
-use core_affinity::CoreId;
+use common::util::thread_affinity::pin_current_thread;
+use std::sync::Arc;
 use std::thread;
 use std::thread::JoinHandle;
 use tokio::sync::broadcast::Sender;
 
 pub fn msg_out_thread(
     msg_out_port: u16,
-    pinned_msg_out_core: CoreId,
+    nack_port: u16,
+    ring_len: usize,
+    thread_config: ThreadConfig,
+    risk: Arc<RiskChecker>,
     tx_engine_queue: Sender<EngineMessage>,
 ) -> JoinHandle<()> {
     thread::spawn(move || {
-        // core_affinity::set_for_current(pinned_msg_out_core);
-        let udp_socket = multicast_receiver(msg_out_port);
+        pin_current_thread("msg_out", thread_config.core);
+        let udp_socket =
+            multicast_receiver_with_busy_poll(msg_out_port, thread_config.busy_poll_micros);
+        let mut idler = thread_config.idle.idler();
 
-        let mut multicast_receiver = NackSequencedMulticastReceiver::new(udp_socket, 9001);
+        let mut multicast_receiver =
+            NackSequencedMulticastReceiver::new(udp_socket, nack_port, ring_len);
 
         loop {
             if let Some(outbound_engine_message) = multicast_receiver.try_recv() {
+                idler.reset();
+                // only this gateway's clients count, see ClientExposure
+                risk.on_engine_message(&outbound_engine_message.message);
                 tx_engine_queue
                     .send(outbound_engine_message.message)
                     .unwrap();
+            } else {
+                while let Some(event) = multicast_receiver.poll_event() {
+                    eprintln!("Engine stream: {}", event);
+                    if let TransportEvent::SenderRestarted(_) = event {
+                        risk.on_engine_restart();
+                    }
+                }
+                idler.idle();
             }
         }
     })
@@ -1,22 +1,44 @@
+use crate::session::gateway_session::SessionWriter;
+use common::codec::message_codec::encode_engine_message;
+use common::session::session_message::HEARTBEAT_INTERVAL;
 use common::transport::sequenced_message::EngineMessage;
-use tokio::io::AsyncWriteExt;
-use tokio::net::tcp::OwnedWriteHalf;
-use tokio::sync::broadcast::Receiver;
+use tokio::sync::mpsc;
+use tokio::time::interval;
 
+/// Ends when the client's side of the session does, i.e. once `rx_session` is closed, or when
+/// the session is dropped from the registry. Engine output it hasn't taken stays queued for the
+/// user's next session.
 pub async fn gateway_to_oe_api_handler(
-    mut tx_oe_api_stream: OwnedWriteHalf,
-    mut rx_engine_queue: Receiver<EngineMessage>,
+    mut writer: SessionWriter<'_>,
+    rx_engine_queue: &mut mpsc::Receiver<EngineMessage>,
+    mut rx_session: mpsc::Receiver<EngineMessage>,
 ) -> Result<(), Box<dyn std::error::Error>> {
     println!("Initialized GW -> OE_API");
-    while let Ok(message) = rx_engine_queue.recv().await {
-        let serialized = common::serialize::serialize::as_bytes(&message);
-        let packet_len = serialized.len();
-        if let Err(err) = tx_oe_api_stream.write_all(&packet_len.to_be_bytes()).await {
-            println!("Gateway write error: {:?}", err);
-        }
+    let mut heartbeat = interval(HEARTBEAT_INTERVAL);
+    loop {
+        let message = tokio::select! {
+            engine_message = rx_engine_queue.recv() => match engine_message {
+                Some(message) => message,
+                None => {
+                    writer.end_session().await?;
+                    break;
+                }
+            },
+            session_message = rx_session.recv() => match session_message {
+                Some(message) => message,
+                None => break,
+            },
+            _ = heartbeat.tick() => {
+                writer.heartbeat().await?;
+                continue;
+            }
+        };
 
-        if let Err(err) = tx_oe_api_stream.write_all(&serialized).await {
-            println!("Gateway write error: {:?}", err);
+        if let Err(err) = writer
+            .send_data(|out| encode_engine_message(&message, out))
+            .await
+        {
+            println!("Gateway write error: {}", err);
             break;
         }
     }
@@ -1,4 +1,7 @@
+pub mod admin_session;
+pub mod client_session;
 pub mod engine_msg_in_thread;
 pub mod engine_msg_out_thread;
 pub mod oe_api_to_gateway;
+pub mod order_admission;
 pub mod gateway_to_oe_api;
\ No newline at end of file
@@ -1,44 +1,36 @@
 use crate::app_state::AppState;
-use std::mem::MaybeUninit;
-use std::ptr;
 
+use crate::process::order_admission::admit;
+use crate::session::session_registry::SessionRoute;
+use crate::session::user_store::SessionIdentity;
 use common::transport::sequenced_message::EngineMessage;
 use std::sync::Arc;
-use tokio::io::AsyncReadExt;
-use tokio::net::tcp::OwnedReadHalf;
+use tokio::sync::mpsc;
 
 pub async fn oe_api_to_gateway_handler(
-    mut stream: OwnedReadHalf,
+    mut rx_inbound: mpsc::Receiver<EngineMessage>,
     state: Arc<AppState>,
+    identity: SessionIdentity,
+    route: Arc<SessionRoute>,
+    tx_session: mpsc::Sender<EngineMessage>,
 ) -> Result<(), Box<dyn std::error::Error>> {
     println!("Initialized OE_API -> GW");
 
-    let mut buffer = [0u8; 4096];
+    let mut throttle = state.throttle.session();
 
-    loop {
-        let mut len_buf = [0u8; 4];
-        if stream.read_exact(&mut len_buf).await.is_err() {
-            return Ok(()); // disconnected
+    while let Some(msg) = rx_inbound.recv().await {
+        if !matches!(
+            msg,
+            EngineMessage::NewOrder(_) | EngineMessage::CancelOrder(_)
+        ) {
+            eprintln!("Dropping non order entry message on client session");
+            continue;
         }
 
-        let frame_len = u32::from_be_bytes(len_buf) as usize;
-
-        if frame_len > 0 {
-            if frame_len > buffer.len() {
-                println!("Frame too large");
-                return Ok(());
-            }
-
-            stream.read_exact(&mut buffer[..frame_len]).await?;
-
-            let mut msg = MaybeUninit::<EngineMessage>::uninit();
-
-            // Own message TODO: fix serialization code
-            unsafe {
-                ptr::copy_nonoverlapping(buffer.as_ptr(), msg.as_mut_ptr() as *mut u8, frame_len);
-                let msg = msg.assume_init();
-                state.tx_oe_queue.send(msg).await?;
-            }
+        if let Some(reject) = admit(&state, &identity, &route, &mut throttle, msg).await? {
+            tx_session.send(reject).await?;
         }
     }
+
+    Ok(())
 }
@@ -0,0 +1,116 @@
+use crate::app_state::AppState;
+use crate::session::session_registry::SessionRoute;
+use crate::session::user_store::SessionIdentity;
+use common::transport::sequenced_message::EngineMessage;
+use common::types::cancel_order::{
+    CancelOrderRequest, CancelOrderStatus, CancelledOrderAck, Reason,
+};
+use common::types::order::{OrderReject, OrderRequest, RejectReason};
+use common::util::throttle::SessionThrottle;
+use common::util::time::system_nanos;
+use tokio::sync::mpsc::error::SendError;
+
+/// Stamps a client order or cancel with the session's identity, runs it through the session
+/// throttle and the risk checks and queues it for the engine. Returns the gateway generated
+/// reject if it was refused, which is drop copied here as the engine never sees it. A trusted
+/// session keeps the client ids in its range and is routed their engine output, any other client
+/// id is refused.
+pub async fn admit(
+    state: &AppState,
+    identity: &SessionIdentity,
+    route: &SessionRoute,
+    throttle: &mut SessionThrottle,
+    mut msg: EngineMessage,
+) -> Result<Option<EngineMessage>, SendError<EngineMessage>> {
+    let client_id = match &mut msg {
+        EngineMessage::NewOrder(order) if !identity.trusted => {
+            order.client_id = identity.client_id;
+            None
+        }
+        EngineMessage::CancelOrder(cancel) if !identity.trusted => {
+            cancel.client_id = identity.client_id;
+            None
+        }
+        EngineMessage::NewOrder(order) => Some(order.client_id),
+        EngineMessage::CancelOrder(cancel) => Some(cancel.client_id),
+        _ => None,
+    };
+
+    if let Some(trace) = msg.trace_mut() {
+        trace.gateway_recv_ns = system_nanos();
+    }
+
+    if let Some(client_id) = client_id {
+        if !identity.may_trade_for(client_id) || !route.add_client(client_id) {
+            return Ok(Some(refuse(state, unknown_client_reject(&msg))));
+        }
+    }
+
+    if !throttle.try_acquire(&msg) {
+        return Ok(Some(refuse(state, throttle_reject(&msg))));
+    }
+
+    match &msg {
+        EngineMessage::NewOrder(order) => {
+            if let Err(reason) = state.risk.check_new_order(order) {
+                return Ok(Some(refuse(state, risk_reject(order, reason))));
+            }
+        }
+        EngineMessage::CancelOrder(cancel) => {
+            if let Err(reason) = state.risk.check_cancel(cancel) {
+                return Ok(Some(refuse(state, cancel_reject(cancel, reason))));
+            }
+        }
+        _ => {}
+    }
+
+    state.tx_oe_queue.send(msg).await?;
+    Ok(None)
+}
+
+fn refuse(state: &AppState, reject: EngineMessage) -> EngineMessage {
+    state.drop_copies.record(&reject);
+    reject
+}
+
+fn risk_reject(order: &OrderRequest, reason: RejectReason) -> EngineMessage {
+    EngineMessage::OrderReject(OrderReject {
+        client_id: order.client_id,
+        client_order_id: order.client_order_id,
+        instrument: order.instrument,
+        side: order.order_side,
+        px: order.px,
+        qty: order.qty,
+        reason,
+        reject_time: system_nanos(),
+        trace: order.trace,
+    })
+}
+
+fn throttle_reject(msg: &EngineMessage) -> EngineMessage {
+    match msg {
+        EngineMessage::CancelOrder(cancel) => cancel_reject(cancel, Reason::Throttled),
+        EngineMessage::NewOrder(order) => risk_reject(order, RejectReason::Throttled),
+        _ => unreachable!(),
+    }
+}
+
+fn unknown_client_reject(msg: &EngineMessage) -> EngineMessage {
+    match msg {
+        EngineMessage::CancelOrder(cancel) => cancel_reject(cancel, Reason::NotOwner),
+        EngineMessage::NewOrder(order) => risk_reject(order, RejectReason::UnknownClient),
+        _ => unreachable!(),
+    }
+}
+
+fn cancel_reject(cancel: &CancelOrderRequest, reason: Reason) -> EngineMessage {
+    EngineMessage::CancelOrderAck(CancelledOrderAck {
+        client_id: cancel.client_id,
+        order_id: cancel.order_id,
+        instrument: cancel.instrument,
+        cancel_order_status: CancelOrderStatus::Rejected,
+        reason,
+        ack_time: system_nanos(),
+        trace: cancel.trace,
+    })
+}
@@ -0,0 +1,201 @@
+use common::types::cancel_order::{CancelOrderStatus, CancelledOrderAck};
+use common::types::execution_report::{ExecType, ExecutionReport, FillType};
+use common::types::order::{NewOrderAck, OrderReject};
+use std::collections::HashMap;
+
+#[derive(Debug, Default, Clone, Copy)]
+pub struct Exposure {
+    pub open_orders: u64,
+    pub open_notional: u64,
+}
+
+#[derive(Debug, Clone, Copy)]
+struct RestingOrder {
+    client_id: u32,
+    px: u64,
+    qty: u64,
+}
+
+/// Open orders and notional per client, reserved when the gateway accepts an order and
+/// released as the engine output reports fills, cancels and rejects. Every gateway sees all of
+/// the engine's output, only that of the clients this gateway reserved for is tracked, the
+/// output for other gateways' clients is left to their own checks.
+#[derive(Default)]
+pub struct ClientExposure {
+    exposure: HashMap<u32, Exposure>,
+    resting: HashMap<u32, RestingOrder>,
+    last_trade_px: Option<u64>,
+}
+
+impl ClientExposure {
+    pub fn exposure(&self, client_id: u32) -> Exposure {
+        self.exposure.get(&client_id).copied().unwrap_or_default()
+    }
+
+    /// The client of an order the engine acked and that is still resting.
+    pub fn owner(&self, order_id: u32) -> Option<u32> {
+        self.resting.get(&order_id).map(|order| order.client_id)
+    }
+
+    pub fn last_trade_px(&self) -> Option<u64> {
+        self.last_trade_px
+    }
+
+    /// Releases every open order, the last trade price is kept.
+    pub fn clear_open_orders(&mut self) {
+        self.exposure.clear();
+        self.resting.clear();
+    }
+
+    pub fn reserve(&mut self, client_id: u32, px: u64, qty: u64) {
+        let exposure = self.exposure.entry(client_id).or_default();
+        exposure.open_orders += 1;
+        exposure.open_notional = exposure
+            .open_notional
+            .saturating_add(px.saturating_mul(qty));
+    }
+
+    pub fn on_new_order_ack(&mut self, ack: &NewOrderAck) {
+        if !self.exposure.contains_key(&ack.client_id) {
+            return;
+        }
+        self.resting.insert(
+            ack.order_id,
+            RestingOrder {
+                client_id: ack.client_id,
+                px: ack.px,
+                qty: ack.qty_rem,
+            },
+        );
+    }
+
+    pub fn on_cancel_ack(&mut self, ack: &CancelledOrderAck) {
+        if !matches!(ack.cancel_order_status, CancelOrderStatus::Cancelled) {
+            return;
+        }
+
+        if let Some(order) = self.resting.remove(&ack.order_id) {
+            self.release(order.client_id, order.px, order.qty, true);
+        }
+    }
+
+    pub fn on_reject(&mut self, reject: &OrderReject) {
+        self.release(reject.client_id, reject.px, reject.qty, true);
+    }
+
+    pub fn on_execution(&mut self, execution: &ExecutionReport) {
+        match execution.exec_type {
+            ExecType::MatchEvent => {
+                self.last_trade_px = Some(execution.exec_px);
+                self.fill(
+                    execution.bid_order_id,
+                    execution.bid_client_id,
+                    execution.bid_order_px,
+                    execution.exec_qty,
+                    execution.bid_fill_type,
+                );
+                self.fill(
+                    execution.ask_order_id,
+                    execution.ask_client_id,
+                    execution.ask_order_px,
+                    execution.exec_qty,
+                    execution.ask_fill_type,
+                );
+            }
+            // the resting order was pulled from the book
+            ExecType::SelfMatchPrevented => {
+                let order_id = execution.bid_order_id.max(execution.ask_order_id);
+                if let Some(order) = self.resting.remove(&order_id) {
+                    self.release(order.client_id, order.px, order.qty, true);
+                }
+            }
+        }
+    }
+
+    fn fill(&mut self, order_id: u32, client_id: u32, px: u64, qty: u64, fill_type: FillType) {
+        let closed = fill_type == FillType::FullFill;
+        self.release(client_id, px, qty, closed);
+
+        if closed {
+            self.resting.remove(&order_id);
+        } else if let Some(order) = self.resting.get_mut(&order_id) {
+            order.qty = order.qty.saturating_sub(qty);
+        }
+    }
+
+    /// Nothing to release for a client this gateway never reserved for.
+    fn release(&mut self, client_id: u32, px: u64, qty: u64, closed: bool) {
+        if let Some(exposure) = self.exposure.get_mut(&client_id) {
+            exposure.open_notional = exposure
+                .open_notional
+                .saturating_sub(px.saturating_mul(qty));
+            if closed {
+                exposure.open_orders = exposure.open_orders.saturating_sub(1);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use common::types::latency_trace::LatencyTrace;
+    use common::types::order::TimeInForce;
+    use common::types::side::Side;
+
+    fn ack(client_id: u32, order_id: u32) -> NewOrderAck {
+        NewOrderAck {
+            client_id,
+            client_order_id: 1,
+            order_id,
+            instrument: [0; 16],
+            side: Side::Buy,
+            px: 100,
+            qty: 2,
+            qty_rem: 2,
+            time_in_force: TimeInForce::GTC,
+            ack_time: 0,
+            trace: LatencyTrace::default(),
+        }
+    }
+
+    fn execution(bid: (u32, u32), ask: (u32, u32), qty: u64) -> ExecutionReport {
+        ExecutionReport {
+            trade_id: 1,
+            bid_client_id: bid.0,
+            bid_order_id: bid.1,
+            bid_client_order_id: 1,
+            bid_order_px: 100,
+            bid_fill_type: FillType::PartialFill,
+            ask_client_id: ask.0,
+            ask_order_id: ask.1,
+            ask_client_order_id: 1,
+            ask_order_px: 100,
+            ask_fill_type: FillType::FullFill,
+            instrument: [0; 16],
+            exec_px: 101,
+            exec_qty: qty,
+            exec_type: ExecType::MatchEvent,
+            exec_ns: 0,
+            trace: LatencyTrace::default(),
+        }
+    }
+
+    #[test]
+    fn output_for_another_gateways_clients_is_ignored() {
+        let mut exposure = ClientExposure::default();
+        exposure.reserve(11, 100, 2);
+
+        exposure.on_new_order_ack(&ack(11, 1));
+        exposure.on_new_order_ack(&ack(21, 2));
+        exposure.on_execution(&execution((11, 1), (21, 2), 1));
+
+        assert_eq!(exposure.owner(1), Some(11));
+        assert_eq!(exposure.owner(2), None);
+        assert_eq!(exposure.exposure(11).open_orders, 1);
+        assert_eq!(exposure.exposure(11).open_notional, 100);
+        assert_eq!(exposure.exposure(21).open_orders, 0);
+        // the market's last trade price is still followed
+        assert_eq!(exposure.last_trade_px(), Some(101));
+    }
+}
@@ -0,0 +1,3 @@
+pub mod client_exposure;
+pub mod position_poller;
+pub mod risk_checker;
@@ -0,0 +1,52 @@
+use crate::risk::risk_checker::RiskChecker;
+use common::types::position::ClientPosition;
+use std::sync::Arc;
+use std::time::Duration;
+
+/// Refreshes the risk checker's view of client positions from the position keeper API.
+pub async fn position_poller_task(
+    position_keeper_url: String,
+    poll_millis: u64,
+    risk: Arc<RiskChecker>,
+) {
+    let client = reqwest::Client::new();
+    let url = format!("{}/positions", position_keeper_url.trim_end_matches('/'));
+
+    let mut ticker = tokio::time::interval(Duration::from_millis(poll_millis.max(1)));
+    ticker.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Skip);
+
+    let mut reachable = false;
+    loop {
+        ticker.tick().await;
+
+        match fetch_positions(&client, &url).await {
+            Ok(positions) => {
+                if !reachable {
+                    println!("Position keeper {} reachable", url);
+                    reachable = true;
+                }
+                risk.set_positions(positions);
+            }
+            Err(e) => {
+                // keep the last known positions rather than failing every order
+                if reachable {
+                    eprintln!("Position keeper {} unreachable: {}", url, e);
+                    reachable = false;
+                }
+            }
+        }
+    }
+}
+
+async fn fetch_positions(
+    client: &reqwest::Client,
+    url: &str,
+) -> Result<Vec<ClientPosition>, reqwest::Error> {
+    client
+        .get(url)
+        .send()
+        .await?
+        .error_for_status()?
+        .json::<Vec<ClientPosition>>()
+        .await
+}
@@ -0,0 +1,387 @@
+use crate::risk::client_exposure::ClientExposure;
+use common::transport::sequenced_message::EngineMessage;
+use common::types::cancel_order::{CancelOrderRequest, Reason};
+use common::types::instrument::Instrument;
+use common::types::order::{OrderRequest, RejectReason};
+use common::types::position::ClientPosition;
+use common::types::risk::{RiskLimitKind, RiskLimits};
+use common::types::side::Side;
+use std::collections::HashMap;
+use std::sync::{Mutex, RwLock};
+
+pub struct RiskChecker {
+    limits: RwLock<RiskLimits>,
+    exposure: Mutex<ClientExposure>,
+    /// Last positions polled from the position keeper, by client and instrument.
+    positions: RwLock<HashMap<(u32, [u8; 16]), ClientPosition>>,
+}
+
+impl RiskChecker {
+    pub fn new(limits: RiskLimits) -> Self {
+        RiskChecker {
+            limits: RwLock::new(limits),
+            exposure: Mutex::new(ClientExposure::default()),
+            positions: RwLock::new(HashMap::new()),
+        }
+    }
+
+    pub fn limits(&self) -> RiskLimits {
+        *self.limits.read().unwrap()
+    }
+
+    pub fn set_limit(&self, limit: RiskLimitKind, value: u64) -> RiskLimits {
+        let mut limits = self.limits.write().unwrap();
+        limits.set(limit, value);
+        println!("Risk limit {:?} set to {}", limit, value);
+        *limits
+    }
+
+    pub fn set_positions(&self, positions: Vec<ClientPosition>) {
+        let positions = positions
+            .into_iter()
+            .map(|position| {
+                let instrument = Instrument::str_to_fixed_char_buffer(&position.instrument);
+                ((position.client_id, instrument), position)
+            })
+            .collect();
+        *self.positions.write().unwrap() = positions;
+    }
+
+    /// Checks a new order and, if it passes, reserves its quantity against the client's open limits.
+    pub fn check_new_order(&self, order: &OrderRequest) -> Result<(), RejectReason> {
+        // nothing would ever ack or fill these to release what they reserve
+        if order.qty == 0 || order.px == 0 {
+            return Err(RejectReason::InvalidOrder);
+        }
+
+        let limits = self.limits();
+        let notional = order.px.saturating_mul(order.qty);
+
+        if order.qty > limits.max_order_qty {
+            return Err(RejectReason::MaxOrderQty);
+        }
+
+        if notional > limits.max_order_notional {
+            return Err(RejectReason::MaxOrderNotional);
+        }
+
+        self.check_position(order, limits.max_position)?;
+
+        let mut exposure = self.exposure.lock().unwrap();
+
+        if let Some(last_trade_px) = exposure.last_trade_px().filter(|&px| px != 0) {
+            let deviation_bps =
+                order.px.abs_diff(last_trade_px) as u128 * 10_000 / last_trade_px as u128;
+            if deviation_bps > limits.max_price_deviation_bps as u128 {
+                return Err(RejectReason::PriceDeviation);
+            }
+        }
+
+        let client_exposure = exposure.exposure(order.client_id);
+        if client_exposure.open_orders + 1 > limits.max_open_orders {
+            return Err(RejectReason::MaxOpenOrders);
+        }
+
+        if client_exposure.open_notional.saturating_add(notional) > limits.max_open_notional {
+            return Err(RejectReason::MaxOpenNotional);
+        }
+
+        exposure.reserve(order.client_id, order.px, order.qty);
+        Ok(())
+    }
+
+    /// Rejects orders that would take the client beyond the position limit if they and all the
+    /// client's resting orders on the same side filled.
+    fn check_position(&self, order: &OrderRequest, max_position: u64) -> Result<(), RejectReason> {
+        let positions = self.positions.read().unwrap();
+        let Some(position) = positions.get(&(order.client_id, order.instrument)) else {
+            return if order.qty > max_position {
+                Err(RejectReason::MaxPosition)
+            } else {
+                Ok(())
+            };
+        };
+
+        // a quantity too large to project is over any limit
+        let projected = match order.order_side {
+            Side::Buy => position
+                .open_buy_qty
+                .checked_add(order.qty)
+                .and_then(|qty| i64::try_from(qty).ok())
+                .and_then(|qty| position.net_qty.checked_add(qty)),
+            Side::Sell => position
+                .open_sell_qty
+                .checked_add(order.qty)
+                .and_then(|qty| i64::try_from(qty).ok())
+                .and_then(|qty| position.net_qty.checked_sub(qty)),
+        };
+
+        match projected {
+            Some(projected) if projected.unsigned_abs() <= max_position => Ok(()),
+            _ => Err(RejectReason::MaxPosition),
+        }
+    }
+
+    /// Refuses a cancel of an order the gateway knows to rest for another client. Orders it
+    /// hasn't seen acked are left to the engine, which checks the owner as well.
+    pub fn check_cancel(&self, cancel: &CancelOrderRequest) -> Result<(), Reason> {
+        match self.exposure.lock().unwrap().owner(cancel.order_id) {
+            Some(owner) if owner != cancel.client_id => Err(Reason::NotOwner),
+            _ => Ok(()),
+        }
+    }
+
+    /// A restarted engine has lost every resting order, so none count against the limits anymore.
+    pub fn on_engine_restart(&self) {
+        self.exposure.lock().unwrap().clear_open_orders();
+    }
+
+    pub fn on_engine_message(&self, message: &EngineMessage) {
+        let mut exposure = self.exposure.lock().unwrap();
+        match message {
+            EngineMessage::NewOrderAck(ack) => exposure.on_new_order_ack(ack),
+            EngineMessage::CancelOrderAck(ack) => exposure.on_cancel_ack(ack),
+            EngineMessage::TradeExecution(execution) => exposure.on_execution(execution),
+            EngineMessage::OrderReject(reject) => exposure.on_reject(reject),
+            _ => {}
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use common::types::execution_report::{ExecType, ExecutionReport, FillType};
+    use common::types::latency_trace::LatencyTrace;
+    use common::types::order::TimeInForce;
+
+    fn limits() -> RiskLimits {
+        RiskLimits {
+            max_order_qty: 100,
+            max_order_notional: 10_000,
+            max_open_orders: 2,
+            max_open_notional: 15_000,
+            max_price_deviation_bps: 100,
+            max_position: 1_000,
+        }
+    }
+
+    fn order(client_id: u32, px: u64, qty: u64) -> OrderRequest {
+        OrderRequest {
+            client_id,
+            client_order_id: 1,
+            instrument: Instrument::str_to_fixed_char_buffer("BTC-USD"),
+            order_side: Side::Buy,
+            px,
+            qty,
+            time_in_force: TimeInForce::GTC,
+            timestamp: 0,
+            trace: LatencyTrace::default(),
+        }
+    }
+
+    fn trade(exec_px: u64) -> EngineMessage {
+        EngineMessage::TradeExecution(ExecutionReport {
+            trade_id: 1,
+            bid_client_id: 91,
+            bid_order_id: 1,
+            bid_client_order_id: 1,
+            bid_order_px: exec_px,
+            bid_fill_type: FillType::FullFill,
+            ask_client_id: 92,
+            ask_order_id: 2,
+            ask_client_order_id: 1,
+            ask_order_px: exec_px,
+            ask_fill_type: FillType::FullFill,
+            instrument: Instrument::str_to_fixed_char_buffer("BTC-USD"),
+            exec_px,
+            exec_qty: 1,
+            exec_type: ExecType::MatchEvent,
+            exec_ns: 0,
+            trace: LatencyTrace::default(),
+        })
+    }
+
+    fn position(net_qty: i64, open_buy_qty: u64, open_sell_qty: u64) -> ClientPosition {
+        ClientPosition {
+            client_id: 1,
+            instrument: "BTC-USD".to_string(),
+            net_qty,
+            avg_entry_px: 0.0,
+            realized_pnl: 0.0,
+            unrealized_pnl: 0.0,
+            open_buy_qty,
+            open_sell_qty,
+            open_buy_notional: 0,
+            open_sell_notional: 0,
+        }
+    }
+
+    fn sell(mut order: OrderRequest) -> OrderRequest {
+        order.order_side = Side::Sell;
+        order
+    }
+
+    /// Each order is checked by a fresh checker, so none counts against the next one.
+    fn check(order: OrderRequest) -> Result<(), RejectReason> {
+        RiskChecker::new(limits()).check_new_order(&order)
+    }
+
+    #[test]
+    fn a_zero_qty_or_price_is_invalid() {
+        assert_eq!(check(order(1, 100, 0)), Err(RejectReason::InvalidOrder));
+        assert_eq!(check(order(1, 0, 1)), Err(RejectReason::InvalidOrder));
+    }
+
+    #[test]
+    fn order_qty_is_limited() {
+        assert_eq!(check(order(1, 1, 100)), Ok(()));
+        assert_eq!(check(order(1, 1, 101)), Err(RejectReason::MaxOrderQty));
+    }
+
+    #[test]
+    fn order_notional_is_limited() {
+        assert_eq!(check(order(1, 100, 100)), Ok(()));
+        assert_eq!(
+            check(order(1, 101, 100)),
+            Err(RejectReason::MaxOrderNotional)
+        );
+    }
+
+    #[test]
+    fn an_overflowing_notional_is_over_the_limit() {
+        let risk = RiskChecker::new(limits());
+        risk.set_limit(RiskLimitKind::MaxOrderQty, u64::MAX);
+        assert_eq!(
+            risk.check_new_order(&order(1, u64::MAX, 2)),
+            Err(RejectReason::MaxOrderNotional)
+        );
+
+        risk.set_limit(RiskLimitKind::MaxOrderNotional, u64::MAX);
+        risk.set_limit(RiskLimitKind::MaxOpenNotional, u64::MAX - 1);
+        risk.set_limit(RiskLimitKind::MaxPosition, u64::MAX);
+        assert_eq!(
+            risk.check_new_order(&order(1, u64::MAX, 2)),
+            Err(RejectReason::MaxOpenNotional)
+        );
+    }
+
+    #[test]
+    fn price_deviation_from_the_last_trade_is_limited() {
+        let risk = RiskChecker::new(limits());
+        risk.on_engine_message(&trade(1_000));
+
+        // 100 bps either side of 1000
+        assert_eq!(risk.check_new_order(&order(1, 1_010, 1)), Ok(()));
+        assert_eq!(risk.check_new_order(&order(2, 990, 1)), Ok(()));
+        assert_eq!(
+            risk.check_new_order(&order(3, 1_011, 1)),
+            Err(RejectReason::PriceDeviation)
+        );
+        assert_eq!(
+            risk.check_new_order(&order(3, 989, 1)),
+            Err(RejectReason::PriceDeviation)
+        );
+    }
+
+    #[test]
+    fn price_deviation_is_not_checked_before_the_first_trade() {
+        assert_eq!(check(order(1, 1, 1)), Ok(()));
+        assert_eq!(check(order(1, 10_000, 1)), Ok(()));
+    }
+
+    #[test]
+    fn open_orders_are_limited_per_client() {
+        let risk = RiskChecker::new(limits());
+
+        assert_eq!(risk.check_new_order(&order(1, 10, 1)), Ok(()));
+        assert_eq!(risk.check_new_order(&order(1, 10, 1)), Ok(()));
+        assert_eq!(
+            risk.check_new_order(&order(1, 10, 1)),
+            Err(RejectReason::MaxOpenOrders)
+        );
+        assert_eq!(risk.check_new_order(&order(2, 10, 1)), Ok(()));
+    }
+
+    #[test]
+    fn open_notional_is_limited_per_client() {
+        let risk = RiskChecker::new(limits());
+
+        assert_eq!(risk.check_new_order(&order(1, 100, 100)), Ok(()));
+        assert_eq!(
+            risk.check_new_order(&order(1, 100, 51)),
+            Err(RejectReason::MaxOpenNotional)
+        );
+        assert_eq!(risk.check_new_order(&order(1, 100, 50)), Ok(()));
+        assert_eq!(risk.check_new_order(&order(2, 100, 100)), Ok(()));
+    }
+
+    #[test]
+    fn a_limit_set_at_runtime_applies_to_the_next_order() {
+        let risk = RiskChecker::new(limits());
+        assert_eq!(
+            risk.check_new_order(&order(1, 1, 150)),
+            Err(RejectReason::MaxOrderQty)
+        );
+
+        let limits = risk.set_limit(RiskLimitKind::MaxOrderQty, 200);
+        assert_eq!(limits.max_order_qty, 200);
+        assert_eq!(risk.limits(), limits);
+        assert_eq!(risk.check_new_order(&order(1, 1, 150)), Ok(()));
+
+        risk.set_limit(RiskLimitKind::MaxOrderQty, 100);
+        assert_eq!(
+            risk.check_new_order(&order(1, 1, 150)),
+            Err(RejectReason::MaxOrderQty)
+        );
+    }
+
+    #[test]
+    fn the_position_projected_with_open_orders_is_limited() {
+        let risk = RiskChecker::new(limits());
+        risk.set_positions(vec![position(500, 400, 200)]);
+
+        // 500 held + 400 open + 100 bought
+        assert_eq!(risk.check_new_order(&order(1, 1, 100)), Ok(()));
+        risk.set_positions(vec![position(501, 400, 200)]);
+        assert_eq!(
+            risk.check_new_order(&order(1, 1, 100)),
+            Err(RejectReason::MaxPosition)
+        );
+
+        // selling works the long position down
+        assert_eq!(risk.check_new_order(&sell(order(1, 1, 100))), Ok(()));
+        assert_eq!(check(order(2, 1, 100)), Ok(()));
+    }
+
+    #[test]
+    fn a_position_too_large_to_project_is_over_the_limit() {
+        let risk = RiskChecker::new(limits());
+        risk.set_limit(RiskLimitKind::MaxPosition, u64::MAX);
+
+        risk.set_positions(vec![position(i64::MAX - 5, 0, 0)]);
+        assert_eq!(
+            risk.check_new_order(&order(1, 1, 10)),
+            Err(RejectReason::MaxPosition)
+        );
+
+        risk.set_positions(vec![position(i64::MIN + 5, 0, 0)]);
+        assert_eq!(
+            risk.check_new_order(&sell(order(1, 1, 10))),
+            Err(RejectReason::MaxPosition)
+        );
+
+        // open quantity past what a signed position holds
+        risk.set_positions(vec![position(0, i64::MAX as u64, 0)]);
+        assert_eq!(
+            risk.check_new_order(&order(1, 1, 10)),
+            Err(RejectReason::MaxPosition)
+        );
+
+        risk.set_positions(vec![position(0, 0, u64::MAX - 5)]);
+        assert_eq!(
+            risk.check_new_order(&sell(order(1, 1, 10))),
+            Err(RejectReason::MaxPosition)
+        );
+    }
+}
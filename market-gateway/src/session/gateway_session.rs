@@ -0,0 +1,351 @@
+use crate::session::retransmit_store::RetransmitStore;
+use crate::session::session_state::SessionKind;
+use crate::session::user_store::{SessionLease, UserStore};
+use common::codec::codec_error::CodecError;
+use common::codec::frame_error::FrameError;
+use common::codec::framing::{
+    encode_frame_with, read_frame_async, FRAME_PREFIX_LEN, MAX_FRAME_LEN,
+};
+use common::session::session_codec::{decode_client, encode_server, PACKET_SEQUENCED_DATA};
+use common::session::session_error::SessionError;
+use common::session::session_message::{
+    ClientPacket, LoginAccepted, LoginRequest, ServerPacket, HEARTBEAT_INTERVAL, SESSION_TIMEOUT,
+};
+use std::net::SocketAddr;
+use tokio::io::AsyncWriteExt;
+use tokio::net::tcp::{OwnedReadHalf, OwnedWriteHalf};
+use tokio::net::TcpStream;
+use tokio::sync::mpsc;
+use tokio::time::{timeout, Instant};
+
+/// Waits for the client's LoginRequest, answers it and replays what the client asked for.
+/// Returns `None` if the logon was refused.
+pub async fn accept_logon(
+    socket: &mut TcpStream,
+    addr: SocketAddr,
+    users: &UserStore,
+    kind: SessionKind,
+) -> Result<Option<SessionLease>, SessionError> {
+    let login = read_login(socket).await?;
+
+    let mut scratch = Vec::new();
+    let mut lease = match users.logon(&login, kind) {
+        Ok(lease) => lease,
+        Err(reason) => {
+            let reply = ServerPacket::LoginRejected(reason);
+            write_packet(socket, &reply, &mut scratch).await?;
+            println!("Session {} logon refused: {:?}", addr, reply);
+            return Ok(None);
+        }
+    };
+
+    let client_id = lease.identity.client_id;
+    let store = &lease.state().store;
+    let sequence = store.resume_from(login.sequence);
+    let accepted = LoginAccepted {
+        client_id,
+        sequence,
+    };
+    write_packet(socket, &ServerPacket::LoginAccepted(accepted), &mut scratch).await?;
+
+    let mut replayed = 0;
+    for payload in store.replay(sequence) {
+        write_packet(socket, &ServerPacket::SequencedData(payload), &mut scratch).await?;
+        replayed += 1;
+    }
+
+    println!(
+        "Session {} logged on as {} ({} client {}) at sequence {}, {} replayed",
+        addr,
+        lease.identity.username,
+        lease.identity.firm,
+        lease.identity.client_id,
+        sequence,
+        replayed
+    );
+    Ok(Some(lease))
+}
+
+/// Reads the LoginRequest a session has to start with.
+pub async fn read_login(socket: &mut TcpStream) -> Result<LoginRequest, SessionError> {
+    let mut buffer = [0u8; MAX_FRAME_LEN];
+    let frame = timeout(SESSION_TIMEOUT, read_frame_async(socket, &mut buffer))
+        .await
+        .map_err(|_| SessionError::TimedOut)??;
+    match decode_client(frame)? {
+        ClientPacket::LoginRequest(login) => Ok(login),
+        _ => Err(SessionError::UnexpectedPacket(frame[0])),
+    }
+}
+
+/// Writes a packet before the socket is split, i.e. the logon reply and any replay.
+pub async fn write_packet(
+    socket: &mut TcpStream,
+    packet: &ServerPacket<'_>,
+    scratch: &mut Vec<u8>,
+) -> Result<(), FrameError> {
+    encode_frame_with(scratch, |out| encode_server(packet, out))?;
+    socket.write_all(scratch).await?;
+    Ok(())
+}
+
+/// Reads a logged on client's packets and forwards the application messages they carry until
+/// the client logs out, disconnects or stays silent for the session timeout.
+pub async fn read_session<T>(
+    mut rx_client: OwnedReadHalf,
+    addr: SocketAddr,
+    decode: fn(&[u8]) -> Result<T, CodecError>,
+    tx_inbound: mpsc::Sender<T>,
+) {
+    let mut buffer = [0u8; MAX_FRAME_LEN];
+    loop {
+        let frame = match timeout(
+            SESSION_TIMEOUT,
+            read_frame_async(&mut rx_client, &mut buffer),
+        )
+        .await
+        {
+            Ok(Ok(frame)) => frame,
+            Ok(Err(FrameError::Closed)) => return,
+            Ok(Err(e)) => {
+                eprintln!("Closing session {}: {}", addr, e);
+                return;
+            }
+            Err(_) => {
+                eprintln!("Closing session {}: no heartbeat", addr);
+                return;
+            }
+        };
+
+        let message = match decode_client(frame) {
+            Ok(ClientPacket::UnsequencedData(payload)) => decode(payload),
+            Ok(ClientPacket::ClientHeartbeat) => continue,
+            Ok(ClientPacket::LogoutRequest) => {
+                println!("Session {} logged out", addr);
+                return;
+            }
+            Ok(ClientPacket::LoginRequest(_)) => {
+                eprintln!("Closing session {}: already logged on", addr);
+                return;
+            }
+            Err(e) => Err(e),
+        };
+
+        match message {
+            Ok(message) => {
+                if tx_inbound.send(message).await.is_err() {
+                    return;
+                }
+            }
+            Err(e) => {
+                eprintln!("Closing session {}: {}", addr, e);
+                return;
+            }
+        }
+    }
+}
+
+/// Writes a logged on client's SequencedData and keeps the session alive with heartbeats.
+pub struct SessionWriter<'a> {
+    tx_client: OwnedWriteHalf,
+    store: Option<&'a mut RetransmitStore>,
+    scratch: Vec<u8>,
+    last_sent: Instant,
+}
+
+impl<'a> SessionWriter<'a> {
+    pub fn new(tx_client: OwnedWriteHalf, store: &'a mut RetransmitStore) -> SessionWriter<'a> {
+        SessionWriter {
+            tx_client,
+            store: Some(store),
+            scratch: Vec::new(),
+            last_sent: Instant::now(),
+        }
+    }
+
+    /// For a session whose data is replayed from elsewhere, e.g. a drop copy session.
+    pub fn without_store(tx_client: OwnedWriteHalf) -> SessionWriter<'a> {
+        SessionWriter {
+            tx_client,
+            store: None,
+            scratch: Vec::new(),
+            last_sent: Instant::now(),
+        }
+    }
+
+    /// Sends the payload `encode` appends as SequencedData. It is stored first, so it can be
+    /// replayed if the write fails.
+    pub async fn send_data(&mut self, encode: impl FnOnce(&mut Vec<u8>)) -> Result<(), FrameError> {
+        encode_frame_with(&mut self.scratch, |out| {
+            out.push(PACKET_SEQUENCED_DATA);
+            encode(out);
+        })?;
+        if let Some(store) = self.store.as_mut() {
+            store.push(&self.scratch[FRAME_PREFIX_LEN + 1..]);
+        }
+        self.write().await
+    }
+
+    /// Sends a heartbeat if nothing has been sent for a heartbeat interval, call it at least
+    /// that often.
+    pub async fn heartbeat(&mut self) -> Result<(), FrameError> {
+        if self.last_sent.elapsed() < HEARTBEAT_INTERVAL {
+            return Ok(());
+        }
+        self.send_packet(&ServerPacket::ServerHeartbeat).await
+    }
+
+    /// Tells the client the gateway is ending the session.
+    pub async fn end_session(&mut self) -> Result<(), FrameError> {
+        self.send_packet(&ServerPacket::EndOfSession).await
+    }
+
+    async fn send_packet(&mut self, packet: &ServerPacket<'_>) -> Result<(), FrameError> {
+        encode_frame_with(&mut self.scratch, |out| encode_server(packet, out))?;
+        self.write().await
+    }
+
+    async fn write(&mut self) -> Result<(), FrameError> {
+        self.tx_client.write_all(&self.scratch).await?;
+        self.last_sent = Instant::now();
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::session::session_registry::SessionRegistry;
+    use common::config::app_config::GatewayUser;
+    use common::session::session_client::{logon_async, read_data_async};
+    use common::session::session_message::LoginRejectReason;
+    use std::sync::Arc;
+    use tokio::net::TcpListener;
+
+    fn users(retransmit_len: usize) -> UserStore {
+        let users = [GatewayUser {
+            username: "trader1".to_owned(),
+            password: "secret".to_owned(),
+            firm: "FIRM".to_owned(),
+            client_id: 11,
+            trusted: false,
+            client_ids: None,
+            drop_copy: false,
+        }];
+        let sessions = Arc::new(SessionRegistry::new(&users));
+        UserStore::new(&users, sessions, retransmit_len)
+    }
+
+    /// Logs trader1 on over loopback, returning the client's end, the gateway's answer and the
+    /// gateway's lease.
+    async fn logon(
+        users: &UserStore,
+        kind: SessionKind,
+        sequence: u64,
+    ) -> (
+        TcpStream,
+        Result<LoginAccepted, SessionError>,
+        Option<SessionLease>,
+    ) {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let login = LoginRequest::new("trader1", "secret").resume(sequence);
+
+        let client = async {
+            let mut stream = TcpStream::connect(addr).await.unwrap();
+            let accepted = logon_async(&mut stream, &login).await;
+            (stream, accepted)
+        };
+        let gateway = async {
+            let (mut socket, addr) = listener.accept().await.unwrap();
+            accept_logon(&mut socket, addr, users, kind).await.unwrap()
+        };
+        let ((stream, accepted), lease) = tokio::join!(client, gateway);
+        (stream, accepted, lease)
+    }
+
+    /// Sends `count` messages on the session and disconnects it.
+    fn send_and_disconnect(mut lease: SessionLease, count: u64) {
+        for n in 1..=count {
+            lease.state().store.push(format!("msg {}", n).as_bytes());
+        }
+    }
+
+    /// What the gateway replayed, up to the socket it closed after the logon.
+    async fn replayed(stream: &mut TcpStream) -> Vec<String> {
+        let mut buffer = [0u8; MAX_FRAME_LEN];
+        let mut payloads = Vec::new();
+        while let Ok(payload) = read_data_async(stream, &mut buffer).await {
+            payloads.push(String::from_utf8(payload.to_vec()).unwrap());
+        }
+        payloads
+    }
+
+    #[tokio::test]
+    async fn relogon_replays_from_the_requested_sequence() {
+        let users = users(10);
+        let (_, accepted, lease) = logon(&users, SessionKind::Engine, 0).await;
+        assert_eq!(accepted.unwrap().sequence, 1);
+        send_and_disconnect(lease.unwrap(), 5);
+
+        let (mut stream, accepted, _lease) = logon(&users, SessionKind::Engine, 3).await;
+
+        assert_eq!(accepted.unwrap().sequence, 3);
+        assert_eq!(replayed(&mut stream).await, ["msg 3", "msg 4", "msg 5"]);
+    }
+
+    #[tokio::test]
+    async fn relogon_from_before_the_store_replays_what_is_retained() {
+        let users = users(2);
+        let (_, _, lease) = logon(&users, SessionKind::Engine, 0).await;
+        send_and_disconnect(lease.unwrap(), 5);
+
+        let (mut stream, accepted, _lease) = logon(&users, SessionKind::Engine, 1).await;
+
+        assert_eq!(accepted.unwrap().sequence, 4);
+        assert_eq!(replayed(&mut stream).await, ["msg 4", "msg 5"]);
+    }
+
+    #[tokio::test]
+    async fn relogon_without_a_sequence_resumes_at_the_next_one() {
+        let users = users(10);
+        let (_, _, lease) = logon(&users, SessionKind::Engine, 0).await;
+        send_and_disconnect(lease.unwrap(), 5);
+
+        let (mut stream, accepted, lease) = logon(&users, SessionKind::Engine, 0).await;
+
+        assert_eq!(accepted.unwrap().sequence, 6);
+        assert!(replayed(&mut stream).await.is_empty());
+
+        // sequencing carries on from the previous session
+        assert_eq!(lease.unwrap().state().store.push(b"msg 6"), 6);
+    }
+
+    #[tokio::test]
+    async fn a_session_on_the_other_port_starts_from_the_first_sequence() {
+        let users = users(10);
+        let (_, _, lease) = logon(&users, SessionKind::Engine, 0).await;
+        send_and_disconnect(lease.unwrap(), 5);
+
+        let (mut stream, accepted, _lease) = logon(&users, SessionKind::Ouch, 3).await;
+
+        assert_eq!(accepted.unwrap().sequence, 1);
+        assert!(replayed(&mut stream).await.is_empty());
+    }
+
+    #[tokio::test]
+    async fn a_user_has_one_live_session() {
+        let users = users(10);
+        let (_, _, _live) = logon(&users, SessionKind::Engine, 0).await;
+
+        let (_, accepted, lease) = logon(&users, SessionKind::Engine, 0).await;
+
+        assert!(matches!(
+            accepted,
+            Err(SessionError::Rejected(
+                LoginRejectReason::SessionUnavailable
+            ))
+        ));
+        assert!(lease.is_none());
+    }
+}
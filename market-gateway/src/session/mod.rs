@@ -0,0 +1,5 @@
+pub mod gateway_session;
+pub mod retransmit_store;
+pub mod session_registry;
+pub mod session_state;
+pub mod user_store;
@@ -0,0 +1,115 @@
+use std::collections::VecDeque;
+
+/// A session's outbound sequence and the payloads of its most recent SequencedData, kept for
+/// replay when the client logs on again.
+pub struct RetransmitStore {
+    next_sequence: u64,
+    retained: VecDeque<Vec<u8>>,
+    capacity: usize,
+}
+
+impl RetransmitStore {
+    pub fn new(capacity: usize) -> RetransmitStore {
+        RetransmitStore {
+            next_sequence: 1,
+            retained: VecDeque::with_capacity(capacity),
+            capacity,
+        }
+    }
+
+    /// Assigns the payload the next sequence number. Once full the oldest payload is dropped and
+    /// its buffer reused.
+    pub fn push(&mut self, payload: &[u8]) -> u64 {
+        let mut buffer = if self.retained.len() == self.capacity {
+            self.retained.pop_front().unwrap_or_default()
+        } else {
+            Vec::new()
+        };
+        buffer.clear();
+        buffer.extend_from_slice(payload);
+        self.retained.push_back(buffer);
+
+        let sequence = self.next_sequence;
+        self.next_sequence += 1;
+        sequence
+    }
+
+    /// Where a client asking for `requested` is resumed: the requested sequence if it is still
+    /// retained, the oldest retained one if it is older, the next one if it is 0 or ahead of
+    /// anything sent.
+    pub fn resume_from(&self, requested: u64) -> u64 {
+        if requested == 0 || requested > self.next_sequence {
+            return self.next_sequence;
+        }
+        requested.max(self.first_retained())
+    }
+
+    /// The retained payloads from `sequence` on, oldest first.
+    pub fn replay(&self, sequence: u64) -> impl Iterator<Item = &[u8]> {
+        let skip = sequence.saturating_sub(self.first_retained()) as usize;
+        self.retained.iter().skip(skip).map(Vec::as_slice)
+    }
+
+    fn first_retained(&self) -> u64 {
+        self.next_sequence - self.retained.len() as u64
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn store(capacity: usize, pushed: u64) -> RetransmitStore {
+        let mut store = RetransmitStore::new(capacity);
+        for sequence in 1..=pushed {
+            assert_eq!(store.push(&sequence.to_be_bytes()), sequence);
+        }
+        store
+    }
+
+    fn replayed(store: &RetransmitStore, sequence: u64) -> Vec<u64> {
+        store
+            .replay(sequence)
+            .map(|payload| u64::from_be_bytes(payload.try_into().unwrap()))
+            .collect()
+    }
+
+    #[test]
+    fn replay_starts_at_the_requested_sequence() {
+        let store = store(10, 5);
+
+        assert_eq!(store.resume_from(3), 3);
+        assert_eq!(replayed(&store, 3), [3, 4, 5]);
+        assert_eq!(replayed(&store, 1), [1, 2, 3, 4, 5]);
+    }
+
+    #[test]
+    fn nothing_is_replayed_for_a_client_that_is_up_to_date() {
+        let store = store(10, 5);
+
+        // 0 is a new client, anything past the next sequence is from an earlier store
+        for requested in [0, 6, 100] {
+            assert_eq!(store.resume_from(requested), 6);
+        }
+        assert!(replayed(&store, 6).is_empty());
+    }
+
+    #[test]
+    fn a_full_store_drops_the_oldest_payloads() {
+        let store = store(3, 5);
+
+        assert_eq!(store.resume_from(1), 3);
+        assert_eq!(store.resume_from(4), 4);
+        assert_eq!(replayed(&store, 3), [3, 4, 5]);
+        assert_eq!(replayed(&store, 4), [4, 5]);
+    }
+
+    #[test]
+    fn an_empty_store_resumes_at_the_first_sequence() {
+        let store = store(3, 0);
+
+        assert_eq!(store.resume_from(0), 1);
+        assert_eq!(store.resume_from(1), 1);
+        assert!(replayed(&store, 1).is_empty());
+    }
+}
@@ -0,0 +1,336 @@
+use crate::session::user_store::SessionIdentity;
+use common::config::app_config::GatewayUser;
+use common::transport::sequenced_message::EngineMessage;
+use std::collections::{HashMap, HashSet};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use tokio::sync::mpsc;
+use tokio::sync::mpsc::error::TrySendError;
+
+/// Engine output queued for one session. A session that falls this far behind is dropped rather
+/// than holding up every other session.
+const SESSION_QUEUE_LEN: usize = 100_000;
+
+/// Which sessions see which engine output. Acks, rejects and executions go to the sessions
+/// trading under the client id of the order (both sides of an execution) and to the drop copy
+/// sessions of the client's firm, engine commands and errors go to every session.
+pub struct SessionRegistry {
+    next_session_id: AtomicU64,
+    routes: Mutex<Routes>,
+}
+
+struct Routes {
+    sessions: HashMap<u64, RoutedSession>,
+    by_client: HashMap<u32, Vec<u64>>,
+    drop_copies: HashMap<String, Vec<u64>>,
+    client_firms: HashMap<u32, String>,
+    /// Client ids of the configured users, never taken on by a trusted session.
+    user_clients: HashSet<u32>,
+}
+
+struct RoutedSession {
+    tx_session: mpsc::Sender<EngineMessage>,
+    firm: String,
+    client_ids: Vec<u32>,
+    drop_copy: bool,
+}
+
+/// A session's registration, removed when dropped.
+pub struct SessionRoute {
+    id: u64,
+    registry: Arc<SessionRegistry>,
+}
+
+impl SessionRegistry {
+    pub fn new(users: &[GatewayUser]) -> SessionRegistry {
+        SessionRegistry {
+            next_session_id: AtomicU64::new(1),
+            routes: Mutex::new(Routes {
+                sessions: HashMap::new(),
+                by_client: HashMap::new(),
+                drop_copies: HashMap::new(),
+                client_firms: users
+                    .iter()
+                    .map(|user| (user.client_id, user.firm.clone()))
+                    .collect(),
+                user_clients: users.iter().map(|user| user.client_id).collect(),
+            }),
+        }
+    }
+
+    /// Registers a logged on session under its client id, and for a drop copy user under its
+    /// firm, and returns the queue its engine output is delivered to.
+    pub fn register(
+        self: &Arc<Self>,
+        identity: &SessionIdentity,
+    ) -> (SessionRoute, mpsc::Receiver<EngineMessage>) {
+        let id = self.next_session_id.fetch_add(1, Ordering::Relaxed);
+        let (tx_session, rx_session) = mpsc::channel(SESSION_QUEUE_LEN);
+
+        let mut routes = self.routes.lock().unwrap();
+        routes.sessions.insert(
+            id,
+            RoutedSession {
+                tx_session,
+                firm: identity.firm.clone(),
+                client_ids: Vec::new(),
+                drop_copy: identity.drop_copy,
+            },
+        );
+        if identity.drop_copy {
+            routes
+                .drop_copies
+                .entry(identity.firm.clone())
+                .or_default()
+                .push(id);
+        }
+        routes.add_client(id, identity.client_id);
+
+        let route = SessionRoute {
+            id,
+            registry: self.clone(),
+        };
+        (route, rx_session)
+    }
+
+    pub fn dispatch(&self, message: &EngineMessage) {
+        if matches!(
+            message,
+            EngineMessage::EngineCommand(_) | EngineMessage::EngineError(_)
+        ) {
+            let mut routes = self.routes.lock().unwrap();
+            let recipients: Vec<u64> = routes.sessions.keys().copied().collect();
+            routes.deliver(&recipients, message);
+            return;
+        }
+        let Some(owners) = owners(message) else {
+            return;
+        };
+
+        let mut routes = self.routes.lock().unwrap();
+        let mut recipients = Vec::new();
+        for owner in owners.into_iter().filter(|&client_id| client_id != 0) {
+            if let Some(sessions) = routes.by_client.get(&owner) {
+                recipients.extend_from_slice(sessions);
+            }
+            let drop_copies = routes
+                .client_firms
+                .get(&owner)
+                .and_then(|firm| routes.drop_copies.get(firm));
+            if let Some(sessions) = drop_copies {
+                recipients.extend_from_slice(sessions);
+            }
+        }
+        recipients.sort_unstable();
+        recipients.dedup();
+        routes.deliver(&recipients, message);
+    }
+
+    /// The firm of a configured user's client id or of a client entered through a trusted
+    /// session.
+    pub fn firm_of(&self, client_id: u32) -> Option<String> {
+        self.routes
+            .lock()
+            .unwrap()
+            .client_firms
+            .get(&client_id)
+            .cloned()
+    }
+
+    fn unregister(&self, id: u64) {
+        self.routes.lock().unwrap().remove(id);
+    }
+}
+
+/// The client ids whose order state a message reports, 0 where there is no second client or
+/// the side was self match prevented. `None` for anything that isn't a client's output.
+pub fn owners(message: &EngineMessage) -> Option<[u32; 2]> {
+    match message {
+        EngineMessage::NewOrderAck(ack) => Some([ack.client_id, 0]),
+        EngineMessage::CancelOrderAck(ack) => Some([ack.client_id, 0]),
+        EngineMessage::OrderReject(reject) => Some([reject.client_id, 0]),
+        EngineMessage::TradeExecution(execution) => {
+            Some([execution.bid_client_id, execution.ask_client_id])
+        }
+        _ => None,
+    }
+}
+
+impl Routes {
+    fn add_client(&mut self, id: u64, client_id: u32) {
+        let Some(session) = self.sessions.get_mut(&id) else {
+            return;
+        };
+        if session.client_ids.contains(&client_id) {
+            return;
+        }
+
+        session.client_ids.push(client_id);
+        self.client_firms
+            .entry(client_id)
+            .or_insert_with(|| session.firm.clone());
+        self.by_client.entry(client_id).or_default().push(id);
+    }
+
+    fn deliver(&mut self, recipients: &[u64], message: &EngineMessage) {
+        for id in recipients {
+            let Some(session) = self.sessions.get(id) else {
+                continue;
+            };
+            match session.tx_session.try_send(message.clone()) {
+                Ok(()) => {}
+                Err(TrySendError::Full(_)) => {
+                    eprintln!("Dropping session {}, engine output backed up", id);
+                    self.remove(*id);
+                }
+                Err(TrySendError::Closed(_)) => self.remove(*id),
+            }
+        }
+    }
+
+    /// Closes the session's queue, the session ends once it has drained it.
+    fn remove(&mut self, id: u64) {
+        let Some(session) = self.sessions.remove(&id) else {
+            return;
+        };
+
+        for client_id in &session.client_ids {
+            if let Some(sessions) = self.by_client.get_mut(client_id) {
+                sessions.retain(|&other| other != id);
+                if sessions.is_empty() {
+                    self.by_client.remove(client_id);
+                }
+            }
+        }
+        if session.drop_copy {
+            if let Some(sessions) = self.drop_copies.get_mut(&session.firm) {
+                sessions.retain(|&other| other != id);
+            }
+        }
+    }
+}
+
+impl SessionRoute {
+    /// Routes a client's engine output to this session as well, used by trusted sessions for
+    /// each client they enter orders for, the client counts as one of the session's firm.
+    /// Returns false for a configured user's client id, whose output stays with that user.
+    pub fn add_client(&self, client_id: u32) -> bool {
+        let mut routes = self.registry.routes.lock().unwrap();
+        if routes.user_clients.contains(&client_id) {
+            return false;
+        }
+        routes.add_client(self.id, client_id);
+        true
+    }
+}
+
+impl Drop for SessionRoute {
+    fn drop(&mut self) {
+        self.registry.unregister(self.id);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use common::config::app_config::ClientIdRange;
+    use common::types::latency_trace::LatencyTrace;
+    use common::types::order::{NewOrderAck, TimeInForce};
+    use common::types::side::Side;
+
+    fn user(username: &str, client_id: u32, trusted: bool) -> GatewayUser {
+        GatewayUser {
+            username: username.to_owned(),
+            password: "secret".to_owned(),
+            firm: "FIRM".to_owned(),
+            client_id,
+            trusted,
+            client_ids: trusted.then_some(ClientIdRange {
+                first: 1000,
+                last: 1999,
+            }),
+            drop_copy: false,
+        }
+    }
+
+    fn identity(user: &GatewayUser) -> SessionIdentity {
+        SessionIdentity {
+            username: user.username.clone(),
+            firm: user.firm.clone(),
+            client_id: user.client_id,
+            trusted: user.trusted,
+            client_ids: user.client_ids,
+            drop_copy: user.drop_copy,
+        }
+    }
+
+    fn ack(client_id: u32) -> EngineMessage {
+        EngineMessage::NewOrderAck(NewOrderAck {
+            client_id,
+            client_order_id: 1,
+            order_id: 1,
+            instrument: [0; 16],
+            side: Side::Buy,
+            px: 100,
+            qty: 1,
+            qty_rem: 1,
+            time_in_force: TimeInForce::GTC,
+            ack_time: 0,
+            trace: LatencyTrace::default(),
+        })
+    }
+
+    #[test]
+    fn a_trusted_session_cannot_take_on_a_configured_user() {
+        let users = [user("router", 900, true), user("trader", 11, false)];
+        let registry = Arc::new(SessionRegistry::new(&users));
+        let (router, mut rx_router) = registry.register(&identity(&users[0]));
+        let (_trader, mut rx_trader) = registry.register(&identity(&users[1]));
+
+        assert!(!router.add_client(11));
+        assert!(router.add_client(1000));
+
+        registry.dispatch(&ack(11));
+        registry.dispatch(&ack(1000));
+
+        assert!(
+            matches!(rx_trader.try_recv(), Ok(EngineMessage::NewOrderAck(a)) if a.client_id == 11)
+        );
+        assert!(
+            matches!(rx_router.try_recv(), Ok(EngineMessage::NewOrderAck(a)) if a.client_id == 1000)
+        );
+        assert!(rx_router.try_recv().is_err());
+        assert!(rx_trader.try_recv().is_err());
+    }
+
+    #[test]
+    fn drop_copy_sessions_get_the_output_of_their_firm() {
+        let mut users = [
+            user("trader", 11, false),
+            user("other", 21, false),
+            user("compliance", 12, false),
+        ];
+        users[1].firm = "OTHER".to_owned();
+        users[2].drop_copy = true;
+        let registry = Arc::new(SessionRegistry::new(&users));
+        let (_trader, mut rx_trader) = registry.register(&identity(&users[0]));
+        let (compliance, mut rx_compliance) = registry.register(&identity(&users[2]));
+
+        registry.dispatch(&ack(11));
+        registry.dispatch(&ack(21));
+
+        assert!(
+            matches!(rx_trader.try_recv(), Ok(EngineMessage::NewOrderAck(a)) if a.client_id == 11)
+        );
+        assert!(
+            matches!(rx_compliance.try_recv(), Ok(EngineMessage::NewOrderAck(a)) if a.client_id == 11)
+        );
+        assert!(rx_compliance.try_recv().is_err());
+
+        // no longer routed once the session is gone
+        drop(compliance);
+        registry.dispatch(&ack(11));
+        assert!(rx_trader.try_recv().is_ok());
+        assert!(rx_compliance.try_recv().is_err());
+    }
+}
@@ -0,0 +1,49 @@
+use crate::ouch::ouch_session::OuchSession;
+use crate::session::retransmit_store::RetransmitStore;
+use crate::session::session_registry::{SessionRegistry, SessionRoute};
+use crate::session::user_store::SessionIdentity;
+use common::transport::sequenced_message::EngineMessage;
+use std::sync::Arc;
+use tokio::sync::mpsc;
+
+/// The port a session is on, a user's state is only resumed on the same kind of port.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SessionKind {
+    Engine,
+    Ouch,
+}
+
+/// What a user's session keeps while the user is disconnected: its registration, so engine
+/// output is still queued for it, its outbound sequence and retransmission store and, on the
+/// OUCH port, its order tokens.
+pub struct SessionState {
+    pub kind: SessionKind,
+    pub route: Arc<SessionRoute>,
+    pub rx_engine_queue: mpsc::Receiver<EngineMessage>,
+    pub store: RetransmitStore,
+    pub ouch: Option<OuchSession>,
+}
+
+impl SessionState {
+    pub fn new(
+        kind: SessionKind,
+        sessions: &Arc<SessionRegistry>,
+        identity: &SessionIdentity,
+        retransmit_len: usize,
+    ) -> SessionState {
+        let (route, rx_engine_queue) = sessions.register(identity);
+        SessionState {
+            kind,
+            route: Arc::new(route),
+            rx_engine_queue,
+            store: RetransmitStore::new(retransmit_len),
+            ouch: None,
+        }
+    }
+
+    /// Whether a new session of `kind` can carry on where this one stopped. Not once the
+    /// registry has dropped it for falling behind.
+    pub fn resumable(&self, kind: SessionKind) -> bool {
+        self.kind == kind && !self.rx_engine_queue.is_closed()
+    }
+}
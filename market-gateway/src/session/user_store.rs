@@ -0,0 +1,125 @@
+use crate::session::session_registry::SessionRegistry;
+use crate::session::session_state::{SessionKind, SessionState};
+use common::config::app_config::{ClientIdRange, GatewayUser};
+use common::session::session_message::{
+    credential, LoginRejectReason, LoginRequest, CREDENTIAL_LEN,
+};
+use common::util::secret::secrets_match;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+
+/// Who a session is logged on as, fixed for the life of the session.
+#[derive(Debug, Clone)]
+pub struct SessionIdentity {
+    pub username: String,
+    pub firm: String,
+    pub client_id: u32,
+    pub trusted: bool,
+    /// The client ids a trusted session may enter orders for.
+    pub client_ids: Option<ClientIdRange>,
+    pub drop_copy: bool,
+}
+
+impl SessionIdentity {
+    pub fn may_trade_for(&self, client_id: u32) -> bool {
+        self.client_ids
+            .is_some_and(|client_ids| client_ids.contains(client_id))
+    }
+}
+
+/// The configured gateway users and their sessions. A user has at most one live session, the
+/// state of a disconnected one is kept for the user's next logon.
+pub struct UserStore {
+    users: HashMap<[u8; CREDENTIAL_LEN], GatewayUser>,
+    sessions: Arc<SessionRegistry>,
+    retransmit_len: usize,
+    user_sessions: Arc<Mutex<HashMap<String, UserSession>>>,
+}
+
+enum UserSession {
+    LoggedOn,
+    Disconnected(Box<SessionState>),
+}
+
+/// Held by a live session, the user can log on again once it is dropped.
+pub struct SessionLease {
+    pub identity: SessionIdentity,
+    state: Option<SessionState>,
+    user_sessions: Arc<Mutex<HashMap<String, UserSession>>>,
+}
+
+impl UserStore {
+    pub fn new(
+        users: &[GatewayUser],
+        sessions: Arc<SessionRegistry>,
+        retransmit_len: usize,
+    ) -> UserStore {
+        UserStore {
+            users: users
+                .iter()
+                .map(|user| (credential(&user.username), user.clone()))
+                .collect(),
+            sessions,
+            retransmit_len,
+            user_sessions: Arc::new(Mutex::new(HashMap::new())),
+        }
+    }
+
+    /// Resumes the user's previous session if it was on the same kind of port, or starts a new
+    /// one.
+    pub fn logon(
+        &self,
+        login: &LoginRequest,
+        kind: SessionKind,
+    ) -> Result<SessionLease, LoginRejectReason> {
+        let user = self
+            .users
+            .get(&login.username)
+            .filter(|user| credentials_match(&credential(&user.password), &login.password))
+            .ok_or(LoginRejectReason::NotAuthorized)?;
+
+        let identity = SessionIdentity {
+            username: user.username.clone(),
+            firm: user.firm.clone(),
+            client_id: user.client_id,
+            trusted: user.trusted,
+            client_ids: user.client_ids,
+            drop_copy: user.drop_copy,
+        };
+
+        let mut user_sessions = self.user_sessions.lock().unwrap();
+        let state = match user_sessions.insert(user.username.clone(), UserSession::LoggedOn) {
+            Some(UserSession::LoggedOn) => return Err(LoginRejectReason::SessionUnavailable),
+            Some(UserSession::Disconnected(state)) if state.resumable(kind) => *state,
+            _ => SessionState::new(kind, &self.sessions, &identity, self.retransmit_len),
+        };
+
+        Ok(SessionLease {
+            identity,
+            state: Some(state),
+            user_sessions: self.user_sessions.clone(),
+        })
+    }
+}
+
+impl SessionLease {
+    pub fn state(&mut self) -> &mut SessionState {
+        self.state.as_mut().unwrap()
+    }
+}
+
+impl Drop for SessionLease {
+    fn drop(&mut self) {
+        if let Some(state) = self.state.take() {
+            self.user_sessions.lock().unwrap().insert(
+                self.identity.username.clone(),
+                UserSession::Disconnected(Box::new(state)),
+            );
+        }
+    }
+}
+
+/// Compares every byte so the time taken doesn't depend on where they differ.
+pub fn credentials_match(expected: &[u8; CREDENTIAL_LEN], actual: &[u8; CREDENTIAL_LEN]) -> bool {
+    secrets_match(expected, actual)
+}
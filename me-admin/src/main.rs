@@ -0,0 +1,182 @@
+use common::codec::framing::{read_message, write_frame, write_message};
+use common::config::config_loader::load_config;
+use common::transport::sequenced_message::EngineMessage;
+use common::types::admin::{
+    AdminCommand, AdminError, AdminRequest, AdminResponse, AdminResponseBody,
+};
+use common::types::fixed_point::FixedPointScale;
+use common::types::instrument::Instrument;
+use common::types::risk::RiskLimitKind;
+use common::types::side::Side;
+use std::env;
+use std::error::Error;
+use std::net::TcpStream;
+use std::process::exit;
+
+const MAX_RESPONSE_FRAME: usize = 256;
+
+const USAGE: &str = "\
+usage: me-admin [--config path] [--addr host:port] [--token token] <command>
+
+commands:
+  status
+  halt <instrument>
+  resume <instrument>
+  dump-book <instrument>
+  cancel-client <instrument> <client_id>
+  snapshot <instrument>
+  shutdown <instrument>
+  risk-limits
+  set-risk <max_order_qty|max_order_notional|max_open_orders|max_open_notional|max_price_deviation_bps|max_position> <value>
+
+the token defaults to ME_ADMIN_TOKEN, the address to the configured gateway.admin_port";
+
+fn main() {
+    if let Err(e) = run() {
+        eprintln!("me-admin: {}", e);
+        exit(1);
+    }
+}
+
+fn run() -> Result<(), Box<dyn Error>> {
+    let (config, args) = load_config(env::args().skip(1))?;
+
+    let mut addr = format!("127.0.0.1:{}", config.gateway.admin_port);
+    let mut token = env::var("ME_ADMIN_TOKEN").ok();
+    let mut positional = Vec::new();
+
+    let mut args = args.into_iter();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--addr" => addr = args.next().ok_or("--addr needs a value")?,
+            "--token" => token = Some(args.next().ok_or("--token needs a value")?),
+            "-h" | "--help" => {
+                println!("{}", USAGE);
+                return Ok(());
+            }
+            _ => positional.push(arg),
+        }
+    }
+
+    let token = token.ok_or("no admin token, pass --token or set ME_ADMIN_TOKEN")?;
+    let request = parse_request(&positional).map_err(|e| format!("{}\n\n{}", e, USAGE))?;
+
+    let mut stream = TcpStream::connect(&addr)
+        .map_err(|e| format!("failed to connect to gateway admin port {}: {}", addr, e))?;
+
+    write_frame(&mut stream, token.as_bytes())?;
+    write_message(
+        &mut stream,
+        &EngineMessage::AdminRequest(request),
+        &mut Vec::new(),
+    )?;
+
+    loop {
+        let response = read_response(&mut stream)
+            .map_err(|_| "connection closed by gateway, check the admin token")?;
+        print_response(&response, &config.engine.scale);
+        if response.last {
+            return Ok(());
+        }
+    }
+}
+
+fn parse_request(args: &[String]) -> Result<AdminRequest, String> {
+    let command_name = args.first().ok_or("missing command")?;
+    let instrument = |command: AdminCommand| -> Result<(AdminCommand, [u8; 16]), String> {
+        let symbol = args
+            .get(1)
+            .ok_or(format!("{} needs an instrument", command.name()))?;
+        Ok((command, Instrument::str_to_fixed_char_buffer(symbol)))
+    };
+
+    let (command, instrument) = match command_name.as_str() {
+        "status" => (AdminCommand::Status, [0; 16]),
+        "risk-limits" => (AdminCommand::GetRiskLimits, [0; 16]),
+        "set-risk" => {
+            let limit = RiskLimitKind::str_to_val(args.get(1).ok_or("set-risk needs a limit")?)?;
+            let value = args
+                .get(2)
+                .ok_or("set-risk needs a value")?
+                .parse::<u64>()
+                .map_err(|e| format!("invalid limit value: {}", e))?;
+            (AdminCommand::SetRiskLimit(limit, value), [0; 16])
+        }
+        "halt" => instrument(AdminCommand::HaltInstrument)?,
+        "resume" => instrument(AdminCommand::ResumeInstrument)?,
+        "dump-book" => instrument(AdminCommand::DumpBook)?,
+        "snapshot" => instrument(AdminCommand::Snapshot)?,
+        "shutdown" => instrument(AdminCommand::Shutdown)?,
+        "cancel-client" => {
+            let client_id = args
+                .get(2)
+                .ok_or("cancel-client needs a client id")?
+                .parse::<u32>()
+                .map_err(|e| format!("invalid client id: {}", e))?;
+            instrument(AdminCommand::CancelClientOrders(client_id))?
+        }
+        _ => return Err(format!("unknown command {}", command_name)),
+    };
+
+    Ok(AdminRequest {
+        // assigned by the gateway
+        request_id: 0,
+        instrument,
+        command,
+    })
+}
+
+fn read_response(stream: &mut TcpStream) -> Result<AdminResponse, Box<dyn Error>> {
+    let mut buffer = [0u8; MAX_RESPONSE_FRAME];
+    match read_message(stream, &mut buffer)? {
+        EngineMessage::AdminResponse(response) => Ok(response),
+        _ => Err("unexpected message from gateway".into()),
+    }
+}
+
+fn print_response(response: &AdminResponse, scale: &FixedPointScale) {
+    match &response.body {
+        AdminResponseBody::Done => println!("ok"),
+        AdminResponseBody::Error(error) => {
+            let reason = match error {
+                AdminError::UnknownInstrument => "instrument is not traded on this engine",
+                AdminError::SnapshotNotConfigured => "engine has no snapshot_path configured",
+                AdminError::SnapshotFailed => "engine failed to write the snapshot",
+                AdminError::UnsupportedCommand => "command is not supported here",
+            };
+            println!("error: {}", reason);
+        }
+        AdminResponseBody::Status(status) => {
+            println!("{:<18}{}", "instrument", Instrument::fixed_char_buffer_to_str(&status.instrument));
+            println!("{:<18}{}", "halted", status.halted);
+            println!("{:<18}{}", "orders on book", status.orders_on_book);
+            println!("{:<18}{}", "bid levels", status.bid_levels);
+            println!("{:<18}{}", "ask levels", status.ask_levels);
+            println!("{:<18}{}", "best bid", scale.px_to_str(status.best_bid));
+            println!("{:<18}{}", "best ask", scale.px_to_str(status.best_ask));
+            println!("{:<18}{}", "orders processed", status.orders_processed);
+            println!("{:<18}{}", "executions", status.executions);
+        }
+        AdminResponseBody::BookLevel(level) => {
+            println!(
+                "{:<5}{:>14}{:>16}{:>8}",
+                Side::val_to_str(level.side),
+                scale.px_to_str(level.px),
+                scale.qty_to_str(level.qty),
+                level.num_orders
+            );
+        }
+        AdminResponseBody::OrdersCancelled(cancelled) => println!("cancelled {} orders", cancelled),
+        AdminResponseBody::RiskLimits(limits) => {
+            println!("{:<25}{}", "max_order_qty", limits.max_order_qty);
+            println!("{:<25}{}", "max_order_notional", limits.max_order_notional);
+            println!("{:<25}{}", "max_open_orders", limits.max_open_orders);
+            println!("{:<25}{}", "max_open_notional", limits.max_open_notional);
+            println!(
+                "{:<25}{}",
+                "max_price_deviation_bps", limits.max_price_deviation_bps
+            );
+            println!("{:<25}{}", "max_position", limits.max_position);
+        }
+    }
+}
@@ -1,16 +1,19 @@
 import {writable} from 'svelte/store';
 
 import {closedTrades, openOrders} from "$lib/stores/state.ts";
+import {clientId, type Credentials} from "$lib/stores/user.ts";
 
 export type EngineMessage =
+    | { type: "ApiLogonAcceptedResponse"; client_id: number }
+    | { type: "ApiLogonRejectResponse"; reason: string }
     | {
     type: "ApiOrderAckResponse";
     client_id: number;
     instrument: string;
     order_id: number;
     side: string;
-    px: number;
-    qty: number;
+    px: string;
+    qty: string;
     ack_time: number
 }
     | {
@@ -28,24 +31,28 @@ export type EngineMessage =
     instrument: string;
     order_id: number;
     fill_type: string;
-    exec_px: number;
-    exec_qty: number;
+    exec_px: string;
+    exec_qty: string;
     exec_type: string;
     exec_ns: number
 };
 
 export type OutgoingMessage =
-    | { type: "ApiOrderRequest"; client_id: number; instrument: string; side: "buy" | "sell"; px: number; qty: number; time_in_force: string }
-    | { type: "ApiOrderCancelRequest"; client_id: number; instrument: string; order_id: number };
+    | { type: "ApiLogonRequest"; username: string; password: string }
+    | { type: "ApiOrderRequest"; instrument: string; side: "buy" | "sell"; px: string; qty: string; time_in_force: string }
+    | { type: "ApiOrderCancelRequest"; instrument: string; order_id: number };
 
 export const wsMessages = writable([]);
 let ws: WebSocket;
 
-export function connectWS(clientId: number) {
-    ws = new WebSocket(`ws://localhost:8080/ws/event_stream/${clientId}`);
+let logonRejected = false;
+
+export function connectWS(credentials: Credentials) {
+    ws = new WebSocket("ws://localhost:8080/ws/event_stream");
 
     ws.onopen = () => {
         console.log("Connected to WebSocket");
+        sendJson({type: "ApiLogonRequest", ...credentials});
     };
 
     ws.onmessage = (event) => {
@@ -59,8 +66,12 @@ export function connectWS(clientId: number) {
     };
 
     ws.onclose = () => {
+        if (logonRejected) {
+            console.log("WS closed");
+            return;
+        }
         console.log("WS closed — reconnecting...");
-        setTimeout(() => connectWS(clientId), 1000);
+        setTimeout(() => connectWS(credentials), 1000);
     };
 
     setInterval(() => {
@@ -73,6 +84,15 @@ export function connectWS(clientId: number) {
 function handleEngineMessage(msg: EngineMessage) {
     console.log(msg);
     switch (msg.type) {
+        case "ApiLogonAcceptedResponse":
+            clientId.set(msg.client_id);
+            break;
+
+        case "ApiLogonRejectResponse":
+            console.error("Logon rejected:", msg.reason);
+            logonRejected = true;
+            break;
+
         case "ApiOrderAckResponse":
             // order ack
             openOrders.update(orders => [
@@ -82,8 +102,8 @@ function handleEngineMessage(msg: EngineMessage) {
                     client_id: msg.client_id,
                     instrument: msg.instrument,
                     side: msg.side as "buy" | "sell",
-                    px: msg.px,
-                    qty: msg.qty,
+                    px: Number(msg.px),
+                    qty: Number(msg.qty),
                 }
             ]);
             break;
@@ -100,7 +120,7 @@ function handleEngineMessage(msg: EngineMessage) {
             openOrders.update(orders =>
                 orders.map(o => {
                     if (o.order_id === msg.order_id) {
-                        const newQty = o.qty - msg.exec_qty;
+                        const newQty = o.qty - Number(msg.exec_qty);
                         return newQty > 0 ? {...o, qty: newQty} : null;
                     }
                     return o;
@@ -115,8 +135,8 @@ function handleEngineMessage(msg: EngineMessage) {
                     client_id: msg.client_id,
                     instrument: msg.instrument,
                     side: "buy", // or msg.side if available
-                    px: msg.exec_px,
-                    qty: msg.exec_qty,
+                    px: Number(msg.exec_px),
+                    qty: Number(msg.exec_qty),
                 }
             ]);
             break;
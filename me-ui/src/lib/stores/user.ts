@@ -1,32 +1,21 @@
 import {writable} from "svelte/store";
 import {browser} from "$app/environment";
-import {boundedRandomNumber} from "$lib/utils/utils.ts";
 
-function createClientId() {
-    let initial = 0;
+export type Credentials = { username: string; password: string };
 
+// the oe-api user from config/engine_btc_usd.json unless others are stored
+function loadCredentials(): Credentials {
     if (browser) {
-        const stored = localStorage.getItem("clientId");
-        if (stored) {
-            initial = Number(stored);
-        } else {
-            initial = boundedRandomNumber(100, 1_000_000);
-            localStorage.setItem("clientId", initial.toString());
+        const username = localStorage.getItem("username");
+        const password = localStorage.getItem("password");
+        if (username && password) {
+            return {username, password};
         }
     }
-
-    const {subscribe, set} = writable<number>(initial);
-
-    return {
-        subscribe,
-        // optional method to reset ID
-        reset: () => {
-            if (!browser) return;
-            const id = Math.floor(Math.random() * 999_999) + 1;
-            localStorage.setItem("clientId", id.toString());
-            set(id);
-        }
-    };
+    return {username: "web1", password: "web1-secret"};
 }
 
-export const clientId = createClientId();
+export const credentials = loadCredentials();
+
+// assigned by oe-api when the logon is accepted
+export const clientId = writable<number>(0);
@@ -1,20 +1,27 @@
 use serde::Deserialize;
 
+/// Must be the first message on a connection; orders are entered under the user's client id.
+#[derive(Debug, Deserialize)]
+#[serde(tag = "type")]
+pub struct ApiLogonRequest {
+    pub username: String,
+    pub password: String,
+}
+
 #[derive(Debug, Deserialize)]
 #[serde(tag = "type")]
 pub struct ApiOrderRequest {
-    pub client_id: u32,
     pub instrument: String,
     pub side: String,
-    pub px: u32,
-    pub qty: u32,
+    /// Decimal strings in the instrument's scale, e.g. `"64250.50"`.
+    pub px: String,
+    pub qty: String,
     pub time_in_force: String,
 }
 
 #[derive(Debug, Deserialize)]
 #[serde(tag = "type")]
 pub struct ApiOrderCancelRequest {
-    pub client_id: u32,
     pub instrument: String,
     pub side: String,
     pub order_id: u32,
@@ -27,6 +34,7 @@ pub struct Heartbeat {}
 #[derive(Deserialize, Debug)]
 #[serde(tag = "type")]
 pub enum IncomingMessage {
+    ApiLogonRequest(ApiLogonRequest),
     ApiOrderRequest(ApiOrderRequest),
     ApiOrderCancelRequest(ApiOrderCancelRequest),
     Heartbeat(Heartbeat),
@@ -1,5 +1,17 @@
 use serde::Serialize;
 
+#[derive(Debug, Serialize)]
+#[serde(tag = "type")]
+pub struct ApiLogonAcceptedResponse {
+    pub client_id: u32,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(tag = "type")]
+pub struct ApiLogonRejectResponse {
+    pub reason: String,
+}
+
 #[derive(Debug, Serialize)]
 #[serde(tag = "type")]
 pub struct ApiOrderAckResponse {
@@ -7,8 +19,8 @@ pub struct ApiOrderAckResponse {
     pub instrument: String,
     pub order_id: u32,
     pub side: String,
-    pub px: u32,
-    pub qty: u32,
+    pub px: String,
+    pub qty: String,
     pub ack_time: u64,
 }
 
@@ -22,6 +34,18 @@ pub struct ApiCancelOrderAckResponse {
     pub reason: String,
     pub ack_time: u64,
 }
+#[derive(Debug, Serialize)]
+#[serde(tag = "type")]
+pub struct ApiOrderRejectResponse {
+    pub client_id: u32,
+    pub instrument: String,
+    pub side: String,
+    pub px: String,
+    pub qty: String,
+    pub reason: String,
+    pub reject_time: u64,
+}
+
 #[derive(Debug, Serialize)]
 #[serde(tag = "type")]
 pub struct ApiExecutionReportResponse {
@@ -29,8 +53,8 @@ pub struct ApiExecutionReportResponse {
     pub instrument: String,
     pub order_id: u32,
     pub fill_type: String,
-    pub exec_px: u32,
-    pub exec_qty: u32,
+    pub exec_px: String,
+    pub exec_qty: String,
     pub exec_type: String,
     pub exec_ns: u64,
 }
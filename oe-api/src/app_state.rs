@@ -1,19 +1,45 @@
+use common::config::app_config::OeApiUser;
 use common::transport::sequenced_message::EngineMessage;
+use common::types::fixed_point::FixedPointScale;
+use common::util::secret::secrets_match;
+use common::util::throttle::Throttle;
 use dashmap::DashMap;
+use std::collections::HashMap;
+use std::sync::Arc;
 use tokio::sync::mpsc;
 
 pub struct AppState {
     pub tx_oe_api_queue: mpsc::Sender<EngineMessage>,
     pub tx_engine_to_client_channel: DashMap<u32, mpsc::Sender<EngineMessage>>,
     pub last_client_heartbeat: DashMap<u32, u64>,
+    pub throttle: Arc<Throttle>,
+    pub scale: FixedPointScale,
+    users: HashMap<String, OeApiUser>,
 }
 
 impl AppState {
-    pub fn new(tx_oe_to_gateway: mpsc::Sender<EngineMessage>) -> AppState {
+    pub fn new(
+        tx_oe_to_gateway: mpsc::Sender<EngineMessage>,
+        throttle: Arc<Throttle>,
+        scale: FixedPointScale,
+        users: &[OeApiUser],
+    ) -> AppState {
         AppState {
             tx_oe_api_queue: tx_oe_to_gateway,
             tx_engine_to_client_channel: DashMap::new(),
             last_client_heartbeat: DashMap::new(),
+            throttle,
+            scale,
+            users: users
+                .iter()
+                .map(|user| (user.username.clone(), user.clone()))
+                .collect(),
         }
     }
+
+    /// Client id of the user, or None for an unknown username or a wrong password.
+    pub fn authenticate(&self, username: &str, password: &str) -> Option<u32> {
+        let user = self.users.get(username)?;
+        secrets_match(user.password.as_bytes(), password.as_bytes()).then_some(user.client_id)
+    }
 }
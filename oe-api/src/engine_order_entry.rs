@@ -1,24 +1,35 @@
+use common::codec::message_codec::encode_engine_message;
+use common::session::session_client::{send_data_async, send_packet_async};
+use common::session::session_message::{ClientPacket, HEARTBEAT_INTERVAL};
 use common::transport::sequenced_message::EngineMessage;
-use tokio::io::AsyncWriteExt;
 use tokio::net::tcp::OwnedWriteHalf;
 use tokio::sync::mpsc;
+use tokio::time::timeout;
 
 pub async fn gateway_order_entry(
     mut tx_gateway_stream: OwnedWriteHalf,
     mut rx: mpsc::Receiver<EngineMessage>,
 ) {
     println!("Initialized OE-API -> GW");
+    let mut serialized = Vec::new();
 
-    while let Some(msg_for_engine) = rx.recv().await {
-        let serialized = common::serialize::serialize::as_bytes(&msg_for_engine);
+    loop {
+        let sent = match timeout(HEARTBEAT_INTERVAL, rx.recv()).await {
+            Ok(Some(msg_for_engine)) => {
+                send_data_async(&mut tx_gateway_stream, &mut serialized, |out| {
+                    encode_engine_message(&msg_for_engine, out)
+                })
+                .await
+            }
+            Ok(None) => break,
+            Err(_) => {
+                let heartbeat = ClientPacket::ClientHeartbeat;
+                send_packet_async(&mut tx_gateway_stream, &heartbeat, &mut serialized).await
+            }
+        };
 
-        let packet_len = serialized.len();
-        if let Err(err) = tx_gateway_stream.write_all(&packet_len.to_be_bytes()).await {
-            println!("Gateway write error: {:?}", err);
-        }
-
-        if let Err(err) = tx_gateway_stream.write_all(&serialized).await {
-            println!("Gateway write error: {:?}", err);
+        if let Err(err) = sent {
+            println!("Gateway write error: {}", err);
             break;
         }
     }
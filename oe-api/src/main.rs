@@ -9,21 +9,62 @@ use crate::engine_order_entry::gateway_order_entry;
 use crate::ws_event_stream::ws_event_stream;
 use axum::routing::get;
 use axum::Router;
+use common::config::config_loader::load_config_or_exit;
+use common::metrics::metrics_registry::MetricsRegistry;
+use common::metrics::metrics_server::spawn_metrics_server;
+use common::session::session_client::logon_async;
+use common::session::session_message::LoginRequest;
 use common::transport::sequenced_message::EngineMessage;
+use common::util::throttle::Throttle;
+use std::net::{Ipv4Addr, SocketAddr, SocketAddrV4};
+use std::process::exit;
 use std::sync::Arc;
 use tokio::net::TcpStream;
 use tokio::sync::mpsc;
 
 #[tokio::main]
 async fn main() {
+    let config = load_config_or_exit();
+    let oe_api_config = &config.oe_api;
+
     let (tx_oe_api_queue, rx_oe_api_queue) = mpsc::channel::<EngineMessage>(10_000);
 
-    let (rx_gw_stream, tx_gateway_stream) = TcpStream::connect("127.0.0.1:3001")
-        .await
-        .expect("cannot connect to TCP market-gateway")
-        .into_split();
+    let mut gateway_stream = TcpStream::connect((
+        oe_api_config.gateway_host.as_str(),
+        oe_api_config.gateway_port,
+    ))
+    .await
+    .expect("cannot connect to TCP market-gateway");
+    let login = LoginRequest::new(
+        &oe_api_config.gateway_username,
+        &oe_api_config.gateway_password,
+    );
+    if let Err(e) = logon_async(&mut gateway_stream, &login).await {
+        eprintln!("Cannot log on to market-gateway: {}", e);
+        exit(1);
+    }
+    let (rx_gw_stream, tx_gateway_stream) = gateway_stream.into_split();
 
-    let state = Arc::new(AppState::new(tx_oe_api_queue));
+    let metrics_registry = Arc::new(MetricsRegistry::new());
+    let throttle = Arc::new(Throttle::new(
+        oe_api_config.throttle.clone(),
+        &metrics_registry,
+        "oe_api",
+    ));
+    spawn_metrics_server(
+        SocketAddr::V4(SocketAddrV4::new(
+            Ipv4Addr::LOCALHOST,
+            oe_api_config.metrics_port,
+        )),
+        metrics_registry,
+    );
+
+    let state = Arc::new(AppState::new(
+        tx_oe_api_queue,
+        throttle,
+        config.engine.scale,
+        &oe_api_config.users,
+    ));
 
     tokio::spawn(gateway_order_entry(tx_gateway_stream, rx_oe_api_queue));
     tokio::spawn(gateway_event_stream(rx_gw_stream, state.clone()));
@@ -31,10 +72,12 @@ async fn main() {
     println!("Connected to market-gateway");
 
     let app = Router::new()
-        .route("/ws/event_stream/{client_id}", get(ws_event_stream))
+        .route("/ws/event_stream", get(ws_event_stream))
         .with_state(state);
 
-    println!("Order API listening on 0.0.0.0:8080");
-    let listener = tokio::net::TcpListener::bind("0.0.0.0:8080").await.unwrap();
+    println!("Order API listening on 0.0.0.0:{}", oe_api_config.port);
+    let listener = tokio::net::TcpListener::bind(("0.0.0.0", oe_api_config.port))
+        .await
+        .unwrap();
     axum::serve(listener, app).await.unwrap();
 }
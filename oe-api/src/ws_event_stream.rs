@@ -1,44 +1,50 @@
-use crate::api_spec::request::IncomingMessage;
+use crate::api_spec::request::{ApiOrderRequest, IncomingMessage};
 use crate::api_spec::response::{
-    ApiCancelOrderAckResponse, ApiExecutionReportResponse, ApiOrderAckResponse,
+    ApiCancelOrderAckResponse, ApiExecutionReportResponse, ApiLogonAcceptedResponse,
+    ApiLogonRejectResponse, ApiOrderAckResponse, ApiOrderRejectResponse,
 };
 use crate::app_state::AppState;
-use axum::extract::ws::{Message, Utf8Bytes};
-use axum::extract::{Path, State, WebSocketUpgrade};
+use axum::extract::ws::{Message, Utf8Bytes, WebSocket};
+use axum::extract::{State, WebSocketUpgrade};
 use axum::response::IntoResponse;
 use common::transport::sequenced_message::EngineMessage;
-use common::types::cancel_order::CancelOrderRequest;
+use common::types::cancel_order::{
+    CancelOrderRequest, CancelOrderStatus, CancelledOrderAck, Reason,
+};
+use common::types::fixed_point::FixedPointScale;
 use common::types::instrument::Instrument;
-use common::types::order::{OrderRequest, TimeInForce};
+use common::types::latency_trace::LatencyTrace;
+use common::types::order::{OrderReject, OrderRequest, RejectReason, TimeInForce};
 use common::types::side::Side;
 use common::util::time::system_nanos;
+use dashmap::mapref::entry::Entry;
 use futures::{SinkExt, StreamExt};
 use std::sync::Arc;
 use std::sync::atomic::{AtomicU64, Ordering};
 use std::time::Duration;
 use tokio::sync::mpsc;
 
+/// How long a new connection has to send its `ApiLogonRequest`.
+const LOGON_TIMEOUT: Duration = Duration::from_secs(5);
+
 pub async fn ws_event_stream(
     State(state): State<Arc<AppState>>,
-    Path(client_id): Path<u32>,
     ws: WebSocketUpgrade,
 ) -> impl IntoResponse {
-    ws.on_upgrade(move |socket| async move {
+    ws.on_upgrade(move |mut socket| async move {
+        let Some((client_id, tx, mut rx)) = logon(&state, &mut socket).await else {
+            return;
+        };
         println!("WS client {} connected", client_id);
 
         let last_heartbeat = Arc::new(AtomicU64::new(system_nanos()));
-        // Channel for engine -> client messages
-        let (tx, mut rx) = mpsc::channel::<EngineMessage>(128);
-        state
-            .tx_engine_to_client_channel
-            .insert(client_id, tx.clone());
 
         let (mut ws_tx, mut ws_rx) = socket.split();
+        let scale = state.scale;
 
         let oe_api_to_client = tokio::spawn(async move {
             while let Some(msg) = rx.recv().await {
-                let json = engine_msg_to_json(client_id, msg);
-                println!("JSON from engine {}", json);
+                let json = engine_msg_to_json(client_id, &scale, msg);
                 if ws_tx
                     .send(Message::Text(Utf8Bytes::from(json)))
                     .await
@@ -52,48 +58,99 @@ pub async fn ws_event_stream(
 
         let state_oe_task = state.clone();
         let last_heartbeat_task = last_heartbeat.clone();
-        let client_to_engine = tokio::spawn(async move {
+        let mut throttle = state.throttle.session();
+        let mut client_to_engine = tokio::spawn(async move {
             while let Some(msg_result) = ws_rx.next().await {
                 match msg_result {
                     Ok(Message::Text(text)) => {
+                        let recv_ns = system_nanos();
+                        let trace = LatencyTrace {
+                            oe_api_recv_ns: recv_ns,
+                            ..LatencyTrace::default()
+                        };
+
+                        // a request that fails to parse is answered with a reject rather than sent on
                         let engine_message = match serde_json::from_str::<IncomingMessage>(&text) {
                             Ok(msg) => match msg {
                                 IncomingMessage::ApiOrderRequest(request) => {
-                                    Some(EngineMessage::NewOrder(OrderRequest {
+                                    let mut order = OrderRequest {
                                         client_id,
+                                        client_order_id: 0,
                                         instrument: Instrument::str_to_fixed_char_buffer(
                                             &request.instrument,
                                         ),
-                                        order_side: Side::str_to_val(&request.side).unwrap(),
-                                        px: request.px,
-                                        qty: request.qty,
-                                        time_in_force: TimeInForce::str_to_val(
-                                            &request.time_in_force,
-                                        )
-                                        .unwrap(),
-                                        timestamp: system_nanos(),
-                                    }))
+                                        order_side: Side::Buy,
+                                        px: 0,
+                                        qty: 0,
+                                        time_in_force: TimeInForce::GTC,
+                                        timestamp: recv_ns,
+                                        trace,
+                                    };
+
+                                    match parse_order(&request, &scale, &mut order) {
+                                        Ok(()) => Ok(Some(EngineMessage::NewOrder(order))),
+                                        Err(e) => {
+                                            eprintln!("Client {} invalid order: {}", client_id, e);
+                                            Err(order_reject(&order, RejectReason::InvalidOrder))
+                                        }
+                                    }
                                 }
                                 IncomingMessage::ApiOrderCancelRequest(request) => {
-                                    Some(EngineMessage::CancelOrder(CancelOrderRequest {
+                                    let mut cancel = CancelOrderRequest {
                                         client_id,
-                                        order_side: Side::str_to_val(&request.side).unwrap(),
+                                        order_side: Side::Buy,
                                         order_id: request.order_id,
                                         instrument: Instrument::str_to_fixed_char_buffer(
                                             &request.instrument,
                                         ),
-                                    }))
+                                        trace,
+                                    };
+
+                                    match Side::str_to_val(&request.side) {
+                                        Ok(side) => {
+                                            cancel.order_side = side;
+                                            Ok(Some(EngineMessage::CancelOrder(cancel)))
+                                        }
+                                        Err(e) => {
+                                            eprintln!("Client {} invalid cancel: {}", client_id, e);
+                                            Err(cancel_reject(&cancel, Reason::InvalidCancel))
+                                        }
+                                    }
+                                }
+                                IncomingMessage::ApiLogonRequest(_) => {
+                                    eprintln!("Client {} is already logged on", client_id);
+                                    Ok(None)
                                 }
                                 IncomingMessage::Heartbeat(_) => {
                                     last_heartbeat_task.store(system_nanos(), Ordering::Relaxed);
-                                    None
+                                    Ok(None)
                                 }
                             },
-                            Err(e) => panic!("Error: {}", e),
+                            Err(e) => {
+                                eprintln!("Client {} malformed message: {}", client_id, e);
+                                Err(malformed_reject(client_id, trace))
+                            }
+                        };
+
+                        let engine_message = match engine_message {
+                            Ok(engine_message) => engine_message,
+                            Err(reject) => {
+                                if tx.send(reject).await.is_err() {
+                                    eprintln!("Send to client failed; closing");
+                                }
+                                continue;
+                            }
                         };
 
                         // Send to engine required - heartbeats for example are not
                         if let Some(engine_message) = engine_message {
+                            if !throttle.try_acquire(&engine_message) {
+                                if tx.send(throttle_reject(&engine_message)).await.is_err() {
+                                    eprintln!("Send to client failed; closing");
+                                }
+                                continue;
+                            }
+
                             if state_oe_task
                                 .tx_oe_api_queue
                                 .send(engine_message)
@@ -124,43 +181,167 @@ pub async fn ws_event_stream(
             }
         });
 
-        let last_heartbeat_guard_task = last_heartbeat.clone();
-        let all_task_heartbeat_guard = tokio::spawn(async move {
-            use tokio::time::{Duration, sleep};
-
+        let heartbeat_timeout = async {
             loop {
-                sleep(Duration::from_secs(15)).await;
+                tokio::time::sleep(Duration::from_secs(15)).await;
 
                 let now = system_nanos();
 
-                if now - last_heartbeat_guard_task.load(Ordering::Acquire)
+                if now - last_heartbeat.load(Ordering::Acquire)
                     > Duration::from_secs(10).as_nanos() as u64
                 {
                     println!("Client {} heartbeat timeout!", client_id);
-
-                    oe_api_to_client.abort();
-                    client_to_engine.abort();
                     break;
                 }
             }
-        });
+        };
 
-        let _ = tokio::join!(all_task_heartbeat_guard);
+        // Until the client goes away or stops sending heartbeats
+        tokio::select! {
+            _ = &mut client_to_engine => {}
+            _ = heartbeat_timeout => {}
+        }
+        oe_api_to_client.abort();
+        client_to_engine.abort();
 
         // Cleanup
         state.tx_engine_to_client_channel.remove(&client_id);
         println!("WS client {} disconnected", client_id);
     })
 }
-fn engine_msg_to_json(client_id: u32, msg: EngineMessage) -> String {
+
+/// Reads the `ApiLogonRequest` that must open the connection and registers the client's channel.
+/// Replies with the outcome; on failure the socket is closed and None is returned.
+async fn logon(
+    state: &AppState,
+    socket: &mut WebSocket,
+) -> Option<(
+    u32,
+    mpsc::Sender<EngineMessage>,
+    mpsc::Receiver<EngineMessage>,
+)> {
+    let result = match tokio::time::timeout(LOGON_TIMEOUT, socket.recv()).await {
+        Ok(Some(Ok(Message::Text(text)))) => match serde_json::from_str::<IncomingMessage>(&text) {
+            Ok(IncomingMessage::ApiLogonRequest(request)) => state
+                .authenticate(&request.username, &request.password)
+                .ok_or("invalid_credentials"),
+            _ => Err("logon_required"),
+        },
+        Ok(_) => return None, // closed before logging on
+        Err(_) => Err("logon_timeout"),
+    };
+
+    let registered =
+        result.and_then(
+            |client_id| match state.tx_engine_to_client_channel.entry(client_id) {
+                Entry::Occupied(_) => Err("already_logged_on"),
+                Entry::Vacant(entry) => {
+                    let (tx, rx) = mpsc::channel::<EngineMessage>(128);
+                    entry.insert(tx.clone());
+                    Ok((client_id, tx, rx))
+                }
+            },
+        );
+
+    let reply = match &registered {
+        Ok((client_id, _, _)) => serde_json::to_string(&ApiLogonAcceptedResponse {
+            client_id: *client_id,
+        }),
+        Err(reason) => {
+            eprintln!("WS logon rejected: {}", reason);
+            serde_json::to_string(&ApiLogonRejectResponse {
+                reason: (*reason).into(),
+            })
+        }
+    }
+    .unwrap();
+
+    let sent = socket.send(Message::Text(Utf8Bytes::from(reply))).await;
+    match registered {
+        Ok((client_id, tx, rx)) if sent.is_ok() => Some((client_id, tx, rx)),
+        Ok((client_id, _, _)) => {
+            state.tx_engine_to_client_channel.remove(&client_id);
+            None
+        }
+        Err(_) => {
+            let _ = socket.send(Message::Close(None)).await;
+            None
+        }
+    }
+}
+
+/// Fills in the side, price, quantity and time in force; anything that fails to parse is left as is.
+fn parse_order(
+    request: &ApiOrderRequest,
+    scale: &FixedPointScale,
+    order: &mut OrderRequest,
+) -> Result<(), String> {
+    order.order_side = Side::str_to_val(&request.side)?;
+    order.px = scale.str_to_px(&request.px)?;
+    order.qty = scale.str_to_qty(&request.qty)?;
+    order.time_in_force = TimeInForce::str_to_val(&request.time_in_force)?;
+    Ok(())
+}
+
+fn order_reject(order: &OrderRequest, reason: RejectReason) -> EngineMessage {
+    EngineMessage::OrderReject(OrderReject {
+        client_id: order.client_id,
+        client_order_id: order.client_order_id,
+        instrument: order.instrument,
+        side: order.order_side,
+        px: order.px,
+        qty: order.qty,
+        reason,
+        reject_time: system_nanos(),
+        trace: order.trace,
+    })
+}
+
+/// A message that isn't valid JSON for any request is answered as an invalid order, there is
+/// nothing in it to say which request it was.
+fn malformed_reject(client_id: u32, trace: LatencyTrace) -> EngineMessage {
+    EngineMessage::OrderReject(OrderReject {
+        client_id,
+        client_order_id: 0,
+        instrument: [0; 16],
+        side: Side::Buy,
+        px: 0,
+        qty: 0,
+        reason: RejectReason::InvalidOrder,
+        reject_time: system_nanos(),
+        trace,
+    })
+}
+
+fn cancel_reject(cancel: &CancelOrderRequest, reason: Reason) -> EngineMessage {
+    EngineMessage::CancelOrderAck(CancelledOrderAck {
+        client_id: cancel.client_id,
+        order_id: cancel.order_id,
+        instrument: cancel.instrument,
+        cancel_order_status: CancelOrderStatus::Rejected,
+        reason,
+        ack_time: system_nanos(),
+        trace: cancel.trace,
+    })
+}
+
+fn throttle_reject(msg: &EngineMessage) -> EngineMessage {
+    match msg {
+        EngineMessage::NewOrder(order) => order_reject(order, RejectReason::Throttled),
+        EngineMessage::CancelOrder(cancel) => cancel_reject(cancel, Reason::Throttled),
+        _ => unreachable!(),
+    }
+}
+
+fn engine_msg_to_json(client_id: u32, scale: &FixedPointScale, msg: EngineMessage) -> String {
     match msg {
         EngineMessage::NewOrderAck(a) => serde_json::to_string(&ApiOrderAckResponse {
             client_id: a.client_id,
             instrument: "BTC-USD".into(),
             order_id: a.order_id,
             side: Side::val_to_str(a.side),
-            px: a.px,
-            qty: a.qty,
+            px: scale.px_to_str(a.px),
+            qty: scale.qty_to_str(a.qty),
             ack_time: a.ack_time,
         })
         .unwrap(),
@@ -168,8 +349,8 @@ fn engine_msg_to_json(client_id: u32, msg: EngineMessage) -> String {
             client_id: a.client_id,
             instrument: "BTC-USD".into(),
             order_id: a.order_id,
-            cancel_order_status: "".into(),
-            reason: "".into(),
+            cancel_order_status: CancelOrderStatus::val_to_str(a.cancel_order_status),
+            reason: Reason::val_to_str(a.reason),
             ack_time: a.ack_time,
         })
         .unwrap(),
@@ -178,12 +359,22 @@ fn engine_msg_to_json(client_id: u32, msg: EngineMessage) -> String {
             instrument: "BTC-USD".into(),
             order_id: e.bid_order_id,
             fill_type: "".into(),
-            exec_px: e.exec_px,
-            exec_qty: e.exec_qty,
+            exec_px: scale.px_to_str(e.exec_px),
+            exec_qty: scale.qty_to_str(e.exec_qty),
             exec_type: "".into(),
             exec_ns: e.exec_ns,
         })
         .unwrap(),
+        EngineMessage::OrderReject(r) => serde_json::to_string(&ApiOrderRejectResponse {
+            client_id: r.client_id,
+            instrument: "BTC-USD".into(),
+            side: Side::val_to_str(r.side),
+            px: scale.px_to_str(r.px),
+            qty: scale.qty_to_str(r.qty),
+            reason: RejectReason::val_to_str(r.reason),
+            reject_time: r.reject_time,
+        })
+        .unwrap(),
         _ => panic!("Unexpected message {:?}", msg),
     }
 }
@@ -1,117 +1,224 @@
-use common::transport::sequenced_message::EngineMessage;
-use common::types::cancel_order::CancelOrderRequest;
+use common::codec::frame_error::FrameError;
+use common::codec::framing::{encode_frame_with, read_frame, MAX_FRAME_LEN};
+use common::config::config_loader::load_config;
+use common::ouch::ouch_codec::{decode_outbound, encode_inbound};
+use common::ouch::ouch_message::{CancelOrder, EnterOrder, OrderToken, OuchInbound, ReplaceOrder};
+use common::session::session_codec::{decode_server, encode_client, PACKET_UNSEQUENCED_DATA};
+use common::session::session_message::{
+    ClientPacket, LoginAccepted, LoginRequest, ServerPacket, HEARTBEAT_INTERVAL, SESSION_TIMEOUT,
+};
+use common::types::fixed_point::FixedPointScale;
 use common::types::instrument::Instrument;
-use common::types::order::{OrderRequest, TimeInForce};
+use common::types::order::TimeInForce;
 use common::types::side::Side;
-use common::util::time::system_nanos;
 use rand::random;
+use std::env;
 use std::error::Error;
-use std::io::{BufRead, Read, Write};
-use std::net::TcpStream;
+use std::io::{BufRead, Write};
+use std::net::{Shutdown, TcpStream};
 use std::process::exit;
 use std::sync::atomic::Ordering::Relaxed;
-use std::sync::atomic::{AtomicBool, AtomicU32};
-use std::sync::mpsc::Receiver;
+use std::sync::atomic::{AtomicBool, AtomicU64};
+use std::sync::mpsc::{Receiver, RecvTimeoutError};
 use std::thread;
+use std::thread::JoinHandle;
 
-fn writer(mut write_stream: TcpStream, sequenced_message_store: Receiver<EngineMessage>) {
-    let mut count = 0;
-    while let Ok(message) = sequenced_message_store.recv() {
-        let serialized = common::serialize::serialize::as_bytes(&message);
+const USAGE: &str = "usage: oe-client [--config path] --user <username> --password <password>";
 
-        let packet_len = serialized.len();
-        if let Err(err) = write_stream.write_all(&packet_len.to_be_bytes()) {
-            println!("Gateway write error: {:?}", err);
+/// Returns whether the session should be resumed, i.e. it wasn't ended by quitting.
+fn writer(
+    mut write_stream: TcpStream,
+    sequenced_message_store: &Receiver<OuchInbound>,
+    reader_thread: &JoinHandle<bool>,
+) -> bool {
+    let mut serialized = Vec::new();
+    loop {
+        if reader_thread.is_finished() {
+            return true;
         }
 
-        if let Err(err) = write_stream.write_all(&serialized) {
-            println!("Gateway write error: {:?}", err);
-            break;
+        let framed = match sequenced_message_store.recv_timeout(HEARTBEAT_INTERVAL) {
+            Ok(message) => encode_frame_with(&mut serialized, |out| {
+                out.push(PACKET_UNSEQUENCED_DATA);
+                encode_inbound(&message, out);
+            }),
+            Err(RecvTimeoutError::Timeout) => encode_frame_with(&mut serialized, |out| {
+                encode_client(&ClientPacket::ClientHeartbeat, out)
+            }),
+            Err(RecvTimeoutError::Disconnected) => return false,
+        };
+
+        if let Err(err) = framed.and_then(|_| Ok(write_stream.write_all(&serialized)?)) {
+            println!("Gateway write error: {}", err);
+            return true;
         }
-        count += 1;
     }
 }
 static SHOULD_LOG: AtomicBool = AtomicBool::new(true);
-static CLIENT_ID: AtomicU32 = AtomicU32::new(0);
+static NEXT_TOKEN: AtomicU64 = AtomicU64::new(1);
+/// Sequence number of the next SequencedData, asked for when logging on again.
+static NEXT_SEQUENCE: AtomicU64 = AtomicU64::new(0);
 
-fn reader(mut read_stream: TcpStream) {
-    let mut buffer: [u8; 4096] = [0; 4096];
+/// Returns whether the session should be resumed, i.e. the connection was lost.
+fn reader(mut read_stream: TcpStream) -> bool {
+    let mut buffer = [0u8; MAX_FRAME_LEN];
 
     loop {
-        let mut len_buf = [0u8; 4];
-        if read_stream.read_exact(&mut len_buf).is_err() {
-            return; // disconnected
-        }
-
-        let frame_len = u32::from_be_bytes(len_buf) as usize;
+        let frame = match read_frame(&mut read_stream, &mut buffer) {
+            Ok(frame) => frame,
+            Err(FrameError::Closed) => return true, // disconnected
+            Err(err) => {
+                println!("Gateway read error: {}", err);
+                return true;
+            }
+        };
 
-        if frame_len > 0 {
-            if frame_len > buffer.len() {
-                println!("Frame too large");
-                return;
+        match decode_server(frame) {
+            Ok(ServerPacket::SequencedData(payload)) => {
+                NEXT_SEQUENCE.fetch_add(1, Relaxed);
+                match decode_outbound(payload) {
+                    Ok(message) => {
+                        if SHOULD_LOG.load(Relaxed) {
+                            println!("{:?}", message);
+                        }
+                    }
+                    Err(err) => {
+                        println!("Gateway sent an unreadable message: {}", err);
+                        return false;
+                    }
+                }
+            }
+            Ok(ServerPacket::ServerHeartbeat) => {}
+            Ok(ServerPacket::EndOfSession) => {
+                println!("Gateway ended the session");
+                return false;
             }
+            Ok(packet) => {
+                println!("Unexpected packet from the gateway: {:?}", packet);
+                return false;
+            }
+            Err(err) => {
+                println!("Gateway sent an unreadable packet: {}", err);
+                return false;
+            }
+        }
+    }
+}
 
-            read_stream.read_exact(&mut buffer[..frame_len]).unwrap();
+/// Logs on and picks up the gateway's sequence numbers from where it resumes the session.
+fn logon(
+    tcp_stream: &mut TcpStream,
+    login: &LoginRequest,
+) -> Result<LoginAccepted, Box<dyn Error>> {
+    let mut serialized = Vec::new();
+    encode_frame_with(&mut serialized, |out| {
+        encode_client(&ClientPacket::LoginRequest(*login), out)
+    })?;
+    tcp_stream.write_all(&serialized)?;
 
-            // if SHOULD_LOG.load(Ordering::Relaxed) {
-            // println!("{:?}", buffer);
-            // }
+    let mut buffer = [0u8; MAX_FRAME_LEN];
+    match decode_server(read_frame(tcp_stream, &mut buffer)?)? {
+        ServerPacket::LoginAccepted(accepted) => {
+            let requested = login.sequence;
+            if requested != 0 && accepted.sequence > requested {
+                println!(
+                    "Gateway no longer has messages {} to {}, they are lost",
+                    requested,
+                    accepted.sequence - 1
+                );
+            } else if accepted.sequence < requested {
+                println!("Gateway restarted its sequence numbers, messages may be lost");
+            }
+            NEXT_SEQUENCE.store(accepted.sequence, Relaxed);
+            Ok(accepted)
         }
+        ServerPacket::LoginRejected(reason) => Err(format!("login rejected: {:?}", reason).into()),
+        packet => Err(format!("unexpected packet {:?}", packet).into()),
     }
 }
 
-fn client_connection(sequenced_message_store: Receiver<EngineMessage>) {
-    let mut tcp_stream = TcpStream::connect("127.0.0.1:3001")
-        .map_err(|e| "Failed to connect to the market-gateway server")
-        .unwrap();
+/// Runs the session, logging on again and resuming it whenever the connection is lost.
+fn client_connection(
+    mut tcp_stream: TcpStream,
+    port: u16,
+    login: LoginRequest,
+    sequenced_message_store: Receiver<OuchInbound>,
+) {
+    loop {
+        let read_stream = tcp_stream.try_clone().unwrap();
+        read_stream.set_read_timeout(Some(SESSION_TIMEOUT)).unwrap();
+        let write_stream = tcp_stream.try_clone().unwrap();
 
-    let read_stream = tcp_stream.try_clone().unwrap();
-    let write_stream = tcp_stream.try_clone().unwrap();
+        let reader_thread = thread::spawn(move || reader(read_stream));
+        let resume = writer(write_stream, &sequenced_message_store, &reader_thread);
+        tcp_stream.shutdown(Shutdown::Both).ok();
+        if !(reader_thread.join().unwrap() && resume) {
+            return;
+        }
 
-    let writer_thread = thread::spawn(move || {
-        writer(write_stream, sequenced_message_store);
-    });
+        tcp_stream = reconnect(port, &login);
+    }
+}
 
-    let reader_thread = thread::spawn(move || {
-        reader(read_stream);
-    });
+fn reconnect(port: u16, login: &LoginRequest) -> TcpStream {
+    loop {
+        thread::sleep(HEARTBEAT_INTERVAL);
+        let login = login.resume(NEXT_SEQUENCE.load(Relaxed));
+        let resumed = TcpStream::connect(("127.0.0.1", port))
+            .map_err(Box::<dyn Error>::from)
+            .and_then(|mut tcp_stream| {
+                logon(&mut tcp_stream, &login)?;
+                Ok(tcp_stream)
+            });
 
-    writer_thread.join().unwrap();
-    reader_thread.join().unwrap();
+        match resumed {
+            Ok(tcp_stream) => {
+                println!("Logged on again at sequence {}", NEXT_SEQUENCE.load(Relaxed));
+                return tcp_stream;
+            }
+            Err(err) => println!("Cannot log on to the market-gateway again: {}", err),
+        }
+    }
 }
 
 #[derive(Debug)]
 enum Command {
-    Buy(u32, u32),
-    Sell(u32, u32),
-    Cancel(bool, u32),
+    Buy(u64, u64),
+    Sell(u64, u64),
+    Cancel(OrderToken),
+    Replace(OrderToken, u64, u64),
     Perf(bool, u32),
     Quit,
 }
 
-fn parse(input: String) -> Result<Command, ()> {
+fn parse(input: String, scale: &FixedPointScale) -> Result<Command, ()> {
     let lower = input.to_lowercase();
     let tokens = lower.split(" ").collect::<Vec<&str>>();
 
     match tokens[0] {
         "buy" | "b" => {
-            let px = tokens[1].parse::<u32>().unwrap();
-            let qty = tokens[2].parse::<u32>().unwrap();
+            let px = scale.str_to_px(tokens[1]).map_err(|e| println!("{}", e))?;
+            let qty = scale.str_to_qty(tokens[2]).map_err(|e| println!("{}", e))?;
             Ok(Command::Buy(px, qty))
         }
         "sell" | "s" => {
-            let px = tokens[1].parse::<u32>().unwrap();
-            let qty = tokens[2].parse::<u32>().unwrap();
+            let px = scale.str_to_px(tokens[1]).map_err(|e| println!("{}", e))?;
+            let qty = scale.str_to_qty(tokens[2]).map_err(|e| println!("{}", e))?;
             Ok(Command::Sell(px, qty))
         }
         "cancel" | "c" => {
-            let side = tokens[1];
-            let order_id = tokens[2].parse::<u32>().unwrap();
-            if side == "b" {
-                Ok(Command::Cancel(true, order_id))
-            } else {
-                Ok(Command::Cancel(false, order_id))
-            }
+            let token = tokens[1]
+                .parse::<OrderToken>()
+                .map_err(|e| println!("{}", e))?;
+            Ok(Command::Cancel(token))
+        }
+        "replace" | "r" => {
+            let token = tokens[1]
+                .parse::<OrderToken>()
+                .map_err(|e| println!("{}", e))?;
+            let px = scale.str_to_px(tokens[2]).map_err(|e| println!("{}", e))?;
+            let qty = scale.str_to_qty(tokens[3]).map_err(|e| println!("{}", e))?;
+            Ok(Command::Replace(token, px, qty))
         }
         "perf" | "p" => {
             let side = tokens[1];
@@ -128,12 +235,42 @@ fn parse(input: String) -> Result<Command, ()> {
 }
 
 fn main() -> Result<(), Box<dyn Error>> {
-    CLIENT_ID.store(random::<u32>(), Relaxed);
-    let (sender, receiver) = std::sync::mpsc::channel::<EngineMessage>();
-    let oe_client_thread = thread::spawn(move || client_connection(receiver));
+    let (config, args) = load_config(env::args().skip(1))?;
+    let scale = config.engine.scale;
+
+    let mut username = None;
+    let mut password = None;
+    let mut args = args.into_iter();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--user" => username = args.next(),
+            "--password" => password = args.next(),
+            _ => {
+                eprintln!("{}", USAGE);
+                exit(2);
+            }
+        }
+    }
+    let (Some(username), Some(password)) = (username, password) else {
+        eprintln!("{}", USAGE);
+        exit(2);
+    };
+
+    let port = config.gateway.ouch_port;
+    let mut tcp_stream = TcpStream::connect(("127.0.0.1", port))
+        .map_err(|_| "Failed to connect to the market-gateway server")?;
+    let login = LoginRequest::new(&username, &password);
+    let accepted = logon(&mut tcp_stream, &login)?;
+    println!("Logged on as {} (client {})", username, accepted.client_id);
+
+    let (sender, receiver) = std::sync::mpsc::channel::<OuchInbound>();
+    let oe_client_thread =
+        thread::spawn(move || client_connection(tcp_stream, port, login, receiver));
 
     println!("-----------------");
-    println!("OE CLIENT\nBUY px qty\nSELL px qty\nCANCEL side order_id\nPERF n_orders\nQUIT");
+    println!(
+        "OE CLIENT (OUCH)\nBUY px qty\nSELL px qty\nCANCEL token\nREPLACE token px qty\nPERF side n_orders\nQUIT"
+    );
     println!("-----------------");
 
     let quit = false;
@@ -143,7 +280,7 @@ fn main() -> Result<(), Box<dyn Error>> {
         let mut line = String::new();
         std::io::stdin().read_line(&mut line).unwrap();
 
-        if let Ok(command) = parse(line.trim().to_string()) {
+        if let Ok(command) = parse(line.trim().to_string(), &scale) {
             let order;
             match command {
                 Command::Buy(px, qty) => {
@@ -154,16 +291,22 @@ fn main() -> Result<(), Box<dyn Error>> {
                     order = build_nos(false, px, qty);
                     sender.clone().send(order).expect("TODO: panic types");
                 }
-                Command::Cancel(is_buy, order_id) => {
-                    order = build_cancel(is_buy, order_id);
+                Command::Cancel(token) => {
+                    order = OuchInbound::CancelOrder(CancelOrder { token });
+                    sender.clone().send(order).expect("TODO: panic types");
+                }
+                Command::Replace(existing_token, px, qty) => {
+                    order = build_replace(existing_token, px, qty);
                     sender.clone().send(order).expect("TODO: panic types");
                 }
                 Command::Perf(is_buy, batch_size) => {
                     SHOULD_LOG.store(false, std::sync::atomic::Ordering::Release);
                     for _ in 0..batch_size {
                         let order;
-                        let px = (random::<u32>() % 100) + 1;
-                        let qty = (random::<u32>() % 100) + 1;
+                        // whole units of 1 to 100
+                        let px = ((random::<u64>() % 100) + 1) * 10u64.pow(scale.px_decimals as u32);
+                        let qty =
+                            ((random::<u64>() % 100) + 1) * 10u64.pow(scale.qty_decimals as u32);
 
                         order = build_nos(is_buy, px, qty);
                         sender.send(order).expect("TODO: panic types");
@@ -185,33 +328,38 @@ fn main() -> Result<(), Box<dyn Error>> {
     Ok(())
 }
 
-fn build_nos(is_buy: bool, px: u32, qty: u32) -> EngineMessage {
+fn build_nos(is_buy: bool, px: u64, qty: u64) -> OuchInbound {
     let side = match is_buy {
         true => Side::Buy,
         false => Side::Sell,
     };
 
-    EngineMessage::NewOrder(OrderRequest {
-        client_id: CLIENT_ID.load(Relaxed),
+    let token = NEXT_TOKEN.fetch_add(1, Relaxed);
+    if SHOULD_LOG.load(Relaxed) {
+        println!("Entering order token {}", token);
+    }
+
+    OuchInbound::EnterOrder(EnterOrder {
+        token,
+        side,
+        qty,
         instrument: Instrument::str_to_fixed_char_buffer("BTC-USD"),
-        order_side: side,
         px,
-        qty,
         time_in_force: TimeInForce::GTC,
-        timestamp: system_nanos(),
     })
 }
 
-fn build_cancel(is_buy: bool, order_id: u32) -> EngineMessage {
-    let side = match is_buy {
-        true => Side::Buy,
-        false => Side::Sell,
-    };
+fn build_replace(existing_token: OrderToken, px: u64, qty: u64) -> OuchInbound {
+    let replacement_token = NEXT_TOKEN.fetch_add(1, Relaxed);
+    println!(
+        "Replacing order token {} with {}",
+        existing_token, replacement_token
+    );
 
-    EngineMessage::CancelOrder(CancelOrderRequest {
-        client_id: CLIENT_ID.load(Relaxed),
-        order_side: side,
-        order_id,
-        instrument: Instrument::str_to_fixed_char_buffer("BTC-USD"),
+    OuchInbound::ReplaceOrder(ReplaceOrder {
+        existing_token,
+        replacement_token,
+        qty,
+        px,
     })
 }
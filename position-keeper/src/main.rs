@@ -0,0 +1,66 @@
+mod position_book;
+mod process;
+
+use crate::position_book::PositionBook;
+use crate::process::engine_out_msg_thread::engine_msg_out_receiver;
+use axum::extract::{Path, State};
+use axum::routing::get;
+use axum::{Json, Router};
+use common::config::config_loader::load_config_or_exit;
+use common::types::position::ClientPosition;
+use std::sync::{Arc, Mutex};
+
+type SharedPositionBook = Arc<Mutex<PositionBook>>;
+
+#[tokio::main]
+async fn main() {
+    let config = load_config_or_exit();
+    let engine_msg_out_port = config.engine.msg_out_port;
+    let msg_out_nack_port = config.transport.msg_out_nack_port;
+    let receive_ring_len = config.transport.receive_ring_len;
+
+    let position_book: SharedPositionBook = Arc::new(Mutex::new(PositionBook::default()));
+
+    // Init MSG_OUT -> position book mc recv thread
+    let msg_out_thread_config = config.position_keeper.threads.msg_out.clone();
+    let receiver_position_book = position_book.clone();
+    std::thread::spawn(move || {
+        engine_msg_out_receiver(
+            engine_msg_out_port,
+            msg_out_nack_port,
+            receive_ring_len,
+            msg_out_thread_config,
+            receiver_position_book,
+        )
+    });
+
+    let app = Router::new()
+        .route("/", get(root))
+        .route("/positions", get(all_positions))
+        .route("/positions/{client_id}", get(client_positions))
+        .with_state(position_book);
+
+    let port = config.position_keeper.port;
+    println!("Position Keeper running on http://127.0.0.1:{}", port);
+    let listener = tokio::net::TcpListener::bind(("0.0.0.0", port))
+        .await
+        .unwrap();
+    axum::serve(listener, app).await.unwrap();
+}
+
+async fn root() -> &'static str {
+    "Position Keeper!"
+}
+
+async fn all_positions(
+    State(position_book): State<SharedPositionBook>,
+) -> Json<Vec<ClientPosition>> {
+    Json(position_book.lock().unwrap().positions(None))
+}
+
+async fn client_positions(
+    Path(client_id): Path<u32>,
+    State(position_book): State<SharedPositionBook>,
+) -> Json<Vec<ClientPosition>> {
+    Json(position_book.lock().unwrap().positions(Some(client_id)))
+}
@@ -0,0 +1,387 @@
+use common::transport::sequenced_message::EngineMessage;
+use common::types::cancel_order::{CancelOrderStatus, CancelledOrderAck};
+use common::types::execution_report::{ExecType, ExecutionReport, FillType};
+use common::types::instrument::Instrument;
+use common::types::order::NewOrderAck;
+use common::types::position::ClientPosition;
+use common::types::side::Side;
+use std::collections::HashMap;
+
+type InstrumentKey = [u8; 16];
+
+#[derive(Debug, Default, Clone, Copy)]
+struct Position {
+    net_qty: i64,
+    avg_entry_px: f64,
+    realized_pnl: f64,
+    open_buy_qty: u64,
+    open_sell_qty: u64,
+    open_buy_notional: u64,
+    open_sell_notional: u64,
+}
+
+#[derive(Debug, Clone, Copy)]
+struct RestingOrder {
+    client_id: u32,
+    side: Side,
+    px: u64,
+    qty: u64,
+}
+
+/// Net position, average entry and PnL per client and instrument, built from the engine output.
+/// Open order exposure follows the acks, fills and cancels of resting orders.
+#[derive(Default)]
+pub struct PositionBook {
+    positions: HashMap<(u32, InstrumentKey), Position>,
+    resting: HashMap<(InstrumentKey, u32), RestingOrder>,
+    last_trade_px: HashMap<InstrumentKey, u64>,
+}
+
+impl PositionBook {
+    pub fn on_engine_message(&mut self, message: &EngineMessage) {
+        match message {
+            EngineMessage::NewOrderAck(ack) => self.on_new_order_ack(ack),
+            EngineMessage::CancelOrderAck(ack) => self.on_cancel_ack(ack),
+            EngineMessage::TradeExecution(execution) => self.on_execution(execution),
+            _ => {}
+        }
+    }
+
+    pub fn positions(&self, client_id: Option<u32>) -> Vec<ClientPosition> {
+        let mut positions: Vec<ClientPosition> = self
+            .positions
+            .iter()
+            .filter(|((id, _), _)| client_id.is_none_or(|client_id| client_id == *id))
+            .map(|((id, instrument), position)| {
+                let unrealized_pnl = match self.last_trade_px.get(instrument) {
+                    Some(mark) => position.net_qty as f64 * (*mark as f64 - position.avg_entry_px),
+                    None => 0.0,
+                };
+
+                ClientPosition {
+                    client_id: *id,
+                    instrument: Instrument::fixed_char_buffer_to_str(instrument),
+                    net_qty: position.net_qty,
+                    avg_entry_px: position.avg_entry_px,
+                    realized_pnl: position.realized_pnl,
+                    unrealized_pnl,
+                    open_buy_qty: position.open_buy_qty,
+                    open_sell_qty: position.open_sell_qty,
+                    open_buy_notional: position.open_buy_notional,
+                    open_sell_notional: position.open_sell_notional,
+                }
+            })
+            .collect();
+
+        positions.sort_by(|a, b| (a.client_id, &a.instrument).cmp(&(b.client_id, &b.instrument)));
+        positions
+    }
+
+    fn on_new_order_ack(&mut self, ack: &NewOrderAck) {
+        let order = RestingOrder {
+            client_id: ack.client_id,
+            side: ack.side,
+            px: ack.px,
+            qty: ack.qty_rem,
+        };
+        self.resting.insert((ack.instrument, ack.order_id), order);
+        self.adjust_open(ack.instrument, &order, order.qty, true);
+    }
+
+    fn on_cancel_ack(&mut self, ack: &CancelledOrderAck) {
+        if !matches!(ack.cancel_order_status, CancelOrderStatus::Cancelled) {
+            return;
+        }
+
+        self.remove_resting(ack.instrument, ack.order_id);
+    }
+
+    fn on_execution(&mut self, execution: &ExecutionReport) {
+        match execution.exec_type {
+            ExecType::MatchEvent => {
+                self.last_trade_px
+                    .insert(execution.instrument, execution.exec_px);
+                self.fill(
+                    execution.instrument,
+                    execution.bid_client_id,
+                    execution.bid_order_id,
+                    Side::Buy,
+                    execution.exec_px,
+                    execution.exec_qty,
+                    execution.bid_fill_type,
+                );
+                self.fill(
+                    execution.instrument,
+                    execution.ask_client_id,
+                    execution.ask_order_id,
+                    Side::Sell,
+                    execution.exec_px,
+                    execution.exec_qty,
+                    execution.ask_fill_type,
+                );
+            }
+            // the resting order was pulled from the book
+            ExecType::SelfMatchPrevented => {
+                let order_id = execution.bid_order_id.max(execution.ask_order_id);
+                self.remove_resting(execution.instrument, order_id);
+            }
+        }
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    fn fill(
+        &mut self,
+        instrument: InstrumentKey,
+        client_id: u32,
+        order_id: u32,
+        side: Side,
+        px: u64,
+        qty: u64,
+        fill_type: FillType,
+    ) {
+        let position = self.positions.entry((client_id, instrument)).or_default();
+        let signed_qty = match side {
+            Side::Buy => qty as i64,
+            Side::Sell => -(qty as i64),
+        };
+        let px = px as f64;
+
+        if position.net_qty == 0 || position.net_qty.signum() == signed_qty.signum() {
+            // adding to the position, re-average the entry
+            let held = position.net_qty.unsigned_abs() as f64;
+            position.avg_entry_px =
+                (position.avg_entry_px * held + px * qty as f64) / (held + qty as f64);
+        } else {
+            let closing_qty = position.net_qty.abs().min(signed_qty.abs()) as f64;
+            position.realized_pnl +=
+                closing_qty * (px - position.avg_entry_px) * position.net_qty.signum() as f64;
+
+            if signed_qty.abs() > position.net_qty.abs() {
+                // flipped through flat, the remainder was opened at this price
+                position.avg_entry_px = px;
+            }
+        }
+
+        position.net_qty += signed_qty;
+        if position.net_qty == 0 {
+            position.avg_entry_px = 0.0;
+        }
+
+        // the aggressor's own fills were never acked onto the book
+        let Some(order) = self.resting.get(&(instrument, order_id)).copied() else {
+            return;
+        };
+
+        self.adjust_open(instrument, &order, qty, false);
+        if fill_type == FillType::FullFill {
+            self.resting.remove(&(instrument, order_id));
+        } else if let Some(order) = self.resting.get_mut(&(instrument, order_id)) {
+            order.qty = order.qty.saturating_sub(qty);
+        }
+    }
+
+    fn remove_resting(&mut self, instrument: InstrumentKey, order_id: u32) {
+        if let Some(order) = self.resting.remove(&(instrument, order_id)) {
+            self.adjust_open(instrument, &order, order.qty, false);
+        }
+    }
+
+    fn adjust_open(
+        &mut self,
+        instrument: InstrumentKey,
+        order: &RestingOrder,
+        qty: u64,
+        add: bool,
+    ) {
+        let position = self
+            .positions
+            .entry((order.client_id, instrument))
+            .or_default();
+        let notional = order.px.saturating_mul(qty);
+
+        let (open_qty, open_notional) = match order.side {
+            Side::Buy => (&mut position.open_buy_qty, &mut position.open_buy_notional),
+            Side::Sell => (
+                &mut position.open_sell_qty,
+                &mut position.open_sell_notional,
+            ),
+        };
+
+        if add {
+            *open_qty += qty;
+            *open_notional += notional;
+        } else {
+            *open_qty = open_qty.saturating_sub(qty);
+            *open_notional = open_notional.saturating_sub(notional);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use common::types::cancel_order::Reason;
+    use common::types::latency_trace::LatencyTrace;
+    use common::types::order::TimeInForce;
+
+    const BUYER: u32 = 1;
+    const SELLER: u32 = 2;
+
+    fn instrument() -> InstrumentKey {
+        Instrument::str_to_fixed_char_buffer("BTC-USD")
+    }
+
+    /// A trade between two aggressors, neither order was acked onto the book.
+    fn trade(bid_client_id: u32, ask_client_id: u32, px: u64, qty: u64) -> EngineMessage {
+        fill(
+            bid_client_id,
+            0,
+            ask_client_id,
+            0,
+            px,
+            qty,
+            FillType::FullFill,
+        )
+    }
+
+    fn fill(
+        bid_client_id: u32,
+        bid_order_id: u32,
+        ask_client_id: u32,
+        ask_order_id: u32,
+        px: u64,
+        qty: u64,
+        resting_fill_type: FillType,
+    ) -> EngineMessage {
+        EngineMessage::TradeExecution(ExecutionReport {
+            trade_id: 1,
+            bid_client_id,
+            bid_order_id,
+            bid_client_order_id: 1,
+            bid_order_px: px,
+            bid_fill_type: resting_fill_type,
+            ask_client_id,
+            ask_order_id,
+            ask_client_order_id: 1,
+            ask_order_px: px,
+            ask_fill_type: FillType::FullFill,
+            instrument: instrument(),
+            exec_px: px,
+            exec_qty: qty,
+            exec_type: ExecType::MatchEvent,
+            exec_ns: 0,
+            trace: LatencyTrace::default(),
+        })
+    }
+
+    fn position(book: &PositionBook, client_id: u32) -> ClientPosition {
+        book.positions(Some(client_id)).pop().unwrap()
+    }
+
+    #[test]
+    fn fills_on_the_same_side_average_the_entry() {
+        let mut book = PositionBook::default();
+        book.on_engine_message(&trade(BUYER, SELLER, 100, 10));
+        book.on_engine_message(&trade(BUYER, SELLER, 110, 10));
+
+        let long = position(&book, BUYER);
+        assert_eq!(long.net_qty, 20);
+        assert_eq!(long.avg_entry_px, 105.0);
+        assert_eq!(long.realized_pnl, 0.0);
+        // marked at the last trade
+        assert_eq!(long.unrealized_pnl, 100.0);
+
+        let short = position(&book, SELLER);
+        assert_eq!(short.net_qty, -20);
+        assert_eq!(short.avg_entry_px, 105.0);
+        assert_eq!(short.unrealized_pnl, -100.0);
+    }
+
+    #[test]
+    fn a_partial_close_realizes_pnl_on_the_closed_qty() {
+        let mut book = PositionBook::default();
+        book.on_engine_message(&trade(BUYER, SELLER, 100, 10));
+        book.on_engine_message(&trade(SELLER, BUYER, 110, 4));
+
+        let long = position(&book, BUYER);
+        assert_eq!(long.net_qty, 6);
+        assert_eq!(long.avg_entry_px, 100.0);
+        assert_eq!(long.realized_pnl, 40.0);
+        assert_eq!(long.unrealized_pnl, 60.0);
+
+        let short = position(&book, SELLER);
+        assert_eq!(short.net_qty, -6);
+        assert_eq!(short.realized_pnl, -40.0);
+        assert_eq!(short.unrealized_pnl, -60.0);
+    }
+
+    #[test]
+    fn closing_to_flat_keeps_the_realized_pnl_only() {
+        let mut book = PositionBook::default();
+        // short 10 at 100, bought back at 90
+        book.on_engine_message(&trade(BUYER, SELLER, 100, 10));
+        book.on_engine_message(&trade(SELLER, BUYER, 90, 10));
+
+        let flat = position(&book, SELLER);
+        assert_eq!(flat.net_qty, 0);
+        assert_eq!(flat.avg_entry_px, 0.0);
+        assert_eq!(flat.realized_pnl, 100.0);
+        assert_eq!(flat.unrealized_pnl, 0.0);
+    }
+
+    #[test]
+    fn flipping_through_flat_opens_the_remainder_at_the_fill_price() {
+        let mut book = PositionBook::default();
+        book.on_engine_message(&trade(BUYER, SELLER, 100, 10));
+        book.on_engine_message(&trade(SELLER, BUYER, 90, 15));
+
+        let flipped = position(&book, BUYER);
+        assert_eq!(flipped.net_qty, -5);
+        assert_eq!(flipped.avg_entry_px, 90.0);
+        assert_eq!(flipped.realized_pnl, -100.0);
+        assert_eq!(flipped.unrealized_pnl, 0.0);
+
+        book.on_engine_message(&trade(SELLER, 3, 80, 1));
+        assert_eq!(position(&book, BUYER).unrealized_pnl, 50.0);
+    }
+
+    #[test]
+    fn open_exposure_follows_acks_fills_and_cancels() {
+        let mut book = PositionBook::default();
+        book.on_engine_message(&EngineMessage::NewOrderAck(NewOrderAck {
+            client_id: BUYER,
+            client_order_id: 1,
+            order_id: 7,
+            instrument: instrument(),
+            side: Side::Buy,
+            px: 100,
+            qty: 10,
+            qty_rem: 10,
+            time_in_force: TimeInForce::GTC,
+            ack_time: 0,
+            trace: LatencyTrace::default(),
+        }));
+        assert_eq!(position(&book, BUYER).open_buy_qty, 10);
+        assert_eq!(position(&book, BUYER).open_buy_notional, 1_000);
+
+        book.on_engine_message(&fill(BUYER, 7, SELLER, 0, 100, 4, FillType::PartialFill));
+        let partly_filled = position(&book, BUYER);
+        assert_eq!(partly_filled.net_qty, 4);
+        assert_eq!(partly_filled.open_buy_qty, 6);
+        assert_eq!(partly_filled.open_buy_notional, 600);
+
+        book.on_engine_message(&EngineMessage::CancelOrderAck(CancelledOrderAck {
+            client_id: BUYER,
+            order_id: 7,
+            instrument: instrument(),
+            cancel_order_status: CancelOrderStatus::Cancelled,
+            reason: Reason::ClientRequested,
+            ack_time: 0,
+            trace: LatencyTrace::default(),
+        }));
+        let cancelled = position(&book, BUYER);
+        assert_eq!(cancelled.net_qty, 4);
+        assert_eq!(cancelled.open_buy_qty, 0);
+        assert_eq!(cancelled.open_buy_notional, 0);
+    }
+}
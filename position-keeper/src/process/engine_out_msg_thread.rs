@@ -0,0 +1,41 @@
+use crate::position_book::PositionBook;
+use common::config::app_config::ThreadConfig;
+use common::network::mutlicast::multicast_receiver_with_busy_poll;
+use common::transport::nack_sequenced_multicast_receiver::NackSequencedMulticastReceiver;
+use common::util::thread_affinity::pin_current_thread;
+use std::sync::{Arc, Mutex};
+
+pub fn engine_msg_out_receiver(
+    engine_msg_out_port: u16,
+    nack_port: u16,
+    ring_len: usize,
+    thread_config: ThreadConfig,
+    position_book: Arc<Mutex<PositionBook>>,
+) {
+    pin_current_thread("msg_out", thread_config.core);
+    let udp_socket =
+        multicast_receiver_with_busy_poll(engine_msg_out_port, thread_config.busy_poll_micros);
+    let mut idler = thread_config.idle.idler();
+    let mut multicast_receiver =
+        NackSequencedMulticastReceiver::new(udp_socket, nack_port, ring_len);
+
+    println!(
+        "Initialized MSG_OUT -> position keeper multicast on port {}",
+        engine_msg_out_port
+    );
+
+    loop {
+        if let Some(outbound_engine_message) = multicast_receiver.try_recv() {
+            idler.reset();
+            position_book
+                .lock()
+                .unwrap()
+                .on_engine_message(&outbound_engine_message.message);
+        } else {
+            while let Some(event) = multicast_receiver.poll_event() {
+                eprintln!("Engine stream: {}", event);
+            }
+            idler.idle();
+        }
+    }
+}
@@ -0,0 +1 @@
+pub(crate) mod engine_out_msg_thread;
@@ -0,0 +1,72 @@
+use common::journal::journal_reader::JournalReader;
+use common::types::latency_trace::LatencyTrace;
+use hdrhistogram::Histogram;
+use std::env;
+use std::error::Error;
+use std::process::exit;
+
+const PERCENTILES: [f64; 5] = [50.0, 90.0, 99.0, 99.9, 99.99];
+
+fn main() -> Result<(), Box<dyn Error>> {
+    let Some(journal_path) = env::args().nth(1) else {
+        eprintln!("usage: latency_report <journal>");
+        exit(1);
+    };
+
+    let new_histogram = || Histogram::<u64>::new_with_bounds(1, 60_000_000_000, 3).unwrap();
+    let mut hop_histograms: Vec<Histogram<u64>> =
+        LatencyTrace::HOP_NAMES.iter().map(|_| new_histogram()).collect();
+    let mut end_to_end = new_histogram();
+
+    let mut messages = 0;
+    let mut traced = 0;
+
+    for sequenced_message in JournalReader::open(&journal_path)? {
+        let sequenced_message = sequenced_message?;
+        messages += 1;
+
+        let Some(trace) = sequenced_message.message.trace() else {
+            continue;
+        };
+
+        if *trace == LatencyTrace::default() {
+            continue;
+        }
+        traced += 1;
+
+        for (histogram, hop) in hop_histograms.iter_mut().zip(trace.hops()) {
+            if let Some(latency) = hop {
+                histogram.saturating_record(latency.max(1));
+            }
+        }
+
+        if let Some(latency) = trace.end_to_end() {
+            end_to_end.saturating_record(latency.max(1));
+        }
+    }
+
+    println!("{}: {} messages, {} traced", journal_path, messages, traced);
+    print_header();
+    for (name, histogram) in LatencyTrace::HOP_NAMES.iter().zip(&hop_histograms) {
+        print_row(name, histogram);
+    }
+    print_row("end to end", &end_to_end);
+
+    Ok(())
+}
+
+fn print_header() {
+    print!("{:<30} {:>10}", "hop (ns)", "count");
+    for percentile in PERCENTILES {
+        print!(" {:>10}", format!("p{}", percentile));
+    }
+    println!(" {:>10}", "max");
+}
+
+fn print_row(name: &str, histogram: &Histogram<u64>) {
+    print!("{:<30} {:>10}", name, histogram.len());
+    for percentile in PERCENTILES {
+        print!(" {:>10}", histogram.value_at_percentile(percentile));
+    }
+    println!(" {:>10}", histogram.max());
+}
@@ -10,9 +10,9 @@ pub struct OrderRecord {
     pub client_id: i32,
     pub instrument: [u8; 16],
     pub side: i16,
-    pub px: i32,
-    pub qty: i32,
-    pub qty_rem: i32,
+    pub px: i64,
+    pub qty: i64,
+    pub qty_rem: i64,
     pub time_in_force: i16,
     pub ack_time: i64,
 }
@@ -48,15 +48,15 @@ pub struct TradeRecord {
     pub trade_id: i32,
     pub bid_client_id: i32,
     pub bid_order_id: i32,
-    pub bid_order_px: i32,
+    pub bid_order_px: i64,
     pub bid_fill_type: i16,
     pub ask_client_id: i32,
     pub ask_order_id: i32,
-    pub ask_order_px: i32,
+    pub ask_order_px: i64,
     pub ask_fill_type: i16,
     pub instrument: [u8; 16],
-    pub exec_px: i32,
-    pub exec_qty: i32,
+    pub exec_px: i64,
+    pub exec_qty: i64,
     pub exec_type: i16,
     pub exec_ns: i64,
 }